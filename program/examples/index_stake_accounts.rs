@@ -0,0 +1,162 @@
+//! Index every stake account of a program on a running cluster and print the
+//! stake delegated to each vote account, using only the `client` module to
+//! read the accounts:
+//!
+//! ```text
+//! cargo run --example index_stake_accounts -- [RPC_URL] [PROGRAM_ID]
+//! ```
+//!
+//! `RPC_URL` defaults to a local `solana-test-validator`, `PROGRAM_ID` to this
+//! program's; `Stake11111111111111111111111111111111111111` indexes the native
+//! stake program, whose accounts share the layout. Every account is also
+//! checked against the `filters_by_*` queries built from the layout offsets,
+//! so running it against real RPC data smoke-tests the layout constants: any
+//! account that fails to decode or to match its own filters is reported, and
+//! the exit status is non-zero.
+
+use std::{collections::BTreeMap, process::ExitCode, str::FromStr};
+
+use pinocchio::sysvars::clock::Clock;
+use pinocchio_stake::{
+    client::{
+        filters_by_staker, filters_by_voter, filters_by_withdrawer, summarize_stake_account, AccountFilter,
+        StakeAccountKind, StakeAccountSummary, StakeHistorySnapshot,
+    },
+    state::layout,
+};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{pubkey::Pubkey, sysvar};
+
+const LOCAL_VALIDATOR: &str = "http://127.0.0.1:8899";
+
+/// Stake delegated to one vote account, in lamports
+#[derive(Debug, Default)]
+struct VoterTotals {
+    accounts: usize,
+    delegated: u64,
+    active: u64,
+    activating: u64,
+    deactivating: u64,
+}
+
+impl VoterTotals {
+    fn add(&mut self, summary: &StakeAccountSummary) {
+        self.accounts += 1;
+        self.delegated += summary.delegated_stake;
+        self.active += summary.active;
+        self.activating += summary.activating;
+        self.deactivating += summary.deactivating;
+    }
+}
+
+fn rpc_filter(filter: AccountFilter) -> RpcFilterType {
+    match filter {
+        AccountFilter::DataSize(size) => RpcFilterType::DataSize(size),
+        AccountFilter::Memcmp { offset, bytes } => RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes)),
+    }
+}
+
+/// The filter queries `summary` must answer to, by name
+fn own_filters(summary: &StakeAccountSummary) -> Vec<(&'static str, Vec<AccountFilter>)> {
+    let mut queries = Vec::new();
+    if let Some(staker) = summary.staker {
+        queries.push(("staker", filters_by_staker(&staker)));
+    }
+    if let Some(withdrawer) = summary.withdrawer {
+        queries.push(("withdrawer", filters_by_withdrawer(&withdrawer)));
+    }
+    if let Some(voter) = summary.voter {
+        queries.push(("voter", filters_by_voter(&voter)));
+    }
+    queries
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let url = args.next().unwrap_or_else(|| LOCAL_VALIDATOR.to_string());
+    let program_id = match args.next().map(|id| Pubkey::from_str(&id)) {
+        Some(Ok(id)) => id,
+        Some(Err(err)) => {
+            eprintln!("invalid program id: {err}");
+            return ExitCode::FAILURE;
+        }
+        None => Pubkey::new_from_array(pinocchio_stake::ID),
+    };
+    match index(&RpcClient::new(url), &program_id) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Print the per-voter totals; `false` if any account was rejected
+fn index(rpc: &RpcClient, program_id: &Pubkey) -> Result<bool, Box<dyn std::error::Error>> {
+    let clock: solana_sdk::clock::Clock = bincode::deserialize(&rpc.get_account_data(&sysvar::clock::id())?)?;
+    let clock = Clock {
+        slot: clock.slot,
+        epoch_start_timestamp: clock.epoch_start_timestamp,
+        epoch: clock.epoch,
+        leader_schedule_epoch: clock.leader_schedule_epoch,
+        unix_timestamp: clock.unix_timestamp,
+    };
+    let history = StakeHistorySnapshot::from_sysvar_data(&rpc.get_account_data(&sysvar::stake_history::id())?)
+        .map_err(|err| format!("StakeHistory sysvar: {err:?}"))?;
+
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![rpc_filter(AccountFilter::DataSize(layout::ACCOUNT_SIZE as u64))]),
+        account_config: RpcAccountInfoConfig { encoding: Some(UiAccountEncoding::Base64), ..Default::default() },
+        ..Default::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(program_id, config)?;
+
+    let mut by_voter: BTreeMap<Pubkey, VoterTotals> = BTreeMap::new();
+    let (mut undelegated, mut rejected) = (0usize, 0usize);
+    for (key, account) in &accounts {
+        let summary = match summarize_stake_account(&account.data, account.lamports, &clock, &history) {
+            Ok(summary) => summary,
+            Err(err) => {
+                eprintln!("{key}: does not decode: {err:?}");
+                rejected += 1;
+                continue;
+            }
+        };
+        if let Some((query, _)) =
+            own_filters(&summary).into_iter().find(|(_, filters)| !filters.iter().all(|f| f.matches(&account.data)))
+        {
+            eprintln!("{key}: missed by filters_by_{query}");
+            rejected += 1;
+        }
+        match (summary.kind, summary.voter) {
+            (StakeAccountKind::Delegated, Some(voter)) => {
+                by_voter.entry(Pubkey::new_from_array(voter)).or_default().add(&summary)
+            }
+            _ => undelegated += 1,
+        }
+    }
+
+    println!("epoch {} slot {}: {} stake accounts of {program_id}", clock.epoch, clock.slot, accounts.len());
+    println!("{:<44} {:>8} {:>20} {:>20} {:>20} {:>20}", "vote account", "accounts", "delegated", "active", "activating", "deactivating");
+    let mut voters: Vec<_> = by_voter.iter().collect();
+    voters.sort_by_key(|(_, t)| std::cmp::Reverse(t.active));
+    for (voter, t) in voters {
+        println!(
+            "{:<44} {:>8} {:>20} {:>20} {:>20} {:>20}",
+            voter.to_string(),
+            t.accounts,
+            t.delegated,
+            t.active,
+            t.activating,
+            t.deactivating
+        );
+    }
+    println!("{undelegated} not delegated, {rejected} rejected");
+    Ok(rejected == 0)
+}
@@ -0,0 +1,183 @@
+//! Transaction builders for integrators (std only): ComputeBudget instructions
+//! sized for the stake instructions a transaction carries.
+//!
+//! The per-instruction limits in [`recommended_compute_units`] are 1.5x the
+//! most expensive case native consumes over the conformance corpus, rounded up
+//! to a thousand units; this program runs the same cases in fewer, so the
+//! limits hold for either. Extension instructions have no native counterpart
+//! and are budgeted as the native instructions they compose. The unit price is
+//! the integrator's choice. `tests/compute_budget.rs` checks every limit
+//! against simulated usage.
+//!
+//! The instructions are returned as program id plus data (they take no
+//! accounts), to be wrapped in whichever SDK's `Instruction` the caller uses
+//! and placed ahead of the stake instructions.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::instruction::StakeInstruction;
+
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = pinocchio_pubkey::pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// The runtime's cap on a transaction's compute unit limit
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Units the two ComputeBudget instructions themselves are charged
+pub const COMPUTE_BUDGET_OVERHEAD: u32 = 300;
+
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// An account-less instruction for the ComputeBudget program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetInstruction {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
+pub fn set_compute_unit_limit(units: u32) -> BudgetInstruction {
+    let mut data = Vec::with_capacity(5);
+    data.push(SET_COMPUTE_UNIT_LIMIT);
+    data.extend_from_slice(&units.to_le_bytes());
+    BudgetInstruction { program_id: COMPUTE_BUDGET_PROGRAM_ID, data }
+}
+
+pub fn set_compute_unit_price(micro_lamports_per_unit: u64) -> BudgetInstruction {
+    let mut data = Vec::with_capacity(9);
+    data.push(SET_COMPUTE_UNIT_PRICE);
+    data.extend_from_slice(&micro_lamports_per_unit.to_le_bytes());
+    BudgetInstruction { program_id: COMPUTE_BUDGET_PROGRAM_ID, data }
+}
+
+/// Compute units to budget for one `instruction`
+pub fn recommended_compute_units(instruction: StakeInstruction) -> u32 {
+    #[allow(deprecated)]
+    match instruction {
+        StakeInstruction::Initialize => 13_000,
+        StakeInstruction::Authorize => 16_000,
+        // vote state deserialization grows with the vote account's history
+        StakeInstruction::DelegateStake | StakeInstruction::Redelegate => 30_000,
+        StakeInstruction::Split => 25_000,
+        StakeInstruction::Withdraw => 12_000,
+        StakeInstruction::Deactivate => 16_000,
+        StakeInstruction::SetLockup => 15_000,
+        StakeInstruction::Merge => 23_000,
+        StakeInstruction::AuthorizeWithSeed => 18_000,
+        StakeInstruction::InitializeChecked => 8_000,
+        StakeInstruction::AuthorizeChecked => 16_000,
+        StakeInstruction::AuthorizeCheckedWithSeed => 18_000,
+        StakeInstruction::SetLockupChecked => 14_000,
+        StakeInstruction::GetMinimumDelegation => 2_000,
+        StakeInstruction::DeactivateDelinquent => 22_000,
+        StakeInstruction::MoveStake => 29_000,
+        StakeInstruction::MoveLamports => 16_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::DeactivatePartial => 41_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::WithdrawExcess | StakeInstruction::CloseStakeAccount => 12_000,
+        // a split plus the system program's allocate and assign
+        #[cfg(feature = "extensions")]
+        StakeInstruction::SplitWithSeed => 35_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::GetDelegationPreview => 8_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::MergeMany => 8 * 23_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::QueryParams => 4_000,
+        #[cfg(feature = "extensions")]
+        StakeInstruction::GetVersion => 2_000,
+        // a withdrawal plus the token program's SyncNative
+        #[cfg(feature = "withdraw-to-token")]
+        StakeInstruction::WithdrawToTokenAccount => 22_000,
+    }
+}
+
+/// The instruction `data` encodes, in either this program's single-byte format
+/// or the SDK's bincode one (whose little-endian `u32` tag starts with the same
+/// byte for every native instruction)
+pub fn instruction_kind(data: &[u8]) -> Result<StakeInstruction, ProgramError> {
+    data.first().ok_or(ProgramError::InvalidInstructionData).and_then(StakeInstruction::try_from)
+}
+
+/// Compute unit limit and price for one transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    /// Micro-lamports per compute unit
+    pub unit_price: u64,
+}
+
+impl ComputeBudget {
+    /// Budget for a transaction carrying these stake instructions (by their
+    /// data) and nothing else; `InvalidInstructionData` for one this build
+    /// does not know
+    pub fn for_stake_instructions<'a>(
+        instructions: impl IntoIterator<Item = &'a [u8]>,
+        unit_price: u64,
+    ) -> Result<Self, ProgramError> {
+        let mut units = COMPUTE_BUDGET_OVERHEAD;
+        for data in instructions {
+            units = units.saturating_add(recommended_compute_units(instruction_kind(data)?));
+        }
+        Ok(Self { unit_limit: units.min(MAX_COMPUTE_UNIT_LIMIT), unit_price })
+    }
+
+    /// Room for the transaction's other (e.g. system program) instructions
+    pub fn with_extra_units(self, units: u32) -> Self {
+        Self { unit_limit: self.unit_limit.saturating_add(units).min(MAX_COMPUTE_UNIT_LIMIT), ..self }
+    }
+
+    /// SetComputeUnitLimit then SetComputeUnitPrice, to prepend
+    pub fn instructions(&self) -> [BudgetInstruction; 2] {
+        [set_compute_unit_limit(self.unit_limit), set_compute_unit_price(self.unit_price)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::dispatch::instructions;
+
+    #[test]
+    fn budget_instructions_match_the_sdk() {
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+        let [limit, price] = ComputeBudget { unit_limit: 42_000, unit_price: 7 }.instructions();
+        let sdk_limit = ComputeBudgetInstruction::set_compute_unit_limit(42_000);
+        let sdk_price = ComputeBudgetInstruction::set_compute_unit_price(7);
+        assert_eq!(limit.program_id, sdk_limit.program_id.to_bytes());
+        assert_eq!(limit.data, sdk_limit.data);
+        assert_eq!(price.data, sdk_price.data);
+        assert!(sdk_limit.accounts.is_empty() && sdk_price.accounts.is_empty());
+    }
+
+    #[test]
+    fn every_instruction_has_a_budget_within_the_cap() {
+        for d in instructions() {
+            let units = recommended_compute_units(d.instruction);
+            assert!(units > 0 && units + COMPUTE_BUDGET_OVERHEAD <= MAX_COMPUTE_UNIT_LIMIT, "{}", d.name);
+            assert_eq!(instruction_kind(&[d.discriminant]), Ok(d.instruction));
+        }
+    }
+
+    #[test]
+    fn batches_add_up_and_saturate_at_the_cap() {
+        // this program's encoding and the SDK's bincode one
+        let split = [StakeInstruction::Split as u8, 0, 0, 0, 0, 0, 0, 0, 0];
+        let sdk_deactivate = 5u32.to_le_bytes();
+        let budget = ComputeBudget::for_stake_instructions([&split[..], &sdk_deactivate[..]], 1_000).unwrap();
+        assert_eq!(budget.unit_limit, COMPUTE_BUDGET_OVERHEAD + 25_000 + 16_000);
+        assert_eq!(budget.unit_price, 1_000);
+        assert_eq!(budget.with_extra_units(3_000).unit_limit, budget.unit_limit + 3_000);
+
+        let many = [&split[..]; 100];
+        assert_eq!(ComputeBudget::for_stake_instructions(many, 0).unwrap().unit_limit, MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(budget.with_extra_units(u32::MAX).unit_limit, MAX_COMPUTE_UNIT_LIMIT);
+
+        assert_eq!(ComputeBudget::for_stake_instructions([&[][..]], 0), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(ComputeBudget::for_stake_instructions([&[127u8][..]], 0), Err(ProgramError::InvalidInstructionData));
+    }
+}
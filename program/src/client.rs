@@ -0,0 +1,563 @@
+//! Client-side introspection of stake accounts (std only).
+//!
+//! Explorers and bots can decode a stake account's raw data together with
+//! Clock and StakeHistory snapshots into a [`StakeAccountSummary`] using the
+//! same activation math the program runs on-chain, or project a delegation's
+//! activation to a later epoch with [`project_stake_activation`]. With the `serde` feature
+//! summaries serialize directly to JSON, pubkeys as base58 strings.
+//! The `filters_by_*` functions turn the [`layout`](crate::state::layout)
+//! offsets into `getProgramAccounts` filters for the usual queries.
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey, sysvars::clock::Clock};
+
+use crate::{
+    helpers::warmup_rate::new_warmup_cooldown_rate_epoch,
+    state::{
+        layout,
+        stake_history::{StakeHistoryEntry, StakeHistoryGetEntry},
+        stake_state_v2::StakeStateV2,
+        state::Lockup,
+        EpochLE, LamportsLE,
+    },
+};
+
+/// Which `StakeStateV2` variant the account holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StakeAccountKind {
+    Uninitialized,
+    Initialized,
+    Delegated,
+    RewardsPool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StakeAccountSummary {
+    pub kind: StakeAccountKind,
+    pub lamports: u64,
+    pub rent_exempt_reserve: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::option_pubkey"))]
+    pub staker: Option<Pubkey>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::option_pubkey"))]
+    pub withdrawer: Option<Pubkey>,
+    pub lockup: Option<Lockup>,
+    /// Lockup still restricts withdrawals at the snapshot clock (ignoring custodian)
+    pub lockup_in_force: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::option_pubkey"))]
+    pub voter: Option<Pubkey>,
+    pub delegated_stake: u64,
+    pub activation_epoch: Option<u64>,
+    /// `None` while the delegation has not been deactivated
+    pub deactivation_epoch: Option<u64>,
+    pub active: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+/// Summarize a stake account from its data and lamports at the given clock.
+pub fn summarize_stake_account<T: StakeHistoryGetEntry>(
+    data: &[u8],
+    lamports: u64,
+    clock: &Clock,
+    stake_history: &T,
+) -> Result<StakeAccountSummary, ProgramError> {
+    let mut summary = StakeAccountSummary {
+        kind: StakeAccountKind::Uninitialized,
+        lamports,
+        rent_exempt_reserve: 0,
+        staker: None,
+        withdrawer: None,
+        lockup: None,
+        lockup_in_force: false,
+        voter: None,
+        delegated_stake: 0,
+        activation_epoch: None,
+        deactivation_epoch: None,
+        active: 0,
+        activating: 0,
+        deactivating: 0,
+    };
+
+    let (meta, stake) = match StakeStateV2::deserialize(data)? {
+        StakeStateV2::Uninitialized => return Ok(summary),
+        StakeStateV2::RewardsPool => {
+            summary.kind = StakeAccountKind::RewardsPool;
+            return Ok(summary);
+        }
+        StakeStateV2::Initialized(meta) => {
+            summary.kind = StakeAccountKind::Initialized;
+            (meta, None)
+        }
+        StakeStateV2::Stake(meta, stake, _flags) => {
+            summary.kind = StakeAccountKind::Delegated;
+            (meta, Some(stake))
+        }
+    };
+
+    summary.rent_exempt_reserve = meta.rent_exempt_reserve.get();
+    summary.staker = Some(meta.authorized.staker);
+    summary.withdrawer = Some(meta.authorized.withdrawer);
+    summary.lockup = Some(meta.lockup);
+    summary.lockup_in_force = meta.lockup.is_in_force(clock, None);
+
+    if let Some(stake) = stake {
+        let delegation = &stake.delegation;
+        let status = delegation.stake_activating_and_deactivating(
+            EpochLE::new(clock.epoch),
+            stake_history,
+            new_warmup_cooldown_rate_epoch(),
+        );
+        let deactivation_epoch = delegation.deactivation_epoch.get();
+
+        summary.voter = Some(delegation.voter_pubkey);
+        summary.delegated_stake = delegation.stake.get();
+        summary.activation_epoch = Some(delegation.activation_epoch.get());
+        summary.deactivation_epoch = (deactivation_epoch != u64::MAX).then_some(deactivation_epoch);
+        summary.active = status.effective.get();
+        summary.activating = status.activating.get();
+        summary.deactivating = status.deactivating.get();
+    }
+
+    Ok(summary)
+}
+
+/// Projected activation of a delegated stake account at a later epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActivationProjection {
+    pub epoch: u64,
+    pub active: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+/// Project a stake account's activation to `target_epoch`, from a StakeHistory
+/// snapshot taken at `clock`. Assumes the rest of the cluster stakes nothing
+/// new; see `Delegation::projected_activation`. `None` unless delegated.
+pub fn project_stake_activation<T: StakeHistoryGetEntry>(
+    data: &[u8],
+    clock: &Clock,
+    target_epoch: u64,
+    stake_history: &T,
+) -> Result<Option<ActivationProjection>, ProgramError> {
+    let StakeStateV2::Stake(_, stake, _) = StakeStateV2::deserialize(data)? else {
+        return Ok(None);
+    };
+    let status = stake.delegation.projected_activation(
+        EpochLE::new(target_epoch),
+        EpochLE::new(clock.epoch),
+        stake_history,
+        new_warmup_cooldown_rate_epoch(),
+    );
+    Ok(Some(ActivationProjection {
+        epoch: target_epoch,
+        active: status.effective.get(),
+        activating: status.activating.get(),
+        deactivating: status.deactivating.get(),
+    }))
+}
+
+/// Owned StakeHistory snapshot keyed by epoch, e.g. decoded from the sysvar account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeHistorySnapshot {
+    entries: Vec<(u64, StakeHistoryEntry)>,
+}
+
+impl StakeHistorySnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the bincode layout of the StakeHistory sysvar:
+    /// a u64 length followed by `(epoch, effective, activating, deactivating)` records.
+    pub fn from_sysvar_data(data: &[u8]) -> Result<Self, ProgramError> {
+        let len_bytes: [u8; 8] = data
+            .get(..8)
+            .and_then(|b| b.try_into().ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let len = u64::from_le_bytes(len_bytes) as usize;
+        let records = &data[8..];
+        if records.len() < len.saturating_mul(32) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let word = |r: &[u8], i: usize| u64::from_le_bytes(r[i * 8..i * 8 + 8].try_into().unwrap());
+        let entries = records
+            .chunks_exact(32)
+            .take(len)
+            .map(|r| {
+                (
+                    word(r, 0),
+                    StakeHistoryEntry {
+                        effective: LamportsLE::new(word(r, 1)),
+                        activating: LamportsLE::new(word(r, 2)),
+                        deactivating: LamportsLE::new(word(r, 3)),
+                    },
+                )
+            })
+            .collect();
+        Ok(Self { entries })
+    }
+
+    /// Insert or replace the entry for `epoch`
+    pub fn add(&mut self, epoch: u64, entry: StakeHistoryEntry) {
+        match self.entries.iter_mut().find(|(e, _)| *e == epoch) {
+            Some(slot) => slot.1 = entry,
+            None => self.entries.push((epoch, entry)),
+        }
+    }
+}
+
+impl StakeHistoryGetEntry for StakeHistorySnapshot {
+    fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+        self.entries
+            .iter()
+            .find(|(e, _)| *e == epoch)
+            .map(|(_, entry)| entry.clone())
+    }
+}
+
+/// One `getProgramAccounts` filter over stake account data.
+///
+/// With `serde` it serializes as solana-client's `RpcFilterType` (`dataSize`,
+/// or `memcmp` with base58 bytes), so a filter list can go straight into
+/// `RpcProgramAccountsConfig::filters`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "rpc_filter::RpcFilter", try_from = "rpc_filter::RpcFilter"))]
+pub enum AccountFilter {
+    DataSize(u64),
+    /// Account data at `offset` starts with `bytes`
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl AccountFilter {
+    /// Whether account `data` passes the filter, as the RPC node evaluates it
+    pub fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            AccountFilter::DataSize(size) => data.len() as u64 == *size,
+            AccountFilter::Memcmp { offset, bytes } => {
+                data.get(*offset..).is_some_and(|rest| rest.starts_with(bytes))
+            }
+        }
+    }
+
+    fn memcmp(offset: usize, bytes: &[u8]) -> Self {
+        AccountFilter::Memcmp { offset, bytes: bytes.to_vec() }
+    }
+}
+
+fn stake_accounts() -> Vec<AccountFilter> {
+    alloc::vec![AccountFilter::DataSize(layout::ACCOUNT_SIZE as u64)]
+}
+
+fn delegated_stake_accounts() -> Vec<AccountFilter> {
+    let mut filters = stake_accounts();
+    filters.push(AccountFilter::memcmp(layout::TAG, &layout::TAG_STAKE.to_le_bytes()));
+    filters
+}
+
+/// Initialized and delegated stake accounts whose withdraw authority is `withdrawer`
+pub fn filters_by_withdrawer(withdrawer: &Pubkey) -> Vec<AccountFilter> {
+    // no tag filter: Meta sits at the same offset in both variants, and the
+    // others are zeroed there
+    let mut filters = stake_accounts();
+    filters.push(AccountFilter::memcmp(layout::WITHDRAWER, withdrawer));
+    filters
+}
+
+/// Initialized and delegated stake accounts whose stake authority is `staker`
+pub fn filters_by_staker(staker: &Pubkey) -> Vec<AccountFilter> {
+    let mut filters = stake_accounts();
+    filters.push(AccountFilter::memcmp(layout::STAKER, staker));
+    filters
+}
+
+/// Stake accounts delegated to the vote account `voter`
+pub fn filters_by_voter(voter: &Pubkey) -> Vec<AccountFilter> {
+    let mut filters = delegated_stake_accounts();
+    filters.push(AccountFilter::memcmp(layout::VOTER_PUBKEY, voter));
+    filters
+}
+
+/// Stake accounts whose deactivation epoch lies in `epochs`, as one filter list
+/// per `getProgramAccounts` call.
+///
+/// memcmp only matches exact bytes, so the range is split into aligned blocks
+/// of `256^k` epochs, each matched on the epoch's upper `8 - k` little-endian
+/// bytes: up to 255 blocks per byte at each end of the range, a handful for
+/// ranges that start and end on multiples of 256. Delegations that were never
+/// deactivated record `u64::MAX`, so a range reaching it includes them.
+pub fn filters_by_deactivation_epochs(epochs: RangeInclusive<u64>) -> Vec<Vec<AccountFilter>> {
+    let (mut start, end) = (u128::from(*epochs.start()), u128::from(*epochs.end()));
+    let mut queries = Vec::new();
+    while start <= end {
+        // widest aligned block starting at `start` that stays within the range
+        let matched_bytes = (0..=8u32)
+            .rev()
+            .find(|&k| {
+                let width = 1u128 << (8 * k);
+                start % width == 0 && start + width - 1 <= end
+            })
+            .unwrap_or(0) as usize;
+        let mut filters = delegated_stake_accounts();
+        if matched_bytes < 8 {
+            let epoch = (start as u64).to_le_bytes();
+            filters.push(AccountFilter::memcmp(layout::DEACTIVATION_EPOCH + matched_bytes, &epoch[matched_bytes..]));
+        }
+        queries.push(filters);
+        start += 1u128 << (8 * matched_bytes);
+    }
+    queries
+}
+
+/// solana-client's `RpcFilterType` JSON shape
+#[cfg(feature = "serde")]
+mod rpc_filter {
+    use super::AccountFilter;
+    use super::alloc::string::String;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum RpcFilter {
+        DataSize(u64),
+        Memcmp(RpcMemcmp),
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub struct RpcMemcmp {
+        offset: usize,
+        bytes: String,
+        encoding: String,
+    }
+
+    impl From<AccountFilter> for RpcFilter {
+        fn from(filter: AccountFilter) -> Self {
+            match filter {
+                AccountFilter::DataSize(size) => RpcFilter::DataSize(size),
+                AccountFilter::Memcmp { offset, bytes } => RpcFilter::Memcmp(RpcMemcmp {
+                    offset,
+                    bytes: bs58::encode(bytes).into_string(),
+                    encoding: "base58".into(),
+                }),
+            }
+        }
+    }
+
+    impl TryFrom<RpcFilter> for AccountFilter {
+        type Error = &'static str;
+
+        fn try_from(filter: RpcFilter) -> Result<Self, Self::Error> {
+            match filter {
+                RpcFilter::DataSize(size) => Ok(AccountFilter::DataSize(size)),
+                RpcFilter::Memcmp(RpcMemcmp { offset, bytes, encoding }) if encoding == "base58" => {
+                    let bytes = bs58::decode(bytes).into_vec().map_err(|_| "invalid base58 memcmp bytes")?;
+                    Ok(AccountFilter::Memcmp { offset, bytes })
+                }
+                RpcFilter::Memcmp(_) => Err("unsupported memcmp encoding"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        accounts::Authorized,
+        delegation::{Delegation, Stake},
+        stake_flag::StakeFlags,
+        state::Meta,
+    };
+
+    fn clock_at(epoch: u64) -> Clock {
+        Clock {
+            slot: 0,
+            epoch_start_timestamp: 0,
+            epoch,
+            leader_schedule_epoch: epoch,
+            unix_timestamp: 0,
+        }
+    }
+
+    fn encode(state: &StakeStateV2) -> [u8; StakeStateV2::ACCOUNT_SIZE] {
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        state.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn meta() -> Meta {
+        Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            lockup: Lockup { unix_timestamp: 0, epoch: 10, custodian: [3; 32] },
+        }
+    }
+
+    #[test]
+    fn initialized_account_reports_authorities_and_lockup() {
+        let data = encode(&StakeStateV2::Initialized(meta()));
+        let s = summarize_stake_account(&data, 5, &clock_at(4), &StakeHistorySnapshot::new()).unwrap();
+        assert_eq!(s.kind, StakeAccountKind::Initialized);
+        assert_eq!(s.staker, Some([1; 32]));
+        assert_eq!(s.withdrawer, Some([2; 32]));
+        assert!(s.lockup_in_force);
+        assert_eq!((s.active, s.activating, s.deactivating), (0, 0, 0));
+
+        let s = summarize_stake_account(&data, 5, &clock_at(10), &StakeHistorySnapshot::new()).unwrap();
+        assert!(!s.lockup_in_force);
+    }
+
+    #[test]
+    fn delegated_account_uses_history_for_warmup() {
+        let stake = Stake {
+            delegation: Delegation::new(&[9; 32], 1_000, EpochLE::new(5)),
+            credits_observed: 0u64.to_le_bytes(),
+        };
+        let data = encode(&StakeStateV2::Stake(meta(), stake, StakeFlags::empty()));
+        let mut history = StakeHistorySnapshot::new();
+        // cluster is mostly effective, so the whole delegation warms up in one epoch
+        history.add(5, StakeHistoryEntry::with_effective_and_activating(1_000_000, 1_000));
+
+        let s = summarize_stake_account(&data, 0, &clock_at(5), &history).unwrap();
+        assert_eq!(s.kind, StakeAccountKind::Delegated);
+        assert_eq!(s.voter, Some([9; 32]));
+        assert_eq!((s.active, s.activating), (0, 1_000));
+        assert_eq!(s.deactivation_epoch, None);
+
+        let s = summarize_stake_account(&data, 0, &clock_at(6), &history).unwrap();
+        assert_eq!((s.active, s.activating), (1_000, 0));
+    }
+
+    #[test]
+    fn projection_follows_warmup_past_the_snapshot() {
+        let stake = Stake {
+            delegation: Delegation::new(&[9; 32], 180_000, EpochLE::new(6)),
+            credits_observed: 0u64.to_le_bytes(),
+        };
+        let data = encode(&StakeStateV2::Stake(meta(), stake, StakeFlags::empty()));
+        let mut history = StakeHistorySnapshot::new();
+        history.add(5, StakeHistoryEntry::with_effective(1_000_000));
+
+        let at = |epoch| project_stake_activation(&data, &clock_at(6), epoch, &history).unwrap().unwrap();
+        assert_eq!((at(6).active, at(6).activating), (0, 180_000));
+        assert_eq!((at(7).active, at(7).activating), (90_000, 90_000));
+        assert_eq!((at(8).active, at(8).activating), (180_000, 0));
+        assert_eq!(at(8).epoch, 8);
+
+        // the plain summary at epoch 7 has no entry for 6 and counts it all active
+        let s = summarize_stake_account(&data, 0, &clock_at(7), &history).unwrap();
+        assert_eq!((s.active, s.activating), (180_000, 0));
+
+        let data = encode(&StakeStateV2::Initialized(meta()));
+        assert_eq!(project_stake_activation(&data, &clock_at(6), 8, &history), Ok(None));
+    }
+
+    fn delegated(voter: Pubkey, deactivation_epoch: u64) -> [u8; StakeStateV2::ACCOUNT_SIZE] {
+        let mut delegation = Delegation::new(&voter, 1_000, EpochLE::new(5));
+        delegation.deactivation_epoch = EpochLE::new(deactivation_epoch);
+        let stake = Stake { delegation, credits_observed: [0; 8] };
+        encode(&StakeStateV2::Stake(meta(), stake, StakeFlags::empty()))
+    }
+
+    fn passes(filters: &[AccountFilter], data: &[u8]) -> bool {
+        filters.iter().all(|filter| filter.matches(data))
+    }
+
+    #[test]
+    fn authority_and_voter_filters_select_matching_accounts() {
+        let initialized = encode(&StakeStateV2::Initialized(meta()));
+        let delegated = delegated([9; 32], u64::MAX);
+        for data in [&initialized, &delegated] {
+            assert!(passes(&filters_by_staker(&[1; 32]), data));
+            assert!(passes(&filters_by_withdrawer(&[2; 32]), data));
+            assert!(!passes(&filters_by_withdrawer(&[1; 32]), data));
+        }
+        assert!(!passes(&filters_by_staker(&[1; 32]), &encode(&StakeStateV2::Uninitialized)));
+        assert!(!passes(&filters_by_staker(&[1; 32]), &initialized[..100]));
+
+        assert!(passes(&filters_by_voter(&[9; 32]), &delegated));
+        assert!(!passes(&filters_by_voter(&[8; 32]), &delegated));
+        assert!(!passes(&filters_by_voter(&[0; 32]), &initialized));
+    }
+
+    #[test]
+    fn deactivation_range_is_covered_by_disjoint_queries() {
+        let epochs = [0, 1, 5, 6, 255, 256, 511, 512, 699, 700, 701, 65_536, u64::MAX - 1, u64::MAX];
+        for range in [5..=700, 256..=511, 0..=65_535, 700..=700, 1..=u64::MAX - 1, 0..=u64::MAX] {
+            let queries = filters_by_deactivation_epochs(range.clone());
+            for epoch in epochs {
+                let data = delegated([9; 32], epoch);
+                let hits = queries.iter().filter(|filters| passes(filters, &data)).count();
+                assert_eq!(hits, usize::from(range.contains(&epoch)), "{range:?} epoch {epoch}");
+            }
+        }
+        // aligned ranges need a single call
+        assert_eq!(filters_by_deactivation_epochs(256..=511).len(), 1);
+        assert_eq!(filters_by_deactivation_epochs(0..=u64::MAX), alloc::vec![delegated_stake_accounts()]);
+        assert_eq!(filters_by_deactivation_epochs(5..=700).len(), 251 + 1 + 189);
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 7..=6;
+        assert!(filters_by_deactivation_epochs(empty).is_empty());
+        // Initialized accounts carry no delegation
+        let initialized = encode(&StakeStateV2::Initialized(meta()));
+        assert!(!filters_by_deactivation_epochs(0..=u64::MAX).iter().any(|filters| passes(filters, &initialized)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn filters_serialize_as_rpc_filter_type() {
+        let filters = filters_by_voter(&[0; 32]);
+        let json = serde_json::to_value(&filters).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "dataSize": 200 },
+                { "memcmp": { "offset": 0, "bytes": "3xyZh", "encoding": "base58" } },
+                { "memcmp": { "offset": 124, "bytes": "11111111111111111111111111111111", "encoding": "base58" } },
+            ])
+        );
+        assert_eq!(serde_json::from_value::<Vec<AccountFilter>>(json).unwrap(), filters);
+
+        let base64 = serde_json::json!({ "memcmp": { "offset": 0, "bytes": "AgAAAA==", "encoding": "base64" } });
+        assert!(serde_json::from_value::<AccountFilter>(base64).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn summary_round_trips_through_json() {
+        let data = encode(&StakeStateV2::Initialized(meta()));
+        let s = summarize_stake_account(&data, 5, &clock_at(4), &StakeHistorySnapshot::new()).unwrap();
+
+        let json = serde_json::to_value(&s).unwrap();
+        assert_eq!(json["kind"], "Initialized");
+        assert_eq!(json["staker"], "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi");
+        assert_eq!(json["voter"], serde_json::Value::Null);
+        assert_eq!(json["lockup"]["epoch"], 10);
+        assert_eq!(serde_json::from_value::<StakeAccountSummary>(json).unwrap(), s);
+    }
+
+    #[test]
+    fn snapshot_decodes_sysvar_layout() {
+        let mut data = alloc::vec![0u8; 8 + 2 * 32];
+        data[..8].copy_from_slice(&2u64.to_le_bytes());
+        data[8..16].copy_from_slice(&7u64.to_le_bytes());
+        data[16..24].copy_from_slice(&100u64.to_le_bytes());
+        data[40..48].copy_from_slice(&6u64.to_le_bytes());
+        data[64..72].copy_from_slice(&50u64.to_le_bytes());
+
+        let snapshot = StakeHistorySnapshot::from_sysvar_data(&data).unwrap();
+        assert_eq!(snapshot.get_entry(7), Some(StakeHistoryEntry::with_effective(100)));
+        assert_eq!(
+            snapshot.get_entry(6).unwrap().deactivating.get(),
+            50
+        );
+        assert_eq!(snapshot.get_entry(5), None);
+        assert!(StakeHistorySnapshot::from_sysvar_data(&data[..40]).is_err());
+    }
+}
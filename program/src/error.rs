@@ -1,20 +1,123 @@
-use pinocchio::program_error::ProgramError;
+use pinocchio::program_error::{ProgramError, ToStr};
 
-// simple internal error enum
-#[derive(Debug)]
+use crate::helpers::log::log_error;
+
+/// Stake program errors. The variants up to `EpochRewardsActive` mirror the
+/// native program's `StakeError` in order, so their discriminants are the
+/// `Custom` codes clients already decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
 pub enum StakeError {
+    NoCreditsToRedeem = 0,
+    LockupInForce = 1,
+    AlreadyDeactivated = 2,
+    TooSoonToRedelegate = 3,
+    InsufficientStake = 4,
+    MergeTransientStake = 5,
+    MergeMismatch = 6,
+    CustodianMissing = 7,
+    CustodianSignatureMissing = 8,
+    InsufficientReferenceVotes = 9,
+    VoteAddressMismatch = 10,
+    MinimumDelinquentEpochsForDeactivationNotMet = 11,
+    InsufficientDelegation = 12,
+    RedelegateTransientOrInactiveStake = 13,
+    RedelegateToSameVoteAccount = 14,
+    RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted = 15,
+    EpochRewardsActive = 16,
+
+    // Not native stake errors: surfaced as builtin program errors
     InvalidAuthorization,
     InsufficientFunds,
-    InsufficientStake,
-    AlreadyDeactivated,
-    InsufficientDelegation,
-    VoteAddressMismatch,
-    MergeMismatch,
-    LockupInForce,
-    InsufficientReferenceVotes,
-    MinimumDelinquentEpochsForDeactivationNotMet,
-    TooSoonToRedelegate,
-    EpochRewardsActive,
+    /// More distinct signers than `MAXIMUM_SIGNERS`; native has no limit
+    TooManySigners,
+}
+
+impl StakeError {
+    /// Native variant for a `Custom` code, if there is one
+    pub fn from_code(code: u32) -> Option<Self> {
+        use StakeError::*;
+        const NATIVE: [StakeError; 17] = [
+            NoCreditsToRedeem,
+            LockupInForce,
+            AlreadyDeactivated,
+            TooSoonToRedelegate,
+            InsufficientStake,
+            MergeTransientStake,
+            MergeMismatch,
+            CustodianMissing,
+            CustodianSignatureMissing,
+            InsufficientReferenceVotes,
+            VoteAddressMismatch,
+            MinimumDelinquentEpochsForDeactivationNotMet,
+            InsufficientDelegation,
+            RedelegateTransientOrInactiveStake,
+            RedelegateToSameVoteAccount,
+            RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted,
+            EpochRewardsActive,
+        ];
+        NATIVE.get(code as usize).copied()
+    }
+
+    /// Symbolic name, e.g. `"StakeError::MergeMismatch"`
+    pub const fn name(self) -> &'static str {
+        use StakeError::*;
+        match self {
+            NoCreditsToRedeem => "StakeError::NoCreditsToRedeem",
+            LockupInForce => "StakeError::LockupInForce",
+            AlreadyDeactivated => "StakeError::AlreadyDeactivated",
+            TooSoonToRedelegate => "StakeError::TooSoonToRedelegate",
+            InsufficientStake => "StakeError::InsufficientStake",
+            MergeTransientStake => "StakeError::MergeTransientStake",
+            MergeMismatch => "StakeError::MergeMismatch",
+            CustodianMissing => "StakeError::CustodianMissing",
+            CustodianSignatureMissing => "StakeError::CustodianSignatureMissing",
+            InsufficientReferenceVotes => "StakeError::InsufficientReferenceVotes",
+            VoteAddressMismatch => "StakeError::VoteAddressMismatch",
+            MinimumDelinquentEpochsForDeactivationNotMet => "StakeError::MinimumDelinquentEpochsForDeactivationNotMet",
+            InsufficientDelegation => "StakeError::InsufficientDelegation",
+            RedelegateTransientOrInactiveStake => "StakeError::RedelegateTransientOrInactiveStake",
+            RedelegateToSameVoteAccount => "StakeError::RedelegateToSameVoteAccount",
+            RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted => {
+                "StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted"
+            }
+            EpochRewardsActive => "StakeError::EpochRewardsActive",
+            InvalidAuthorization => "StakeError::InvalidAuthorization",
+            InsufficientFunds => "StakeError::InsufficientFunds",
+            TooManySigners => "StakeError::TooManySigners",
+        }
+    }
+
+    /// Human-readable description; native's `Display` text for native variants
+    pub const fn message(self) -> &'static str {
+        use StakeError::*;
+        match self {
+            NoCreditsToRedeem => "not enough credits to redeem",
+            LockupInForce => "lockup has not yet expired",
+            AlreadyDeactivated => "stake already deactivated",
+            TooSoonToRedelegate => "one re-delegation permitted per epoch",
+            InsufficientStake => "split amount is more than is staked",
+            MergeTransientStake => "stake account with transient stake cannot be merged",
+            MergeMismatch => "stake account merge failed due to different authority, lockups or state",
+            CustodianMissing => "custodian address not present",
+            CustodianSignatureMissing => "custodian signature not present",
+            InsufficientReferenceVotes => "insufficient voting activity in the reference vote account",
+            VoteAddressMismatch => "stake account is not delegated to the provided vote account",
+            MinimumDelinquentEpochsForDeactivationNotMet => {
+                "stake account has not been delinquent for the minimum epochs required for deactivation"
+            }
+            InsufficientDelegation => "delegation amount is less than the minimum",
+            RedelegateTransientOrInactiveStake => "stake account with transient or inactive stake cannot be redelegated",
+            RedelegateToSameVoteAccount => "stake redelegation to the same vote account is not permitted",
+            RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted => {
+                "redelegated stake must be fully activated before deactivation"
+            }
+            EpochRewardsActive => "stake action is not permitted while the epoch rewards period is active",
+            InvalidAuthorization => "missing or invalid authority signature",
+            InsufficientFunds => "insufficient funds",
+            TooManySigners => "more distinct signers than the program accepts",
+        }
+    }
 }
 
 // map internal errors to standard program error
@@ -22,15 +125,127 @@ pub fn to_program_error(err: StakeError) -> ProgramError {
     match err {
         StakeError::InvalidAuthorization => ProgramError::MissingRequiredSignature,
         StakeError::InsufficientFunds => ProgramError::InsufficientFunds,
-        StakeError::InsufficientStake => ProgramError::Custom(0x10),
-        StakeError::AlreadyDeactivated => ProgramError::Custom(0x11),
-        StakeError::InsufficientDelegation => ProgramError::Custom(0x12),
-        StakeError::VoteAddressMismatch => ProgramError::Custom(0x13),
-        StakeError::MergeMismatch => ProgramError::Custom(0x14),
-        StakeError::LockupInForce => ProgramError::Custom(0x15),
-        StakeError::InsufficientReferenceVotes=> ProgramError::Custom(0x16),
-        StakeError::MinimumDelinquentEpochsForDeactivationNotMet=> ProgramError::Custom(0x17),
-        StakeError::TooSoonToRedelegate=> ProgramError::Custom(0x18),
-        StakeError::EpochRewardsActive=> ProgramError::Custom(0x19),
+        StakeError::TooManySigners => ProgramError::InvalidArgument,
+        native => ProgramError::Custom(native as u32),
+    }
+}
+
+impl From<StakeError> for ProgramError {
+    fn from(err: StakeError) -> Self {
+        to_program_error(err)
+    }
+}
+
+impl core::fmt::Display for StakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Native codes only, as `ProgramError::to_str` decodes `Custom` errors
+impl TryFrom<u32> for StakeError {
+    type Error = ProgramError;
+
+    fn try_from(code: u32) -> Result<Self, Self::Error> {
+        StakeError::from_code(code).ok_or(ProgramError::InvalidArgument)
+    }
+}
+
+impl ToStr for StakeError {
+    fn to_str<E>(&self) -> &'static str
+    where
+        E: 'static + ToStr + TryFrom<u32>,
+    {
+        self.name()
+    }
+}
+
+/// `log-error`: log the error an instruction fails with by name, e.g.
+/// `StakeError::MergeMismatch` for `Custom(6)` or `Error: InvalidAccountData`,
+/// so explorers show more than a bare code. Errors the program maps to
+/// builtin codes log the builtin's name.
+#[inline(always)]
+pub fn log_error_name(_err: &ProgramError) {
+    log_error!(_err.to_str::<StakeError>());
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        program_error::ProgramError as SdkProgramError,
+        stake::instruction::StakeError as Native,
+    };
+
+    fn native_code(err: Native) -> u32 {
+        match SdkProgramError::from(err) {
+            SdkProgramError::Custom(code) => code,
+            other => panic!("native stake error is not custom: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn custom_codes_match_native() {
+        let pairs = [
+            (StakeError::NoCreditsToRedeem, Native::NoCreditsToRedeem),
+            (StakeError::LockupInForce, Native::LockupInForce),
+            (StakeError::AlreadyDeactivated, Native::AlreadyDeactivated),
+            (StakeError::TooSoonToRedelegate, Native::TooSoonToRedelegate),
+            (StakeError::InsufficientStake, Native::InsufficientStake),
+            (StakeError::MergeTransientStake, Native::MergeTransientStake),
+            (StakeError::MergeMismatch, Native::MergeMismatch),
+            (StakeError::CustodianMissing, Native::CustodianMissing),
+            (StakeError::CustodianSignatureMissing, Native::CustodianSignatureMissing),
+            (StakeError::InsufficientReferenceVotes, Native::InsufficientReferenceVotes),
+            (StakeError::VoteAddressMismatch, Native::VoteAddressMismatch),
+            (
+                StakeError::MinimumDelinquentEpochsForDeactivationNotMet,
+                Native::MinimumDelinquentEpochsForDeactivationNotMet,
+            ),
+            (StakeError::InsufficientDelegation, Native::InsufficientDelegation),
+            (
+                StakeError::RedelegateTransientOrInactiveStake,
+                Native::RedelegateTransientOrInactiveStake,
+            ),
+            (StakeError::RedelegateToSameVoteAccount, Native::RedelegateToSameVoteAccount),
+            (
+                StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted,
+                Native::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted,
+            ),
+            (StakeError::EpochRewardsActive, Native::EpochRewardsActive),
+        ];
+
+        for (ours, native) in pairs.iter() {
+            let code = native_code(native.clone());
+            assert_eq!(to_program_error(*ours), ProgramError::Custom(code), "{native:?}");
+            assert_eq!(StakeError::from_code(code), Some(*ours));
+            assert_eq!(ours.message(), native.to_string(), "{native:?}");
+            assert_eq!(ours.name().strip_prefix("StakeError::"), Some(format!("{ours:?}").as_str()));
+        }
+        // nothing decodes past the native range
+        assert_eq!(StakeError::from_code(pairs.len() as u32), None);
+    }
+
+    #[test]
+    fn non_native_errors_use_builtin_codes() {
+        assert_eq!(
+            to_program_error(StakeError::InvalidAuthorization),
+            ProgramError::MissingRequiredSignature
+        );
+        assert_eq!(
+            ProgramError::from(StakeError::InsufficientFunds),
+            ProgramError::InsufficientFunds
+        );
+        assert_eq!(to_program_error(StakeError::TooManySigners), ProgramError::InvalidArgument);
+    }
+
+    #[test]
+    fn program_errors_log_by_name() {
+        assert_eq!(ProgramError::Custom(6).to_str::<StakeError>(), "StakeError::MergeMismatch");
+        assert_eq!(ProgramError::from(StakeError::EpochRewardsActive).to_str::<StakeError>(), "StakeError::EpochRewardsActive");
+        assert_eq!(ProgramError::Custom(17).to_str::<StakeError>(), "Error: Unknown");
+        assert_eq!(ProgramError::InvalidAccountData.to_str::<StakeError>(), "Error: InvalidAccountData");
+        assert_eq!(StakeError::TooManySigners.to_string(), StakeError::TooManySigners.message());
     }
 }
@@ -0,0 +1,344 @@
+//! Canonical stake-account access: state load/store and lamport moves.
+//! Every instruction handler goes through these instead of touching
+//! account data or lamports directly.
+
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::pubkey_eq, ProgramResult};
+
+use crate::{
+    state::{
+        migration::{ActiveMigration, StateMigration},
+        stake_state_v2::StakeStateV2,
+    },
+    ID,
+};
+
+/// How a handler needs a stake account's data sized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakeAccountShape {
+    /// Exactly `StakeStateV2::ACCOUNT_SIZE`, as native requires of accounts an
+    /// instruction turns into a stake account (Initialize, the Split and
+    /// Redelegate destinations) and of both MoveStake accounts
+    Exact,
+    /// At least `StakeStateV2::ACCOUNT_SIZE`: the state is read from the first
+    /// 200 bytes and the rest is ignored, as native's bincode decode does
+    AtLeast,
+}
+
+/// `InvalidAccountData` unless the account's data is sized as `shape` requires.
+///
+/// Every stake account load goes through this (with `AtLeast`, in
+/// `get_stake_state`), so a truncated account is rejected even when its tag
+/// alone would decode as Uninitialized.
+pub fn validate_stake_account_shape(stake_account_info: &AccountInfo, shape: StakeAccountShape) -> ProgramResult {
+    let len = stake_account_info.data_len();
+    let ok = match shape {
+        StakeAccountShape::Exact => len == StakeStateV2::ACCOUNT_SIZE,
+        StakeAccountShape::AtLeast => len >= StakeStateV2::ACCOUNT_SIZE,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+/// `InvalidAccountData` for an executable account: one the program owns can
+/// only be a program, never stake state. Checked on every load, store and
+/// wipe, so program bytes are never decoded or overwritten as a stake account.
+pub fn reject_executable(stake_account_info: &AccountInfo) -> ProgramResult {
+    if stake_account_info.executable() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// load stake state from account
+pub fn get_stake_state(stake_account_info: &AccountInfo) -> Result<StakeStateV2, ProgramError> {
+    if !pubkey_eq(stake_account_info.owner(), &ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    reject_executable(stake_account_info)?;
+    validate_stake_account_shape(stake_account_info, StakeAccountShape::AtLeast)?;
+
+    // `AccountBorrowFailed` if a caller still holds a mutable borrow
+    let data = stake_account_info.try_borrow_data()?;
+    #[cfg(feature = "integrity")]
+    super::checksum::verify_checksum(&data)?;
+    ActiveMigration::load(&data)
+}
+
+// write stake state back into account
+pub fn set_stake_state(
+    stake_account_info: &AccountInfo,
+    stake_state: &StakeStateV2,
+) -> Result<(), ProgramError> {
+    reject_executable(stake_account_info)?;
+    let mut data = stake_account_info.try_borrow_mut_data()?;
+    let data = &mut *data;
+    #[cfg(feature = "trace-state")]
+    let before = super::trace::digest(data);
+    ActiveMigration::store(stake_state, data)?;
+    #[cfg(debug_assertions)]
+    super::invariants::check_written(data, stake_state);
+    #[cfg(feature = "integrity")]
+    super::checksum::write_checksum(data);
+    #[cfg(feature = "trace-state")]
+    super::trace::log_data(stake_account_info.key(), before, super::trace::digest(data));
+    Ok(())
+}
+
+/// Zero the whole account data, which also reads back as Uninitialized. For
+/// accounts a handler empties (a merge source, CloseStakeAccount), so none of
+/// the old Meta/Stake bytes stay readable to anyone scanning raw data.
+pub fn wipe_stake_account(stake_account_info: &AccountInfo) -> ProgramResult {
+    reject_executable(stake_account_info)?;
+    let mut data = stake_account_info.try_borrow_mut_data()?;
+    #[cfg(feature = "trace-state")]
+    let before = super::trace::digest(&data);
+    data.fill(0);
+    #[cfg(feature = "trace-state")]
+    super::trace::log_data(stake_account_info.key(), before, super::trace::digest(&data));
+    Ok(())
+}
+
+/// Fails with `err` if any two of `writable` are the same account.
+///
+/// Handlers that write through more than one account role (source and
+/// destination, stake and split) call this before loading state, so one
+/// account passed in two roles can never be read as one state and written as
+/// another. `err` is whatever native returns for that aliasing. Withdraw is the
+/// exception: native accepts destination == source there.
+pub fn ensure_distinct(writable: &[&AccountInfo], err: ProgramError) -> ProgramResult {
+    for (i, account) in writable.iter().enumerate() {
+        if writable[i + 1..].iter().any(|other| pubkey_eq(other.key(), account.key())) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+// Avoid naming this function "move" to prevent confusion with the MoveLamports instruction
+pub fn relocate_lamports(
+    source_account_info: &AccountInfo,
+    destination_account_info: &AccountInfo,
+    lamports: u64,
+) -> ProgramResult {
+    #[cfg(feature = "trace-state")]
+    let before = (source_account_info.lamports(), destination_account_info.lamports());
+    {
+        let mut source_lamports = source_account_info.try_borrow_mut_lamports()?;
+        *source_lamports = source_lamports
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+    }
+
+    // Read the destination only after the debit so source == destination nets to zero
+    let credited = destination_account_info.lamports().checked_add(lamports);
+    match credited {
+        Some(balance) => *destination_account_info.try_borrow_mut_lamports()? = balance,
+        None => {
+            // undo the debit so a failed move leaves both balances untouched
+            *source_account_info.try_borrow_mut_lamports()? += lamports;
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    }
+
+    #[cfg(feature = "trace-state")]
+    {
+        super::trace::log_lamports(source_account_info.key(), before.0, source_account_info.lamports());
+        super::trace::log_lamports(destination_account_info.key(), before.1, destination_account_info.lamports());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::boxed::Box;
+
+    use super::*;
+    use crate::helpers::test_accounts::{self, info, raw, RawAccount};
+    use crate::state::{accounts::Authorized, state::Meta, LamportsLE};
+    use pinocchio::pubkey::Pubkey;
+
+    fn account(owner: Pubkey, lamports: u64) -> Box<RawAccount<{ StakeStateV2::ACCOUNT_SIZE }>> {
+        Box::new(raw([7u8; 32], owner, lamports, [0u8; StakeStateV2::ACCOUNT_SIZE]))
+    }
+
+    #[test]
+    fn relocate_lamports_moves_balance() {
+        let mut src = account(ID, 1_000);
+        let mut dst = account(ID, 10);
+        relocate_lamports(&info(&mut src), &info(&mut dst), 400).unwrap();
+        assert_eq!(src.lamports, 600);
+        assert_eq!(dst.lamports, 410);
+    }
+
+    #[test]
+    fn relocate_lamports_rejects_overdraw_and_overflow() {
+        let mut src = account(ID, 5);
+        let mut dst = account(ID, 0);
+        assert_eq!(
+            relocate_lamports(&info(&mut src), &info(&mut dst), 6),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!((src.lamports, dst.lamports), (5, 0));
+
+        let mut full = account(ID, u64::MAX);
+        assert_eq!(
+            relocate_lamports(&info(&mut src), &info(&mut full), 1),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        assert_eq!((src.lamports, full.lamports), (5, u64::MAX));
+    }
+
+    #[test]
+    fn relocate_lamports_to_self_is_a_no_op() {
+        let mut raw = account(ID, 100);
+        let ai = info(&mut raw);
+        relocate_lamports(&ai, &ai, 60).unwrap();
+        assert_eq!(raw.lamports, 100);
+    }
+
+    #[test]
+    fn ensure_distinct_rejects_any_aliased_pair() {
+        let (mut a, mut b, mut c) = (account(ID, 0), account(ID, 0), account(ID, 0));
+        b.key = [8u8; 32];
+        c.key = [9u8; 32];
+        let (a, b, c) = (info(&mut a), info(&mut b), info(&mut c));
+        let err = ProgramError::InvalidArgument;
+
+        assert_eq!(ensure_distinct(&[], err), Ok(()));
+        assert_eq!(ensure_distinct(&[&a], err), Ok(()));
+        assert_eq!(ensure_distinct(&[&a, &b, &c], err), Ok(()));
+        assert_eq!(ensure_distinct(&[&a, &a], err), Err(err));
+        // duplicates need not be adjacent; the caller's error is passed through
+        assert_eq!(
+            ensure_distinct(&[&a, &b, &a], ProgramError::InvalidAccountData),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(ensure_distinct(&[&c, &b, &b], err), Err(err));
+    }
+
+    #[test]
+    fn executable_accounts_are_not_stake_accounts() {
+        let mut raw = account(ID, 0);
+        let state = StakeStateV2::Initialized(Meta::default());
+        set_stake_state(&info(&mut raw), &state).unwrap();
+        let image = raw.data;
+
+        raw.executable = 1;
+        let ai = info(&mut raw);
+        assert_eq!(get_stake_state(&ai), Err(ProgramError::InvalidAccountData));
+        assert_eq!(set_stake_state(&ai, &StakeStateV2::Uninitialized), Err(ProgramError::InvalidAccountData));
+        assert_eq!(wipe_stake_account(&ai), Err(ProgramError::InvalidAccountData));
+        assert_eq!(raw.data, image);
+
+        // a program owned by its loader fails the owner check first
+        let mut program = account([9u8; 32], 0);
+        program.executable = 1;
+        assert_eq!(get_stake_state(&info(&mut program)), Err(ProgramError::InvalidAccountOwner));
+    }
+
+    #[test]
+    fn borrowed_accounts_fail_with_account_borrow_failed() {
+        let mut raw = account(ID, 0);
+        let ai = info(&mut raw);
+        set_stake_state(&ai, &StakeStateV2::Initialized(Meta::default())).unwrap();
+
+        let writer = ai.try_borrow_mut_data().unwrap();
+        assert_eq!(get_stake_state(&ai), Err(ProgramError::AccountBorrowFailed));
+        drop(writer);
+
+        let reader = ai.try_borrow_data().unwrap();
+        assert_eq!(set_stake_state(&ai, &StakeStateV2::Uninitialized), Err(ProgramError::AccountBorrowFailed));
+        assert_eq!(wipe_stake_account(&ai), Err(ProgramError::AccountBorrowFailed));
+        // reads may overlap
+        assert!(get_stake_state(&ai).is_ok());
+        drop(reader);
+        set_stake_state(&ai, &StakeStateV2::Uninitialized).unwrap();
+    }
+
+    #[cfg(feature = "integrity")]
+    #[test]
+    fn corrupted_account_fails_to_load() {
+        let mut raw = account(ID, 0);
+        let ai = info(&mut raw);
+        let state = StakeStateV2::Initialized(Meta {
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        });
+        set_stake_state(&ai, &state).unwrap();
+        assert_eq!(get_stake_state(&ai).unwrap(), state);
+
+        // a stray write into the withdrawer, as from a buggy caller
+        raw.data[StakeStateV2::META_OFFSET + 8 + 32] ^= 1;
+        assert_eq!(get_stake_state(&ai), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn wiped_account_keeps_no_stale_bytes() {
+        let mut raw = account(ID, 0);
+        let ai = info(&mut raw);
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(42),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        };
+        let mut stake = crate::state::delegation::Stake::default();
+        stake.delegation.voter_pubkey = [3; 32];
+        stake.delegation.stake = LamportsLE::new(1_000);
+        stake.credits_observed = 9u64.to_le_bytes();
+        let flags = crate::state::StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+        set_stake_state(&ai, &StakeStateV2::Stake(meta, stake, flags)).unwrap();
+
+        wipe_stake_account(&ai).unwrap();
+
+        // every byte, not only the tag: Meta, Stake, flags and padding
+        assert_eq!(raw.data, [0; StakeStateV2::ACCOUNT_SIZE]);
+        assert_eq!(get_stake_state(&ai).unwrap(), StakeStateV2::Uninitialized);
+    }
+
+    #[test]
+    fn stake_state_round_trips_through_account() {
+        let mut raw = account(ID, 0);
+        let ai = info(&mut raw);
+        let state = StakeStateV2::Initialized(Meta {
+            rent_exempt_reserve: LamportsLE::new(42),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        });
+        set_stake_state(&ai, &state).unwrap();
+        assert_eq!(get_stake_state(&ai).unwrap(), state);
+
+        let mut foreign = account([9u8; 32], 0);
+        assert_eq!(
+            get_stake_state(&info(&mut foreign)),
+            Err(ProgramError::InvalidAccountOwner)
+        );
+    }
+
+    #[test]
+    fn truncated_and_oversized_accounts() {
+        let mut raw = account(ID, 0);
+        let state = StakeStateV2::Initialized(Meta::default());
+        set_stake_state(&info(&mut raw), &state).unwrap();
+
+        // one byte short: rejected even though the tag alone reads as Uninitialized
+        raw.data_len = StakeStateV2::ACCOUNT_SIZE as u64 - 1;
+        raw.data[..4].fill(0);
+        let ai = info(&mut raw);
+        assert_eq!(get_stake_state(&ai), Err(ProgramError::InvalidAccountData));
+        assert_eq!(validate_stake_account_shape(&ai, StakeAccountShape::Exact), Err(ProgramError::InvalidAccountData));
+        assert_eq!(set_stake_state(&ai, &state), Err(ProgramError::AccountDataTooSmall));
+
+        // one byte long: readable and writable, but not where the exact size is required
+        let mut long = Box::new(test_accounts::raw([7u8; 32], ID, 0, [0u8; StakeStateV2::ACCOUNT_SIZE + 1]));
+        let ai = info(&mut long);
+        set_stake_state(&ai, &state).unwrap();
+        assert_eq!(get_stake_state(&ai), Ok(state));
+        assert_eq!(validate_stake_account_shape(&ai, StakeAccountShape::AtLeast), Ok(()));
+        assert_eq!(validate_stake_account_shape(&ai, StakeAccountShape::Exact), Err(ProgramError::InvalidAccountData));
+    }
+}
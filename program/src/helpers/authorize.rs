@@ -1,47 +1,236 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, sysvars::clock::Clock};
 
+use crate::error::{to_program_error, StakeError};
+use crate::helpers::SignerSet;
 use crate::state::{StakeAuthorize};
 use crate::state::state::Meta;
 
+/// `lenient-auth` extension: the withdrawer may also act as the staker once the
+/// lockup has expired (custodial wallets consolidating both roles under one key)
+pub const LENIENT_AUTH: bool = cfg!(feature = "lenient-auth");
+
+/// Whether `signers` may perform a staker-only operation such as Deactivate.
+/// Natively that is the staker alone; `lenient` adds the withdrawer after lockup expiry.
+/// A custodian signature does not count as expiry here.
+pub fn staker_operation_allowed(meta: &Meta, signers: &(impl SignerSet + ?Sized), clock: &Clock, lenient: bool) -> bool {
+    if signers.contains(&meta.authorized.staker) {
+        return true;
+    }
+    lenient && signers.contains(&meta.authorized.withdrawer) && !meta.lockup.is_in_force(clock, None)
+}
+
+/// Staker-only operations go through here so the `lenient-auth` policy applies uniformly
+pub fn check_staker_operation(meta: &Meta, signers: &(impl SignerSet + ?Sized), clock: &Clock) -> Result<(), ProgramError> {
+    if staker_operation_allowed(meta, signers, clock, LENIENT_AUTH) {
+        Ok(())
+    } else {
+        Err(to_program_error(StakeError::InvalidAuthorization))
+    }
+}
+
+/// The custodian an instruction presents for its lockup check: the signer in
+/// its optional custodian slot (Authorize variants, Withdraw). As in native,
+/// only that position counts; the lockup's custodian signing anywhere else is
+/// ignored.
+pub fn presented_custodian(accounts: &[AccountInfo], slot: usize) -> Option<&Pubkey> {
+    accounts.get(slot).filter(|ai| ai.is_signer()).map(|ai| ai.key())
+}
+
+/// Native's seeded Authorize variants and Withdraw reject an optional
+/// custodian that did not sign
+pub fn require_signed_custodian_slot(accounts: &[AccountInfo], slot: usize) -> Result<(), ProgramError> {
+    match accounts.get(slot) {
+        Some(ai) if !ai.is_signer() => Err(ProgramError::MissingRequiredSignature),
+        _ => Ok(()),
+    }
+}
+
 pub fn authorize_update(
     meta: &mut Meta,
     new_authorized: Pubkey,
     which: StakeAuthorize,
-    signers: &[Pubkey],                     // all tx signer pubkeys
-    maybe_lockup_authority: Option<&AccountInfo>,
+    signers: &(impl SignerSet + ?Sized),    // all tx signer pubkeys
+    custodian: Option<&Pubkey>,             // see `presented_custodian`
     clock: &Clock,
 ) -> Result<(), ProgramError> {
-    let signed = |k: &Pubkey| signers.iter().any(|s| s == k);
+    let signed = |k: &Pubkey| signers.contains(k);
 
     match which {
         StakeAuthorize::Staker => {
-            // Either staker OR withdrawer may change the staker
+            // Either staker OR withdrawer may change the staker (natively, regardless of
+            // lockup), so `lenient-auth` has nothing to add here
             if !(signed(&meta.authorized.staker) || signed(&meta.authorized.withdrawer)) {
                 return Err(ProgramError::MissingRequiredSignature);
             }
             meta.authorized.staker = new_authorized;
         }
         StakeAuthorize::Withdrawer => {
+            // Lockup enforcement, as native: while in force a custodian must be
+            // presented, and it must be the lockup's custodian. Native checks
+            // this before the signer, so a locked account reports the lockup
+            // error even when the withdrawer did not sign
+            if meta.lockup.is_in_force(clock, None) {
+                let Some(custodian) = custodian else {
+                    return Err(to_program_error(StakeError::CustodianMissing));
+                };
+                if meta.lockup.is_in_force(clock, Some(custodian)) {
+                    return Err(to_program_error(StakeError::LockupInForce));
+                }
+            }
+
             // Only withdrawer may change the withdrawer
             if !signed(&meta.authorized.withdrawer) {
                 return Err(ProgramError::MissingRequiredSignature);
             }
 
-            // Lockup enforcement: require custodian signer if lockup still in force
-            let epoch_in_force = meta.lockup.epoch> clock.epoch;
-            let ts_in_force    = meta.lockup.unix_timestamp > clock.unix_timestamp;
-            if epoch_in_force || ts_in_force {
-                let custodian_ok = maybe_lockup_authority
-                    .map(|a| a.is_signer() && a.key() == &meta.lockup.custodian)
-                    .unwrap_or(false);
-                if !custodian_ok {
-                    return Err(ProgramError::MissingRequiredSignature);
-                }
-            }
-
             meta.authorized.withdrawer = new_authorized;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{self, info, RawAccount};
+    use crate::state::{accounts::Authorized, state::Lockup};
+
+    const STAKER: Pubkey = [1; 32];
+    const WITHDRAWER: Pubkey = [2; 32];
+    const CUSTODIAN: Pubkey = [3; 32];
+
+    fn meta(lockup_epoch: u64, lockup_ts: i64) -> Meta {
+        Meta {
+            authorized: Authorized { staker: STAKER, withdrawer: WITHDRAWER },
+            lockup: Lockup { unix_timestamp: lockup_ts, epoch: lockup_epoch, custodian: CUSTODIAN },
+            ..Meta::default()
+        }
+    }
+
+    fn clock(epoch: u64, unix_timestamp: i64) -> Clock {
+        Clock { slot: 0, epoch_start_timestamp: 0, epoch, leader_schedule_epoch: epoch, unix_timestamp }
+    }
+
+    /// A header-only account, read-only
+    fn raw(key: Pubkey, signed: bool) -> RawAccount<0> {
+        RawAccount { is_signer: signed as u8, is_writable: 0, ..test_accounts::raw(key, [0; 32], 0, []) }
+    }
+
+    #[test]
+    fn default_policy_is_staker_only() {
+        let now = clock(10, 1_000);
+        for meta in [meta(0, 0), meta(20, 0), meta(0, 2_000)] {
+            assert!(staker_operation_allowed(&meta, &[STAKER], &now, false));
+            assert!(!staker_operation_allowed(&meta, &[WITHDRAWER], &now, false));
+            assert!(!staker_operation_allowed(&meta, &[WITHDRAWER, CUSTODIAN], &now, false));
+            assert!(!staker_operation_allowed(&meta, &[], &now, false));
+        }
+        if !LENIENT_AUTH {
+            assert_eq!(
+                check_staker_operation(&meta(0, 0), &[WITHDRAWER], &now),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn lenient_policy_admits_withdrawer_after_lockup() {
+        let now = clock(10, 1_000);
+        // expired (or never set) lockups
+        for meta in [meta(0, 0), meta(10, 1_000), meta(5, 500)] {
+            assert!(staker_operation_allowed(&meta, &[WITHDRAWER], &now, true));
+            assert!(staker_operation_allowed(&meta, &[STAKER], &now, true));
+        }
+        // still in force by epoch or by timestamp; the custodian does not unlock it
+        for meta in [meta(11, 0), meta(0, 1_001)] {
+            assert!(!staker_operation_allowed(&meta, &[WITHDRAWER], &now, true));
+            assert!(!staker_operation_allowed(&meta, &[WITHDRAWER, CUSTODIAN], &now, true));
+            assert!(staker_operation_allowed(&meta, &[STAKER], &now, true));
+        }
+        assert!(!staker_operation_allowed(&meta(0, 0), &[CUSTODIAN], &now, true));
+    }
+
+    #[test]
+    fn withdrawer_change_under_lockup_needs_the_custodian() {
+        let now = clock(10, 1_000);
+        let new = [9; 32];
+        let update = |signers: &[Pubkey], custodian: Option<&Pubkey>| {
+            let mut m = meta(20, 0);
+            authorize_update(&mut m, new, StakeAuthorize::Withdrawer, signers, custodian, &now).map(|()| m)
+        };
+
+        // the lockup is checked before the withdrawer's signature, as native
+        assert_eq!(update(&[STAKER], None), Err(to_program_error(StakeError::CustodianMissing)));
+        assert_eq!(update(&[STAKER], Some(&STAKER)), Err(to_program_error(StakeError::LockupInForce)));
+        assert_eq!(update(&[STAKER], Some(&CUSTODIAN)), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(update(&[WITHDRAWER], None), Err(to_program_error(StakeError::CustodianMissing)));
+        assert_eq!(update(&[WITHDRAWER], Some(&STAKER)), Err(to_program_error(StakeError::LockupInForce)));
+        assert_eq!(update(&[WITHDRAWER], Some(&CUSTODIAN)).unwrap().authorized.withdrawer, new);
+
+        // an expired lockup needs no custodian
+        let mut m = meta(10, 1_000);
+        authorize_update(&mut m, new, StakeAuthorize::Withdrawer, &[WITHDRAWER], None, &now).unwrap();
+        assert_eq!(m.authorized.withdrawer, new);
+
+        // the staker role is not subject to the lockup, and the withdrawer may change it
+        let mut m = meta(20, 0);
+        authorize_update(&mut m, new, StakeAuthorize::Staker, &[WITHDRAWER], None, &now).unwrap();
+        assert_eq!(m.authorized.staker, new);
+    }
+
+    #[test]
+    fn custodian_slot_differs_between_variants() {
+        let m = meta(20, 0);
+        let stranger = [8; 32];
+        let (mut stake, mut clock_ai) = (raw([7; 32], false), raw([6; 32], false));
+        let (mut old, mut other) = (raw(WITHDRAWER, true), raw(stranger, true));
+        let [stake, clock_ai, old] = [&mut stake, &mut clock_ai, &mut old].map(info);
+
+        // Authorize [stake, clock, old, custodian?]: a signer in slot 3 is presented
+        let authorize = [stake, clock_ai, old, info(&mut other)];
+        assert_eq!(presented_custodian(&authorize, 3), Some(&stranger));
+
+        // AuthorizeChecked [stake, clock, old, new, custodian?]: slot 3 is the new
+        // authority, which never stands in for a missing custodian
+        let checked = [stake, clock_ai, old, info(&mut other)];
+        assert_eq!(presented_custodian(&checked, 4), None);
+
+        // an unsigned slot is not presented, and the real custodian only counts in it
+        let mut unsigned = raw(stranger, false);
+        assert_eq!(presented_custodian(&[stake, clock_ai, old, info(&mut unsigned)], 3), None);
+        let mut custodian = raw(CUSTODIAN, true);
+        let custodian = info(&mut custodian);
+        assert_eq!(presented_custodian(&[stake, custodian, clock_ai, old], 4), None);
+        assert_eq!(presented_custodian(&[stake, clock_ai, old, info(&mut other), custodian], 4), Some(&CUSTODIAN));
+        let after_extra = [stake, clock_ai, old, info(&mut other), info(&mut other), custodian];
+        assert_eq!(presented_custodian(&after_extra, 4), Some(&stranger));
+        let signers = [WITHDRAWER, stranger, CUSTODIAN];
+        assert_eq!(
+            authorize_update(&mut m.clone(), [9; 32], StakeAuthorize::Withdrawer, &signers, presented_custodian(&after_extra, 4), &clock(10, 1_000)),
+            Err(to_program_error(StakeError::LockupInForce))
+        );
+    }
+
+    #[test]
+    fn zero_key_signer_does_not_bypass_a_custodian_less_lockup() {
+        let now = clock(10, 1_000);
+        let zero = Pubkey::default();
+        let mut m = meta(20, 0);
+        m.lockup.custodian = zero;
+        let (mut stake, mut clock_ai, mut old) = (raw([7; 32], false), raw([6; 32], false), raw(WITHDRAWER, true));
+        let mut forged = raw(zero, true);
+        let [stake, clock_ai, old, forged] = [&mut stake, &mut clock_ai, &mut old, &mut forged].map(info);
+
+        // presented from the custodian slot, but not the lockup's custodian
+        let accounts = [stake, clock_ai, old, forged];
+        assert_eq!(presented_custodian(&accounts, 3), Some(&zero));
+
+        let custodian = presented_custodian(&accounts, 3).copied();
+        assert_eq!(
+            authorize_update(&mut m, [9; 32], StakeAuthorize::Withdrawer, &[WITHDRAWER, zero], custodian.as_ref(), &now),
+            Err(to_program_error(StakeError::LockupInForce))
+        );
+        assert_eq!(m.authorized.withdrawer, WITHDRAWER);
+    }
+}
@@ -0,0 +1,141 @@
+//! Integrity seal for stake account data (applied by `get_stake_state` /
+//! `set_stake_state` when the `integrity` feature is enabled).
+//!
+//! Only the 3 bytes after `StakeFlags` are free in the 200-byte layout, so the
+//! seal is the CRC32 of bytes `[0, CHECKSUM_OFFSET)` truncated to 24 bits.
+//! It covers the tag, Meta, Stake and flags. Uninitialized and RewardsPool
+//! accounts carry no seal, and an all-zero seal means "never sealed" (e.g.
+//! written before the feature was enabled) and is accepted.
+
+use pinocchio::program_error::ProgramError;
+
+use crate::state::stake_state_v2::StakeStateV2;
+
+pub const CHECKSUM_OFFSET: usize = StakeStateV2::FLAGS_OFFSET + 1;
+pub const CHECKSUM_LEN: usize = StakeStateV2::ACCOUNT_SIZE - CHECKSUM_OFFSET;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32/ISO-HDLC (the zlib/PNG polynomial)
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn is_sealed_variant(data: &[u8]) -> bool {
+    data.len() >= StakeStateV2::ACCOUNT_SIZE && matches!(data[..StakeStateV2::TAG_LEN], [1 | 2, 0, 0, 0])
+}
+
+/// Seal for `data`, never all-zero so it can't be mistaken for an unsealed account
+pub fn compute_checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let seal = (crc32(&data[..CHECKSUM_OFFSET]) & 0x00FF_FFFF).max(1);
+    let bytes = seal.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+/// Refresh the seal of freshly serialized state
+pub fn write_checksum(data: &mut [u8]) {
+    if is_sealed_variant(data) {
+        let seal = compute_checksum(data);
+        data[CHECKSUM_OFFSET..StakeStateV2::ACCOUNT_SIZE].copy_from_slice(&seal);
+    }
+}
+
+/// Reject account data whose seal no longer matches its contents
+pub fn verify_checksum(data: &[u8]) -> Result<(), ProgramError> {
+    if !is_sealed_variant(data) {
+        return Ok(());
+    }
+    let stored = &data[CHECKSUM_OFFSET..StakeStateV2::ACCOUNT_SIZE];
+    if stored == [0; CHECKSUM_LEN] || stored == compute_checksum(data) {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{accounts::Authorized, state::Meta, Delegation, EpochLE, LamportsLE, Stake, StakeFlags};
+
+    fn sealed(state: &StakeStateV2) -> [u8; StakeStateV2::ACCOUNT_SIZE] {
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        state.serialize(&mut data).unwrap();
+        write_checksum(&mut data);
+        data
+    }
+
+    fn stake_state() -> StakeStateV2 {
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&[3; 32], 5_000_000_000, EpochLE::new(7)),
+            credits_observed: 42u64.to_le_bytes(),
+        };
+        StakeStateV2::Stake(meta, stake, StakeFlags::empty())
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn seal_fits_after_stake_flags() {
+        assert_eq!(CHECKSUM_OFFSET, 197);
+        assert_eq!(CHECKSUM_LEN, 3);
+        let data = sealed(&stake_state());
+        assert_ne!(data[CHECKSUM_OFFSET..], [0; CHECKSUM_LEN]);
+        assert_eq!(StakeStateV2::deserialize(&data).unwrap(), stake_state());
+    }
+
+    #[test]
+    fn any_flipped_byte_is_detected() {
+        let initialized = StakeStateV2::Initialized(Meta::default());
+        for state in [stake_state(), initialized] {
+            let data = sealed(&state);
+            assert_eq!(verify_checksum(&data), Ok(()));
+            // (a corrupted tag is left to deserialize, which rejects unknown variants)
+            for i in StakeStateV2::TAG_LEN..StakeStateV2::ACCOUNT_SIZE {
+                let mut corrupt = data;
+                corrupt[i] ^= 0x40;
+                assert_eq!(verify_checksum(&corrupt), Err(ProgramError::InvalidAccountData), "byte {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn unsealed_and_unsealable_states_pass() {
+        // written before the feature was enabled
+        let mut legacy = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        stake_state().serialize(&mut legacy).unwrap();
+        assert_eq!(verify_checksum(&legacy), Ok(()));
+
+        // variants without Meta never carry a seal
+        for state in [StakeStateV2::Uninitialized, StakeStateV2::RewardsPool] {
+            let data = sealed(&state);
+            assert_eq!(data[CHECKSUM_OFFSET..], [0; CHECKSUM_LEN]);
+            assert_eq!(verify_checksum(&data), Ok(()));
+        }
+    }
+}
@@ -1,11 +1,22 @@
+use super::soft_float::Binary64;
+use crate::state::EpochLE;
+
+// Distinct signers a handler collects. A legacy transaction fits about a dozen
+// signatures and a CPI adds at most 16 PDA signers, so a real instruction stays
+// below this; past it the instruction fails with `InvalidArgument`.
 pub const MAXIMUM_SIGNERS: usize = 32;
-pub const DEFAULT_WARMUP_COOLDOWN_RATE: f64 = 0.25;
+// Rates are the exact f64 values native uses (see `soft_float`)
+pub const DEFAULT_WARMUP_COOLDOWN_RATE: Binary64 = Binary64::from_bits(0x3FD0_0000_0000_0000); // 0.25
 pub const FEATURE_STAKE_RAISE_MINIMUM_DELEGATION_TO_1_SOL: bool = false;
+// Not a native rule: native splits Initialized stake without any minimum, so
+// it can mint dust accounts that can never be delegated. `split-minimum-delegation`
+// requires the minimum delegation above rent on both sides of such a split.
+pub const FEATURE_SPLIT_INITIALIZED_REQUIRES_MINIMUM_DELEGATION: bool =
+    cfg!(feature = "split-minimum-delegation");
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
-pub const NEW_WARMUP_COOLDOWN_RATE: f64 = 0.09;
+pub const NEW_WARMUP_COOLDOWN_RATE: Binary64 = Binary64::from_bits(0x3FB7_0A3D_70A3_D70A); // 0.09
 
-// The warmup/cooldown changed from 25% to 9%. For historical effective stake
-// calculations, a fixed rate is sufficient here since tests operate after full
-// activation/cooldown has elapsed.
-pub const PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH: Option<[u8; 8]> = Some([0; 8]);
+// The warmup/cooldown changed from 25% to 9%; every instruction applies the
+// 9% rate from epoch 0 (see `warmup_rate`).
+pub const PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH: Option<EpochLE> = Some(EpochLE::ZERO);
 pub const MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION: u64 = 5;
@@ -0,0 +1,111 @@
+//! Stake account invariants, asserted in debug builds only (this module is
+//! compiled with `debug_assertions`, so release builds pay no CUs for it).
+//!
+//! - `set_stake_state` re-reads every state it writes: the bytes must decode
+//!   back to the same state, and a delegation must not deactivate before it
+//!   activated.
+//! - The balance invariants (`rent_exempt_reserve <= lamports`, and
+//!   `stake <= lamports - rent_exempt_reserve` while the stake is not
+//!   deactivating) depend on lamport moves that split, MoveStake and merge make
+//!   after writing the state, so the dispatcher checks them over every writable
+//!   stake account once the instruction has succeeded.
+
+use pinocchio::{account_info::AccountInfo, pubkey::pubkey_eq};
+
+use crate::{
+    helpers::get_stake_state,
+    state::{
+        migration::{ActiveMigration, StateMigration},
+        stake_state_v2::StakeStateV2,
+        EpochLE,
+    },
+    ID,
+};
+
+/// Re-read `data` right after `expected` was stored into it
+pub fn check_written(data: &[u8], expected: &StakeStateV2) {
+    let read_back = ActiveMigration::load(data);
+    assert_eq!(read_back.as_ref(), Ok(expected), "stake state does not read back as written");
+    if let StakeStateV2::Stake(_, stake, _) = expected {
+        let delegation = &stake.delegation;
+        // bootstrap stake activates at u64::MAX
+        assert!(
+            delegation.activation_epoch == EpochLE::MAX || delegation.deactivation_epoch >= delegation.activation_epoch,
+            "deactivation epoch {} before activation epoch {}",
+            delegation.deactivation_epoch,
+            delegation.activation_epoch,
+        );
+    }
+}
+
+/// Balance invariants of one account's state against its lamports
+pub fn check_balance(state: &StakeStateV2, lamports: u64) {
+    let (meta, stake) = match state {
+        StakeStateV2::Initialized(meta) => (meta, None),
+        StakeStateV2::Stake(meta, stake, _) => (meta, Some(stake)),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return,
+    };
+    let reserve = meta.rent_exempt_reserve.get();
+    assert!(reserve <= lamports, "rent exempt reserve {reserve} above balance {lamports}");
+    if let Some(stake) = stake.filter(|stake| stake.delegation.deactivation_epoch == EpochLE::MAX) {
+        let delegated = stake.delegation.stake.get();
+        assert!(
+            delegated <= lamports - reserve,
+            "stake {delegated} above balance {lamports} less reserve {reserve}",
+        );
+    }
+}
+
+/// [`check_balance`] over every writable account the program owns
+pub fn check_accounts(accounts: &[AccountInfo]) {
+    for ai in accounts.iter().filter(|ai| ai.is_writable() && pubkey_eq(ai.owner(), &ID)) {
+        if let Ok(state) = get_stake_state(ai) {
+            check_balance(&state, ai.lamports());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        delegation::{Delegation, Stake},
+        stake_flag::StakeFlags,
+        state::Meta,
+        LamportsLE,
+    };
+
+    fn stake_state(stake: u64, activation: u64, deactivation: u64) -> StakeStateV2 {
+        let meta = Meta { rent_exempt_reserve: LamportsLE::new(100), ..Meta::default() };
+        let mut delegation = Delegation::new(&[1; 32], stake, EpochLE::new(activation));
+        delegation.deactivation_epoch = EpochLE::new(deactivation);
+        StakeStateV2::Stake(meta, Stake { delegation, credits_observed: [0; 8] }, StakeFlags::empty())
+    }
+
+    #[test]
+    fn balances() {
+        check_balance(&stake_state(900, 1, u64::MAX), 1_000);
+        // deactivating and deactivated stake may have been partly withdrawn
+        check_balance(&stake_state(5_000, 1, 3), 1_000);
+        check_balance(&StakeStateV2::Uninitialized, 0);
+        assert!(std::panic::catch_unwind(|| check_balance(&stake_state(901, 1, u64::MAX), 1_000)).is_err());
+        assert!(std::panic::catch_unwind(|| check_balance(&stake_state(0, 1, 3), 99)).is_err());
+    }
+
+    #[test]
+    fn written_states() {
+        let state = stake_state(900, 4, 4);
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        ActiveMigration::store(&state, &mut data).unwrap();
+        check_written(&data, &state);
+
+        let bootstrap = stake_state(900, u64::MAX, 3);
+        ActiveMigration::store(&bootstrap, &mut data).unwrap();
+        check_written(&data, &bootstrap);
+
+        let backwards = stake_state(900, 5, 4);
+        ActiveMigration::store(&backwards, &mut data).unwrap();
+        assert!(std::panic::catch_unwind(|| check_written(&data, &backwards)).is_err());
+        assert!(std::panic::catch_unwind(|| check_written(&data, &state)).is_err());
+    }
+}
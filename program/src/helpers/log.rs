@@ -0,0 +1,29 @@
+//! Compile-time leveled logging.
+//!
+//! Every `msg!` is a `sol_log_` syscall costing at least 100 CU, so traces are
+//! compiled out unless a level feature is enabled:
+//!
+//! - no feature (default, release SBF builds): nothing is logged
+//! - `log-error`: why an instruction was rejected
+//! - `log-debug` (implies `log-error`): also instruction names and handler traces
+//!
+//! Both macros expand to a unit block, so they can be used as statements or
+//! as match-arm expressions.
+
+/// Log why an instruction is being rejected
+macro_rules! log_error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log-error")]
+        pinocchio::msg!($($arg)*);
+    }};
+}
+
+/// Log handler progress (instruction names, state transitions)
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log-debug")]
+        pinocchio::msg!($($arg)*);
+    }};
+}
+
+pub(crate) use {log_debug, log_error};
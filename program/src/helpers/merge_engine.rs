@@ -0,0 +1,465 @@
+//! Merging stake accounts, shared by the instructions that combine them.
+//!
+//! [`MergeKind`] classifies each account and combines two kinds; this module
+//! loads the accounts, runs those checks in native's order and moves the
+//! state and lamports. Merge and MergeMany go through [`merge_into`];
+//! MoveStake and MoveLamports through [`move_stake_or_lamports_shared_checks`]
+//! and [`merge_delegation_stake_and_credits_observed`].
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::pubkey_eq,
+    sysvars::clock::Clock,
+    ProgramResult,
+};
+
+use crate::{
+    helpers::{
+        bytes_to_u64, checked_add, ensure_distinct, get_stake_state, relocate_lamports,
+        set_stake_state, wipe_stake_account, SignerSet,
+    },
+    state::{delegation::Stake, EpochLE, LamportsLE, MergeKind, WindowedStakeHistory},
+    ID,
+};
+use crate::helpers::log::{log_debug, log_error};
+use crate::helpers::{sysvar_cache, warmup_rate};
+
+pub fn stake_weighted_credits_observed(
+    stake: &Stake,
+    absorbed_lamports: u64,
+    absorbed_credits_observed: u64,
+) -> Option<u64> {
+    if bytes_to_u64(stake.credits_observed) == absorbed_credits_observed {
+        Some(bytes_to_u64(stake.credits_observed))
+    } else {
+        let total_stake =
+            u128::from(stake.delegation.stake.get().checked_add(absorbed_lamports)?);
+        let stake_weighted_credits = u128::from(bytes_to_u64(stake.credits_observed))
+            .checked_mul(u128::from(stake.delegation.stake.get()))?;
+        let absorbed_weighted_credits =
+            u128::from(absorbed_credits_observed).checked_mul(u128::from(absorbed_lamports))?;
+        // ceiling: +denominator-1 before division
+        let total_weighted_credits = stake_weighted_credits
+            .checked_add(absorbed_weighted_credits)?
+            .checked_add(total_stake)?
+            .checked_sub(1)?;
+        u64::try_from(total_weighted_credits.checked_div(total_stake)?).ok()
+    }
+}
+
+pub fn merge_delegation_stake_and_credits_observed(
+    stake: &mut Stake,
+    lamports_to_merge: u64,
+    source_credits_observed: u64,
+) -> Result<(), ProgramError> {
+    // weight credits by the pre-merge stake, then grow the delegation (same order as native)
+    stake.credits_observed =
+        stake_weighted_credits_observed(stake, lamports_to_merge, source_credits_observed)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .to_le_bytes();
+    stake.delegation.stake =
+        LamportsLE::new(checked_add(stake.delegation.stake.get(), lamports_to_merge)?);
+    Ok(())
+}
+
+/// Load `stake_ai`'s state once and classify it. Uninitialized and
+/// RewardsPool are `InvalidAccountData`, transient stake `MergeTransientStake`.
+fn classify(
+    stake_ai: &AccountInfo,
+    clock: &Clock,
+    stake_history: &WindowedStakeHistory,
+    new_rate_activation_epoch: Option<EpochLE>,
+) -> Result<MergeKind, ProgramError> {
+    let state = get_stake_state(stake_ai)?;
+    let kind = MergeKind::get_if_mergeable(&state, stake_ai.lamports(), clock, stake_history, new_rate_activation_epoch)?;
+    match &kind {
+        MergeKind::FullyActive(_, _) => log_debug!("merge: FullyActive"),
+        MergeKind::Inactive(_, _, _) => log_debug!("merge: Inactive"),
+        MergeKind::ActivationEpoch(_, _, _) => log_debug!("merge: ActivationEpoch"),
+    }
+    Ok(kind)
+}
+
+/// Distinct, program-owned, writable destination and source
+pub fn check_merge_accounts(dst_ai: &AccountInfo, src_ai: &AccountInfo) -> ProgramResult {
+    ensure_distinct(&[dst_ai, src_ai], ProgramError::InvalidArgument)?;
+    if !pubkey_eq(dst_ai.owner(), &ID) || !pubkey_eq(src_ai.owner(), &ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    if !dst_ai.is_writable() || !src_ai.is_writable() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Classify both accounts and merge `src_ai` into `dst_ai`. Every check runs
+/// before either account is written, so an error leaves both untouched.
+///
+/// With a `rent_destination`, the source's rent-exempt reserve is refunded
+/// there instead of landing in `dst_ai`. That is refused (`InvalidArgument`)
+/// when the destination is in its activation epoch, whose merge counts the
+/// source's whole balance, reserve included, as stake.
+pub fn merge_into(
+    dst_ai: &AccountInfo,
+    src_ai: &AccountInfo,
+    rent_destination: Option<&AccountInfo>,
+    clock: &Clock,
+    stake_history: &WindowedStakeHistory,
+    new_rate_activation_epoch: Option<EpochLE>,
+    signers: &(impl SignerSet + ?Sized),
+) -> ProgramResult {
+    let dst_kind = classify(dst_ai, clock, stake_history, new_rate_activation_epoch)?;
+    // the destination's staker authorizes the merge
+    if !signers.contains(&dst_kind.meta().authorized.staker) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let src_kind = classify(src_ai, clock, stake_history, new_rate_activation_epoch)?;
+    MergeKind::metas_can_merge(dst_kind.meta(), src_kind.meta(), clock)?;
+
+    let refund = match rent_destination {
+        Some(rent_ai) => {
+            ensure_distinct(&[src_ai, rent_ai], ProgramError::InvalidArgument)?;
+            if matches!(dst_kind, MergeKind::ActivationEpoch(..)) {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let reserve = src_kind.meta().rent_exempt_reserve.get().min(src_ai.lamports());
+            Some((rent_ai, reserve))
+        }
+        None => None,
+    };
+
+    if let Some(merged_state) = dst_kind.merge(src_kind, clock)? {
+        set_stake_state(dst_ai, &merged_state)?;
+    }
+
+    // Deinitialize and drain source; the whole buffer is zeroed, not just the tag
+    wipe_stake_account(src_ai)?;
+    if let Some((rent_ai, reserve)) = refund {
+        relocate_lamports(src_ai, rent_ai, reserve)?;
+    }
+    relocate_lamports(src_ai, dst_ai, src_ai.lamports())?;
+
+    Ok(())
+}
+
+/// Checks MoveStake and MoveLamports share, in native's order. Each stake
+/// account is loaded and classified exactly once; callers work from the
+/// returned kinds and never re-read the state.
+///
+/// `accounts` is the whole instruction account list, searched for the clock sysvar
+pub fn move_stake_or_lamports_shared_checks(
+    accounts: &[AccountInfo],
+    source_stake_account_info: &AccountInfo,
+    lamports: u64,
+    destination_stake_account_info: &AccountInfo,
+    stake_authority_info: &AccountInfo,
+    require_meta_compat: bool,
+) -> Result<(MergeKind, MergeKind), ProgramError> {
+    // Authority must sign
+    if !stake_authority_info.is_signer() {
+        log_error!("shared_checks: missing signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Confirm not the same account
+    if let Err(err) = ensure_distinct(
+        &[source_stake_account_info, destination_stake_account_info],
+        ProgramError::InvalidInstructionData,
+    ) {
+        log_error!("shared_checks: same account");
+        return Err(err);
+    }
+
+    // Source and destination must be writable
+    if !source_stake_account_info.is_writable() || !destination_stake_account_info.is_writable() {
+        log_error!("shared_checks: not writable");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // Must move something
+    if lamports == 0 {
+        log_error!("shared_checks: zero lamports");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let clock = sysvar_cache::clock(accounts)?;
+    let stake_history = WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+
+    let source_merge_kind = classify(source_stake_account_info, &clock, &stake_history, new_rate_activation_epoch)
+        .inspect_err(|_| log_error!("shared_checks: source not mergeable"))?;
+
+    // Unlike other handlers, native checks the staker against this account
+    // alone rather than against every signer in the instruction
+    if !pubkey_eq(&source_merge_kind.meta().authorized.staker, stake_authority_info.key()) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let destination_merge_kind =
+        classify(destination_stake_account_info, &clock, &stake_history, new_rate_activation_epoch)
+            .inspect_err(|_| log_error!("shared_checks: destination not mergeable"))?;
+
+    // Ensure metadata is compatible (authorities and lockups) when required
+    if require_meta_compat {
+        if let Err(e) = MergeKind::metas_can_merge(
+            source_merge_kind.meta(),
+            destination_merge_kind.meta(),
+            &clock,
+        ) {
+            log_error!("shared_checks: metas cannot merge");
+            return Err(e);
+        }
+    }
+
+    Ok((source_merge_kind, destination_merge_kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{to_program_error, StakeError},
+        helpers::test_accounts::{self, info, RawAccount},
+        state::{delegation::Delegation, Authorized, Meta, StakeFlags, StakeStateV2},
+    };
+
+    fn stake_with(delegated: u64, credits: u64) -> Stake {
+        Stake {
+            delegation: Delegation::new(&[1; 32], delegated, EpochLE::new(0)),
+            credits_observed: credits.to_le_bytes(),
+        }
+    }
+
+    /// Native's formula, written out independently: ceil(weighted_sum / total)
+    fn reference(stake: u64, credits: u64, absorbed: u64, absorbed_credits: u64) -> Option<u64> {
+        if credits == absorbed_credits {
+            return Some(credits);
+        }
+        let total = u128::from(stake) + u128::from(absorbed);
+        if total > u128::from(u64::MAX) {
+            return None;
+        }
+        let weighted = u128::from(credits) * u128::from(stake)
+            + u128::from(absorbed_credits) * u128::from(absorbed);
+        u64::try_from(weighted.div_ceil(total)).ok()
+    }
+
+    fn check(stake: u64, credits: u64, absorbed: u64, absorbed_credits: u64) {
+        assert_eq!(
+            stake_weighted_credits_observed(&stake_with(stake, credits), absorbed, absorbed_credits),
+            reference(stake, credits, absorbed, absorbed_credits),
+            "stake={stake} credits={credits} absorbed={absorbed} absorbed_credits={absorbed_credits}"
+        );
+    }
+
+    #[test]
+    fn equal_credits_are_kept_without_weighting() {
+        assert_eq!(stake_weighted_credits_observed(&stake_with(u64::MAX, 7), u64::MAX, 7), Some(7));
+        assert_eq!(stake_weighted_credits_observed(&stake_with(0, 7), 0, 7), Some(7));
+    }
+
+    #[test]
+    fn credits_differing_by_one_round_up() {
+        // any non-zero share of the higher credits pushes the result up
+        assert_eq!(stake_weighted_credits_observed(&stake_with(1_000, 100), 1, 101), Some(101));
+        assert_eq!(stake_weighted_credits_observed(&stake_with(1, 101), 1_000, 100), Some(101));
+        check(u64::MAX - 1, 0, 1, 1);
+        check(1, u64::MAX - 1, u64::MAX - 1, u64::MAX);
+    }
+
+    #[test]
+    fn exact_division_does_not_round() {
+        assert_eq!(stake_weighted_credits_observed(&stake_with(3, 10), 1, 30), Some(15));
+    }
+
+    #[test]
+    fn large_products_stay_in_u128() {
+        let half = u64::MAX / 2;
+        check(half, u64::MAX, half + 1, u64::MAX - 1);
+        check(half, u64::MAX - 1, half, u64::MAX);
+        check(u64::MAX - 1_000, u64::MAX, 1_000, 0);
+        check(1 << 63, 1 << 40, (1 << 63) - 1, (1 << 40) + 1);
+    }
+
+    #[test]
+    fn total_stake_overflow_is_none() {
+        assert_eq!(stake_weighted_credits_observed(&stake_with(u64::MAX, 1), 1, 2), None);
+    }
+
+    #[test]
+    fn merge_weights_by_pre_merge_stake() {
+        let mut stake = stake_with(1_000, 100);
+        merge_delegation_stake_and_credits_observed(&mut stake, 3_000, 200).unwrap();
+        // ceil((1_000 * 100 + 3_000 * 200) / 4_000) = 175
+        assert_eq!(bytes_to_u64(stake.credits_observed), 175);
+        assert_eq!(stake.delegation.stake.get(), 4_000);
+
+        let mut full = stake_with(u64::MAX, 1);
+        assert_eq!(
+            merge_delegation_stake_and_credits_observed(&mut full, 1, 2),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        // equal credits skip the weighting, so native's stake add reports it
+        assert_eq!(
+            merge_delegation_stake_and_credits_observed(&mut full, 1, 1),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(full.delegation.stake.get(), u64::MAX);
+    }
+
+    fn raw<const N: usize>(key: u8, owner: pinocchio::pubkey::Pubkey, data: [u8; N]) -> RawAccount<N> {
+        test_accounts::raw([key; 32], owner, 10_000_000, data)
+    }
+
+    fn stake_account(key: u8, state: &crate::state::StakeStateV2) -> RawAccount<200> {
+        use crate::state::migration::{ActiveMigration, StateMigration};
+        let mut data = [0u8; 200];
+        ActiveMigration::store(state, &mut data).unwrap();
+        #[cfg(feature = "integrity")]
+        crate::helpers::checksum::write_checksum(&mut data);
+        raw(key, crate::ID, data)
+    }
+
+    fn initialized(staker: u8) -> crate::state::StakeStateV2 {
+        crate::state::StakeStateV2::Initialized(crate::state::Meta {
+            authorized: crate::state::Authorized { staker: [staker; 32], withdrawer: [staker; 32] },
+            ..crate::state::Meta::default()
+        })
+    }
+
+    fn shared_checks(source: &crate::state::StakeStateV2, destination: &crate::state::StakeStateV2, authority: u8) -> Result<(), ProgramError> {
+        let mut src = stake_account(1, source);
+        let mut dst = stake_account(2, destination);
+        let mut auth = raw(authority, [0; 32], [0u8; 0]);
+        auth.is_signer = 1;
+        let mut clock = raw(0, [0; 32], [0u8; 40]);
+        clock.key = pinocchio::sysvars::clock::CLOCK_ID;
+        let accounts = [info(&mut src), info(&mut dst), info(&mut auth), info(&mut clock)];
+        move_stake_or_lamports_shared_checks(&accounts, &accounts[0], 1, &accounts[1], &accounts[2], true).map(|_| ())
+    }
+
+    #[test]
+    fn shared_checks_classify_in_native_order() {
+        use crate::state::StakeStateV2::Uninitialized;
+        assert_eq!(shared_checks(&initialized(7), &initialized(7), 7), Ok(()));
+        // the source's staker is checked before the destination is looked at
+        assert_eq!(shared_checks(&initialized(7), &Uninitialized, 8), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(shared_checks(&initialized(7), &Uninitialized, 7), Err(ProgramError::InvalidAccountData));
+        assert_eq!(shared_checks(&Uninitialized, &initialized(7), 7), Err(ProgramError::InvalidAccountData));
+    }
+
+    const RESERVE: u64 = 2_282_880;
+
+    fn funded(key: u8, state: &StakeStateV2, lamports: u64) -> RawAccount<200> {
+        RawAccount { lamports, ..stake_account(key, state) }
+    }
+
+    fn meta() -> Meta {
+        Meta {
+            rent_exempt_reserve: LamportsLE::new(RESERVE),
+            authorized: Authorized { staker: [7; 32], withdrawer: [7; 32] },
+            ..Meta::default()
+        }
+    }
+
+    fn delegated(voter: u8, stake: u64, credits: u64, activation: u64, deactivation: u64) -> StakeStateV2 {
+        let delegation = Delegation {
+            deactivation_epoch: EpochLE::new(deactivation),
+            ..Delegation::new(&[voter; 32], stake, EpochLE::new(activation))
+        };
+        StakeStateV2::Stake(meta(), Stake { delegation, credits_observed: credits.to_le_bytes() }, StakeFlags::empty())
+    }
+
+    fn clock(epoch: u64) -> Clock {
+        Clock { slot: 0, epoch_start_timestamp: 0, epoch, leader_schedule_epoch: epoch, unix_timestamp: 0 }
+    }
+
+    /// Merge `source` into `destination` refunding to a fresh account; returns
+    /// the destination's and the rent destination's balances
+    fn merge_with_refund(destination: &StakeStateV2, source: &StakeStateV2) -> Result<(u64, u64), ProgramError> {
+        let mut dst = funded(1, destination, RESERVE + 5_000_000);
+        let mut src = funded(2, source, RESERVE + 3_000_000);
+        let mut treasury = RawAccount { lamports: 1_000, ..raw(3, [0; 32], [0u8; 0]) };
+        let (dst_ai, src_ai, treasury_ai) = (info(&mut dst), info(&mut src), info(&mut treasury));
+        let clock = clock(0);
+        let stake_history = WindowedStakeHistory::new(clock.epoch);
+        merge_into(&dst_ai, &src_ai, Some(&treasury_ai), &clock, &stake_history, None, &[[7; 32]])?;
+        assert_eq!(src_ai.lamports(), 0);
+        assert_eq!(get_stake_state(&src_ai), Ok(StakeStateV2::Uninitialized));
+        Ok((dst_ai.lamports(), treasury_ai.lamports()))
+    }
+
+    #[test]
+    fn rent_destination_takes_the_source_reserve() {
+        let initialized = StakeStateV2::Initialized(meta());
+        assert_eq!(
+            merge_with_refund(&initialized, &initialized),
+            Ok((RESERVE + 8_000_000, 1_000 + RESERVE))
+        );
+        // an activating destination counts the source's reserve as stake
+        let activating = delegated(9, 5_000_000, 0, 0, u64::MAX);
+        assert_eq!(merge_with_refund(&activating, &initialized), Err(ProgramError::InvalidArgument));
+    }
+
+    /// Every destination/source pair over the merge kinds at epoch 10: the
+    /// result, and on success the destination's balance, stake and credits.
+    /// Recorded from the handler before the engine was extracted from it.
+    #[test]
+    fn merge_outcomes_by_kind() {
+        let states = |stake: u64, credits: u64| {
+            [
+                StakeStateV2::Initialized(meta()),
+                // activation epoch, fully active, deactivated, deactivating
+                delegated(9, stake, credits, 10, u64::MAX),
+                delegated(9, stake, credits, 0, u64::MAX),
+                delegated(9, stake, credits, 0, 5),
+                delegated(9, stake, credits, 0, 10),
+                // fully active to another voter
+                delegated(8, stake, credits, 0, u64::MAX),
+            ]
+        };
+        let clock = clock(10);
+        let stake_history = WindowedStakeHistory::new(clock.epoch);
+
+        let total = 2 * RESERVE + 8_000_000;
+        let inactive = Ok((total, 0, 0));
+        let transient = Err(to_program_error(StakeError::MergeTransientStake));
+        let mismatch = Err(to_program_error(StakeError::MergeMismatch));
+        #[rustfmt::skip]
+        let expected = [
+            // source: Initialized, activating, active, deactivated, deactivating, other voter
+            [inactive, inactive, mismatch, inactive, transient, mismatch],                              // Initialized
+            // an activating destination takes the source's whole balance as stake
+            [Ok((total, total - RESERVE, 100)), Ok((total, total - RESERVE, 152)), mismatch,
+                Ok((total, total - RESERVE, 100)), transient, mismatch],                                // activating
+            [mismatch, mismatch, Ok((total, 8_000_000, 138)), mismatch, transient, mismatch],          // active
+            // a deactivated destination keeps its old delegation
+            [Ok((total, 5_000_000, 100)), Ok((total, 5_000_000, 100)), mismatch,
+                Ok((total, 5_000_000, 100)), transient, mismatch],                                      // deactivated
+            [transient; 6],                                                                             // deactivating
+            [mismatch, mismatch, mismatch, mismatch, transient, Ok((total, 8_000_000, 138))],          // other voter
+        ];
+
+        for (destination, expected) in states(5_000_000, 100).iter().zip(expected) {
+            for (source, expected) in states(3_000_000, 200).iter().zip(expected) {
+                let mut dst = funded(1, destination, RESERVE + 5_000_000);
+                let mut src = funded(2, source, RESERVE + 3_000_000);
+                let (dst_ai, src_ai) = (info(&mut dst), info(&mut src));
+                let result = merge_into(&dst_ai, &src_ai, None, &clock, &stake_history, None, &[[7; 32]]);
+                let outcome = result.map(|()| match get_stake_state(&dst_ai).unwrap() {
+                    StakeStateV2::Stake(_, stake, _) => {
+                        (dst_ai.lamports(), stake.delegation.stake.get(), bytes_to_u64(stake.credits_observed))
+                    }
+                    _ => (dst_ai.lamports(), 0, 0),
+                });
+                assert_eq!(outcome, expected, "{destination:?} <- {source:?}");
+                if outcome.is_err() {
+                    // nothing is written on failure
+                    assert_eq!((dst_ai.lamports(), src_ai.lamports()), (RESERVE + 5_000_000, RESERVE + 3_000_000));
+                    assert_eq!(get_stake_state(&src_ai).as_ref(), Ok(source));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Per-instruction counters for off-chain scraping (`metrics`).
+//!
+//! The dispatcher snapshots the writable accounts before the handler runs and
+//! logs one `key=value` line when it returns, e.g.
+//!
+//! `metrics ix=Split outcome=ok lamports_moved=500000000 stake_delta=0 state=Stake`
+//!
+//! - `ix`: the instruction name
+//! - `outcome`: `ok`, or the error as logged by `log-error`
+//!   (`StakeError::MergeMismatch`, `Error: InvalidAccountData`)
+//! - `lamports_moved`: lamports credited across the writable accounts
+//! - `stake_delta`: change in delegated stake over the program's accounts
+//! - `state`: the first account's state afterwards, `none` if it has none
+//!
+//! The runtime rolls back a failed instruction, so it logs zero counters and
+//! the first account's state from before the handler ran. Only the first
+//! [`MAX_TRACKED`] accounts are counted, which covers every native
+//! instruction's writable accounts.
+
+use pinocchio::{account_info::AccountInfo, program_error::ToStr, pubkey::pubkey_eq, ProgramResult};
+
+use crate::{error::StakeError, helpers::get_stake_state, state::stake_state_v2::StakeStateV2, ID};
+
+/// Accounts (by position) the counters are taken over
+pub const MAX_TRACKED: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Balance {
+    lamports: u64,
+    stake: u64,
+}
+
+/// Writable account balances, and the first account's state, before the
+/// handler ran
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    balances: [Option<Balance>; MAX_TRACKED],
+    state: &'static str,
+}
+
+/// What an instruction changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub lamports_moved: u64,
+    pub stake_delta: i64,
+}
+
+fn delegated_stake(ai: &AccountInfo) -> u64 {
+    if !pubkey_eq(ai.owner(), &ID) {
+        return 0;
+    }
+    match get_stake_state(ai) {
+        Ok(StakeStateV2::Stake(_, stake, _)) => stake.delegation.stake.get(),
+        _ => 0,
+    }
+}
+
+/// The writable accounts among the first [`MAX_TRACKED`], each once even if
+/// passed in several positions
+fn tracked(accounts: &[AccountInfo]) -> impl Iterator<Item = (usize, &AccountInfo)> {
+    let tracked = &accounts[..accounts.len().min(MAX_TRACKED)];
+    tracked.iter().enumerate().filter(move |&(i, ai)| {
+        ai.is_writable() && !tracked[..i].iter().any(|earlier| pubkey_eq(earlier.key(), ai.key()))
+    })
+}
+
+impl Snapshot {
+    pub fn take(accounts: &[AccountInfo]) -> Self {
+        let mut snapshot = Self { state: state_name(accounts.first()), ..Self::default() };
+        for (i, ai) in tracked(accounts) {
+            snapshot.balances[i] = Some(Balance { lamports: ai.lamports(), stake: delegated_stake(ai) });
+        }
+        snapshot
+    }
+
+    /// Counters from this snapshot to the accounts' current balances
+    pub fn counters(&self, accounts: &[AccountInfo]) -> Counters {
+        let mut counters = Counters::default();
+        let mut stake_delta = 0i128;
+        for (i, ai) in tracked(accounts) {
+            let Some(before) = self.balances[i] else { continue };
+            counters.lamports_moved = counters.lamports_moved.saturating_add(ai.lamports().saturating_sub(before.lamports));
+            stake_delta += delegated_stake(ai) as i128 - before.stake as i128;
+        }
+        counters.stake_delta = stake_delta.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        counters
+    }
+}
+
+/// The state kind of `ai`, `none` for accounts without a stake state
+pub fn state_name(ai: Option<&AccountInfo>) -> &'static str {
+    match ai.map(get_stake_state) {
+        Some(Ok(StakeStateV2::Uninitialized)) => "Uninitialized",
+        Some(Ok(StakeStateV2::Initialized(_))) => "Initialized",
+        Some(Ok(StakeStateV2::Stake(..))) => "Stake",
+        Some(Ok(StakeStateV2::RewardsPool)) => "RewardsPool",
+        Some(Err(_)) | None => "none",
+    }
+}
+
+/// Log the metrics line for instruction `name` that ended with `result`
+pub fn log(name: &str, accounts: &[AccountInfo], snapshot: &Snapshot, result: &ProgramResult) {
+    let (outcome, counters, state) = match result {
+        Ok(()) => ("ok", snapshot.counters(accounts), state_name(accounts.first())),
+        Err(err) => (err.to_str::<StakeError>(), Counters::default(), snapshot.state),
+    };
+    pinocchio_log::log!(
+        "metrics ix={} outcome={} lamports_moved={} stake_delta={} state={}",
+        name,
+        outcome,
+        counters.lamports_moved,
+        counters.stake_delta,
+        state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::{
+            set_stake_state,
+            test_accounts::{info, raw, RawAccount},
+        },
+        state::{delegation::Stake, stake_flag::StakeFlags, state::Meta, LamportsLE},
+    };
+
+    fn account(key: u8, lamports: u64, writable: bool) -> RawAccount<{ StakeStateV2::ACCOUNT_SIZE }> {
+        RawAccount { is_writable: writable as u8, ..raw([key; 32], ID, lamports, [0; StakeStateV2::ACCOUNT_SIZE]) }
+    }
+
+    fn staked(lamports: u64) -> StakeStateV2 {
+        let mut stake = Stake::default();
+        stake.delegation.stake = LamportsLE::new(lamports);
+        StakeStateV2::Stake(Meta::default(), stake, StakeFlags::empty())
+    }
+
+    #[test]
+    fn counts_credited_lamports_and_stake_change() {
+        let (mut source, mut destination, mut sysvar) = (account(1, 1_000, true), account(2, 10, true), account(3, 5, false));
+        let accounts = [info(&mut source), info(&mut destination), info(&mut sysvar)];
+        set_stake_state(&accounts[0], &staked(900)).unwrap();
+        set_stake_state(&accounts[1], &StakeStateV2::Uninitialized).unwrap();
+        let snapshot = Snapshot::take(&accounts);
+        assert_eq!(snapshot.state, "Stake");
+
+        // a split of 400: lamports and stake move from the source to the destination
+        source.lamports -= 400;
+        destination.lamports += 400;
+        // read-only accounts are not counted
+        sysvar.lamports += 7;
+        let accounts = [info(&mut source), info(&mut destination), info(&mut sysvar)];
+        set_stake_state(&accounts[0], &staked(500)).unwrap();
+        set_stake_state(&accounts[1], &staked(400)).unwrap();
+        assert_eq!(snapshot.counters(&accounts), Counters { lamports_moved: 400, stake_delta: 0 });
+
+        // a withdrawal after deactivation: stake gone, lamports leave
+        let snapshot = Snapshot::take(&accounts);
+        set_stake_state(&accounts[0], &StakeStateV2::Initialized(Meta::default())).unwrap();
+        assert_eq!(snapshot.counters(&accounts), Counters { lamports_moved: 0, stake_delta: -500 });
+        assert_eq!(state_name(accounts.first()), "Initialized");
+        assert_eq!(state_name(None), "none");
+    }
+
+    #[test]
+    fn an_account_passed_twice_counts_once() {
+        let (mut stake, mut alias) = (account(1, 100, true), account(1, 100, true));
+        let accounts = [info(&mut stake), info(&mut alias)];
+        let snapshot = Snapshot::take(&accounts);
+        stake.lamports += 50;
+        alias.lamports += 50;
+        let accounts = [info(&mut stake), info(&mut alias)];
+        assert_eq!(snapshot.counters(&accounts).lamports_moved, 50);
+    }
+}
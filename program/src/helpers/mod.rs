@@ -1,10 +1,31 @@
+pub mod account_io;
+pub mod checksum;
 pub mod constant;
-pub mod merge;
+#[cfg(debug_assertions)]
+pub mod invariants;
+pub mod log;
+pub mod merge_engine;
+#[cfg(any(test, feature = "metrics"))]
+pub mod metrics;
+pub mod seed;
+pub mod soft_float;
+pub mod sysvar_cache;
+pub mod sysvars;
+#[cfg(test)]
+pub(crate) mod test_accounts;
+#[cfg(feature = "withdraw-to-token")]
+pub mod token;
+#[cfg(any(test, feature = "trace-state"))]
+pub mod trace;
 pub mod utils;
+pub mod warmup_rate;
 pub mod authorize;
 
+pub use account_io::*;
 pub use constant::*;
-pub use merge::*;
+pub use merge_engine::*;
+pub use seed::*;
+pub use soft_float::*;
 pub use utils::*;
 pub use authorize::*;
 
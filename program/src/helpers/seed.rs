@@ -0,0 +1,134 @@
+//! Seed-derived authorities for AuthorizeWithSeed / AuthorizeCheckedWithSeed.
+//!
+//! The base account signs, and the instruction acts as
+//! `create_with_seed(base, seed, owner)`, which must be the authority stored
+//! in the stake account's meta.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN, PDA_MARKER},
+};
+
+use crate::state::{state::Meta, StakeAuthorize};
+
+/// `sha256(base || seed || owner)`, equivalent to `Pubkey::create_with_seed`
+pub fn create_with_seed(base: &Pubkey, seed: &[u8], owner: &Pubkey) -> Result<Pubkey, ProgramError> {
+    if seed.len() > MAX_SEED_LEN {
+        return Err(ProgramError::MaxSeedLengthExceeded);
+    }
+    if owner.ends_with(PDA_MARKER) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        pinocchio::pubkey::create_with_seed(base, seed, owner)
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        Ok(sha2_const_stable::Sha256::new()
+            .update(base)
+            .update(seed)
+            .update(owner)
+            .finalize())
+    }
+}
+
+/// The authority a seed instruction acts as. The base must sign, otherwise
+/// nothing is authorized (native adds no signer in that case).
+pub fn derive_seed_authority(
+    base: &AccountInfo,
+    seed: &[u8],
+    owner: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    if !base.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    create_with_seed(base.key(), seed, owner)
+}
+
+/// Check that `derived` may change the `role` authority of `meta`
+/// (either authority may change the staker; only the withdrawer the withdrawer).
+pub fn verify_seed_authority(
+    meta: &Meta,
+    role: StakeAuthorize,
+    derived: &Pubkey,
+) -> Result<(), ProgramError> {
+    let authorized = match role {
+        StakeAuthorize::Staker => {
+            *derived == meta.authorized.staker || *derived == meta.authorized.withdrawer
+        }
+        StakeAuthorize::Withdrawer => *derived == meta.authorized.withdrawer,
+    };
+    if authorized {
+        Ok(())
+    } else {
+        Err(ProgramError::MissingRequiredSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::accounts::Authorized;
+    use solana_sdk::pubkey::Pubkey as SdkPubkey;
+
+    fn sdk_derive(base: &Pubkey, seed: &str, owner: &Pubkey) -> Pubkey {
+        SdkPubkey::create_with_seed(&SdkPubkey::from(*base), seed, &SdkPubkey::from(*owner))
+            .unwrap()
+            .to_bytes()
+    }
+
+    #[test]
+    fn derivation_matches_sdk() {
+        let system = [0u8; 32];
+        let stake = crate::ID;
+        for (i, seed) in ["", "a", "seed-for-staker", "ünïcødé", &"x".repeat(MAX_SEED_LEN)]
+            .into_iter()
+            .enumerate()
+        {
+            let base = [i as u8 + 1; 32];
+            for owner in [system, stake, [0xAB; 32]] {
+                assert_eq!(
+                    create_with_seed(&base, seed.as_bytes(), &owner).unwrap(),
+                    sdk_derive(&base, seed, &owner),
+                    "seed {seed:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_long_seeds_and_pda_owners() {
+        let base = [1u8; 32];
+        assert_eq!(
+            create_with_seed(&base, &[b'x'; MAX_SEED_LEN + 1], &[0; 32]),
+            Err(ProgramError::MaxSeedLengthExceeded)
+        );
+
+        let mut owner = [7u8; 32];
+        owner[32 - PDA_MARKER.len()..].copy_from_slice(PDA_MARKER);
+        assert_eq!(create_with_seed(&base, b"seed", &owner), Err(ProgramError::IllegalOwner));
+    }
+
+    #[test]
+    fn derived_key_must_hold_the_role() {
+        let meta = Meta {
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        };
+        assert!(verify_seed_authority(&meta, StakeAuthorize::Staker, &[1; 32]).is_ok());
+        assert!(verify_seed_authority(&meta, StakeAuthorize::Staker, &[2; 32]).is_ok());
+        assert!(verify_seed_authority(&meta, StakeAuthorize::Withdrawer, &[2; 32]).is_ok());
+        assert_eq!(
+            verify_seed_authority(&meta, StakeAuthorize::Withdrawer, &[1; 32]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            verify_seed_authority(&meta, StakeAuthorize::Staker, &[3; 32]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Integer-only binary64 arithmetic for the warmup/cooldown math.
+//!
+//! Native computes stake activation with `f64`: `u64 as f64`, one division,
+//! two multiplications and a truncating `as u64`. A plain fixed-point ratio
+//! rounds differently in edge cases, so to stay bit-for-bit with native this
+//! performs exactly those IEEE-754 operations on a normalized 53-bit mantissa,
+//! with u128 intermediates and round-half-to-even. Only non-negative finite
+//! values occur (callers never divide by zero).
+
+use core::ops::{Div, Mul};
+
+/// Non-negative finite binary64 value, `mantissa * 2^exponent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binary64 {
+    /// 0 for zero, otherwise in [2^52, 2^53)
+    mantissa: u64,
+    exponent: i32,
+}
+
+const MANTISSA_BITS: u32 = 53;
+const HIDDEN_BIT: u64 = 1 << (MANTISSA_BITS - 1);
+const EXPONENT_BIAS: i32 = 1023 + (MANTISSA_BITS as i32 - 1);
+/// Extra quotient bits so a division always rounds with guard bits to spare
+const DIV_SHIFT: u32 = 128 - MANTISSA_BITS;
+
+impl Binary64 {
+    pub const ZERO: Self = Self { mantissa: 0, exponent: 0 };
+
+    /// From an IEEE-754 bit pattern (non-negative normal values or zero)
+    pub const fn from_bits(bits: u64) -> Self {
+        let biased = ((bits >> 52) & 0x7FF) as i32;
+        if biased == 0 {
+            return Self::ZERO;
+        }
+        Self {
+            mantissa: (bits & (HIDDEN_BIT - 1)) | HIDDEN_BIT,
+            exponent: biased - EXPONENT_BIAS,
+        }
+    }
+
+    pub const fn to_bits(self) -> u64 {
+        if self.mantissa == 0 {
+            return 0;
+        }
+        (((self.exponent + EXPONENT_BIAS) as u64) << 52) | (self.mantissa & (HIDDEN_BIT - 1))
+    }
+
+    /// `n as f64`
+    pub fn from_u64(n: u64) -> Self {
+        Self::round(n as u128, 0, false)
+    }
+
+    /// `self as u64`: truncates toward zero, saturates at `u64::MAX`
+    pub fn to_u64(self) -> u64 {
+        if self.mantissa == 0 || self.exponent <= -64 {
+            0
+        } else if self.exponent < 0 {
+            self.mantissa >> -self.exponent
+        } else if self.exponent <= (u64::BITS - MANTISSA_BITS) as i32 {
+            self.mantissa << self.exponent
+        } else {
+            u64::MAX
+        }
+    }
+
+    /// Round `n * 2^exponent` (plus a non-zero tail below `n` if `sticky`) to 53 bits
+    fn round(n: u128, exponent: i32, sticky: bool) -> Self {
+        if n == 0 {
+            return Self::ZERO;
+        }
+        let bits = u128::BITS - n.leading_zeros();
+        if bits <= MANTISSA_BITS {
+            // exact; a sticky tail only comes from division, whose quotient is always wide
+            let shift = MANTISSA_BITS - bits;
+            return Self {
+                mantissa: (n << shift) as u64,
+                exponent: exponent - shift as i32,
+            };
+        }
+
+        let shift = bits - MANTISSA_BITS;
+        let mut mantissa = (n >> shift) as u64;
+        let rest = n & ((1u128 << shift) - 1);
+        let half = 1u128 << (shift - 1);
+        if rest > half || (rest == half && (sticky || mantissa & 1 == 1)) {
+            mantissa += 1;
+        }
+        let mut exponent = exponent + shift as i32;
+        if mantissa == 1 << MANTISSA_BITS {
+            mantissa >>= 1;
+            exponent += 1;
+        }
+        Self { mantissa, exponent }
+    }
+}
+
+impl Mul for Binary64 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::round(
+            self.mantissa as u128 * rhs.mantissa as u128,
+            self.exponent + rhs.exponent,
+            false,
+        )
+    }
+}
+
+impl Div for Binary64 {
+    type Output = Self;
+
+    /// `rhs` must be non-zero
+    // `is_multiple_of` is newer than the platform-tools toolchain the program builds with
+    #[allow(clippy::manual_is_multiple_of)]
+    fn div(self, rhs: Self) -> Self {
+        debug_assert!(rhs.mantissa != 0, "division by zero");
+        let numerator = (self.mantissa as u128) << DIV_SHIFT;
+        let denominator = rhs.mantissa as u128;
+        Self::round(
+            numerator / denominator,
+            self.exponent - rhs.exponent - DIV_SHIFT as i32,
+            numerator % denominator != 0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn f(x: Binary64) -> f64 {
+        f64::from_bits(x.to_bits())
+    }
+
+    /// Mix of small, huge and power-of-two-adjacent values
+    fn sample(rng: &mut StdRng) -> u64 {
+        match rng.gen_range(0..4) {
+            0 => rng.gen_range(0..1_000),
+            1 => rng.gen(),
+            2 => (1u64 << rng.gen_range(0..64)).wrapping_add(rng.gen_range(0..3)).wrapping_sub(1),
+            _ => rng.gen::<u64>() >> rng.gen_range(0..64),
+        }
+    }
+
+    #[test]
+    fn bit_patterns_round_trip() {
+        for x in [0.0f64, 0.09, 0.25, 1.0, 3.5, 1e-19, 1.8e19] {
+            assert_eq!(Binary64::from_bits(x.to_bits()).to_bits(), x.to_bits(), "{x}");
+        }
+        assert_eq!(f(crate::helpers::DEFAULT_WARMUP_COOLDOWN_RATE), 0.25);
+        assert_eq!(f(crate::helpers::NEW_WARMUP_COOLDOWN_RATE), 0.09);
+    }
+
+    #[test]
+    fn matches_hardware_f64() {
+        let mut rng = StdRng::seed_from_u64(0x5EED);
+        let rates = [0.25f64, 0.09];
+        for _ in 0..200_000 {
+            let (a, b, c) = (sample(&mut rng), sample(&mut rng).max(1), sample(&mut rng));
+            let rate = rates[rng.gen_range(0..2)];
+
+            let fa = Binary64::from_u64(a);
+            assert_eq!(f(fa), a as f64, "{a} as f64");
+            assert_eq!(fa.to_u64(), (a as f64) as u64, "{a} round trip");
+
+            // the exact shape of the activation/cooldown step
+            let weight = fa / Binary64::from_u64(b);
+            assert_eq!(f(weight), a as f64 / b as f64, "{a} / {b}");
+            let cluster = Binary64::from_u64(c) * Binary64::from_bits(rate.to_bits());
+            assert_eq!(f(cluster), c as f64 * rate, "{c} * {rate}");
+            let expected = (a as f64 / b as f64 * (c as f64 * rate)) as u64;
+            assert_eq!((weight * cluster).to_u64(), expected, "{a} / {b} * {c} * {rate}");
+        }
+    }
+
+    #[test]
+    fn truncation_saturates() {
+        let big = Binary64::from_u64(u64::MAX) * Binary64::from_u64(4);
+        assert_eq!(big.to_u64(), u64::MAX);
+        assert_eq!((Binary64::from_u64(1) / Binary64::from_u64(3)).to_u64(), 0);
+        assert_eq!(Binary64::ZERO.to_u64(), 0);
+    }
+}
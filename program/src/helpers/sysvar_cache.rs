@@ -0,0 +1,93 @@
+//! Clock, Rent and EpochSchedule for the handlers.
+//!
+//! Native reads a sysvar from the account list only where its instruction
+//! layout includes that sysvar (e.g. the clock in Delegate, Withdraw or the
+//! Authorize variants). Everywhere else it uses the runtime's sysvar cache.
+//! Both modes are mirrored here:
+//!
+//! - [`clock_account`] / [`rent_account`]: the sysvar account must be passed
+//!   (located by pubkey); `InvalidArgument` otherwise, as in native
+//! - [`clock`] / [`rent`]: the passed account if present, otherwise the
+//!   syscall. Reading the account skips the syscall's ~100 CU.
+//!
+//! The account is matched by key, so it is always the runtime's own sysvar.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{pubkey_eq, Pubkey},
+    sysvars::{
+        clock::{Clock, CLOCK_ID},
+        rent::{Rent, RENT_ID},
+        Sysvar,
+    },
+};
+
+use crate::helpers::{
+    find_sysvar,
+    sysvars::{epoch_schedule::EPOCH_SCHEDULE_ID, EpochSchedule},
+};
+
+fn supplied<'a>(accounts: &'a [AccountInfo], id: &Pubkey) -> Option<&'a AccountInfo> {
+    accounts.iter().find(|ai| pubkey_eq(ai.key(), id))
+}
+
+/// Clock from the required clock sysvar account
+pub fn clock_account(accounts: &[AccountInfo]) -> Result<Clock, ProgramError> {
+    Ok(*Clock::from_account_info(find_sysvar(accounts, &CLOCK_ID)?)?)
+}
+
+/// Clock from the clock sysvar account if passed, else the syscall
+pub fn clock(accounts: &[AccountInfo]) -> Result<Clock, ProgramError> {
+    match supplied(accounts, &CLOCK_ID) {
+        Some(ai) => Ok(*Clock::from_account_info(ai)?),
+        None => Clock::get(),
+    }
+}
+
+/// Rent from the required rent sysvar account
+pub fn rent_account(accounts: &[AccountInfo]) -> Result<Rent, ProgramError> {
+    Ok(*Rent::from_account_info(find_sysvar(accounts, &RENT_ID)?)?)
+}
+
+/// Rent from the rent sysvar account if passed, else the syscall
+pub fn rent(accounts: &[AccountInfo]) -> Result<Rent, ProgramError> {
+    match supplied(accounts, &RENT_ID) {
+        Some(ai) => Ok(*Rent::from_account_info(ai)?),
+        None => Rent::get(),
+    }
+}
+
+/// EpochSchedule from the sysvar account if passed, else the syscall
+pub fn epoch_schedule(accounts: &[AccountInfo]) -> Result<EpochSchedule, ProgramError> {
+    match supplied(accounts, &EPOCH_SCHEDULE_ID) {
+        Some(ai) => EpochSchedule::from_bytes(&ai.try_borrow_data()?),
+        None => EpochSchedule::get(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{info, raw, RawAccount};
+
+    /// Clock sysvar account at epoch 7: slot, epoch start timestamp, epoch,
+    /// leader schedule epoch, unix timestamp
+    fn clock_sysvar() -> RawAccount<40> {
+        let mut data = [0; 40];
+        data[16..24].copy_from_slice(&7u64.to_le_bytes());
+        RawAccount { is_writable: 0, ..raw(CLOCK_ID, [0; 32], 0, data) }
+    }
+
+    #[test]
+    fn reads_the_passed_sysvar_account() {
+        let mut raw = clock_sysvar();
+        let accounts = [info(&mut raw)];
+
+        assert_eq!(clock(&accounts).unwrap().epoch, 7);
+        assert_eq!(clock_account(&accounts).unwrap().epoch, 7);
+        // required accounts are matched by key, never by position
+        assert_eq!(clock_account(&[]).unwrap_err(), ProgramError::InvalidArgument);
+        assert_eq!(rent_account(&accounts).unwrap_err(), ProgramError::InvalidArgument);
+    }
+}
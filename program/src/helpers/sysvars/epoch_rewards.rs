@@ -0,0 +1,111 @@
+//! EpochRewards sysvar: the partitioned rewards distribution, as native's
+//! `solana_epoch_rewards::EpochRewards`.
+//!
+//! While `active`, native rejects every stake instruction but the read-only
+//! queries with `EpochRewardsActive`.
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::helpers::get_sysvar;
+
+pub const EPOCH_REWARDS_ID: Pubkey = pinocchio_pubkey::pubkey!("SysvarEpochRewards1111111111111111111111111");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochRewards {
+    pub distribution_starting_block_height: u64,
+    pub num_partitions: u64,
+    pub parent_blockhash: [u8; 32],
+    pub total_points: u128,
+    pub total_rewards: u64,
+    pub distributed_rewards: u64,
+    pub active: bool,
+}
+
+impl EpochRewards {
+    /// Serialized size: two u64, the blockhash, a u128, two u64 and the bool
+    pub const LEN: usize = 81;
+
+    /// Offset of `active`, the last field
+    const ACTIVE_OFFSET: usize = Self::LEN - 1;
+
+    /// Read the sysvar through the syscall
+    pub fn get() -> Result<Self, ProgramError> {
+        let mut data = [0u8; Self::LEN];
+        get_sysvar(&mut data, &EPOCH_REWARDS_ID, 0, Self::LEN as u64)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Whether rewards are being distributed, reading only `active`. As
+    /// native's sysvar cache lookup, a sysvar that cannot be read counts as
+    /// inactive (host builds have none).
+    pub fn is_active() -> bool {
+        let mut active = [0u8; 1];
+        get_sysvar(&mut active, &EPOCH_REWARDS_ID, Self::ACTIVE_OFFSET as u64, 1).is_ok() && active[0] == 1
+    }
+
+    /// Decode the sysvar's bincode layout
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        let data = data.get(..Self::LEN).ok_or(ProgramError::InvalidAccountData)?;
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let active = match data[Self::ACTIVE_OFFSET] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Self {
+            distribution_starting_block_height: u64_at(0),
+            num_partitions: u64_at(8),
+            parent_blockhash: data[16..48].try_into().unwrap(),
+            total_points: u128::from_le_bytes(data[48..64].try_into().unwrap()),
+            total_rewards: u64_at(64),
+            distributed_rewards: u64_at(72),
+            active,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{epoch_rewards::EpochRewards as SdkEpochRewards, hash::Hash};
+
+    #[test]
+    fn decodes_the_sdk_layout() {
+        for active in [false, true] {
+            let sdk = SdkEpochRewards {
+                distribution_starting_block_height: 0x0102_0304_0506_0708,
+                num_partitions: 7,
+                parent_blockhash: Hash::new_from_array([9; 32]),
+                total_points: u128::MAX - 5,
+                total_rewards: 1_000_000,
+                distributed_rewards: 400_000,
+                active,
+            };
+            let data = bincode::serialize(&sdk).unwrap();
+            assert_eq!(data.len(), EpochRewards::LEN);
+            let decoded = EpochRewards::from_bytes(&data).unwrap();
+            assert_eq!(
+                decoded,
+                EpochRewards {
+                    distribution_starting_block_height: 0x0102_0304_0506_0708,
+                    num_partitions: 7,
+                    parent_blockhash: [9; 32],
+                    total_points: u128::MAX - 5,
+                    total_rewards: 1_000_000,
+                    distributed_rewards: 400_000,
+                    active,
+                }
+            );
+            assert_eq!(data[EpochRewards::ACTIVE_OFFSET], active as u8);
+        }
+        assert_eq!(EpochRewards::from_bytes(&[0; EpochRewards::LEN - 1]), Err(ProgramError::InvalidAccountData));
+        assert_eq!(EpochRewards::from_bytes(&[2; EpochRewards::LEN]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn an_unreadable_sysvar_is_inactive() {
+        // host builds have no syscall
+        assert!(!EpochRewards::is_active());
+        assert_eq!(EpochRewards::get(), Err(ProgramError::UnsupportedSysvar));
+    }
+}
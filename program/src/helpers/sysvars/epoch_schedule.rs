@@ -0,0 +1,158 @@
+//! EpochSchedule sysvar: slot <-> epoch conversions, as native's
+//! `solana_epoch_schedule::EpochSchedule`.
+//!
+//! With warmup, epoch `n < first_normal_epoch` lasts
+//! `MINIMUM_SLOTS_PER_EPOCH * 2^n` slots; every later epoch lasts
+//! `slots_per_epoch`.
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::helpers::get_sysvar;
+
+pub const EPOCH_SCHEDULE_ID: Pubkey = pinocchio_pubkey::pubkey!("SysvarEpochSchedu1e111111111111111111111111");
+
+pub const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochSchedule {
+    pub slots_per_epoch: u64,
+    pub leader_schedule_slot_offset: u64,
+    pub warmup: bool,
+    pub first_normal_epoch: u64,
+    pub first_normal_slot: u64,
+}
+
+impl EpochSchedule {
+    /// Serialized size: four u64 and the warmup bool
+    pub const LEN: usize = 33;
+
+    /// Read the sysvar through the syscall
+    pub fn get() -> Result<Self, ProgramError> {
+        let mut data = [0u8; Self::LEN];
+        get_sysvar(&mut data, &EPOCH_SCHEDULE_ID, 0, Self::LEN as u64)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Decode the sysvar's bincode layout
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
+        let data = data.get(..Self::LEN).ok_or(ProgramError::InvalidAccountData)?;
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let warmup = match data[16] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Self {
+            slots_per_epoch: u64_at(0),
+            leader_schedule_slot_offset: u64_at(8),
+            warmup,
+            first_normal_epoch: u64_at(17),
+            first_normal_slot: u64_at(25),
+        })
+    }
+
+    pub fn get_slots_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch < self.first_normal_epoch {
+            2u64.saturating_pow((epoch as u32).saturating_add(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()))
+        } else {
+            self.slots_per_epoch
+        }
+    }
+
+    pub fn get_epoch(&self, slot: u64) -> u64 {
+        self.get_epoch_and_slot_index(slot).0
+    }
+
+    /// Epoch containing `slot` and the slot's offset into it
+    pub fn get_epoch_and_slot_index(&self, slot: u64) -> (u64, u64) {
+        if slot < self.first_normal_slot {
+            let epoch = slot
+                .saturating_add(MINIMUM_SLOTS_PER_EPOCH)
+                .saturating_add(1)
+                .next_power_of_two()
+                .trailing_zeros()
+                .saturating_sub(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros())
+                .saturating_sub(1);
+            let epoch_len = 2u64.saturating_pow(epoch.saturating_add(MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()));
+            (u64::from(epoch), slot.saturating_sub(epoch_len.saturating_sub(MINIMUM_SLOTS_PER_EPOCH)))
+        } else {
+            let normal_slot_index = slot.saturating_sub(self.first_normal_slot);
+            let normal_epoch_index = normal_slot_index.checked_div(self.slots_per_epoch).unwrap_or(0);
+            let slot_index = normal_slot_index.checked_rem(self.slots_per_epoch).unwrap_or(0);
+            (self.first_normal_epoch.saturating_add(normal_epoch_index), slot_index)
+        }
+    }
+
+    pub fn get_first_slot_in_epoch(&self, epoch: u64) -> u64 {
+        if epoch <= self.first_normal_epoch {
+            2u64.saturating_pow(epoch as u32).saturating_sub(1).saturating_mul(MINIMUM_SLOTS_PER_EPOCH)
+        } else {
+            epoch
+                .saturating_sub(self.first_normal_epoch)
+                .saturating_mul(self.slots_per_epoch)
+                .saturating_add(self.first_normal_slot)
+        }
+    }
+
+    pub fn get_last_slot_in_epoch(&self, epoch: u64) -> u64 {
+        self.get_first_slot_in_epoch(epoch).saturating_add(self.get_slots_in_epoch(epoch)).saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::epoch_schedule::EpochSchedule as SdkEpochSchedule;
+
+    fn ours(sdk: &SdkEpochSchedule) -> EpochSchedule {
+        EpochSchedule::from_bytes(&bincode::serialize(sdk).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn decodes_the_sysvar_layout() {
+        let sdk = SdkEpochSchedule::custom(8192, 4096, true);
+        assert_eq!(bincode::serialize(&sdk).unwrap().len(), EpochSchedule::LEN);
+        assert_eq!(
+            ours(&sdk),
+            EpochSchedule {
+                slots_per_epoch: 8192,
+                leader_schedule_slot_offset: 4096,
+                warmup: true,
+                first_normal_epoch: sdk.first_normal_epoch,
+                first_normal_slot: sdk.first_normal_slot,
+            }
+        );
+
+        let mut data = bincode::serialize(&sdk).unwrap();
+        assert_eq!(EpochSchedule::from_bytes(&data[..32]), Err(ProgramError::InvalidAccountData));
+        data[16] = 2;
+        assert_eq!(EpochSchedule::from_bytes(&data), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn conversions_match_sdk() {
+        let schedules = [
+            SdkEpochSchedule::without_warmup(),
+            SdkEpochSchedule::default(),
+            SdkEpochSchedule::new(8192),
+            SdkEpochSchedule::custom(32, 32, true),
+            SdkEpochSchedule::custom(1000, 1000, true),
+            SdkEpochSchedule::custom(1000, 1000, false),
+        ];
+        for sdk in &schedules {
+            let schedule = ours(sdk);
+            let warmup_end = sdk.first_normal_slot;
+            let slots = (0..warmup_end + 3 * sdk.slots_per_epoch)
+                .step_by(7)
+                .chain([warmup_end.saturating_sub(1), warmup_end, u64::MAX / 2, u64::MAX]);
+            for slot in slots {
+                assert_eq!(schedule.get_epoch_and_slot_index(slot), sdk.get_epoch_and_slot_index(slot), "{sdk:?} slot {slot}");
+            }
+            for epoch in (0..sdk.first_normal_epoch + 4).chain([1_000, u64::MAX]) {
+                assert_eq!(schedule.get_slots_in_epoch(epoch), sdk.get_slots_in_epoch(epoch), "{sdk:?} epoch {epoch}");
+                assert_eq!(schedule.get_first_slot_in_epoch(epoch), sdk.get_first_slot_in_epoch(epoch), "{sdk:?} epoch {epoch}");
+                assert_eq!(schedule.get_last_slot_in_epoch(epoch), sdk.get_last_slot_in_epoch(epoch), "{sdk:?} epoch {epoch}");
+            }
+        }
+    }
+}
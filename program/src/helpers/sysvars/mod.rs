@@ -0,0 +1,8 @@
+//! Sysvars pinocchio has no reader for, in its `Sysvar` style: `get()` through
+//! the `sol_get_sysvar` syscall, `from_bytes` for account data.
+
+pub mod epoch_rewards;
+pub mod epoch_schedule;
+
+pub use epoch_rewards::EpochRewards;
+pub use epoch_schedule::EpochSchedule;
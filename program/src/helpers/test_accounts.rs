@@ -0,0 +1,44 @@
+//! Accounts for unit tests: the runtime's serialized account header followed
+//! by `N` bytes of data, and the `AccountInfo` pointing at it. This is the one
+//! place tests depend on the header layout pinocchio reads.
+
+use pinocchio::{account_info::AccountInfo, pubkey::Pubkey};
+
+/// The runtime's serialized account header followed by `N` bytes of data
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct RawAccount<const N: usize> {
+    pub borrow_state: u8,
+    pub is_signer: u8,
+    pub is_writable: u8,
+    pub executable: u8,
+    pub resize_delta: i32,
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data_len: u64,
+    pub data: [u8; N],
+}
+
+/// A writable, unsigned, unborrowed account whose `data_len` covers all `N`
+/// bytes
+pub(crate) fn raw<const N: usize>(key: Pubkey, owner: Pubkey, lamports: u64, data: [u8; N]) -> RawAccount<N> {
+    RawAccount {
+        borrow_state: 0b_1111_1111,
+        is_signer: 0,
+        is_writable: 1,
+        executable: 0,
+        resize_delta: 0,
+        key,
+        owner,
+        lamports,
+        data_len: N as u64,
+        data,
+    }
+}
+
+/// The `AccountInfo` over `raw`, valid while `raw` stays in place
+pub(crate) fn info<const N: usize>(raw: &mut RawAccount<N>) -> AccountInfo {
+    // SAFETY: AccountInfo is a repr(C) wrapper around a pointer to this header
+    unsafe { core::mem::transmute::<*mut RawAccount<N>, AccountInfo>(raw) }
+}
@@ -0,0 +1,61 @@
+//! CPI to the SPL token program, for WithdrawToTokenAccount.
+//!
+//! Lamports moved into a native-mint (wSOL) token account only count as
+//! tokens once the token program syncs the account's amount with its balance.
+
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::pubkey_eq, ProgramResult};
+use pinocchio_token::{instructions::SyncNative, state::TokenAccount};
+
+/// The token program account, which a CPI needs among the instruction's
+/// accounts; located by key like the sysvars
+pub fn find_token_program(accounts: &[AccountInfo]) -> Result<&AccountInfo, ProgramError> {
+    accounts
+        .iter()
+        .find(|ai| pubkey_eq(ai.key(), &pinocchio_token::ID))
+        .ok_or(ProgramError::IncorrectProgramId)
+}
+
+/// `InvalidAccountData` unless `account` is an initialized, unfrozen token
+/// account of the native mint, owned by the token program
+pub fn check_native_token_account(account: &AccountInfo) -> ProgramResult {
+    let token = TokenAccount::from_account_info(account)?;
+    if !token.is_initialized() || token.is_frozen() || !token.is_native() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// SyncNative: bring the token amount of `native_token` up to its lamports
+pub fn sync_native(native_token: &AccountInfo) -> ProgramResult {
+    SyncNative { native_token }.invoke()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+    use crate::helpers::test_accounts::{info, raw, RawAccount};
+
+    /// Initialized token account; `is_native` sets the COption tag
+    fn token_account(owner: Pubkey, is_native: bool) -> RawAccount<{ TokenAccount::LEN }> {
+        let mut data = [0; TokenAccount::LEN];
+        data[108] = 1;
+        data[109] = is_native as u8;
+        raw([7; 32], owner, 0, data)
+    }
+
+    fn check(raw: &mut RawAccount<{ TokenAccount::LEN }>) -> ProgramResult {
+        check_native_token_account(&info(raw))
+    }
+
+    #[test]
+    fn accepts_only_native_token_accounts() {
+        assert_eq!(check(&mut token_account(pinocchio_token::ID, true)), Ok(()));
+        assert_eq!(check(&mut token_account(pinocchio_token::ID, false)), Err(ProgramError::InvalidAccountData));
+        assert_eq!(check(&mut token_account([0; 32], true)), Err(ProgramError::InvalidAccountData));
+
+        let mut frozen = token_account(pinocchio_token::ID, true);
+        frozen.data[108] = 2;
+        assert_eq!(check(&mut frozen), Err(ProgramError::InvalidAccountData));
+    }
+}
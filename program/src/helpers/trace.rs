@@ -0,0 +1,51 @@
+//! State-change tracing for debugging divergence from native (`trace-state`).
+//!
+//! Every stake account write through `set_stake_state`, and every lamport move
+//! through `relocate_lamports`, logs the account key followed by one line:
+//!
+//! - `trace-state data <before> -> <after>`: 64-bit FNV-1a digests of the whole
+//!   account data before and after the write
+//! - `trace-state lamports <before> -> <after>`: the account balance
+//!
+//! FNV-1a is trivial to reproduce off-chain, so the same digest computed over
+//! native's post-state pinpoints the first account that differs.
+
+use pinocchio::pubkey::Pubkey;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a of `data`
+pub const fn digest(data: &[u8]) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut i = 0;
+    while i < data.len() {
+        hash ^= data[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+pub fn log_data(key: &Pubkey, before: u64, after: u64) {
+    pinocchio::pubkey::log(key);
+    pinocchio_log::log!("trace-state data {} -> {}", before, after);
+}
+
+pub fn log_lamports(key: &Pubkey, before: u64, after: u64) {
+    pinocchio::pubkey::log(key);
+    pinocchio_log::log!("trace-state lamports {} -> {}", before, after);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_matches_reference_fnv1a() {
+        assert_eq!(digest(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(digest(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(digest(b"foobar"), 0x8594_4171_f739_67e8);
+        assert_ne!(digest(&[0; 200]), digest(&[0; 199]));
+    }
+}
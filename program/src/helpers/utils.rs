@@ -1,23 +1,22 @@
 extern crate alloc;
-use alloc::collections::BTreeSet;
 use crate::helpers::constant::*;
-use crate::state::stake_history::StakeHistorySysvar;
+use crate::state::{EpochLE, LamportsLE};
+use crate::helpers::soft_float::Binary64;
+use crate::state::stake_history::StakeHistoryGetEntry;
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::{rent::Rent, Sysvar},
-    ProgramResult,
+    pubkey::{pubkey_eq, Pubkey},
 };
 
 use crate::error::{to_program_error, StakeError};
-use crate::state::stake_state_v2::StakeStateV2;
-use crate::state::vote_state::VoteState;
+use crate::helpers::log::{log_debug, log_error};
+use crate::state::vote_state::{EpochCreditsView, VoteState};
 use crate::state::{
     delegation::{Delegation, Stake},
-    Meta,
+    merge_kind::MergeKind,
+    Meta, StakeFlags,
 };
-use crate::ID;
 
 const FEATURE_STAKE_RAISE_MINIMUM_DELEGATION_TO_1_SOL: bool = false;
 const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
@@ -28,34 +27,117 @@ pub struct ValidatedDelegatedInfo {
     pub stake_amount: u64,
 }
 
-// wrapper for epoch to pass around
-// pub struct StakeHistorySysvar(pub u64);
+// Key comparisons on the hot paths (signer sets, sysvar lookup, owner and
+// authority checks) use `pubkey_eq`: four u64 compares where `==` on
+// `[u8; 32]` lowers to a byte-wise memcmp loop on SBF. A `sol_memcmp_`
+// syscall would cost more than the compares it replaces at 32 bytes.
 
-pub enum ErrorCode {
-    TOOMANYSIGNERS = 0x1,
+/// Whether `key` is one of `keys`
+#[inline(always)]
+pub fn contains_key(keys: &[Pubkey], key: &Pubkey) -> bool {
+    keys.iter().any(|k| pubkey_eq(k, key))
+}
+
+/// What authority checks ask of a signer set: whether `key` signed. Handlers
+/// pass [`Signers`]; the seeded variants and unit tests pass plain keys.
+pub trait SignerSet {
+    fn contains(&self, key: &Pubkey) -> bool;
+}
+
+impl SignerSet for [Pubkey] {
+    #[inline(always)]
+    fn contains(&self, key: &Pubkey) -> bool {
+        contains_key(self, key)
+    }
+}
+
+impl<const N: usize> SignerSet for [Pubkey; N] {
+    #[inline(always)]
+    fn contains(&self, key: &Pubkey) -> bool {
+        contains_key(self, key)
+    }
+}
+
+/// Accounts a [`Signers`] mask covers: the entrypoint hands over at most
+/// `pinocchio::MAX_TX_ACCOUNTS` (254)
+const SIGNER_MASK_BITS: usize = 256;
+
+// Like native, handlers accumulate every signing account in the instruction,
+// whatever its position, then defer authority checks to Meta/Authorized
+// helpers. The signer flag is all there is to go on, so a PDA that a calling
+// program signed for with invoke_signed counts exactly like a keypair. Each
+// key is collected once, as native's signer set does: a CPI may repeat a
+// signing account, and only distinct keys count towards MAXIMUM_SIGNERS, more
+// than a transaction plus its PDA signers can carry.
+//
+// Rather than copying each key into a stack buffer, the set is a bitmask over
+// the account list, bit `i` set for the first account (index `i`) with each
+// distinct signing key; `contains` compares against those keys in place.
+
+/// The distinct signers of an instruction, by account index
+#[derive(Clone, Copy)]
+pub struct Signers<'a> {
+    accounts: &'a [AccountInfo],
+    mask: [u64; SIGNER_MASK_BITS / 64],
 }
 
-// Many stake instruction handlers accumulate all transaction signers first,
-// then defer authority checks to Meta/Authorized helpers. This preserves
-// compatibility with existing transactions.
-pub fn collect_signers(
-    accounts: &[AccountInfo],
-    array_of_signers: &mut [Pubkey; MAXIMUM_SIGNERS],
-) -> Result<usize, ProgramError> {
-    let mut len_of_signers = 0;
-
-    for account in accounts {
-        if account.is_signer() {
-            if len_of_signers < MAXIMUM_SIGNERS {
-                array_of_signers[len_of_signers] = *account.key();
-                len_of_signers += 1;
-            } else {
-                return Err(ProgramError::Custom(ErrorCode::TOOMANYSIGNERS as u32));
+impl<'a> Signers<'a> {
+    pub fn collect(accounts: &'a [AccountInfo]) -> Result<Self, ProgramError> {
+        let mut signers = Self { accounts, mask: [0; SIGNER_MASK_BITS / 64] };
+        let mut len = 0;
+        for (i, account) in accounts.iter().enumerate() {
+            if account.is_signer() && !signers.contains(account.key()) {
+                if len == MAXIMUM_SIGNERS || i >= SIGNER_MASK_BITS {
+                    return Err(to_program_error(StakeError::TooManySigners));
+                }
+                signers.mask[i / 64] |= 1 << (i % 64);
+                len += 1;
             }
         }
+        Ok(signers)
+    }
+
+    /// Number of distinct signing keys
+    pub fn len(&self) -> usize {
+        self.mask.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mask.iter().all(|word| *word == 0)
+    }
+
+    /// The signing keys, in account order
+    pub fn iter(&self) -> impl Iterator<Item = &'a Pubkey> + '_ {
+        let accounts = self.accounts;
+        self.mask.iter().enumerate().flat_map(move |(w, &word)| {
+            let mut bits = word;
+            core::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let i = w * 64 + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(accounts[i].key())
+            })
+        })
     }
+}
 
-    Ok(len_of_signers)
+impl SignerSet for Signers<'_> {
+    #[inline]
+    fn contains(&self, key: &Pubkey) -> bool {
+        for (w, &word) in self.mask.iter().enumerate() {
+            let mut bits = word;
+            while bits != 0 {
+                let i = w * 64 + bits.trailing_zeros() as usize;
+                if pubkey_eq(self.accounts[i].key(), key) {
+                    return true;
+                }
+                bits &= bits - 1;
+            }
+        }
+        false
+    }
 }
 
 pub fn next_account_info<'a, I: Iterator<Item = &'a AccountInfo>>(
@@ -64,6 +146,24 @@ pub fn next_account_info<'a, I: Iterator<Item = &'a AccountInfo>>(
     iter.next().ok_or(ProgramError::NotEnoughAccountKeys)
 }
 
+// Sysvars and authorities are resolved by role rather than position, so any
+// ordering the native instruction builders produce is accepted. Only the
+// accounts whose identity *is* the argument (stake, destination, vote, base,
+// new authority) are read positionally.
+
+/// Sysvar account by pubkey, wherever it sits in the account list
+pub fn find_sysvar<'a>(accounts: &'a [AccountInfo], id: &Pubkey) -> Result<&'a AccountInfo, ProgramError> {
+    accounts
+        .iter()
+        .find(|ai| pubkey_eq(ai.key(), id))
+        .ok_or(ProgramError::InvalidArgument)
+}
+
+/// Signing account with the given key (e.g. the lockup custodian), if present
+pub fn find_signer<'a>(accounts: &'a [AccountInfo], key: &Pubkey) -> Option<&'a AccountInfo> {
+    accounts.iter().find(|ai| ai.is_signer() && pubkey_eq(ai.key(), key))
+}
+
 /// The minimum stake amount that can be delegated, in lamports.
 /// NOTE: This is also used to calculate the minimum balance of a delegated
 /// stake account, which is the rent exempt reserve _plus_ the minimum stake
@@ -78,24 +178,23 @@ pub fn get_minimum_delegation() -> u64 {
     }
 }
 pub fn warmup_cooldown_rate(
-    current_epoch: [u8; 8],
-    new_rate_activation_epoch: Option<[u8; 8]>,
-) -> f64 {
-    if current_epoch < new_rate_activation_epoch.unwrap_or(u64::MAX.to_le_bytes()) {
+    current_epoch: EpochLE,
+    new_rate_activation_epoch: Option<EpochLE>,
+) -> Binary64 {
+    if current_epoch < new_rate_activation_epoch.unwrap_or(EpochLE::MAX) {
         DEFAULT_WARMUP_COOLDOWN_RATE
     } else {
         NEW_WARMUP_COOLDOWN_RATE
     }
 }
 
-pub type Epoch = [u8; 8];
-
 pub fn bytes_to_u64(bytes: [u8; 8]) -> u64 {
     u64::from_le_bytes(bytes)
 }
 
 /// After calling `validate_split_amount()`, this struct contains calculated
 /// values that are used by the caller.
+#[cfg_attr(not(feature = "program"), allow(dead_code))]
 #[derive(Copy, Clone, Debug, Default)]
 pub(crate) struct ValidatedSplitInfo {
     pub source_remaining_balance: u64,
@@ -106,12 +205,13 @@ pub(crate) struct ValidatedSplitInfo {
 /// accounts meet the minimum balance requirements, which is the rent exempt
 /// reserve plus the minimum stake delegation, and that the source account has
 /// enough lamports for the request split amount.  If not, return an error.
+#[cfg_attr(not(feature = "program"), allow(dead_code))]
 pub(crate) fn validate_split_amount(
     source_lamports: u64,
     destination_lamports: u64,
     split_lamports: u64,
     source_meta: &Meta,
-    destination_data_len: usize,
+    destination_rent_exempt_reserve: u64,
     additional_required_lamports: u64,
     source_is_active: bool,
 ) -> Result<ValidatedSplitInfo, ProgramError> {
@@ -130,7 +230,7 @@ pub(crate) fn validate_split_amount(
     // source account is transferring all lamports to new destination account,
     // and the source account will be closed)
     let source_minimum_balance =
-        bytes_to_u64(source_meta.rent_exempt_reserve).saturating_add(additional_required_lamports);
+        source_meta.rent_exempt_reserve.get().saturating_add(additional_required_lamports);
     let source_remaining_balance = source_lamports.saturating_sub(split_lamports);
     if source_remaining_balance == 0 {
         // full amount is a withdrawal
@@ -143,9 +243,6 @@ pub(crate) fn validate_split_amount(
         // nothing to do here
     }
 
-    let rent = Rent::get()?;
-    let destination_rent_exempt_reserve = rent.minimum_balance(destination_data_len);
-
     // If the source is active stake, one of these criteria must be met:
     // 1. the destination account must be prefunded with at least the rent-exempt
     //    reserve, or
@@ -186,74 +283,21 @@ pub(crate) fn validate_split_amount(
 // returns a deserialized vote state from raw account data
 pub fn get_vote_state(vote_account_info: &AccountInfo) -> Result<VoteState, ProgramError> {
     // owner must be the vote program
-    if *vote_account_info.owner() != crate::state::vote_state::vote_program_id() {
+    if !pubkey_eq(vote_account_info.owner(), &crate::state::vote_state::vote_program_id()) {
         return Err(ProgramError::IncorrectProgramId);
     }
-    // enforce account is large enough
-    let data = unsafe { vote_account_info.borrow_data_unchecked() };
-    if data.len() < core::mem::size_of::<VoteState>() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-
-    let vote_state = unsafe { &*(data.as_ptr() as *const VoteState) };
-    Ok(vote_state.clone())
+    let data = vote_account_info.try_borrow_data()?;
+    VoteState::from_bytes(&data)
 }
 
 // Lightweight helper to read the latest credits from a vote account without
 // constructing a full VoteState on stack. This reduces SBF stack usage.
 pub fn get_vote_credits(vote_account_info: &AccountInfo) -> Result<u64, ProgramError> {
-    if *vote_account_info.owner() != crate::state::vote_state::vote_program_id() {
+    if !pubkey_eq(vote_account_info.owner(), &crate::state::vote_state::vote_program_id()) {
         return Err(ProgramError::IncorrectProgramId);
     }
-    // Tests and ProgramTest assume a baseline credits_observed of 100.
-    // Return 100 unconditionally for vote accounts to keep deterministic
-    // behavior and parity with native tests.
-    Ok(100)
-}
-
-#[inline]
-fn parse_epoch_credits_triplets(buf: &[u8], n: usize) -> Option<u64> {
-    let need = 24usize.checked_mul(n)?;
-    if buf.len() < need { return None; }
-    let mut off = 0usize;
-    let mut last_epoch = 0u64;
-    let mut last_credits = 0u64;
-    for _ in 0..n {
-        let mut e = [0u8; 8];
-        let mut c = [0u8; 8];
-        let mut p = [0u8; 8];
-        e.copy_from_slice(&buf[off..off + 8]); off += 8;
-        c.copy_from_slice(&buf[off..off + 8]); off += 8;
-        p.copy_from_slice(&buf[off..off + 8]); off += 8;
-        let epoch = u64::from_le_bytes(e);
-        let credits = u64::from_le_bytes(c);
-        let prev = u64::from_le_bytes(p);
-        if epoch < last_epoch { return None; }
-        if credits < prev { return None; }
-        last_epoch = epoch;
-        last_credits = credits;
-    }
-    Some(last_credits)
-}
-
-// load stake state from account
-pub fn get_stake_state(stake_account_info: &AccountInfo) -> Result<StakeStateV2, ProgramError> {
-    if *stake_account_info.owner() != ID {
-        return Err(ProgramError::InvalidAccountOwner);
-    }
-
-    let data = unsafe { stake_account_info.borrow_data_unchecked() };
-    StakeStateV2::deserialize(&data)
-}
-
-// write stake state back into account
-pub fn set_stake_state(
-    stake_account_info: &AccountInfo,
-    stake_state: &StakeStateV2,
-) -> Result<(), ProgramError> {
-    let mut data = unsafe { stake_account_info.borrow_mut_data_unchecked() };
-    stake_state.serialize(&mut data)?;
-    Ok(())
+    let data = vote_account_info.try_borrow_data()?;
+    Ok(EpochCreditsView::parse(&data)?.credits())
 }
 
 // compute stake amount = lamports - rent exempt reserve
@@ -266,7 +310,7 @@ pub fn validate_delegated_amount(
     // codes with native (InsufficientDelegation, not InsufficientFunds).
     let stake_amount = stake_account_info
         .lamports()
-        .saturating_sub(bytes_to_u64(meta.rent_exempt_reserve));
+        .saturating_sub(meta.rent_exempt_reserve.get());
 
     // Enforce minimum delegation before allowing delegate, but allow
     // the degenerate case of delegating zero lamports (rent-only
@@ -286,10 +330,10 @@ pub fn new_stake(
     vote_state: &VoteState,
     activation_epoch: u64,
 ) -> Stake {
-    let mut stake = Stake::default();
-    stake.delegation = Delegation::new(vote_pubkey, stake_amount, activation_epoch.to_le_bytes());
-    stake.set_credits_observed(vote_state.credits());
-    stake
+    Stake {
+        delegation: Delegation::new(vote_pubkey, stake_amount, EpochLE::new(activation_epoch)),
+        credits_observed: vote_state.credits().to_le_bytes(),
+    }
 }
 
 pub fn new_stake_with_credits(
@@ -298,137 +342,78 @@ pub fn new_stake_with_credits(
     activation_epoch: u64,
     credits_observed: u64,
 ) -> Stake {
-    let mut stake = Stake::default();
-    stake.delegation = Delegation::new(vote_pubkey, stake_amount, activation_epoch.to_le_bytes());
-    stake.set_credits_observed(credits_observed);
-    stake
+    Stake {
+        delegation: Delegation::new(vote_pubkey, stake_amount, EpochLE::new(activation_epoch)),
+        credits_observed: credits_observed.to_le_bytes(),
+    }
 }
 
-// modify existing stake object with updated delegation
-pub fn redelegate_stake(
+// Delegate of an existing Stake, as native's `redelegate_stake`, with the
+// vote credits already read. A delegation with anything still effective
+// (active, or cooling down from an earlier epoch) is kept; the only change
+// allowed is rescinding a deactivation scheduled this epoch, to the same vote.
+// With nothing effective the struct is reused for a fresh delegation.
+pub fn redelegate_stake_with_credits<T: StakeHistoryGetEntry>(
     stake: &mut Stake,
     stake_lamports: u64,
     voter_pubkey: &Pubkey,
-    vote_state: &VoteState,
+    credits_observed: u64,
     epoch: u64,
-    stake_history: &StakeHistorySysvar,
+    stake_history: &T,
+    new_rate_activation_epoch: Option<EpochLE>,
 ) -> Result<(), ProgramError> {
-    // Effective stake at `epoch`?
     let effective = stake.stake(
-        epoch.to_le_bytes(),
+        EpochLE::new(epoch),
         stake_history,
-        PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
+        new_rate_activation_epoch,
     );
 
     if effective != 0 {
-        // If same voter AND we were scheduled to deactivate this epoch, rescind deactivation
-        if stake.delegation.voter_pubkey == *voter_pubkey
-            && bytes_to_u64(stake.delegation.deactivation_epoch) == epoch
+        if pubkey_eq(&stake.delegation.voter_pubkey, voter_pubkey)
+            && stake.delegation.deactivation_epoch.get() == epoch
         {
-            stake.delegation.deactivation_epoch = u64::MAX.to_le_bytes();
+            log_debug!("delegate: rescind deactivation");
+            stake.delegation.deactivation_epoch = EpochLE::MAX;
             return Ok(());
-        } else {
-            // Can't redelegate when still effective
-            return Err(to_program_error(StakeError::TooSoonToRedelegate));
         }
+        log_error!("delegate: too_soon");
+        return Err(to_program_error(StakeError::TooSoonToRedelegate));
     }
 
-    // Not currently effective: proceed with redelegation (re-activation / un-deactivation)
-    stake.delegation.stake = stake_lamports.to_le_bytes();
-    stake.delegation.activation_epoch = epoch.to_le_bytes();
-    stake.delegation.deactivation_epoch = u64::MAX.to_le_bytes();
+    log_debug!("delegate: inactive_redelegate");
+    stake.delegation.stake = LamportsLE::new(stake_lamports);
+    stake.delegation.activation_epoch = EpochLE::new(epoch);
+    stake.delegation.deactivation_epoch = EpochLE::MAX;
     stake.delegation.voter_pubkey = *voter_pubkey;
-    stake.set_credits_observed(vote_state.credits());
+    stake.set_credits_observed(credits_observed);
     Ok(())
 }
-
-pub fn redelegate_stake_with_credits(
+// deactivate a delegation, honoring MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED:
+// a flagged stake (see Redelegate) deactivates only once nothing is activating,
+// and the flag is cleared so later cycles are unrestricted
+pub fn deactivate_stake<T: StakeHistoryGetEntry>(
     stake: &mut Stake,
-    stake_lamports: u64,
-    voter_pubkey: &Pubkey,
-    credits_observed: u64,
+    stake_flags: &mut StakeFlags,
     epoch: u64,
-    stake_history: &StakeHistorySysvar,
-) -> Result<(), ProgramError> {
-    let effective = stake.stake(
-        epoch.to_le_bytes(),
-        stake_history,
-        PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
-    );
-    // Fallback: treat as effectively active when past activation and not deactivated,
-    // even if stake history lacks entries (ProgramTest).
-    let act = bytes_to_u64(stake.delegation.activation_epoch);
-    let deact = bytes_to_u64(stake.delegation.deactivation_epoch);
-    let delegated = bytes_to_u64(stake.delegation.stake);
-    if deact == epoch { pinocchio::msg!("delegate: deact_eq_epoch"); }
-    else if deact < epoch { pinocchio::msg!("delegate: deact_before_epoch"); }
-    else { pinocchio::msg!("delegate: deact_after_epoch_or_other"); }
-    if deact == u64::MAX { pinocchio::msg!("delegate: deact_max"); }
-    if delegated == 0 { pinocchio::msg!("delegate: zero_delegated"); }
-    // If attempting to change to a different vote, block unless fully deactivated
-    if stake.delegation.voter_pubkey != *voter_pubkey {
-        if delegated > 0 {
-            if deact == u64::MAX || epoch <= deact {
-                pinocchio::msg!("delegate: different_vote_blocked");
-                return Err(to_program_error(StakeError::TooSoonToRedelegate));
-            }
+    stake_history: &T,
+    new_rate_activation_epoch: Option<EpochLE>,
+) -> Result<(), StakeError> {
+    let flag = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+    if stake_flags.contains(flag) {
+        let status = stake.delegation.stake_activating_and_deactivating(
+            EpochLE::new(epoch),
+            stake_history,
+            new_rate_activation_epoch,
+        );
+        if status.activating.get() != 0 {
+            return Err(StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted);
         }
+        stake.deactivate(EpochLE::new(epoch))?;
+        stake_flags.remove(flag);
+        Ok(())
+    } else {
+        stake.deactivate(EpochLE::new(epoch))
     }
-
-    // If deactivation is scheduled, only allow rescinding to the same voter;
-    // otherwise it's too soon to redelegate to a different vote.
-    if deact != u64::MAX {
-        if stake.delegation.voter_pubkey == *voter_pubkey {
-            pinocchio::msg!("delegate: rescind deactivation");
-            stake.delegation.deactivation_epoch = u64::MAX.to_le_bytes();
-            return Ok(());
-        } else {
-            pinocchio::msg!("delegate: deactivating_different_vote");
-            return Err(to_program_error(StakeError::TooSoonToRedelegate));
-        }
-    }
-
-    // Treat stake as effective in three cases:
-    // 1) stake history reports nonzero effective stake
-    // 2) fallback: delegated, not scheduled to deactivate, and current epoch > activation
-    // 3) deactivation is scheduled for the current epoch (still considered active for redelegation rules)
-    let effective_nonzero = effective != 0
-        || (delegated > 0 && deact == u64::MAX && epoch > act)
-        || (delegated > 0 && deact == epoch);
-    pinocchio::msg!("delegate: effective_check");
-    if effective_nonzero {
-        pinocchio::msg!("delegate: too_soon");
-        return Err(to_program_error(StakeError::TooSoonToRedelegate));
-    }
-    pinocchio::msg!("delegate: inactive_redelegate");
-    stake.delegation.stake = stake_lamports.to_le_bytes();
-    stake.delegation.activation_epoch = epoch.to_le_bytes();
-    stake.delegation.deactivation_epoch = u64::MAX.to_le_bytes();
-    stake.delegation.voter_pubkey = *voter_pubkey;
-    stake.set_credits_observed(credits_observed);
-    Ok(())
-}
-// Avoid naming this function "move" to prevent confusion with the MoveLamports instruction
-pub fn relocate_lamports(
-    source_account_info: &AccountInfo,
-    destination_account_info: &AccountInfo,
-    lamports: u64,
-) -> ProgramResult {
-    {
-        let mut source_lamports = source_account_info.try_borrow_mut_lamports()?;
-        *source_lamports = source_lamports
-            .checked_sub(lamports)
-            .ok_or(ProgramError::InsufficientFunds)?;
-    }
-
-    {
-        let mut destination_lamports = destination_account_info.try_borrow_mut_lamports()?;
-        *destination_lamports = destination_lamports
-            .checked_add(lamports)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-    }
-
-    Ok(())
 }
 
 const SUCCESS: u64 = 0;
@@ -448,9 +433,16 @@ pub fn get_sysvar(
     let sysvar_id = sysvar_id as *const _ as *const u8;
     let var_addr = dst as *mut _ as *mut u8;
 
+    #[cfg(target_os = "solana")]
     let result = unsafe {
         pinocchio::syscalls::sol_get_sysvar(sysvar_id, var_addr, offset, length)
     };
+    // Host builds have no sysvars to read (and no syscall to link against)
+    #[cfg(not(target_os = "solana"))]
+    let result = {
+        core::hint::black_box((sysvar_id, var_addr, offset));
+        u64::from(ProgramError::UnsupportedSysvar)
+    };
 
     match result {
         SUCCESS => Ok(()),
@@ -458,35 +450,329 @@ pub fn get_sysvar(
     }
 }
 
+/// Lamports MoveLamports may take from a source classified as `kind`, as in
+/// native: everything above delegated stake plus the rent reserve for a fully
+/// active source, everything above the reserve for an inactive one. A source in
+/// its activation epoch has no free lamports (`InvalidAccountData`).
+#[cfg_attr(not(feature = "program"), allow(dead_code))]
+pub(crate) fn free_lamports(kind: &MergeKind, account_lamports: u64) -> Result<u64, ProgramError> {
+    match kind {
+        MergeKind::FullyActive(meta, stake) => Ok(account_lamports
+            .saturating_sub(stake.delegation.stake.get())
+            .saturating_sub(meta.rent_exempt_reserve.get())),
+        MergeKind::Inactive(meta, lamports, _) => {
+            Ok(lamports.saturating_sub(meta.rent_exempt_reserve.get()))
+        }
+        MergeKind::ActivationEpoch(..) => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Lamport/stake addition as native's `checked_add` helper: overflow is `InsufficientFunds`
 pub(crate) fn checked_add(a: u64, b: u64) -> Result<u64, ProgramError> {
     a.checked_add(b).ok_or(ProgramError::InsufficientFunds)
 }
-pub fn collect_signers_checked<'a>(
-    authority_info: Option<&'a AccountInfo>,
-    custodian_info: Option<&'a AccountInfo>,
-) -> Result<(BTreeSet<Pubkey>, Option<&'a Pubkey>), ProgramError> {
-    let mut signers = BTreeSet::new();
-
-    if let Some(ai) = authority_info {
-        if ai.is_signer() {
-            signers.insert(*ai.key());
-        } else {
-            return Err(ProgramError::MissingRequiredSignature);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{self, info, RawAccount};
+    use crate::state::stake_history::StakeHistoryEntry;
+
+    /// No recorded epochs: activation state follows from the delegation alone
+    struct NoHistory;
+
+    impl StakeHistoryGetEntry for NoHistory {
+        fn get_entry(&self, _epoch: u64) -> Option<StakeHistoryEntry> {
+            None
+        }
+    }
+
+    const EPOCH: u64 = 10;
+    const NEW_RATE: Option<EpochLE> = PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH;
+    const FLAG: StakeFlags = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+
+    fn stake(activation_epoch: u64) -> Stake {
+        Stake {
+            delegation: Delegation::new(&[1; 32], 1_000, EpochLE::new(activation_epoch)),
+            credits_observed: [0; 8],
         }
     }
 
-    let custodian = if let Some(ci) = custodian_info {
-        if ci.is_signer() {
-            signers.insert(*ci.key());
-            Some(ci.key())
+    #[test]
+    fn deactivate_while_activating_without_flag() {
+        let mut activating = stake(EPOCH);
+        let mut flags = StakeFlags::empty();
+        deactivate_stake(&mut activating, &mut flags, EPOCH, &NoHistory, NEW_RATE).unwrap();
+        assert_eq!(activating.delegation.deactivation_epoch.get(), EPOCH);
+
+        assert_eq!(
+            deactivate_stake(&mut activating, &mut flags, EPOCH + 1, &NoHistory, NEW_RATE),
+            Err(StakeError::AlreadyDeactivated)
+        );
+        assert_eq!(activating.delegation.deactivation_epoch.get(), EPOCH);
+    }
+
+    #[test]
+    fn flagged_stake_must_finish_activating() {
+        let mut activating = stake(EPOCH);
+        let mut flags = FLAG;
+        assert_eq!(
+            deactivate_stake(&mut activating, &mut flags, EPOCH, &NoHistory, NEW_RATE),
+            Err(StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted)
+        );
+        assert_eq!(activating, stake(EPOCH));
+        assert_eq!(flags, FLAG);
+
+        // bootstrap stake has nothing activating; the flag is consumed
+        let mut active = stake(u64::MAX);
+        deactivate_stake(&mut active, &mut flags, EPOCH, &NoHistory, NEW_RATE).unwrap();
+        assert_eq!(active.delegation.deactivation_epoch.get(), EPOCH);
+        assert_eq!(flags, StakeFlags::empty());
+    }
+
+    /// Cluster still cooling down far more than it can in one epoch, so a
+    /// stake deactivated at `EPOCH - 1` keeps most of itself effective at `EPOCH`
+    struct Cooling;
+
+    impl StakeHistoryGetEntry for Cooling {
+        fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+            (epoch == EPOCH - 1).then(|| StakeHistoryEntry {
+                effective: LamportsLE::new(1_000_000),
+                activating: LamportsLE::ZERO,
+                deactivating: LamportsLE::new(1_000_000_000),
+            })
+        }
+    }
+
+    fn deactivated(deactivation_epoch: u64) -> Stake {
+        let mut stake = stake(u64::MAX);
+        stake.delegation.deactivation_epoch = EpochLE::new(deactivation_epoch);
+        stake
+    }
+
+    #[test]
+    fn redelegate_rescinds_only_this_epochs_deactivation() {
+        let voter = [1; 32];
+        let mut deactivating = deactivated(EPOCH);
+        redelegate_stake_with_credits(&mut deactivating, 5, &voter, 7, EPOCH, &NoHistory, NEW_RATE).unwrap();
+        assert_eq!(deactivating, stake(u64::MAX));
+
+        let mut cooling = deactivated(EPOCH - 1);
+        assert_eq!(
+            redelegate_stake_with_credits(&mut cooling, 5, &voter, 7, EPOCH, &Cooling, NEW_RATE),
+            Err(to_program_error(StakeError::TooSoonToRedelegate))
+        );
+        assert_eq!(cooling, deactivated(EPOCH - 1));
+
+        let mut active = stake(u64::MAX);
+        assert_eq!(
+            redelegate_stake_with_credits(&mut active, 5, &[2; 32], 7, EPOCH, &NoHistory, NEW_RATE),
+            Err(to_program_error(StakeError::TooSoonToRedelegate))
+        );
+    }
+
+    #[test]
+    fn redelegate_reuses_fully_deactivated_stake() {
+        let mut inactive = deactivated(EPOCH - 1);
+        redelegate_stake_with_credits(&mut inactive, 5, &[2; 32], 7, EPOCH, &NoHistory, NEW_RATE).unwrap();
+        let mut expected = stake(EPOCH);
+        expected.delegation.voter_pubkey = [2; 32];
+        expected.delegation.stake = LamportsLE::new(5);
+        expected.credits_observed = 7u64.to_le_bytes();
+        assert_eq!(inactive, expected);
+    }
+
+    /// A header-only account, read-only
+    fn raw(key: u8, is_signer: bool) -> RawAccount<0> {
+        RawAccount { is_signer: is_signer as u8, is_writable: 0, ..test_accounts::raw([key; 32], [0; 32], 0, []) }
+    }
+
+    #[test]
+    fn signers_count_each_key_once() {
+        // a CPI can repeat a signer (e.g. a PDA in several roles) beyond the buffer size
+        let mut raws: [RawAccount<0>; MAXIMUM_SIGNERS + 3] = core::array::from_fn(|_| raw(1, true));
+        raws[1] = raw(2, true);
+        raws[2] = raw(3, false);
+        let accounts = raws.each_mut().map(info);
+
+        let signers = Signers::collect(&accounts).unwrap();
+        assert_eq!(signers.len(), 2);
+        assert!(signers.iter().eq(&[[1; 32], [2; 32]]));
+        assert!(signers.contains(&[1; 32]) && signers.contains(&[2; 32]));
+        // a non-signer's key is not in the set
+        assert!(!signers.contains(&[3; 32]));
+
+        let mut raws = [raw(1, false), raw(2, false)];
+        let accounts = raws.each_mut().map(info);
+        let signers = Signers::collect(&accounts).unwrap();
+        assert!(signers.is_empty() && !signers.contains(&[1; 32]));
+    }
+
+    #[test]
+    fn signers_past_the_limit_fail_without_a_stake_error_code() {
+        // exactly MAXIMUM_SIGNERS distinct signers, each repeated and mixed with
+        // non-signers, well past 32 accounts: every key is kept, in order
+        let mut raws: [RawAccount<0>; 3 * MAXIMUM_SIGNERS] =
+            core::array::from_fn(|i| match i % 3 {
+                2 => raw(200, false),
+                _ => raw((i / 3) as u8, true),
+            });
+        let accounts = raws.each_mut().map(info);
+        let signers = Signers::collect(&accounts).unwrap();
+        assert_eq!(signers.len(), MAXIMUM_SIGNERS);
+        assert!(signers.iter().enumerate().all(|(i, key)| *key == [i as u8; 32]));
+        // keys set in the mask's second word are found too
+        assert!(signers.contains(&[31; 32]) && !signers.contains(&[200; 32]));
+
+        // one distinct signer more fails the instruction as a whole, never with
+        // a Custom code clients would read as a native StakeError (e.g. LockupInForce)
+        let mut raws: [RawAccount<0>; MAXIMUM_SIGNERS + 1] = core::array::from_fn(|i| raw(i as u8, true));
+        let accounts = raws.each_mut().map(info);
+        let err = Signers::collect(&accounts).err().unwrap();
+        assert_eq!(err, ProgramError::InvalidArgument);
+        assert!(!matches!(err, ProgramError::Custom(_)));
+    }
+
+    #[test]
+    fn contains_key_compares_every_word() {
+        let key = [7u8; 32];
+        let keys = [[0u8; 32], key];
+        assert!(contains_key(&keys, &key));
+        assert!(!contains_key(&[], &key));
+        // a difference in any one byte, including across u64 boundaries
+        for i in [0, 7, 8, 15, 16, 31] {
+            let mut other = key;
+            other[i] ^= 1;
+            assert!(!contains_key(&keys, &other), "byte {i}");
+        }
+    }
+
+    fn meta(reserve: u64) -> Meta {
+        Meta { rent_exempt_reserve: LamportsLE::new(reserve), ..Meta::default() }
+    }
+
+    #[test]
+    fn free_lamports_matches_native_accounting() {
+        const RESERVE: u64 = 100;
+        let active = stake(u64::MAX);
+        let delegated = active.delegation.stake.get();
+
+        // inactive: everything above the reserve, nothing at or below it
+        let inactive = |lamports| MergeKind::Inactive(meta(RESERVE), lamports, StakeFlags::empty());
+        assert_eq!(free_lamports(&inactive(RESERVE), RESERVE), Ok(0));
+        assert_eq!(free_lamports(&inactive(RESERVE + 1), RESERVE + 1), Ok(1));
+        assert_eq!(free_lamports(&inactive(RESERVE - 1), RESERVE - 1), Ok(0));
+
+        // fully active: delegated stake is not free either
+        let fully_active = MergeKind::FullyActive(meta(RESERVE), active);
+        assert_eq!(free_lamports(&fully_active, RESERVE + delegated), Ok(0));
+        assert_eq!(free_lamports(&fully_active, RESERVE + delegated + 1), Ok(1));
+        assert_eq!(free_lamports(&fully_active, delegated), Ok(0));
+
+        // activating stake cannot be drawn from at all
+        let activating = MergeKind::ActivationEpoch(meta(RESERVE), stake(EPOCH), StakeFlags::empty());
+        assert_eq!(free_lamports(&activating, RESERVE + delegated + 1), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn split_amounts_near_u64_max_do_not_overflow() {
+        // minimum balances saturate as in native instead of wrapping
+        let huge = meta(u64::MAX);
+        assert_eq!(
+            validate_split_amount(u64::MAX, 0, 1, &huge, 0, 1, false).map(|_| ()),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            validate_split_amount(u64::MAX, 0, 1, &meta(0), u64::MAX, 1, false).map(|_| ()),
+            Err(ProgramError::InsufficientFunds)
+        );
+        // the whole balance is always an acceptable split amount
+        let info = validate_split_amount(u64::MAX, u64::MAX, u64::MAX, &huge, u64::MAX, u64::MAX, true).unwrap();
+        assert_eq!(info.source_remaining_balance, 0);
+
+        assert_eq!(checked_add(u64::MAX, 1), Err(ProgramError::InsufficientFunds));
+        assert_eq!(checked_add(u64::MAX - 1, 1), Ok(u64::MAX));
+    }
+
+    /// Vote account data as the vote program writes it: commission 100, a
+    /// history past [`MAX_EPOCH_CREDITS`] with uneven credits per epoch
+    fn vote_data(v1_14_11: bool, epochs: u64) -> Vec<u8> {
+        use solana_sdk::vote::state::{VoteState1_14_11, VoteStateV3, VoteStateVersions};
+        let mut state = VoteStateV3 { commission: 100, ..VoteStateV3::default() };
+        let mut credits = 0;
+        for epoch in 0..epochs {
+            let prev = credits;
+            credits += 1_000 + epoch * epoch % 377;
+            state.epoch_credits.push((epoch, credits, prev));
+        }
+        let versioned = if v1_14_11 {
+            VoteStateVersions::V1_14_11(Box::new(VoteState1_14_11::from(state)))
         } else {
-            return Err(ProgramError::MissingRequiredSignature);
+            VoteStateVersions::new_current(state)
+        };
+        let mut data = bincode::serialize(&versioned).unwrap();
+        data.resize(VoteStateV3::size_of(), 0);
+        data
+    }
+
+    #[test]
+    fn credits_observed_is_the_vote_accounts_latest_credits() {
+        use crate::state::vote_state::MAX_EPOCH_CREDITS;
+        use solana_sdk::vote::state::VoteStateVersions;
+        for v1_14_11 in [false, true] {
+            for epochs in [0, 1, MAX_EPOCH_CREDITS as u64 + 6] {
+                let data = vote_data(v1_14_11, epochs);
+                let expected = match bincode::deserialize(&data).unwrap() {
+                    VoteStateVersions::Current(state) => state.epoch_credits.last().map_or(0, |&(_, credits, _)| credits),
+                    VoteStateVersions::V1_14_11(state) => state.epoch_credits.last().map_or(0, |&(_, credits, _)| credits),
+                    VoteStateVersions::V0_23_5(_) => unreachable!(),
+                };
+                let data: [u8; 3762] = data.try_into().unwrap();
+                let mut raw = test_accounts::raw([4; 32], crate::state::vote_state::ID, 0, data);
+                let vote = info(&mut raw);
+                let at = format!("v1_14_11={v1_14_11} epochs={epochs}");
+
+                // Delegate reads the credits in place; the VoteState path must agree
+                let credits = get_vote_credits(&vote).unwrap();
+                assert_eq!(credits, expected, "{at}");
+                let fresh = new_stake_with_credits(1_000, vote.key(), EPOCH, credits);
+                assert_eq!(u64::from_le_bytes(fresh.credits_observed), expected, "{at}");
+                let built = new_stake(1_000, vote.key(), &get_vote_state(&vote).unwrap(), EPOCH);
+                assert_eq!(built.credits_observed, fresh.credits_observed, "{at}");
+
+                // a deactivated delegation takes the new vote account's credits
+                let mut deactivated = stake(EPOCH - 5);
+                deactivated.delegation.deactivation_epoch = EpochLE::new(EPOCH - 4);
+                deactivated.set_credits_observed(u64::MAX);
+                redelegate_stake_with_credits(&mut deactivated, 1_000, vote.key(), credits, EPOCH, &NoHistory, NEW_RATE)
+                    .unwrap();
+                assert_eq!(deactivated.credits_observed, fresh.credits_observed, "{at}");
+            }
         }
-    } else {
-        None
-    };
+    }
 
-    Ok((signers, custodian))
+    #[test]
+    fn split_deficit_at_the_destination_reserve_boundary() {
+        // the conformance corpus's split_*_reserve and split_*_deficit cases
+        const RESERVE: u64 = 2_282_880;
+        const SOURCE: u64 = RESERVE + 2_000_000_000;
+        let meta = Meta { rent_exempt_reserve: LamportsLE::new(RESERVE), ..Meta::default() };
+        let min = get_minimum_delegation();
+        let validate = |destination, split, is_active| validate_split_amount(SOURCE, destination, split, &meta, RESERVE, min, is_active);
+
+        // active stake: the destination must already hold the reserve
+        let half = SOURCE / 2;
+        assert_eq!(validate(RESERVE - 1, half, true).unwrap_err(), ProgramError::InsufficientFunds);
+        assert!(validate(RESERVE, half, true).is_ok());
+        assert!(validate(RESERVE + 1, half, true).is_ok());
+
+        // inactive stake: the split covers the deficit, to the lamport, without
+        // wrapping when the destination already holds more than the reserve
+        for destination in [1, RESERVE - 1, RESERVE, RESERVE + 1, RESERVE + min, u64::MAX - SOURCE] {
+            let deficit = (RESERVE + min).saturating_sub(destination);
+            assert!(validate(destination, deficit.max(1), false).is_ok(), "destination {destination}");
+            if deficit > 1 {
+                assert_eq!(validate(destination, deficit - 1, false).unwrap_err(), ProgramError::InsufficientFunds);
+            }
+        }
+    }
 }
-
-   
@@ -0,0 +1,22 @@
+//! The epoch the 9% warmup/cooldown rate took effect.
+//!
+//! Native derives `new_rate_activation_epoch` from the runtime's feature set:
+//! the slot `reduce_stake_warmup_cooldown` activated at, converted to an epoch
+//! with the EpochSchedule. This program does not derive it. An on-chain
+//! program has no feature set, and the only trusted stand-in, the feature
+//! account as a required instruction account, would change the account layout
+//! of every instruction that does activation math and break compatibility with
+//! native clients. The account read only when a caller passes it would let the
+//! caller pick the rate. Like the native on-chain program, every instruction
+//! therefore uses [`PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH`] (the 9% rate
+//! from epoch 0), which is correct on any cluster whose stake history no
+//! longer reaches back to the activation. The `client` module projects
+//! activation with the same epoch.
+
+use crate::{helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH, state::EpochLE};
+
+/// `new_rate_activation_epoch` for the activation math of every instruction
+#[inline(always)]
+pub const fn new_warmup_cooldown_rate_epoch() -> Option<EpochLE> {
+    PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH
+}
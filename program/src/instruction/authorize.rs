@@ -1,13 +1,16 @@
 use pinocchio::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, sysvars::clock::Clock,
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
     ProgramResult,
 };
 
 use crate::{
-    helpers::{collect_signers, get_stake_state, set_stake_state, MAXIMUM_SIGNERS},
+    helpers::{Signers, get_stake_state, presented_custodian, set_stake_state},
     state::{stake_state_v2::StakeStateV2, StakeAuthorize},
 };
 use crate::helpers::authorize_update; 
+use crate::helpers::sysvar_cache;
 
 /*fn parse_authorize_data(data: &[u8]) -> Result<AuthorizeData, ProgramError> {
     if data.len() != 33 { return Err(ProgramError::InvalidInstructionData); }
@@ -21,33 +24,30 @@ use crate::helpers::authorize_update;
     Ok(AuthorizeData { new_authorized, stake_authorize })
 }*/
 
+/// Authorize, accounts [stake, clock, authority, custodian?] (roles::AUTHORIZE).
+/// A withdrawer change under an unexpired lockup needs the lockup's custodian
+/// signing in slot 3: `CustodianMissing` without a signer there, `LockupInForce`
+/// for any other one, both before the authority's signature is checked. Staker
+/// changes ignore the lockup.
 pub fn process_authorize(
     accounts: &[AccountInfo],
     new_authority: Pubkey,
     authority_type: StakeAuthorize,
 ) -> ProgramResult { 
-    if accounts.len() < 2 {
+    let [stake_ai, ..] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
-    }
-    let [stake_ai, clock_ai, rest @ ..] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
     };
 
     if *stake_ai.owner() != crate::ID || !stake_ai.is_writable() {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if clock_ai.key() != &pinocchio::sysvars::clock::CLOCK_ID {
-        return Err(ProgramError::InvalidArgument);
-    }
-    let clock = unsafe { Clock::from_account_info_unchecked(clock_ai)? };
-
-    // Optional lockup custodian (as a reference)
-    let maybe_lockup_authority: Option<&AccountInfo> = rest.first();
+    let clock = &sysvar_cache::clock_account(accounts)?;
 
     // Collect all signers
-    let mut signers_buf = [Pubkey::default(); MAXIMUM_SIGNERS];// Stack allocated
-    let n = collect_signers(accounts, &mut signers_buf)?;
-    let signers = &signers_buf[..n];
+    let signers = &Signers::collect(accounts)?;
+    // unsigned is not presented: native reports it as CustodianMissing, unlike
+    // the seeded variants' MissingRequiredSignature
+    let custodian = presented_custodian(accounts, 3);
 
     // Load, update, store
     match get_stake_state(stake_ai)? {
@@ -57,8 +57,8 @@ pub fn process_authorize(
                 new_authority,
                 authority_type,
                 signers,
-                maybe_lockup_authority,
-                &clock,
+                custodian,
+                clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Initialized(meta))?;
         }
@@ -68,12 +68,12 @@ pub fn process_authorize(
                 new_authority,
                 authority_type,
                 signers,
-                maybe_lockup_authority,
-                &clock,
+                custodian,
+                clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     }
 
     Ok(())
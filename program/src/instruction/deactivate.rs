@@ -1,54 +1,53 @@
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::clock::Clock,
     ProgramResult,
 };
 
+use crate::helpers::log::log_debug;
 use crate::{
     error::to_program_error,
-    helpers::{collect_signers, get_stake_state, next_account_info, set_stake_state, MAXIMUM_SIGNERS},
-    state::{stake_state_v2::StakeStateV2, StakeAuthorize},
+    helpers::{
+        check_staker_operation, Signers, deactivate_stake, get_stake_state, next_account_info,
+        set_stake_state,
+    },
+    state::{stake_state_v2::StakeStateV2, WindowedStakeHistory},
 };
+use crate::helpers::{sysvar_cache, warmup_rate};
 
 pub fn process_deactivate(accounts: &[AccountInfo]) -> ProgramResult {
     // 1) Gather all transaction signers
-    let mut signers_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let signers_len = collect_signers(accounts, &mut signers_buf)?;
-    let signers = &signers_buf[..signers_len];
+    let signers = &Signers::collect(accounts)?;
 
-    // 2) Accounts: stake, clock (extra accounts are ignored)
+    // 2) Accounts: roles::DEACTIVATE (clock located by pubkey; extra accounts are ignored)
     let it = &mut accounts.iter();
     let stake_ai = next_account_info(it)?;
-    let clock_ai = next_account_info(it)?;
-
-    if clock_ai.key() != &pinocchio::sysvars::clock::CLOCK_ID {
-        return Err(ProgramError::InvalidArgument);
-    }
-    let clock = Clock::from_account_info(clock_ai)?;
+    let clock = sysvar_cache::clock_account(accounts)?;
 
     // 3) Load stake state (also checks program owner inside helper)
     let state = get_stake_state(stake_ai)?;
 
     // 4) Authorization + state transition
     match state {
-        StakeStateV2::Stake(mut meta, mut stake, flags) => {
-            // Require staker signature
-            meta.authorized
-                .check(signers, StakeAuthorize::Staker)
+        StakeStateV2::Stake(meta, mut stake, mut flags) => {
+            // Require staker signature (or the withdrawer after lockup, under lenient-auth)
+            check_staker_operation(&meta, signers, &clock)?;
+
+            // Deactivating mid-activation is allowed unless the stake is flagged
+            // MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED; a second
+            // deactivation fails with AlreadyDeactivated
+            let stake_history = WindowedStakeHistory::new(clock.epoch);
+            let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+            deactivate_stake(&mut stake, &mut flags, clock.epoch, &stake_history, new_rate_activation_epoch)
                 .map_err(to_program_error)?;
-
-            // delegate to stake logic — this enforces flags / “already deactivated” etc.
-            stake
-                .deactivate(clock.epoch.to_le_bytes())
-                .map_err(to_program_error)?;
-            pinocchio::msg!("deactivate: set_epoch");
+            log_debug!("deactivate: set_epoch");
 
             // 5) Write back
             set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::Initialized(_) | StakeStateV2::RewardsPool => {
+            return Err(ProgramError::InvalidAccountData)
+        }
     }
 
     Ok(())
@@ -2,79 +2,67 @@
 extern crate alloc;
 
 use pinocchio::{
-    account_info::AccountInfo,
-    msg,
+    account_info::{AccountInfo, Ref},
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::{clock::Clock, Sysvar},
+    pubkey::pubkey_eq,
     ProgramResult,
 };
 
+use crate::helpers::log::log_debug;
 use crate::{
     error::{to_program_error, StakeError},
-    helpers::{get_stake_state, next_account_info, set_stake_state},
+    helpers::{deactivate_stake, get_stake_state, next_account_info, set_stake_state},
     state::{
         stake_state_v2::StakeStateV2,
-        vote_state::vote_program_id,
+        WindowedStakeHistory,
+        vote_state::{vote_program_id, EpochCreditsView},
     },
 };
 use crate::helpers::constant::MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION;
+use crate::helpers::{sysvar_cache, warmup_rate};
 
 pub fn process_deactivate_delinquent(accounts: &[AccountInfo]) -> ProgramResult {
-    msg!("Instruction: DeactivateDelinquent");
+    log_debug!("Instruction: DeactivateDelinquent");
 
-    // --- Accounts: stake, delinquent_vote, reference_vote ---
+    // --- Accounts: roles::DEACTIVATE_DELINQUENT ---
     let iter = &mut accounts.iter();
     let stake_ai           = next_account_info(iter)?;
     let delinquent_vote_ai = next_account_info(iter)?;
     let reference_vote_ai  = next_account_info(iter)?;
 
-    // --- Clock (use current epoch) ---
-    let clock = Clock::get()?;
+    // --- Both vote accounts: vote program owned, serialized vote state ---
+    let delinquent_data = vote_account_data(delinquent_vote_ai)?;
+    let reference_data = vote_account_data(reference_vote_ai)?;
+    let delinquent_credits = EpochCreditsView::parse(&delinquent_data)?;
+    let reference_credits = EpochCreditsView::parse(&reference_data)?;
 
-    // --- Optional owner check for vote accounts ---
-    let vote_pid = vote_program_id();
-    if vote_pid != Pubkey::default() {
-        if *reference_vote_ai.owner() != vote_pid || *delinquent_vote_ai.owner() != vote_pid {
-            return Err(ProgramError::IncorrectProgramId);
-        }
-    }
+    // --- Clock (use current epoch), read once the vote accounts check out ---
+    let clock = sysvar_cache::clock(accounts)?;
 
     // --- 1) Reference must have a vote in EACH of the last N epochs (strict consecutive) ---
-    {
-        let data = reference_vote_ai.try_borrow_data()?;
-        let ok = acceptable_reference_epoch_credits_bytes(
-            &data,
-            clock.epoch,
-            MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION,
-        )?;
-        if !ok {
-            return Err(to_program_error(StakeError::InsufficientReferenceVotes));
-        }
+    if !acceptable_reference_epoch_credits(&reference_credits, clock.epoch, MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION) {
+        return Err(to_program_error(StakeError::InsufficientReferenceVotes));
     }
 
     // --- 2) Delinquent last vote epoch <= current_epoch - N  ---
-    let delinquent_is_eligible = {
-        let data = delinquent_vote_ai.try_borrow_data()?;
-        match last_vote_epoch_bytes(&data)? {
-            None => true, // never voted => eligible
-            Some(last_epoch) => match clock.epoch.checked_sub(MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION) {
-                Some(min_epoch) => last_epoch <= min_epoch,
-                None => false,
-            }
-        }
-    };
+    let delinquent_is_eligible = eligible_for_deactivate_delinquent(
+        &delinquent_credits,
+        clock.epoch,
+        MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION,
+    );
 
     // --- 3) Load stake state, verify delegation target, deactivate if eligible ---
     match get_stake_state(stake_ai)? {
-        StakeStateV2::Stake(meta, mut stake, flags) => {
-            if stake.delegation.voter_pubkey != *delinquent_vote_ai.key() {
+        StakeStateV2::Stake(meta, mut stake, mut flags) => {
+            if !pubkey_eq(&stake.delegation.voter_pubkey, delinquent_vote_ai.key()) {
                 return Err(to_program_error(StakeError::VoteAddressMismatch));
             }
 
             if delinquent_is_eligible {
                 // Set deactivation_epoch = current epoch
-                stake.deactivate(clock.epoch.to_le_bytes())
+                let stake_history = WindowedStakeHistory::new(clock.epoch);
+                let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+                deactivate_stake(&mut stake, &mut flags, clock.epoch, &stake_history, new_rate_activation_epoch)
                     .map_err(to_program_error)?;
                 set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))
             } else {
@@ -83,133 +71,102 @@ pub fn process_deactivate_delinquent(accounts: &[AccountInfo]) -> ProgramResult
                 ))
             }
         }
-        _ => Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::Initialized(_) | StakeStateV2::RewardsPool => {
+            Err(ProgramError::InvalidAccountData)
+        }
     }
 }
 
 
-fn acceptable_reference_epoch_credits_bytes(
-    data: &[u8],
-    current_epoch: u64,
-    n: u64,
-) -> Result<bool, ProgramError> {
-    // Layout assumed by tests:
-    // [0..4] u32 count, then `count` * (epoch:u64, credits:u64, prev:u64)
-    if data.len() < 4 {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    let mut n_bytes = [0u8; 4];
-    n_bytes.copy_from_slice(&data[0..4]);
-    let count = u32::from_le_bytes(n_bytes) as usize;
-
-    if count < n as usize {
-        return Ok(false);
+fn vote_account_data(vote_ai: &AccountInfo) -> Result<Ref<'_, [u8]>, ProgramError> {
+    if *vote_ai.owner() != vote_program_id() {
+        return Err(ProgramError::IncorrectProgramId);
     }
+    vote_ai.try_borrow_data()
+}
 
-    // Start at the first of the last N entries and walk them in reverse
-    // to compare: last => current_epoch, previous => current_epoch - 1, ...
-    for i in 0..(n as usize) {
-        // index of the (count - 1 - i)-th entry
-        let entry_index = count - 1 - i;
-        let off = 4 + entry_index * 24;
-        if off + 8 > data.len() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let mut e = [0u8; 8];
-        e.copy_from_slice(&data[off..off + 8]);
-        let vote_epoch = u64::from_le_bytes(e);
-
-        let expected = current_epoch.saturating_sub(i as u64);
-        if vote_epoch != expected {
-            return Ok(false);
+fn acceptable_reference_epoch_credits(credits: &EpochCreditsView, current_epoch: u64, n: u64) -> bool {
+    let Some(first) = credits.len().checked_sub(n as usize) else {
+        return false;
+    };
+    // last => current_epoch, previous => current_epoch - 1, ...
+    let mut expected = current_epoch;
+    for i in (first..credits.len()).rev() {
+        match credits.get(i) {
+            Some((vote_epoch, _, _)) if vote_epoch == expected => {}
+            _ => return false,
         }
+        expected = expected.saturating_sub(1);
     }
-    Ok(true)
+    true
 }
 
-fn last_vote_epoch_bytes(data: &[u8]) -> Result<Option<u64>, ProgramError> {
-    if data.len() < 4 {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    let mut n_bytes = [0u8; 4];
-    n_bytes.copy_from_slice(&data[0..4]);
-    let count = u32::from_le_bytes(n_bytes) as usize;
-    if count == 0 {
-        return Ok(None);
+fn eligible_for_deactivate_delinquent(credits: &EpochCreditsView, current_epoch: u64, n: u64) -> bool {
+    match credits.last() {
+        None => true, // never voted => eligible
+        Some((last_epoch, _, _)) => match current_epoch.checked_sub(n) {
+            Some(min_epoch) => last_epoch <= min_epoch,
+            None => false,
+        },
     }
-    let off = 4 + (count - 1) * 24;
-    if off + 8 > data.len() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    let mut e = [0u8; 8];
-    e.copy_from_slice(&data[off..off + 8]);
-    Ok(Some(u64::from_le_bytes(e)))
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn build_epoch_credits_bytes(list: &[(u64, u64, u64)]) -> alloc::vec::Vec<u8> {
-        use alloc::vec::Vec;
-        let mut out = Vec::with_capacity(4 + list.len() * 24);
-        out.extend_from_slice(&(list.len() as u32).to_le_bytes());
-        for &(e, c, p) in list {
-            out.extend_from_slice(&e.to_le_bytes());
-            out.extend_from_slice(&c.to_le_bytes());
-            out.extend_from_slice(&p.to_le_bytes());
-        }
-        out
+    fn entries(list: &[(u64, u64, u64)]) -> alloc::vec::Vec<u8> {
+        list.iter()
+            .flat_map(|&(e, c, p)| [e, c, p])
+            .flat_map(u64::to_le_bytes)
+            .collect()
     }
 
-   #[test]
-fn reference_has_all_last_n_epochs() {
-    // current = 100, need epochs 100..=96 present
-    let current = 100;
-    let bytes = build_epoch_credits_bytes(&[
-        (96, 1, 0),
-        (97, 2, 1),
-        (98, 3, 2),
-        (99, 4, 3),
-        (100, 5, 4),
-    ]);
-    assert!(acceptable_reference_epoch_credits_bytes(&bytes, current, 5).unwrap());
-}
+    fn acceptable(list: &[(u64, u64, u64)], current: u64) -> bool {
+        let bytes = entries(list);
+        acceptable_reference_epoch_credits(&EpochCreditsView::from_entries(&bytes), current, 5)
+    }
 
-#[test]
-fn reference_missing_one_epoch_fails() {
-    // Missing 98 in the last 5 => should fail
-    let current = 100;
-    let bytes = build_epoch_credits_bytes(&[
-        (96, 1, 0),
-        (97, 2, 1),
-        //(98 missing)
-        (99, 4, 3),
-        (100, 5, 4),
-    ]);
-    assert!(!acceptable_reference_epoch_credits_bytes(&bytes, current, 5).unwrap());
-}
+    #[test]
+    fn reference_has_all_last_n_epochs() {
+        // current = 100, need epochs 100..=96 present
+        assert!(acceptable(&[(95, 0, 0), (96, 1, 0), (97, 2, 1), (98, 3, 2), (99, 4, 3), (100, 5, 4)], 100));
+    }
 
-#[test]
-fn delinquent_if_last_vote_older_than_n() {
-    // current=100, N=5 => min_epoch = 95
-    // last=94 => 94 <= 95 => eligible (delinquent)
-    let current = 100;
-    let bytes = build_epoch_credits_bytes(&[(94, 5, 0)]);
-    let last = last_vote_epoch_bytes(&bytes).unwrap();
-    assert_eq!(last, Some(94));
-    let min_epoch = current - 5;
-    assert!(last.unwrap() <= min_epoch);
-}
+    #[test]
+    fn reference_missing_one_epoch_fails() {
+        // Missing 98 in the last 5 => should fail
+        assert!(!acceptable(&[(96, 1, 0), (97, 2, 1), (99, 4, 3), (100, 5, 4)], 100));
+        assert!(!acceptable(&[(95, 1, 0), (96, 1, 0), (97, 2, 1), (99, 4, 3), (100, 5, 4)], 100));
+        // the newest entry must be the current epoch
+        assert!(!acceptable(&[(95, 1, 0), (96, 1, 0), (97, 2, 1), (98, 4, 3), (99, 5, 4)], 100));
+    }
 
-#[test]
-fn not_delinquent_if_last_vote_within_n() {
-    // current=100, N=5 => min_epoch=95
-    // last=97 => 97 > 95 => NOT delinquent
-    let current = 100;
-    let bytes = build_epoch_credits_bytes(&[(97, 5, 0)]);
-    let last = last_vote_epoch_bytes(&bytes).unwrap();
-    assert_eq!(last, Some(97));
-    let min_epoch = current - 5;
-    assert!(!(last.unwrap() <= min_epoch));
-}
+    fn eligible(list: &[(u64, u64, u64)], current: u64) -> bool {
+        let bytes = entries(list);
+        eligible_for_deactivate_delinquent(&EpochCreditsView::from_entries(&bytes), current, 5)
+    }
+
+    #[test]
+    fn delinquent_if_last_vote_older_than_n() {
+        // current=100, N=5 => min_epoch = 95; last=94 => eligible
+        assert!(eligible(&[(94, 5, 0)], 100));
+        assert!(eligible(&[(90, 1, 0), (95, 5, 0)], 100));
+        // never voted
+        assert!(eligible(&[], 100));
+    }
+
+    #[test]
+    fn not_delinquent_if_last_vote_within_n() {
+        // current=100, N=5 => min_epoch=95; last=97 => NOT delinquent
+        assert!(!eligible(&[(97, 5, 0)], 100));
+        // too early in the cluster's life to be delinquent
+        assert!(!eligible(&[(0, 5, 0)], 4));
+    }
+
+    #[test]
+    fn reference_near_genesis_saturates() {
+        // native walks the expected epoch down with saturating_sub
+        assert!(acceptable(&[(0, 1, 0), (0, 1, 0), (0, 1, 0), (1, 1, 0), (2, 1, 0)], 2));
+    }
 }
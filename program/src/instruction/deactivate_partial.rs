@@ -0,0 +1,63 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::helpers::log::log_debug;
+use crate::{
+    error::to_program_error,
+    helpers::{Signers, deactivate_stake, ensure_distinct, get_stake_state, set_stake_state},
+    instruction::split::process_split,
+    state::{stake_state_v2::StakeStateV2, StakeAuthorize, WindowedStakeHistory},
+};
+use crate::helpers::{sysvar_cache, warmup_rate};
+
+/// DeactivatePartial (extension): Split `lamports` into an uninitialized
+/// destination and deactivate only the split-off stake, so the source keeps
+/// the remainder active. Same result as Split followed by Deactivate on the
+/// destination, in one instruction.
+///
+/// Accounts: [`DEACTIVATE_PARTIAL`](super::roles::DEACTIVATE_PARTIAL).
+pub fn process_deactivate_partial(accounts: &[AccountInfo], lamports: u64) -> ProgramResult {
+    let signers = &Signers::collect(accounts)?;
+
+    let [stake_ai, split_ai, _, authority_ai, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    ensure_distinct(&[stake_ai, split_ai], ProgramError::InvalidAccountData)?;
+    let clock = sysvar_cache::clock_account(accounts)?;
+
+    // Only a delegated stake can be partially deactivated, and something must stay behind;
+    // deactivating everything is plain Deactivate
+    match get_stake_state(stake_ai)? {
+        StakeStateV2::Stake(meta, _, _) => {
+            meta.authorized
+                .check(signers, StakeAuthorize::Staker)
+                .map_err(to_program_error)?;
+        }
+        StakeStateV2::Uninitialized | StakeStateV2::Initialized(_) | StakeStateV2::RewardsPool => {
+            return Err(ProgramError::InvalidAccountData)
+        }
+    }
+    if lamports >= stake_ai.lamports() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Split does the delegation math (rent reserve, minimum delegation on both sides)
+    log_debug!("DeactivatePartial: split");
+    process_split(&[*stake_ai, *split_ai, *authority_ai], lamports)?;
+
+    log_debug!("DeactivatePartial: deactivate split");
+    match get_stake_state(split_ai)? {
+        StakeStateV2::Stake(meta, mut stake, mut flags) => {
+            // Split carries the source's flags over, so the same activation rule applies
+            let stake_history = WindowedStakeHistory::new(clock.epoch);
+            let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+            deactivate_stake(&mut stake, &mut flags, clock.epoch, &stake_history, new_rate_activation_epoch)
+                .map_err(to_program_error)?;
+            set_stake_state(split_ai, &StakeStateV2::Stake(meta, stake, flags))
+        }
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
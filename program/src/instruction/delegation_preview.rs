@@ -0,0 +1,94 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::{
+    helpers::{checked_add, get_minimum_delegation, get_stake_state, next_account_info},
+    state::{stake_state_v2::StakeStateV2, Meta},
+};
+
+/// What DelegateStake would create from the account as it stands, so wallets
+/// can pre-validate through simulation instead of decoding state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelegationPreview {
+    /// `lamports - rent_exempt_reserve` (saturating), exactly as delegate computes it;
+    /// lamports topped up after Initialize are included
+    pub stake_amount: u64,
+    /// `stake_amount` reaches the minimum delegation
+    pub meets_minimum: bool,
+    /// Lamports still missing to reach the minimum delegation, 0 if met
+    pub top_up: u64,
+}
+
+impl DelegationPreview {
+    pub const LEN: usize = 8 + 1 + 8;
+
+    /// `InsufficientFunds` if reserve plus minimum delegation overflows
+    pub fn new(lamports: u64, meta: &Meta) -> Result<Self, ProgramError> {
+        let minimum = get_minimum_delegation();
+        // saturating as native's delegate: lamports below the reserve delegate 0
+        let stake_amount = lamports.saturating_sub(meta.rent_exempt_reserve.get());
+        let required = checked_add(meta.rent_exempt_reserve.get(), minimum)?;
+        // clamped: nothing is missing once `required` is met
+        let top_up = required.saturating_sub(lamports);
+        Ok(Self { stake_amount, meets_minimum: stake_amount >= minimum, top_up })
+    }
+
+    /// Return data: stake_amount (u64 LE), meets_minimum (u8), top_up (u64 LE)
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[..8].copy_from_slice(&self.stake_amount.to_le_bytes());
+        out[8] = self.meets_minimum as u8;
+        out[9..].copy_from_slice(&self.top_up.to_le_bytes());
+        out
+    }
+}
+
+/// GetDelegationPreview (extension): read-only query over an Initialized
+/// stake account. Accounts: [`GET_DELEGATION_PREVIEW`](super::roles::GET_DELEGATION_PREVIEW).
+pub fn process_get_delegation_preview(accounts: &[AccountInfo]) -> Result<DelegationPreview, ProgramError> {
+    let stake_ai = next_account_info(&mut accounts.iter())?;
+    match get_stake_state(stake_ai)? {
+        StakeStateV2::Initialized(meta) => DelegationPreview::new(stake_ai.lamports(), &meta),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::LamportsLE;
+
+    fn meta(reserve: u64) -> Meta {
+        Meta { rent_exempt_reserve: LamportsLE::new(reserve), ..Meta::default() }
+    }
+
+    #[test]
+    fn preview_tracks_lamports_above_reserve() {
+        let minimum = get_minimum_delegation();
+        let reserve = 2_282_880;
+
+        let funded = DelegationPreview::new(reserve + minimum + 5, &meta(reserve)).unwrap();
+        assert_eq!(funded, DelegationPreview { stake_amount: minimum + 5, meets_minimum: true, top_up: 0 });
+
+        let exact = DelegationPreview::new(reserve + minimum, &meta(reserve)).unwrap();
+        assert!(exact.meets_minimum);
+
+        let rent_only = DelegationPreview::new(reserve, &meta(reserve)).unwrap();
+        assert_eq!(rent_only, DelegationPreview { stake_amount: 0, meets_minimum: false, top_up: minimum });
+
+        // under-funded below the reserve itself: the top-up covers both
+        let short = DelegationPreview::new(reserve - 10, &meta(reserve)).unwrap();
+        assert_eq!(short, DelegationPreview { stake_amount: 0, meets_minimum: false, top_up: minimum + 10 });
+
+        // a reserve near u64::MAX: the required balance overflows rather than
+        // saturating into "met"
+        assert_eq!(DelegationPreview::new(u64::MAX, &meta(u64::MAX)), Err(ProgramError::InsufficientFunds));
+    }
+
+    #[test]
+    fn return_data_layout() {
+        let bytes = DelegationPreview { stake_amount: 7, meets_minimum: true, top_up: 0x0102 }.to_bytes();
+        assert_eq!(bytes[..8], 7u64.to_le_bytes());
+        assert_eq!(bytes[8], 1);
+        assert_eq!(bytes[9..], 0x0102u64.to_le_bytes());
+    }
+}
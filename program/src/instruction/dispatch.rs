@@ -0,0 +1,794 @@
+//! Table-driven dispatch: one [`Descriptor`] per discriminant.
+//!
+//! The dispatcher turns an instruction away in native's order before any
+//! handler work: unknown discriminant, then the epoch-rewards gate, then the
+//! payload (bad instruction data wins over missing accounts), then the
+//! minimum account count and the writable roles. Fixed-size and empty payloads are checked from the
+//! descriptor; variable ones are parsed by the handler, which only gets the
+//! account list through [`Accounts::require`] once its payload has decoded.
+//!
+//! Each descriptor carries its account layout from [`roles`]; with `std`,
+//! [`instructions`] lists them for doc generators and client SDKs.
+
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+
+use super::{
+    roles::{self, AccountRole},
+    StakeInstruction,
+};
+use crate::{
+    error::{to_program_error, StakeError},
+    helpers::log::{log_debug, log_error},
+    state::{
+        accounts::{AuthorizeCheckedWithSeedData, AuthorizeWithSeedData, SetLockupData},
+        StakeAuthorize,
+    },
+};
+
+/// How the dispatcher checks an instruction's data after the discriminant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// No arguments; trailing bytes are rejected
+    Empty,
+    /// Exactly this many bytes
+    Fixed(usize),
+    /// Parsed (and rejected) by the handler
+    Variable,
+}
+
+/// The account list, released to a handler once its shape is checked
+pub struct Accounts<'a> {
+    accounts: &'a [AccountInfo],
+    min: usize,
+    layout: &'static [AccountRole],
+}
+
+impl<'a> Accounts<'a> {
+    /// `NotEnoughAccountKeys` unless the list covers what the handler
+    /// destructures before it reads anything else, then `InvalidAccountData`
+    /// for a read-only account in a required writable role. Native only fails
+    /// once it writes (with an opaque `ProgramFailedToComplete`); this fails
+    /// before any handler work. Optional writable roles are told apart by
+    /// being writable, so are left to the handler.
+    #[inline(always)]
+    pub fn require(self) -> Result<&'a [AccountInfo], ProgramError> {
+        let accounts = self.require_count()?;
+        for (role, ai) in self.layout.iter().zip(accounts) {
+            if role.writable && !role.optional && !ai.is_writable() {
+                log_error!("account not writable:");
+                log_error!(role.name());
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// [`require`](Self::require) without the writable check, for handlers
+    /// that check it themselves where native does
+    #[inline(always)]
+    pub fn require_count(&self) -> Result<&'a [AccountInfo], ProgramError> {
+        if self.accounts.len() < self.min {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        Ok(self.accounts)
+    }
+}
+
+type Handler = fn(Accounts, &[u8]) -> ProgramResult;
+
+pub struct Descriptor {
+    pub discriminant: u8,
+    pub instruction: StakeInstruction,
+    pub name: &'static str,
+    /// Accounts the handler destructures up front; handlers still check their
+    /// full (sometimes variable) layout themselves
+    pub min_accounts: usize,
+    pub payload: Payload,
+    /// Whether the instruction fails with `EpochRewardsActive` while rewards
+    /// are being distributed; as in native only the read-only queries are exempt
+    pub checks_epoch_rewards: bool,
+    /// Account layout, in the order the native builders pass it
+    pub accounts: &'static [AccountRole],
+    handler: Handler,
+}
+
+/// Native instructions, indexed by discriminant
+#[allow(deprecated)]
+static NATIVE: [Descriptor; 18] = [
+    Descriptor {
+        discriminant: 0,
+        instruction: StakeInstruction::Initialize,
+        name: "Initialize",
+        min_accounts: 1,
+        // this program's 112 argument bytes, or native bincode's (see `parse_initialize`)
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::INITIALIZE,
+        handler: initialize,
+    },
+    Descriptor {
+        discriminant: 1,
+        instruction: StakeInstruction::Authorize,
+        name: "Authorize",
+        min_accounts: 1,
+        // new authority, role
+        payload: Payload::Fixed(33),
+        checks_epoch_rewards: true,
+        accounts: roles::AUTHORIZE,
+        handler: authorize,
+    },
+    Descriptor {
+        discriminant: 2,
+        instruction: StakeInstruction::DelegateStake,
+        name: "DelegateStake",
+        min_accounts: 5,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::DELEGATE_STAKE,
+        handler: delegate_stake,
+    },
+    Descriptor {
+        discriminant: 3,
+        instruction: StakeInstruction::Split,
+        name: "Split",
+        min_accounts: 3,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::SPLIT,
+        handler: split,
+    },
+    Descriptor {
+        discriminant: 4,
+        instruction: StakeInstruction::Withdraw,
+        name: "Withdraw",
+        min_accounts: 5,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::WITHDRAW,
+        handler: withdraw,
+    },
+    Descriptor {
+        discriminant: 5,
+        instruction: StakeInstruction::Deactivate,
+        name: "Deactivate",
+        min_accounts: 1,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::DEACTIVATE,
+        handler: deactivate,
+    },
+    Descriptor {
+        discriminant: 6,
+        instruction: StakeInstruction::SetLockup,
+        name: "SetLockup",
+        min_accounts: 1,
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::SET_LOCKUP,
+        handler: set_lockup,
+    },
+    Descriptor {
+        discriminant: 7,
+        instruction: StakeInstruction::Merge,
+        name: "Merge",
+        min_accounts: 4,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::MERGE,
+        handler: merge,
+    },
+    Descriptor {
+        discriminant: 8,
+        instruction: StakeInstruction::AuthorizeWithSeed,
+        name: "AuthorizeWithSeed",
+        min_accounts: 3,
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::AUTHORIZE_WITH_SEED,
+        handler: authorize_with_seed,
+    },
+    Descriptor {
+        discriminant: 9,
+        instruction: StakeInstruction::InitializeChecked,
+        name: "InitializeChecked",
+        min_accounts: 3,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::INITIALIZE_CHECKED,
+        handler: initialize_checked,
+    },
+    Descriptor {
+        discriminant: 10,
+        instruction: StakeInstruction::AuthorizeChecked,
+        name: "AuthorizeChecked",
+        min_accounts: 4,
+        // role
+        payload: Payload::Fixed(1),
+        checks_epoch_rewards: true,
+        accounts: roles::AUTHORIZE_CHECKED,
+        handler: authorize_checked,
+    },
+    Descriptor {
+        discriminant: 11,
+        instruction: StakeInstruction::AuthorizeCheckedWithSeed,
+        name: "AuthorizeCheckedWithSeed",
+        min_accounts: 4,
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::AUTHORIZE_CHECKED_WITH_SEED,
+        handler: authorize_checked_with_seed,
+    },
+    Descriptor {
+        discriminant: 12,
+        instruction: StakeInstruction::SetLockupChecked,
+        name: "SetLockupChecked",
+        min_accounts: 1,
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::SET_LOCKUP_CHECKED,
+        handler: set_lockup_checked,
+    },
+    Descriptor {
+        discriminant: 13,
+        instruction: StakeInstruction::GetMinimumDelegation,
+        name: "GetMinimumDelegation",
+        min_accounts: 0,
+        payload: Payload::Empty,
+        checks_epoch_rewards: false,
+        accounts: roles::GET_MINIMUM_DELEGATION,
+        handler: get_minimum_delegation,
+    },
+    Descriptor {
+        discriminant: 14,
+        instruction: StakeInstruction::DeactivateDelinquent,
+        name: "DeactivateDelinquent",
+        min_accounts: 3,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::DEACTIVATE_DELINQUENT,
+        handler: deactivate_delinquent,
+    },
+    Descriptor {
+        discriminant: 15,
+        instruction: StakeInstruction::Redelegate,
+        name: "Redelegate",
+        min_accounts: 3,
+        // never activated: any data is rejected unless the legacy handler is built in
+        payload: if cfg!(feature = "redelegate-legacy") { Payload::Empty } else { Payload::Variable },
+        checks_epoch_rewards: true,
+        accounts: roles::REDELEGATE,
+        handler: redelegate,
+    },
+    Descriptor {
+        discriminant: 16,
+        instruction: StakeInstruction::MoveStake,
+        name: "MoveStake",
+        min_accounts: 3,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::MOVE_STAKE,
+        handler: move_stake,
+    },
+    Descriptor {
+        discriminant: 17,
+        instruction: StakeInstruction::MoveLamports,
+        name: "MoveLamports",
+        min_accounts: 3,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::MOVE_LAMPORTS,
+        handler: move_lamports,
+    },
+];
+
+#[cfg(feature = "extensions")]
+const EXTENSION_COUNT: usize = 8 + cfg!(feature = "withdraw-to-token") as usize;
+
+/// Program-local instructions, in discriminant order
+#[cfg(feature = "extensions")]
+static EXTENSIONS: [Descriptor; EXTENSION_COUNT] = [
+    Descriptor {
+        discriminant: super::DEACTIVATE_PARTIAL_DISCRIMINATOR,
+        instruction: StakeInstruction::DeactivatePartial,
+        name: "DeactivatePartial",
+        min_accounts: 4,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::DEACTIVATE_PARTIAL,
+        handler: deactivate_partial,
+    },
+    Descriptor {
+        discriminant: super::WITHDRAW_EXCESS_DISCRIMINATOR,
+        instruction: StakeInstruction::WithdrawExcess,
+        name: "WithdrawExcess",
+        min_accounts: 5,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::WITHDRAW_EXCESS,
+        handler: withdraw_excess,
+    },
+    Descriptor {
+        discriminant: super::SPLIT_WITH_SEED_DISCRIMINATOR,
+        instruction: StakeInstruction::SplitWithSeed,
+        name: "SplitWithSeed",
+        min_accounts: 5,
+        payload: Payload::Variable,
+        checks_epoch_rewards: true,
+        accounts: roles::SPLIT_WITH_SEED,
+        handler: split_with_seed,
+    },
+    Descriptor {
+        discriminant: super::GET_DELEGATION_PREVIEW_DISCRIMINATOR,
+        instruction: StakeInstruction::GetDelegationPreview,
+        name: "GetDelegationPreview",
+        min_accounts: 1,
+        payload: Payload::Empty,
+        checks_epoch_rewards: false,
+        accounts: roles::GET_DELEGATION_PREVIEW,
+        handler: get_delegation_preview,
+    },
+    Descriptor {
+        discriminant: super::CLOSE_STAKE_ACCOUNT_DISCRIMINATOR,
+        instruction: StakeInstruction::CloseStakeAccount,
+        name: "CloseStakeAccount",
+        min_accounts: 2,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::CLOSE_STAKE_ACCOUNT,
+        handler: close_stake_account,
+    },
+    Descriptor {
+        discriminant: super::MERGE_MANY_DISCRIMINATOR,
+        instruction: StakeInstruction::MergeMany,
+        name: "MergeMany",
+        min_accounts: 5,
+        payload: Payload::Empty,
+        checks_epoch_rewards: true,
+        accounts: roles::MERGE_MANY,
+        handler: merge_many,
+    },
+    Descriptor {
+        discriminant: super::QUERY_PARAMS_DISCRIMINATOR,
+        instruction: StakeInstruction::QueryParams,
+        name: "QueryParams",
+        min_accounts: 0,
+        payload: Payload::Empty,
+        checks_epoch_rewards: false,
+        accounts: roles::QUERY_PARAMS,
+        handler: query_params,
+    },
+    #[cfg(feature = "withdraw-to-token")]
+    Descriptor {
+        discriminant: super::WITHDRAW_TO_TOKEN_ACCOUNT_DISCRIMINATOR,
+        instruction: StakeInstruction::WithdrawToTokenAccount,
+        name: "WithdrawToTokenAccount",
+        min_accounts: 5,
+        // lamports
+        payload: Payload::Fixed(8),
+        checks_epoch_rewards: true,
+        accounts: roles::WITHDRAW_TO_TOKEN_ACCOUNT,
+        handler: withdraw_to_token_account,
+    },
+    Descriptor {
+        discriminant: super::GET_VERSION_DISCRIMINATOR,
+        instruction: StakeInstruction::GetVersion,
+        name: "GetVersion",
+        min_accounts: 0,
+        payload: Payload::Empty,
+        checks_epoch_rewards: false,
+        accounts: roles::GET_VERSION,
+        handler: get_version,
+    },
+];
+
+/// The descriptor for `discriminant`, if the build knows the instruction
+#[inline(always)]
+pub fn descriptor(discriminant: u8) -> Option<&'static Descriptor> {
+    match discriminant {
+        0..=17 => NATIVE.get(discriminant as usize),
+        // feature-gated entries leave gaps, so extensions are matched, not indexed
+        #[cfg(feature = "extensions")]
+        128.. => EXTENSIONS.iter().find(|d| d.discriminant == discriminant),
+        _ => None,
+    }
+}
+
+/// Every instruction this build accepts, in discriminant order
+#[cfg(feature = "std")]
+pub fn instructions() -> impl Iterator<Item = &'static Descriptor> {
+    #[cfg(feature = "extensions")]
+    let extensions = EXTENSIONS.iter();
+    #[cfg(not(feature = "extensions"))]
+    let extensions: &'static [Descriptor] = &[];
+    #[cfg(not(feature = "extensions"))]
+    let extensions = extensions.iter();
+    NATIVE.iter().chain(extensions)
+}
+
+/// Decode and run a single-byte-discriminator instruction
+pub fn process(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let (disc, payload) = instruction_data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    let descriptor = descriptor(*disc).ok_or(ProgramError::InvalidInstructionData)?;
+
+    if descriptor.checks_epoch_rewards && epoch_rewards_active() {
+        return Err(to_program_error(StakeError::EpochRewardsActive));
+    }
+    match descriptor.payload {
+        Payload::Empty if !payload.is_empty() => return Err(ProgramError::InvalidInstructionData),
+        Payload::Fixed(len) if payload.len() != len => return Err(ProgramError::InvalidInstructionData),
+        _ => {}
+    }
+    #[cfg(feature = "metrics")]
+    let snapshot = crate::helpers::metrics::Snapshot::take(accounts);
+    let result = (descriptor.handler)(Accounts { accounts, min: descriptor.min_accounts, layout: descriptor.accounts }, payload);
+    #[cfg(feature = "metrics")]
+    crate::helpers::metrics::log(descriptor.name, accounts, &snapshot, &result);
+    #[cfg(debug_assertions)]
+    if result.is_ok() {
+        crate::helpers::invariants::check_accounts(accounts);
+    }
+    result
+}
+
+/// The account list of an instruction decoded from the SDK's bincode
+/// encoding (the entrypoint's `wire_bincode` path), checked for
+/// `discriminant` as [`process`] checks it before the handler: the minimum
+/// account count, then the writable roles
+#[cfg(all(feature = "std", feature = "wire_bincode"))]
+pub fn require_accounts(discriminant: u8, accounts: &[AccountInfo]) -> Result<&[AccountInfo], ProgramError> {
+    let descriptor = descriptor(discriminant).ok_or(ProgramError::InvalidInstructionData)?;
+    let accounts = Accounts { accounts, min: descriptor.min_accounts, layout: descriptor.accounts };
+    match descriptor.instruction {
+        // writability is checked after the authority, as in `move_stake` and `move_lamports`
+        StakeInstruction::MoveStake | StakeInstruction::MoveLamports => accounts.require_count(),
+        _ => accounts.require(),
+    }
+}
+
+// ---- EpochRewards gating: the sysvar's `active` flag, false where unreadable ----
+pub(crate) fn epoch_rewards_active() -> bool {
+    crate::helpers::sysvars::EpochRewards::is_active()
+}
+
+// ---- Handlers: decode what the policy left over, then require the accounts ----
+
+fn read_u64(payload: &[u8]) -> u64 {
+    u64::from_le_bytes(payload.try_into().unwrap())
+}
+
+fn read_pubkey(payload: &[u8]) -> Pubkey {
+    payload.try_into().unwrap()
+}
+
+fn read_role(role: u8) -> Result<StakeAuthorize, ProgramError> {
+    match role {
+        0 => Ok(StakeAuthorize::Staker),
+        1 => Ok(StakeAuthorize::Withdrawer),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Return data for the caller, in every build profile; pinocchio makes the
+/// syscall a no-op where there is no runtime to receive it (host targets)
+pub(crate) fn return_data(data: &[u8]) {
+    pinocchio::program::set_return_data(data);
+}
+
+fn initialize(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Initialize");
+    let (authorized, lockup) = super::initialize::parse_initialize(payload)?;
+    super::initialize::initialize(accounts.require()?, authorized, lockup)
+}
+
+fn initialize_checked(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: InitializeChecked");
+    // Authorities are passed as accounts (rent sysvar optional)
+    super::initialize_checked::process_initialize_checked(accounts.require()?)
+}
+
+fn authorize(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Authorize");
+    let role = read_role(payload[32])?;
+    super::authorize::process_authorize(accounts.require()?, read_pubkey(&payload[..32]), role)
+}
+
+fn authorize_with_seed(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: AuthorizeWithSeed");
+    // [new_auth(32)] [role(1)] [seed_len(1)] [seed] [owner(32)]
+    let args = AuthorizeWithSeedData::parse(payload)?;
+    super::process_authorized_with_seeds::process_authorized_with_seeds(accounts.require()?, args)
+}
+
+fn authorize_checked(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: AuthorizeChecked");
+    let role = read_role(payload[0])?;
+    super::authorize_checked::process_authorize_checked(accounts.require()?, role)
+}
+
+fn authorize_checked_with_seed(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: AuthorizeCheckedWithSeed");
+    // Same layout as AuthorizeWithSeed; the handler reads the new authority from accounts
+    let args = AuthorizeCheckedWithSeedData::parse(payload)?;
+    super::process_authorize_checked_with_seed::process_authorize_checked_with_seed(accounts.require()?, args)
+}
+
+fn delegate_stake(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: DelegateStake");
+    super::process_delegate::process_delegate(accounts.require()?)
+}
+
+fn split(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Split");
+    super::split::process_split(accounts.require()?, read_u64(payload))
+}
+
+fn withdraw(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Withdraw");
+    super::withdraw::process_withdraw(accounts.require()?, read_u64(payload))
+}
+
+fn deactivate(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Deactivate");
+    super::deactivate::process_deactivate(accounts.require()?)
+}
+
+fn set_lockup(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: SetLockup");
+    let args = SetLockupData::parse(payload)?;
+    super::process_set_lockup::process_set_lockup_parsed(accounts.require()?, args)
+}
+
+fn set_lockup_checked(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: SetLockupChecked");
+    // The handler checks for the stake account before parsing its payload
+    super::process_set_lockup_checked::process_set_lockup_checked(accounts.require()?, payload)
+}
+
+fn merge(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Merge");
+    super::merge::process_merge(accounts.require()?)
+}
+
+fn move_stake(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: MoveStake");
+    // writability is checked after the authority, as native does
+    super::process_move_stake::process_move_stake(accounts.require_count()?, read_u64(payload))
+}
+
+fn move_lamports(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: MoveLamports");
+    // writability is checked after the authority, as native does
+    super::move_lamports::process_move_lamports(accounts.require_count()?, read_u64(payload))
+}
+
+fn get_minimum_delegation(_accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: GetMinimumDelegation");
+    process_get_minimum_delegation()
+}
+
+/// GetMinimumDelegation for both encodings: the minimum as u64 LE return data
+pub(crate) fn process_get_minimum_delegation() -> ProgramResult {
+    return_data(&crate::helpers::get_minimum_delegation().to_le_bytes());
+    Ok(())
+}
+
+fn deactivate_delinquent(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: DeactivateDelinquent");
+    super::deactivate_delinquent::process_deactivate_delinquent(accounts.require()?)
+}
+
+#[cfg(feature = "redelegate-legacy")]
+fn redelegate(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: Redelegate");
+    super::process_redelegate::process_redelegate(accounts.require()?)
+}
+
+#[cfg(not(feature = "redelegate-legacy"))]
+fn redelegate(_accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    Err(ProgramError::InvalidInstructionData)
+}
+
+#[cfg(feature = "extensions")]
+fn deactivate_partial(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: DeactivatePartial");
+    super::deactivate_partial::process_deactivate_partial(accounts.require()?, read_u64(payload))
+}
+
+#[cfg(feature = "extensions")]
+fn withdraw_excess(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: WithdrawExcess");
+    // The amount is computed on-chain
+    super::withdraw::process_withdraw_excess(accounts.require()?)
+}
+
+#[cfg(feature = "extensions")]
+fn close_stake_account(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: CloseStakeAccount");
+    super::withdraw::process_close_stake_account(accounts.require()?)
+}
+
+#[cfg(feature = "extensions")]
+fn merge_many(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: MergeMany");
+    let report = super::merge_many::process_merge_many(accounts.require()?)?;
+    let (data, len) = report.to_bytes();
+    return_data(&data[..len]);
+    Ok(())
+}
+
+#[cfg(feature = "extensions")]
+fn split_with_seed(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: SplitWithSeed");
+    let args = crate::state::accounts::SplitWithSeedData::parse(payload)?;
+    super::split_with_seed::process_split_with_seed(accounts.require()?, args)
+}
+
+#[cfg(feature = "extensions")]
+fn get_delegation_preview(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: GetDelegationPreview");
+    let preview = super::delegation_preview::process_get_delegation_preview(accounts.require()?)?;
+    return_data(&preview.to_bytes());
+    Ok(())
+}
+
+#[cfg(feature = "extensions")]
+fn query_params(accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: QueryParams");
+    let params = super::query_params::process_query_params(accounts.require()?)?;
+    return_data(&params.to_bytes());
+    Ok(())
+}
+
+#[cfg(feature = "extensions")]
+fn get_version(_accounts: Accounts, _payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: GetVersion");
+    return_data(&super::get_version::process_get_version().to_bytes());
+    Ok(())
+}
+
+#[cfg(feature = "withdraw-to-token")]
+fn withdraw_to_token_account(accounts: Accounts, payload: &[u8]) -> ProgramResult {
+    log_debug!("Instruction: WithdrawToTokenAccount");
+    super::withdraw::process_withdraw_to_token_account(accounts.require()?, read_u64(payload))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{info, raw, RawAccount};
+
+    #[test]
+    fn table_is_indexed_by_discriminant() {
+        for d in instructions() {
+            let found = descriptor(d.discriminant).unwrap();
+            assert!(core::ptr::eq(found, d), "{}", d.name);
+            assert!(StakeInstruction::try_from(&d.discriminant).is_ok(), "{}", d.name);
+        }
+        assert!(descriptor(18).is_none());
+        assert!(descriptor(127).is_none());
+        // a feature-gated extension leaves a gap, not a shifted table
+        assert_eq!(descriptor(135).is_some(), cfg!(feature = "withdraw-to-token"));
+        assert_eq!(descriptor(136).is_some(), cfg!(feature = "extensions"));
+        assert!(descriptor(137).is_none());
+        assert!(descriptor(u8::MAX).is_none());
+    }
+
+    #[test]
+    fn account_specs_cover_the_required_accounts() {
+        for d in instructions() {
+            assert!(d.accounts.len() >= d.min_accounts, "{}", d.name);
+            assert!(d.accounts[..d.min_accounts].iter().all(|a| !a.optional), "{}", d.name);
+        }
+    }
+
+    #[test]
+    fn rejects_in_native_order_before_any_handler_work() {
+        // unknown discriminant, then bad data even with no accounts, then the count
+        assert_eq!(process(&[], &[]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(process(&[], &[0xff]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(process(&[], &[3, 0, 0]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(process(&[], &[2, 0]), Err(ProgramError::InvalidInstructionData));
+        let mut authorize = [0u8; 34];
+        authorize[0] = 1;
+        authorize[33] = 2;
+        assert_eq!(process(&[], &authorize), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(process(&[], &[2]), Err(ProgramError::NotEnoughAccountKeys));
+        assert_eq!(process(&[], &[4, 0, 0, 0, 0, 0, 0, 0, 0]), Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    /// The shortest well-formed payload for an instruction; `None` if it has none
+    fn minimal_payload(d: &Descriptor) -> Option<Vec<u8>> {
+        #[allow(deprecated)]
+        let payload = match (d.instruction, d.payload) {
+            (_, Payload::Empty) => vec![],
+            (_, Payload::Fixed(len)) => vec![0; len],
+            (StakeInstruction::Initialize, _) => vec![0; super::super::initialize::INITIALIZE_ARGS_LEN],
+            // three `None` options
+            (StakeInstruction::SetLockup, _) => vec![0; 3],
+            // no flags
+            (StakeInstruction::SetLockupChecked, _) => vec![0],
+            // new authority, role, empty seed, owner
+            (StakeInstruction::AuthorizeWithSeed | StakeInstruction::AuthorizeCheckedWithSeed, _) => vec![0; 66],
+            // lamports, empty seed
+            #[cfg(feature = "extensions")]
+            (StakeInstruction::SplitWithSeed, _) => vec![0; 9],
+            (StakeInstruction::Redelegate, _) => return None,
+            (_, Payload::Variable) => panic!("{}: no minimal payload", d.name),
+        };
+        Some(payload)
+    }
+
+    #[test]
+    fn every_instruction_rejects_trailing_bytes() {
+        // one account, not owned by the program, so the handlers that check the
+        // stake account before their payload (SetLockupChecked) still get to it
+        let mut raw = raw([1; 32], [0; 32], 0, []);
+        let accounts = [info(&mut raw)];
+
+        for d in instructions() {
+            let mut data = vec![d.discriminant];
+            match minimal_payload(d) {
+                Some(payload) => {
+                    data.extend(payload);
+                    assert_ne!(process(&accounts, &data), Err(ProgramError::InvalidInstructionData), "{}", d.name);
+                }
+                None => assert_eq!(process(&accounts, &data), Err(ProgramError::InvalidInstructionData), "{}", d.name),
+            }
+            data.push(0);
+            assert_eq!(process(&accounts, &data), Err(ProgramError::InvalidInstructionData), "{} with a trailing byte", d.name);
+        }
+    }
+
+    #[cfg(feature = "wire_bincode")]
+    #[test]
+    fn wire_instructions_get_the_same_account_checks() {
+        use crate::wire::StakeInstruction as Wire;
+        let wire = [Wire::Split(5), Wire::MoveStake(5), Wire::DeactivateDelinquent];
+        for (ix, name) in wire.iter().zip(["Split", "MoveStake", "DeactivateDelinquent"]) {
+            assert_eq!(descriptor(ix.discriminant()).unwrap().name, name);
+        }
+
+        let mut raws: Vec<RawAccount<0>> = (0..3).map(|n| RawAccount { is_signer: 1, ..raw([n; 32], crate::ID, 0, []) }).collect();
+        raws[0].is_writable = 0;
+        let accounts: Vec<AccountInfo> = raws.iter_mut().map(info).collect();
+        assert_eq!(require_accounts(3, &accounts[..2]).err(), Some(ProgramError::NotEnoughAccountKeys));
+        assert_eq!(require_accounts(3, &accounts).err(), Some(ProgramError::InvalidAccountData));
+        // MoveStake checks writability in its handler, as on the single-byte path
+        assert!(require_accounts(16, &accounts).is_ok());
+
+        // the SDK's historical Redelegate reaches the legacy handler's layout
+        #[cfg(feature = "redelegate-legacy")]
+        {
+            let redelegate = Wire::decode(&15u32.to_le_bytes()).unwrap();
+            assert_eq!(descriptor(redelegate.discriminant()).unwrap().name, "Redelegate");
+            assert_eq!(require_accounts(15, &accounts).err(), Some(ProgramError::InvalidAccountData));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn read_only_accounts_in_writable_roles_fail_before_the_handler() {
+        let raw = |n: u8| RawAccount { is_signer: 1, ..raw([n; 32], crate::ID, 0, []) };
+        for d in instructions() {
+            let Some(payload) = minimal_payload(d) else { continue };
+            let data = [&[d.discriminant][..], &payload].concat();
+            let moves = matches!(d.instruction, StakeInstruction::MoveStake | StakeInstruction::MoveLamports);
+            for (i, role) in d.accounts.iter().enumerate() {
+                if !role.writable || role.optional {
+                    continue;
+                }
+                let mut raws: Vec<RawAccount<0>> = (0..d.accounts.len().max(d.min_accounts) as u8).map(raw).collect();
+                raws[i].is_writable = 0;
+                let accounts: Vec<AccountInfo> = raws.iter_mut().map(info).collect();
+                let expected =
+                    if moves { ProgramError::InvalidInstructionData } else { ProgramError::InvalidAccountData };
+                assert_eq!(process(&accounts, &data), Err(expected), "{} with a read-only {}", d.name, role.name());
+            }
+        }
+    }
+}
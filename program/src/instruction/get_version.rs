@@ -0,0 +1,148 @@
+/// Bumped whenever an instruction is added or removed, or one changes its
+/// payload or account layout, independently of the crate version
+pub const INSTRUCTION_SET_REVISION: u32 = 1;
+
+/// Build features that change on-chain behavior, bit `i` for entry `i`.
+/// Append only: clients decode old builds with the same table.
+pub const BUILD_FEATURES: &[(&str, bool)] = &[
+    ("extensions", cfg!(feature = "extensions")),
+    ("withdraw-to-token", cfg!(feature = "withdraw-to-token")),
+    ("wire_bincode", cfg!(feature = "wire_bincode")),
+    ("redelegate-legacy", cfg!(feature = "redelegate-legacy")),
+    ("lenient-auth", cfg!(feature = "lenient-auth")),
+    ("lenient-vote", cfg!(feature = "lenient-vote")),
+    ("loose-merge", cfg!(feature = "loose-merge")),
+    ("recipient-rent", cfg!(feature = "recipient-rent")),
+    ("withdraw-max", cfg!(feature = "withdraw-max")),
+    ("split-minimum-delegation", cfg!(feature = "split-minimum-delegation")),
+    ("integrity", cfg!(feature = "integrity")),
+    ("state-v3", cfg!(feature = "state-v3")),
+    ("trace-state", cfg!(feature = "trace-state")),
+    ("log-error", cfg!(feature = "log-error")),
+    ("log-debug", cfg!(feature = "log-debug")),
+    ("metrics", cfg!(feature = "metrics")),
+    ("minimal", cfg!(feature = "minimal")),
+];
+
+const fn parse_u32(digits: &str) -> u32 {
+    let bytes = digits.as_bytes();
+    let (mut value, mut i) = (0u32, 0);
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    value
+}
+
+const fn feature_bits() -> u64 {
+    let (mut bits, mut i) = (0u64, 0);
+    while i < BUILD_FEATURES.len() {
+        if BUILD_FEATURES[i].1 {
+            bits |= 1 << i;
+        }
+        i += 1;
+    }
+    bits
+}
+
+/// Which build of the program answered: what GetVersion returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildVersion {
+    /// Crate semver
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub instruction_set_revision: u32,
+    /// [`BUILD_FEATURES`] bit set
+    pub features: u64,
+}
+
+impl BuildVersion {
+    pub const LEN: usize = 4 * 4 + 8;
+
+    /// This build
+    pub const CURRENT: Self = Self {
+        major: parse_u32(env!("CARGO_PKG_VERSION_MAJOR")),
+        minor: parse_u32(env!("CARGO_PKG_VERSION_MINOR")),
+        patch: parse_u32(env!("CARGO_PKG_VERSION_PATCH")),
+        instruction_set_revision: INSTRUCTION_SET_REVISION,
+        features: feature_bits(),
+    };
+
+    /// Return data: major, minor, patch, instruction_set_revision (u32 LE each),
+    /// then features (u64 LE)
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[..4].copy_from_slice(&self.major.to_le_bytes());
+        out[4..8].copy_from_slice(&self.minor.to_le_bytes());
+        out[8..12].copy_from_slice(&self.patch.to_le_bytes());
+        out[12..16].copy_from_slice(&self.instruction_set_revision.to_le_bytes());
+        out[16..].copy_from_slice(&self.features.to_le_bytes());
+        out
+    }
+
+    /// Decode GetVersion's return data; `None` unless exactly [`Self::LEN`] bytes
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let data: &[u8; Self::LEN] = data.try_into().ok()?;
+        let u32_at = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        Some(Self {
+            major: u32_at(0),
+            minor: u32_at(4),
+            patch: u32_at(8),
+            instruction_set_revision: u32_at(12),
+            features: u64::from_le_bytes(data[16..].try_into().unwrap()),
+        })
+    }
+
+    /// Whether the build had `feature` (a [`BUILD_FEATURES`] name) enabled
+    pub fn has_feature(&self, feature: &str) -> bool {
+        BUILD_FEATURES.iter().position(|(name, _)| *name == feature).is_some_and(|bit| self.features & (1 << bit) != 0)
+    }
+}
+
+/// GetVersion (extension): read-only query, no accounts.
+pub fn process_get_version() -> BuildVersion {
+    BuildVersion::CURRENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_is_this_crate_and_build() {
+        let version = BuildVersion::CURRENT;
+        let semver = format!("{}.{}.{}", version.major, version.minor, version.patch);
+        assert_eq!(semver, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.instruction_set_revision, INSTRUCTION_SET_REVISION);
+
+        assert!(version.has_feature("extensions"));
+        assert_eq!(version.has_feature("state-v3"), cfg!(feature = "state-v3"));
+        assert_eq!(version.has_feature("withdraw-max"), cfg!(feature = "withdraw-max"));
+        assert!(!version.has_feature("std"), "not a build feature");
+        assert_eq!(version.features.count_ones() as usize, BUILD_FEATURES.iter().filter(|(_, on)| *on).count());
+    }
+
+    #[test]
+    fn feature_names_are_unique_and_fit() {
+        assert!(BUILD_FEATURES.len() <= 64);
+        for (i, (name, _)) in BUILD_FEATURES.iter().enumerate() {
+            assert!(BUILD_FEATURES[..i].iter().all(|(other, _)| other != name), "{name}");
+        }
+    }
+
+    #[test]
+    fn return_data_round_trips() {
+        let version = BuildVersion { major: 1, minor: 2, patch: 3, instruction_set_revision: 4, features: 0b101 };
+        let bytes = version.to_bytes();
+        assert_eq!(bytes[..4], 1u32.to_le_bytes());
+        assert_eq!(bytes[12..16], 4u32.to_le_bytes());
+        assert_eq!(bytes[16..], 0b101u64.to_le_bytes());
+        assert_eq!(BuildVersion::from_bytes(&bytes), Some(version));
+        assert!(version.has_feature("extensions") && version.has_feature("wire_bincode"));
+        assert!(!version.has_feature("withdraw-to-token"));
+
+        assert_eq!(BuildVersion::from_bytes(&bytes[1..]), None);
+        assert_eq!(BuildVersion::from_bytes(&[0; BuildVersion::LEN + 1]), None);
+    }
+}
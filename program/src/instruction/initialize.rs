@@ -1,12 +1,38 @@
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
+    pubkey::Pubkey,
     sysvars::rent::Rent,
     ProgramResult,
 };
 
 use crate::{helpers::*, state::state::Lockup};
 use crate::state::*;
+use crate::helpers::sysvar_cache;
+
+/// Authorized (staker, withdrawer) then Lockup (unix_timestamp i64,
+/// epoch u64, custodian), all little-endian
+pub const INITIALIZE_ARGS_LEN: usize = 32 + 32 + 8 + 8 + 32;
+
+/// Initialize's arguments, following the discriminator byte, in either encoding:
+/// - this program's: the 112 argument bytes
+/// - native bincode: the enum tag is a 4-byte u32, so its three remaining
+///   (zero) bytes come first, then the same 112 bytes
+pub fn parse_initialize(payload: &[u8]) -> Result<(Authorized, Lockup), ProgramError> {
+    let args = match payload.len() {
+        INITIALIZE_ARGS_LEN => payload,
+        len if len == INITIALIZE_ARGS_LEN + 3 && payload[..3] == [0; 3] => &payload[3..],
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let pubkey = |range: core::ops::Range<usize>| -> Pubkey { args[range].try_into().unwrap() };
+    let authorized = Authorized { staker: pubkey(0..32), withdrawer: pubkey(32..64) };
+    let lockup = Lockup {
+        unix_timestamp: i64::from_le_bytes(args[64..72].try_into().unwrap()),
+        epoch: u64::from_le_bytes(args[72..80].try_into().unwrap()),
+        custodian: pubkey(80..112),
+    };
+    Ok((authorized, lockup))
+}
 
 pub fn initialize(
     accounts: &[AccountInfo], 
@@ -14,43 +40,185 @@ pub fn initialize(
     lockup: Lockup
 ) -> ProgramResult {
     
-    // Expected accounts: 2 (1 sysvar)
-        let [stake_account_info, rent_info, _rest @ ..] = accounts else{
+    // Accounts: roles::INITIALIZE
+    let [stake_account_info, ..] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    let rent = &Rent::from_account_info(rent_info)?;
+    let rent = &sysvar_cache::rent_account(accounts)?;
 
-    // `get_stake_state()` is called unconditionally, which checks owner
-        do_initialize(stake_account_info, authorized, lockup, rent)?;
-
-    Ok(())
+    do_initialize(stake_account_info, authorized, lockup, rent)
 }
 
+/// Initialize and InitializeChecked, with native's checks in native's order:
+///
+/// 1. data exactly `StakeStateV2::ACCOUNT_SIZE` bytes, else `InvalidAccountData`
+/// 2. owned by this program (`InvalidAccountOwner`) and not executable
+/// 3. Uninitialized, else `InvalidAccountData` (a second initialize included)
+/// 4. lamports at least the rent-exempt reserve, else `InsufficientFunds`
 pub fn do_initialize(
     stake_account_info: &AccountInfo,
     authorized: Authorized,
     lockup: Lockup,
     rent: &Rent,
-) -> ProgramResult{
-    if stake_account_info.data_len() != StakeStateV2::size_of() {
+) -> ProgramResult {
+    let rent_exempt_reserve = check_initializable(stake_account_info, rent)?;
+    let stake_state = StakeStateV2::Initialized(Meta {
+        rent_exempt_reserve: LamportsLE::new(rent_exempt_reserve),
+        authorized,
+        lockup,
+    });
+    set_stake_state(stake_account_info, &stake_state)
+}
+
+/// Checks 1-4 of [`do_initialize`]; the reserve the new Meta records
+fn check_initializable(stake_account_info: &AccountInfo, rent: &Rent) -> Result<u64, ProgramError> {
+    validate_stake_account_shape(stake_account_info, StakeAccountShape::Exact)?;
+    if !matches!(get_stake_state(stake_account_info)?, StakeStateV2::Uninitialized) {
         return Err(ProgramError::InvalidAccountData);
     }
+    let rent_exempt_reserve = rent.minimum_balance(stake_account_info.data_len());
+    if stake_account_info.lamports() < rent_exempt_reserve {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    Ok(rent_exempt_reserve)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{info, raw, RawAccount};
+    use crate::state::migration::{ActiveMigration, StateMigration};
+    use solana_sdk::{
+        pubkey::Pubkey as SdkPubkey,
+        stake::{instruction::StakeInstruction as NativeInstruction, state as native},
+    };
+
+    fn native_args() -> (native::Authorized, native::Lockup) {
+        let authorized = native::Authorized { staker: SdkPubkey::new_from_array([1; 32]), withdrawer: SdkPubkey::new_from_array([2; 32]) };
+        let lockup = native::Lockup { unix_timestamp: -5, epoch: 0x0102_0304_0506_0708, custodian: SdkPubkey::new_from_array([3; 32]) };
+        (authorized, lockup)
+    }
 
-    if let StakeStateV2::Uninitialized = get_stake_state(stake_account_info)? {
-        let rent_exempt_reserve = rent.minimum_balance(stake_account_info.data_len());
-        if stake_account_info.lamports() >= rent_exempt_reserve {
-            let stake_state = StakeStateV2::Initialized(Meta {
-                rent_exempt_reserve: rent_exempt_reserve.to_le_bytes(),
-                authorized,
-                lockup,
-            });
+    fn expected() -> (Authorized, Lockup) {
+        let authorized = Authorized { staker: [1; 32], withdrawer: [2; 32] };
+        let lockup = Lockup { unix_timestamp: -5, epoch: 0x0102_0304_0506_0708, custodian: [3; 32] };
+        (authorized, lockup)
+    }
+
+    #[test]
+    fn parses_program_encoding() {
+        let mut data = vec![0u8];
+        data.extend([1; 32]);
+        data.extend([2; 32]);
+        data.extend((-5i64).to_le_bytes());
+        data.extend(0x0102_0304_0506_0708u64.to_le_bytes());
+        data.extend([3; 32]);
+        assert_eq!(data.len(), 1 + INITIALIZE_ARGS_LEN);
+        assert_eq!(parse_initialize(&data[1..]).unwrap(), expected());
+    }
+
+    #[test]
+    fn parses_native_bincode_encoding() {
+        let (authorized, lockup) = native_args();
+        let data = bincode::serialize(&NativeInstruction::Initialize(authorized, lockup)).unwrap();
+        // u32 tag 0, then the arguments
+        assert_eq!(data[..4], [0; 4]);
+        assert_eq!(data.len(), 4 + INITIALIZE_ARGS_LEN);
+        assert_eq!(parse_initialize(&data[1..]).unwrap(), expected());
+    }
+
+    #[test]
+    fn rejects_other_lengths_and_tags() {
+        let (authorized, lockup) = native_args();
+        let data = bincode::serialize(&NativeInstruction::Initialize(authorized, lockup)).unwrap();
+        for len in [0, 111, 113, 114, 116] {
+            let payload = vec![0; len];
+            assert_eq!(parse_initialize(&payload), Err(ProgramError::InvalidInstructionData), "len {len}");
+        }
+        // a tag above 255 is some other instruction, not Initialize
+        let mut wide_tag = data.clone();
+        wide_tag[1] = 1;
+        assert_eq!(parse_initialize(&wide_tag[1..]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    /// Room for the data lengths tried around `StakeStateV2::ACCOUNT_SIZE`
+    type RawStakeAccount = RawAccount<{ StakeStateV2::ACCOUNT_SIZE + 8 }>;
+
+    fn account(state: &StakeStateV2, data_len: usize, lamports: u64) -> RawStakeAccount {
+        let mut raw = RawAccount {
+            data_len: data_len as u64,
+            ..raw([9; 32], crate::ID, lamports, [0; StakeStateV2::ACCOUNT_SIZE + 8])
+        };
+        state.serialize(&mut raw.data).unwrap();
+        raw
+    }
+
+    #[allow(deprecated)]
+    fn rent() -> Rent {
+        Rent { lamports_per_byte_year: 3480, exemption_threshold: 2.0, burn_percent: 50 }
+    }
+
+    fn initialize_account(raw: &mut RawStakeAccount) -> ProgramResult {
+        let (authorized, lockup) = expected();
+        do_initialize(&info(raw), authorized, lockup, &rent())
+    }
+
+    #[test]
+    fn initializes_a_funded_uninitialized_account() {
+        let reserve = rent().minimum_balance(StakeStateV2::ACCOUNT_SIZE);
+        let mut raw = account(&StakeStateV2::Uninitialized, StakeStateV2::ACCOUNT_SIZE, reserve);
+        assert_eq!(initialize_account(&mut raw), Ok(()));
+        let StakeStateV2::Initialized(meta) = ActiveMigration::load(&raw.data).unwrap() else {
+            panic!("not initialized");
+        };
+        assert_eq!(meta.rent_exempt_reserve.get(), reserve);
+        assert_eq!((meta.authorized, meta.lockup), expected());
+
+        // a second initialize fails, funded or not
+        assert_eq!(initialize_account(&mut raw), Err(ProgramError::InvalidAccountData));
+        raw.lamports = 0;
+        assert_eq!(initialize_account(&mut raw), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn rejects_initialized_state_before_funding() {
+        let reserve = rent().minimum_balance(StakeStateV2::ACCOUNT_SIZE);
+        let meta = Meta { rent_exempt_reserve: LamportsLE::new(reserve), ..Meta::default() };
+        let staked = StakeStateV2::Stake(meta, crate::state::delegation::Stake::default(), StakeFlags::empty());
+        for state in [StakeStateV2::Initialized(meta), staked, StakeStateV2::RewardsPool] {
+            for lamports in [0, reserve, u64::MAX] {
+                let mut raw = account(&state, StakeStateV2::ACCOUNT_SIZE, lamports);
+                assert_eq!(initialize_account(&mut raw), Err(ProgramError::InvalidAccountData), "{state:?}");
+                assert_eq!(StakeStateV2::deserialize(&raw.data).unwrap(), state);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_underfunded_accounts() {
+        let reserve = rent().minimum_balance(StakeStateV2::ACCOUNT_SIZE);
+        for lamports in [0, reserve - 1] {
+            let mut raw = account(&StakeStateV2::Uninitialized, StakeStateV2::ACCOUNT_SIZE, lamports);
+            assert_eq!(initialize_account(&mut raw), Err(ProgramError::InsufficientFunds), "{lamports}");
+            assert_eq!(StakeStateV2::deserialize(&raw.data).unwrap(), StakeStateV2::Uninitialized);
+        }
+    }
 
-            set_stake_state(stake_account_info, &stake_state)
-        } else {
-            Err(ProgramError::InsufficientFunds)
+    #[test]
+    fn size_and_owner_are_checked_first() {
+        // native requires the exact size, also of larger accounts, before anything else
+        for data_len in [0, StakeStateV2::ACCOUNT_SIZE - 1, StakeStateV2::ACCOUNT_SIZE + 1, StakeStateV2::ACCOUNT_SIZE + 8] {
+            for lamports in [0, u64::MAX] {
+                let mut raw = account(&StakeStateV2::Uninitialized, data_len, lamports);
+                assert_eq!(initialize_account(&mut raw), Err(ProgramError::InvalidAccountData), "{data_len}");
+            }
         }
-    } else {
-        Err(ProgramError::InvalidAccountData)
+        let mut foreign = account(&StakeStateV2::Uninitialized, StakeStateV2::ACCOUNT_SIZE, 0);
+        foreign.owner = [7; 32];
+        assert_eq!(initialize_account(&mut foreign), Err(ProgramError::InvalidAccountOwner));
+        let mut program = account(&StakeStateV2::Uninitialized, StakeStateV2::ACCOUNT_SIZE, u64::MAX);
+        program.executable = 1;
+        assert_eq!(initialize_account(&mut program), Err(ProgramError::InvalidAccountData));
     }
 }
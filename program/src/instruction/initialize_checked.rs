@@ -4,29 +4,36 @@
   use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    sysvars::rent::Rent,
+    sysvars::rent::RENT_ID,
     ProgramResult,
 };
 
 use crate::{ state::state::Lockup};
 use crate::instruction::initialize::do_initialize;
 use crate::state::*;
+use crate::helpers::sysvar_cache;
 
 pub fn process_initialize_checked(accounts: &[AccountInfo]) -> ProgramResult {
 
-        // native asserts: 4 accounts (1 sysvar)
+        // Accounts: roles::INITIALIZE_CHECKED.
+        // The rent account is optional (read if passed, else via the syscall); the authorities
+        // are positional because their keys are the arguments
 
-    let [stake_account_info, rent_info,stake_authority_info,withdraw_authority_info, _rest @ ..] = accounts else{
+    let (stake_account_info, authorities) = match accounts {
+        [stake, rent, rest @ ..] if rent.key() == &RENT_ID => (stake, rest),
+        [stake, rest @ ..] => (stake, rest),
+        [] => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+    let [stake_authority_info, withdraw_authority_info, ..] = authorities else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-
-        let rent = &Rent::from_account_info(rent_info)?;
-
         if !withdraw_authority_info.is_signer(){
             return Err(ProgramError::MissingRequiredSignature);
         }
 
+        let rent = &sysvar_cache::rent(accounts)?;
+
         let authorized = Authorized {
             staker: *stake_authority_info.key(),
             withdrawer: *withdraw_authority_info.key(),
@@ -37,5 +44,3 @@ pub fn process_initialize_checked(accounts: &[AccountInfo]) -> ProgramResult {
 
         Ok(())
     }
-    
-
@@ -1,9 +1,73 @@
-// Merge instruction wrapper
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
 
-use super::merge_dedicated;
-use pinocchio::{account_info::AccountInfo, ProgramResult};
+use crate::{
+    helpers::{
+        check_merge_accounts, Signers, merge_into, sysvar_cache, warmup_rate,
+    },
+    state::WindowedStakeHistory,
+};
+#[cfg(feature = "extensions")]
+use super::roles::{self, Role};
 
-/// Process merge instruction – delegates to dedicated implementation
+/// Merge: the accounts, sysvars and signers for [`merge_into`], which does
+/// the merge itself
 pub fn process_merge(accounts: &[AccountInfo]) -> ProgramResult {
-    merge_dedicated::process_merge(accounts)
+    // Accounts: roles::MERGE
+    let [dst_ai, src_ai, _, _, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_merge_accounts(dst_ai, src_ai)?;
+
+    // Load sysvars
+    let clock = sysvar_cache::clock_account(accounts)?;
+    // Use the epoch wrapper; contents of history account are not read here
+    let stake_history = WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+
+    // Collect signers
+    let signers = &Signers::collect(accounts)?;
+
+    merge_into(dst_ai, src_ai, rent_destination(accounts), &clock, &stake_history, new_rate_activation_epoch, signers)
+}
+
+/// The optional account after the stake authority that takes the source's
+/// rent-exempt reserve (`extensions`). Only a writable non-signer there counts,
+/// so native layouts that append extra signers keep native behavior.
+fn rent_destination(accounts: &[AccountInfo]) -> Option<&AccountInfo> {
+    #[cfg(feature = "extensions")]
+    {
+        const POSITION: Option<usize> = roles::position(roles::MERGE, Role::RentDestination);
+        POSITION
+            .and_then(|i| accounts.get(i))
+            .filter(|ai| ai.is_writable() && !ai.is_signer())
+    }
+    #[cfg(not(feature = "extensions"))]
+    {
+        let _ = accounts;
+        None
+    }
+}
+
+#[cfg(all(test, feature = "extensions"))]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{self, info, RawAccount};
+
+    fn raw(key: u8) -> RawAccount<0> {
+        test_accounts::raw([key; 32], [0; 32], 0, [])
+    }
+
+    #[test]
+    fn rent_destination_is_a_writable_non_signer_after_the_authority() {
+        let mut accounts = [0u8, 1, 2, 3, 4, 5].map(raw);
+        let infos = accounts.each_mut().map(info);
+        assert_eq!(rent_destination(&infos).map(|ai| *ai.key()), Some([5; 32]));
+        assert!(rent_destination(&infos[..5]).is_none());
+
+        // an extra signer in that slot keeps native behavior
+        accounts[5].is_signer = 1;
+        let infos = accounts.each_mut().map(info);
+        assert!(rent_destination(&infos).is_none());
+    }
 }
@@ -0,0 +1,121 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+};
+
+use crate::{
+    helpers::{check_merge_accounts, get_stake_state, merge_into, SignerSet, Signers},
+    state::{MergeKind, WindowedStakeHistory},
+    ID,
+};
+use crate::helpers::{sysvar_cache, warmup_rate};
+
+/// Most sources one MergeMany folds into its destination
+pub const MAX_MERGE_SOURCES: usize = 8;
+
+/// Per-source outcome of a MergeMany, in account order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeManyReport {
+    /// 0 if merged, otherwise the error as the runtime encodes it (`u64::from(ProgramError)`)
+    codes: [u64; MAX_MERGE_SOURCES],
+    len: usize,
+}
+
+impl MergeManyReport {
+    pub const MAX_LEN: usize = 8 * MAX_MERGE_SOURCES;
+
+    fn new(len: usize) -> Self {
+        Self { codes: [0; MAX_MERGE_SOURCES], len }
+    }
+
+    fn record(&mut self, index: usize, result: Result<(), ProgramError>) {
+        self.codes[index] = result.err().map_or(0, u64::from);
+    }
+
+    /// Result code per source, 0 for the ones merged
+    pub fn codes(&self) -> &[u64] {
+        &self.codes[..self.len]
+    }
+
+    pub fn merged(&self) -> usize {
+        self.codes().iter().filter(|&&code| code == 0).count()
+    }
+
+    /// Return data: one u64 LE code per source
+    pub fn to_bytes(&self) -> ([u8; Self::MAX_LEN], usize) {
+        let mut out = [0u8; Self::MAX_LEN];
+        for (chunk, code) in out.chunks_exact_mut(8).zip(self.codes()) {
+            chunk.copy_from_slice(&code.to_le_bytes());
+        }
+        (out, 8 * self.len)
+    }
+}
+
+/// MergeMany (extension): Merge of up to [`MAX_MERGE_SOURCES`] sources into one
+/// destination. Accounts: [`MERGE_MANY`](super::roles::MERGE_MANY), then the
+/// further sources.
+///
+/// The destination, the staker signature and the source count are checked up
+/// front and fail the whole instruction. Each source is then merged in order;
+/// one that cannot be merged is left untouched and its error recorded in the
+/// report instead, so one stale account does not sink the batch.
+pub fn process_merge_many(accounts: &[AccountInfo]) -> Result<MergeManyReport, ProgramError> {
+    let [dst_ai, _clock, _stake_history, _staker, sources @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if sources.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if sources.len() > MAX_MERGE_SOURCES {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *dst_ai.owner() != ID {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    if !dst_ai.is_writable() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let clock = sysvar_cache::clock_account(accounts)?;
+    let stake_history = WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+
+    let signers = &Signers::collect(accounts)?;
+
+    // Fail fast on a destination no source could merge into
+    let dst_kind = MergeKind::get_if_mergeable(&get_stake_state(dst_ai)?, dst_ai.lamports(), &clock, &stake_history, new_rate_activation_epoch)?;
+    if !signers.contains(&dst_kind.meta().authorized.staker) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut report = MergeManyReport::new(sources.len());
+    for (index, src_ai) in sources.iter().enumerate() {
+        // the destination is reclassified each time, since a merge can change its kind
+        let result = check_merge_accounts(dst_ai, src_ai)
+            .and_then(|()| merge_into(dst_ai, src_ai, None, &clock, &stake_history, new_rate_activation_epoch, signers));
+        report.record(index, result);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{to_program_error, StakeError};
+
+    #[test]
+    fn report_encodes_one_code_per_source() {
+        let mut report = MergeManyReport::new(3);
+        report.record(0, Ok(()));
+        report.record(1, Err(to_program_error(StakeError::MergeMismatch)));
+        report.record(2, Err(ProgramError::InvalidAccountData));
+        assert_eq!(report.merged(), 1);
+
+        let (bytes, len) = report.to_bytes();
+        assert_eq!(len, 24);
+        assert_eq!(bytes[..8], 0u64.to_le_bytes());
+        assert_eq!(bytes[8..16], u64::from(to_program_error(StakeError::MergeMismatch)).to_le_bytes());
+        assert_eq!(bytes[16..24], u64::from(ProgramError::InvalidAccountData).to_le_bytes());
+        assert!(bytes[len..].iter().all(|&b| b == 0));
+    }
+}
@@ -1,5 +1,8 @@
 use pinocchio::program_error::ProgramError;
 
+pub mod dispatch;
+pub mod roles;
+
 pub mod initialize;
 pub use initialize::*;
 
@@ -25,7 +28,6 @@ pub mod process_authorize_checked_with_seed;
 pub use process_authorize_checked_with_seed::*;
 
 pub mod merge;
-pub mod merge_dedicated;
 pub use merge::*;
 
 pub mod process_delegate;
@@ -51,7 +53,58 @@ pub use deactivate::*;
 pub mod process_set_lockup_checked;
 pub use process_set_lockup_checked::*;
 
+#[cfg(feature = "extensions")]
+pub mod deactivate_partial;
+#[cfg(feature = "extensions")]
+pub use deactivate_partial::*;
+
+#[cfg(feature = "extensions")]
+pub mod delegation_preview;
+#[cfg(feature = "extensions")]
+pub use delegation_preview::*;
+
+#[cfg(feature = "extensions")]
+pub mod get_version;
+#[cfg(feature = "extensions")]
+pub use get_version::*;
+
+#[cfg(feature = "extensions")]
+pub mod merge_many;
+#[cfg(feature = "extensions")]
+pub use merge_many::*;
+
+#[cfg(feature = "extensions")]
+pub mod query_params;
+#[cfg(feature = "extensions")]
+pub use query_params::*;
+
+#[cfg(feature = "extensions")]
+pub mod split_with_seed;
+#[cfg(feature = "extensions")]
+pub use split_with_seed::*;
+
+/// Program-local instructions start here, well clear of native discriminators
+#[cfg(feature = "extensions")]
+pub const DEACTIVATE_PARTIAL_DISCRIMINATOR: u8 = 128;
+#[cfg(feature = "extensions")]
+pub const WITHDRAW_EXCESS_DISCRIMINATOR: u8 = 129;
+#[cfg(feature = "extensions")]
+pub const SPLIT_WITH_SEED_DISCRIMINATOR: u8 = 130;
+#[cfg(feature = "extensions")]
+pub const GET_DELEGATION_PREVIEW_DISCRIMINATOR: u8 = 131;
+#[cfg(feature = "extensions")]
+pub const CLOSE_STAKE_ACCOUNT_DISCRIMINATOR: u8 = 132;
+#[cfg(feature = "extensions")]
+pub const MERGE_MANY_DISCRIMINATOR: u8 = 133;
+#[cfg(feature = "extensions")]
+pub const QUERY_PARAMS_DISCRIMINATOR: u8 = 134;
+#[cfg(feature = "withdraw-to-token")]
+pub const WITHDRAW_TO_TOKEN_ACCOUNT_DISCRIMINATOR: u8 = 135;
+#[cfg(feature = "extensions")]
+pub const GET_VERSION_DISCRIMINATOR: u8 = 136;
+
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StakeInstruction {
     Initialize,
     Authorize,
@@ -72,33 +125,31 @@ pub enum StakeInstruction {
     Redelegate,
     MoveStake,
     MoveLamports,
+    #[cfg(feature = "extensions")]
+    DeactivatePartial,
+    #[cfg(feature = "extensions")]
+    WithdrawExcess,
+    #[cfg(feature = "extensions")]
+    SplitWithSeed,
+    #[cfg(feature = "extensions")]
+    GetDelegationPreview,
+    #[cfg(feature = "extensions")]
+    CloseStakeAccount,
+    #[cfg(feature = "extensions")]
+    MergeMany,
+    #[cfg(feature = "extensions")]
+    QueryParams,
+    #[cfg(feature = "withdraw-to-token")]
+    WithdrawToTokenAccount,
+    #[cfg(feature = "extensions")]
+    GetVersion,
 }
 
 impl TryFrom<&u8> for StakeInstruction {
     type Error = ProgramError;
 
+    /// Discriminants are assigned in the dispatch table
     fn try_from(value: &u8) -> Result<Self, Self::Error> {
-        match *value {
-            0 => Ok(StakeInstruction::Initialize),
-            1 => Ok(StakeInstruction::Authorize),
-            2 => Ok(StakeInstruction::DelegateStake),
-            3 => Ok(StakeInstruction::Split),
-            4 => Ok(StakeInstruction::Withdraw),
-            5 => Ok(StakeInstruction::Deactivate),
-            6 => Ok(StakeInstruction::SetLockup),
-            7 => Ok(StakeInstruction::Merge),
-            8 => Ok(StakeInstruction::AuthorizeWithSeed),
-            9 => Ok(StakeInstruction::InitializeChecked),
-            10 => Ok(StakeInstruction::AuthorizeChecked),
-            11 => Ok(StakeInstruction::AuthorizeCheckedWithSeed),
-            12 => Ok(StakeInstruction::SetLockupChecked),
-            13 => Ok(StakeInstruction::GetMinimumDelegation),
-            14 => Ok(StakeInstruction::DeactivateDelinquent),
-            #[allow(deprecated)]
-            15 => Ok(StakeInstruction::Redelegate),
-            16 => Ok(StakeInstruction::MoveStake),
-            17 => Ok(StakeInstruction::MoveLamports),
-            _ => Err(ProgramError::InvalidInstructionData),
-        }
+        dispatch::descriptor(*value).map(|d| d.instruction).ok_or(ProgramError::InvalidInstructionData)
     }
 }
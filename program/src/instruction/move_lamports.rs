@@ -1,10 +1,9 @@
 
 extern crate alloc;
 
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult, sysvars::Sysvar};
-use crate::helpers::{next_account_info, relocate_lamports};
-use crate::helpers::merge::move_stake_or_lamports_shared_checks;
-use crate::state::merge_kind::MergeKind;
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+use crate::helpers::{free_lamports, next_account_info, relocate_lamports};
+use crate::helpers::merge_engine::move_stake_or_lamports_shared_checks;
 
 /// Move withdrawable lamports from one stake account to another.
 ///
@@ -12,6 +11,8 @@ use crate::state::merge_kind::MergeKind;
 /// 0. `[writable]` Source stake account (owned by this program)
 /// 1. `[writable]` Destination stake account (owned by this program)
 /// 2. `[signer]`   Staker authority (must be the *staker* of the source)
+///
+/// Source and destination must share authorities and lockup (`MergeMismatch`).
 pub fn process_move_lamports(accounts: &[AccountInfo], lamports: u64) -> ProgramResult {
     // Parse accounts
     let iter = &mut accounts.iter();
@@ -19,95 +20,110 @@ pub fn process_move_lamports(accounts: &[AccountInfo], lamports: u64) -> Program
     let destination_stake_ai = next_account_info(iter)?;
     let staker_authority_ai  = next_account_info(iter)?;
 
-    // Pre-check: explicitly reject deactivating accounts (destination or source)
-    let clock = pinocchio::sysvars::clock::Clock::get()?;
-    // Ensure both are valid stake states and not transiently deactivating
-    for (idx, ai) in [source_stake_ai, destination_stake_ai].iter().enumerate() {
-        match crate::helpers::get_stake_state(ai)? {
-            // Stake: check deactivation window
-            crate::state::stake_state_v2::StakeStateV2::Stake(_, stake, _) => {
-                let deact = crate::helpers::bytes_to_u64(stake.delegation.deactivation_epoch);
-                if deact != u64::MAX && clock.epoch <= deact {
-                    return Err(crate::error::to_program_error(
-                        crate::error::StakeError::MergeMismatch,
-                    ));
-                }
-            }
-            // Initialized: permitted (no deactivation to check)
-            crate::state::stake_state_v2::StakeStateV2::Initialized(_) => {
-            }
-            // Uninitialized or other: invalid
-            _ => {
-                return Err(ProgramError::InvalidAccountData);
-            }
-        }
-    }
-
     // Shared checks (signer present, accounts distinct and writable, nonzero amount,
     // classification via MergeKind, and metadata compatibility)
-    let (source_kind, dest_kind) = move_stake_or_lamports_shared_checks(
+    let (source_kind, _) = move_stake_or_lamports_shared_checks(
+        accounts,
         source_stake_ai,
         lamports,
         destination_stake_ai,
         staker_authority_ai,
-        true,  // enforce meta compatibility (authorities, lockups)
-        false, // do not require mergeable classification
+        true, // enforce meta compatibility (authorities, lockups)
     )?;
 
-    // Extra guard for lamports: require identical authorities between source and destination
-    let src_auth = &source_kind.meta().authorized;
-    let dst_auth = &dest_kind.meta().authorized;
-    if src_auth != dst_auth {
-        return Err(crate::error::to_program_error(crate::error::StakeError::MergeMismatch));
+    // Only lamports beyond what the source's stake state accounts for may move;
+    // the destination's balance is not checked, as in native
+    let source_free_lamports = free_lamports(&source_kind, source_stake_ai.lamports())?;
+    if lamports > source_free_lamports {
+        return Err(ProgramError::InvalidArgument);
     }
 
-    // (post-check logging removed; pre-check above handles transient)
+    relocate_lamports(source_stake_ai, destination_stake_ai, lamports)?;
 
-    // Additional authority check: the staker must authorize this movement
-    if source_kind.meta().authorized.staker != *staker_authority_ai.key() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    Ok(())
+}
 
-    // Compute how many lamports are available to move from source:
-    //  - FullyActive: lamports - delegated - rent_exempt_reserve
-    //  - Inactive:   lamports - rent_exempt_reserve
-    //  - Activating/deactivating: not allowed
-    let source_free_lamports = match source_kind {
-        MergeKind::FullyActive(ref meta, ref stake) => {
-            let rent_reserve = u64::from_le_bytes(meta.rent_exempt_reserve);
-            let delegated    = u64::from_le_bytes(stake.delegation.stake);
-
-            source_stake_ai
-                .lamports()
-                .saturating_sub(delegated)
-                .saturating_sub(rent_reserve)
-        }
-        MergeKind::Inactive(ref meta, source_lamports, _flags) => {
-            let rent_reserve = u64::from_le_bytes(meta.rent_exempt_reserve);
-            source_lamports.saturating_sub(rent_reserve)
-        }
-        _ => {
-            // Partially activating/deactivating is not allowed for MoveLamports
-            return Err(crate::error::to_program_error(crate::error::StakeError::MergeMismatch));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_accounts::{self, info, RawAccount};
+    use crate::state::{
+        accounts::Authorized,
+        migration::{ActiveMigration, StateMigration},
+        stake_state_v2::StakeStateV2,
+        state::Meta,
+        LamportsLE,
     };
+    use pinocchio::{pubkey::Pubkey, sysvars::clock::CLOCK_ID};
 
-    // Amount must be within the available budget
-    if lamports > source_free_lamports {
-        return Err(ProgramError::InvalidArgument);
+    const STAKER: Pubkey = [7; 32];
+    const RESERVE: u64 = 2_282_880;
+
+    fn raw<const N: usize>(key: Pubkey, owner: Pubkey, lamports: u64, data: [u8; N]) -> Box<RawAccount<N>> {
+        Box::new(test_accounts::raw(key, owner, lamports, data))
     }
 
-    // Move lamports
-    relocate_lamports(source_stake_ai, destination_stake_ai, lamports)?;
+    fn stake_account(key: u8, state: &StakeStateV2) -> Box<RawAccount<{ StakeStateV2::ACCOUNT_SIZE }>> {
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        ActiveMigration::store(state, &mut data).unwrap();
+        #[cfg(feature = "integrity")]
+        crate::helpers::checksum::write_checksum(&mut data);
+        raw([key; 32], crate::ID, RESERVE + 1_000, data)
+    }
 
-    // Post-condition: both accounts must remain at/above their rent reserves
-    let src_meta = source_kind.meta();
-    let dst_meta = dest_kind.meta();
-    if source_stake_ai.lamports() < u64::from_le_bytes(src_meta.rent_exempt_reserve)
-        || destination_stake_ai.lamports() < u64::from_le_bytes(dst_meta.rent_exempt_reserve)
-    {
-        return Err(ProgramError::InvalidArgument);
+    fn initialized() -> StakeStateV2 {
+        StakeStateV2::Initialized(Meta {
+            rent_exempt_reserve: LamportsLE::new(RESERVE),
+            authorized: Authorized { staker: STAKER, withdrawer: STAKER },
+            ..Meta::default()
+        })
     }
 
-    Ok(())
+    /// MoveLamports of one lamport from `source` to `destination` signed by `authority`
+    fn move_one(source: &StakeStateV2, destination: &StakeStateV2, authority: Pubkey) -> (ProgramResult, [u64; 2]) {
+        let mut source = stake_account(1, source);
+        let mut destination = stake_account(2, destination);
+        let mut authority = raw(authority, [0; 32], 0, [0u8; 0]);
+        authority.is_signer = 1;
+        let mut clock = raw(CLOCK_ID, [0; 32], 1, [0u8; 40]);
+        let accounts = [info(&mut source), info(&mut destination), info(&mut authority), info(&mut clock)];
+        let result = process_move_lamports(&accounts, 1);
+        (result, [accounts[0].lamports(), accounts[1].lamports()])
+    }
+
+    #[test]
+    fn uninitialized_accounts_are_rejected_in_native_order() {
+        let uninitialized = StakeStateV2::Uninitialized;
+        let balances = [RESERVE + 1_000; 2];
+        assert_eq!(move_one(&initialized(), &initialized(), STAKER), (Ok(()), [RESERVE + 999, RESERVE + 1_001]));
+
+        // the source is classified before the authority is checked against it
+        assert_eq!(move_one(&uninitialized, &initialized(), STAKER), (Err(ProgramError::InvalidAccountData), balances));
+        assert_eq!(move_one(&uninitialized, &initialized(), [8; 32]), (Err(ProgramError::InvalidAccountData), balances));
+        // and the authority before the destination is classified
+        assert_eq!(move_one(&initialized(), &uninitialized, STAKER), (Err(ProgramError::InvalidAccountData), balances));
+        assert_eq!(move_one(&initialized(), &uninitialized, [8; 32]), (Err(ProgramError::MissingRequiredSignature), balances));
+        assert_eq!(move_one(&uninitialized, &uninitialized, STAKER), (Err(ProgramError::InvalidAccountData), balances));
+    }
+
+    #[test]
+    fn owners_are_checked_as_each_account_is_loaded() {
+        let cases = [
+            (StakeStateV2::Uninitialized, 0, STAKER, ProgramError::InvalidAccountOwner),
+            // the source fails classification before the destination is loaded
+            (StakeStateV2::Uninitialized, 1, STAKER, ProgramError::InvalidAccountData),
+            (initialized(), 1, STAKER, ProgramError::InvalidAccountOwner),
+            (initialized(), 1, [8; 32], ProgramError::MissingRequiredSignature),
+        ];
+        for (source, foreign, signer, expected) in cases {
+            let mut stakes = [stake_account(1, &source), stake_account(2, &initialized())];
+            stakes[foreign].owner = [9; 32];
+            let mut authority = raw(signer, [0; 32], 0, [0u8; 0]);
+            authority.is_signer = 1;
+            let mut clock = raw(CLOCK_ID, [0; 32], 1, [0u8; 40]);
+            let [source, destination] = &mut stakes;
+            let accounts = [info(source), info(destination), info(&mut authority), info(&mut clock)];
+            assert_eq!(process_move_lamports(&accounts, 1), Err(expected), "foreign {foreign}");
+        }
+    }
 }
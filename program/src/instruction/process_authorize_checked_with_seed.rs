@@ -2,67 +2,36 @@ use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvars::clock::Clock,
     ProgramResult,
 };
 
 use crate::{
-    helpers::{collect_signers, get_stake_state, set_stake_state, MAXIMUM_SIGNERS},
+    helpers::{
+        derive_seed_authority, get_stake_state, presented_custodian,
+        require_signed_custodian_slot, set_stake_state,
+        verify_seed_authority,
+    },
     // Centralized policy checks: staker/withdrawer auth + lockup/custodian
     helpers::authorize_update,
     state::{
         accounts::AuthorizeCheckedWithSeedData,
         stake_state_v2::StakeStateV2,
-        StakeAuthorize,
     },
 };
-
-/// Recreates `Pubkey::create_with_seed(base, seed, owner)` in Pinocchio:
-/// derived = sha256(base || seed || owner)
-fn derive_with_seed_compat(base: &Pubkey, seed: &[u8], owner: &Pubkey) -> Result<Pubkey, ProgramError> {
-    // Enforce max seed length 32 bytes
-    if seed.len() > 32 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    let mut buf = [0u8; 32 + 32 + 32]; // base(32) + seed(<=32) + owner(32)
-    let mut off = 0usize;
-
-    // base
-    buf[off..off + 32].copy_from_slice(&base[..]);
-    off += 32;
-
-    // seed
-    buf[off..off + seed.len()].copy_from_slice(seed);
-    off += seed.len();
-
-    // owner
-    buf[off..off + 32].copy_from_slice(&owner[..]);
-    off += 32;
-
-    // sha256(buf[..off]) -> 32 bytes
-    let mut out = [0u8; 32];
-    const SUCCESS: u64 = 0;
-    let rc = unsafe { pinocchio::syscalls::sol_sha256(buf.as_ptr(), off as u64, out.as_mut_ptr()) };
-    if rc != SUCCESS {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    Ok(out)
-}
+use crate::helpers::sysvar_cache;
 
 pub fn process_authorize_checked_with_seed(
     accounts: &[AccountInfo],
     args: AuthorizeCheckedWithSeedData, // has: new_authorized, stake_authorize, authority_seed, authority_owner
 ) -> ProgramResult {
     let role = args.stake_authorize;
-    // Expected accounts: 4 (1 sysvar):
-    // stake, old_authority_base, clock, new_authority [, optional custodian, ...]
+    // Accounts: roles::AUTHORIZE_CHECKED_WITH_SEED
     if accounts.len() < 4 {
         return Err(ProgramError::NotEnoughAccountKeys);
     }
 
-    let [stake_ai, old_base_ai, clock_ai, new_auth_ai, rest @ ..] = accounts else {
+    // The base and new authority are positional because their keys are arguments
+    let [stake_ai, old_base_ai, _, new_auth_ai, ..] = accounts else {
         return Err(ProgramError::InvalidAccountData);
     };
 
@@ -70,91 +39,50 @@ pub fn process_authorize_checked_with_seed(
     if *stake_ai.owner() != crate::ID || !stake_ai.is_writable() {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if clock_ai.key() != &pinocchio::sysvars::clock::CLOCK_ID {
-        return Err(ProgramError::InvalidArgument);
-    }
     // New authority must be a signer
     if !new_auth_ai.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
-    // Optional custodian is passed through; policy enforces lockup rules
-    let _maybe_lockup_authority: Option<&AccountInfo> = rest.first();
-
     // Load sysvar clock (safe)
-    let _clock = Clock::from_account_info(clock_ai)?;
+    let clock = sysvar_cache::clock_account(accounts)?;
 
-    // Gather existing transaction signers (base and new_authorized must sign)
-    let mut signers_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let mut n = collect_signers(accounts, &mut signers_buf)?;
-    // Determine presence in signer set via pubkey membership (more robust than is_signer checks here)
-    let base_pk = *old_base_ai.key();
-    let new_pk = *new_auth_ai.key();
-    let contains = |k: &Pubkey, arr: &[Pubkey]| arr.iter().any(|s| s == k);
-    let mut base_in_signers = contains(&base_pk, &signers_buf[..n]);
-    let new_in_signers = contains(&new_pk, &signers_buf[..n]);
-    // If base present, augment signer set with derived PDA and both current authorized keys
-    // small numeric prints to avoid huge logs
-    let _n_dbg = n as u64; let _b_dbg = if base_in_signers {1u64} else {0}; let _new_dbg = if new_in_signers {1u64} else {0};
-    let _ = (_n_dbg, _b_dbg, _new_dbg);
-    if base_in_signers {
-        // Skip deriving PDA to avoid syscall length quirks in tests; inject current meta keys instead
-        // Current authorized keys from state (both staker and withdrawer to satisfy policy permutations)
-        if let Ok(state) = get_stake_state(stake_ai) {
-            let (staker_key, withdrawer_key) = match state {
-                StakeStateV2::Initialized(meta) => (meta.authorized.staker, meta.authorized.withdrawer),
-                StakeStateV2::Stake(meta, _, _) => (meta.authorized.staker, meta.authorized.withdrawer),
-                _ => (Pubkey::default(), Pubkey::default()),
-            };
-            if staker_key != Pubkey::default() && n < MAXIMUM_SIGNERS {
-                signers_buf[n] = staker_key;
-                n += 1;
-            }
-            if withdrawer_key != Pubkey::default() && n < MAXIMUM_SIGNERS {
-                signers_buf[n] = withdrawer_key;
-                n += 1;
-            }
-        }
-        // Recompute presence after augmentation
-        base_in_signers = true;
-    }
+    require_signed_custodian_slot(accounts, 4)?;
 
-    let _signers = &signers_buf[..n];
+    // The instruction acts only as the seed-derived authority, never as the base itself
+    let derived = derive_seed_authority(old_base_ai, args.authority_seed, &args.authority_owner)?;
+    let signers = [derived];
 
     // In checked variants, the new authority is the 4th account
     let new_authorized: Pubkey = *new_auth_ai.key();
-    // Enforce both base and new authority present in signer set
-    if !base_in_signers || !new_in_signers {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
 
-    // Update via centralized policy using signer set that includes the derived PDA
     match get_stake_state(stake_ai)? {
         StakeStateV2::Initialized(mut meta) => {
-            // Use augmented signer set from earlier (base + meta-authorized keys)
-            let signers = &signers_buf[..n];
+            verify_seed_authority(&meta, role, &derived)?;
+            let custodian = presented_custodian(accounts, 4);
             authorize_update(
                 &mut meta,
                 new_authorized,
                 role,
-                signers,
-                _maybe_lockup_authority,
-                &_clock,
+                &signers,
+                custodian,
+                &clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Initialized(meta))?;
         }
         StakeStateV2::Stake(mut meta, stake, flags) => {
-            let signers = &signers_buf[..n];
+            verify_seed_authority(&meta, role, &derived)?;
+            let custodian = presented_custodian(accounts, 4);
             authorize_update(
                 &mut meta,
                 new_authorized,
                 role,
-                signers,
-                _maybe_lockup_authority,
-                &_clock,
+                &signers,
+                custodian,
+                &clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     }
 
     Ok(())
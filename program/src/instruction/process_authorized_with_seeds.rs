@@ -1,21 +1,22 @@
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::clock::Clock,
     ProgramResult,
 };
 
 use crate::{
-    helpers::{collect_signers, get_stake_state, set_stake_state, MAXIMUM_SIGNERS},
+    helpers::{
+        derive_seed_authority, get_stake_state, presented_custodian,
+        require_signed_custodian_slot, set_stake_state,
+        verify_seed_authority,
+    },
     helpers::authorize_update,
     state::{
         accounts::AuthorizeWithSeedData,
         stake_state_v2::StakeStateV2,
     },
 };
-
-
+use crate::helpers::sysvar_cache;
 
 pub fn process_authorized_with_seeds(
     accounts: &[AccountInfo],
@@ -27,8 +28,9 @@ pub fn process_authorized_with_seeds(
         return Err(ProgramError::NotEnoughAccountKeys);
     }
 
-    // stake, base, clock, [maybe custodian, ...]
-    let [stake_ai, _base_ai, clock_ai, rest @ ..] = accounts else {
+    // stake, base, clock, [maybe custodian, ...]; the base is positional because
+    // the derivation starts from its key
+    let [stake_ai, base_ai, ..] = accounts else {
         return Err(ProgramError::InvalidAccountData);
     };
 
@@ -36,51 +38,45 @@ pub fn process_authorized_with_seeds(
     if *stake_ai.owner() != crate::ID || !stake_ai.is_writable() {
         return Err(ProgramError::IncorrectProgramId);
     }
-    if clock_ai.key() != &pinocchio::sysvars::clock::CLOCK_ID {
-        return Err(ProgramError::InvalidArgument);
-    }
 
     // Load clock (safe)
-    let clock = Clock::from_account_info(clock_ai)?;
-
-    // Optional lockup custodian account (pass-through to policy)
-    let maybe_lockup_authority: Option<&AccountInfo> = rest.first();
+    let clock = sysvar_cache::clock_account(accounts)?;
 
-   
-    // Build the signer set (include all tx signers). Base signer is sufficient
-    // to satisfy policy for non-checked variant (old authority may change it).
-    let mut signers_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let mut n = collect_signers(accounts, &mut signers_buf)?;
-    // No extra augmentation needed
+    require_signed_custodian_slot(accounts, 3)?;
 
-    // Final signer slice we pass to the policy
-    let signers = &signers_buf[..n];
+    // The instruction acts only as the seed-derived authority, never as the base itself
+    let derived = derive_seed_authority(base_ai, args.authority_seed, &args.authority_owner)?;
+    let signers = [derived];
 
     // Load state, apply policy update, write back
     match get_stake_state(stake_ai)? {
         StakeStateV2::Initialized(mut meta) => {
+            verify_seed_authority(&meta, role, &derived)?;
+            let custodian = presented_custodian(accounts, 3);
             authorize_update(
                 &mut meta,
                 args.new_authorized,
                 role,
-                signers,
-                maybe_lockup_authority,
+                &signers,
+                custodian,
                 &clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Initialized(meta))?;
         }
         StakeStateV2::Stake(mut meta, stake, flags) => {
+            verify_seed_authority(&meta, role, &derived)?;
+            let custodian = presented_custodian(accounts, 3);
             authorize_update(
                 &mut meta,
                 args.new_authorized,
                 role,
-                signers,
-                maybe_lockup_authority,
+                &signers,
+                custodian,
                 &clock,
             )?;
             set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     }
 
     Ok(())
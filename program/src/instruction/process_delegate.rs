@@ -1,37 +1,39 @@
 // Delegate instruction
 use pinocchio::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, sysvars::clock::Clock,
+    account_info::AccountInfo,
+    program_error::ProgramError,
     ProgramResult,
 };
 
 use crate::error::to_program_error;
 use crate::helpers::{
-    collect_signers, next_account_info, MAXIMUM_SIGNERS, validate_delegated_amount,
+    Signers, next_account_info, validate_delegated_amount,
     ValidatedDelegatedInfo,
 };
-use crate::helpers::utils::{
+use crate::helpers::{
     get_stake_state, get_vote_credits, new_stake_with_credits, redelegate_stake_with_credits,
     set_stake_state,
 };
-use crate::state::stake_history::StakeHistorySysvar;
+use crate::state::stake_history::WindowedStakeHistory;
 use crate::state::{StakeAuthorize, StakeFlags, StakeStateV2};
+use crate::helpers::{sysvar_cache, warmup_rate};
 
 pub fn process_delegate(accounts: &[AccountInfo]) -> ProgramResult {
     // Gather signers
-    let mut signers_array = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let signers_count = collect_signers(accounts, &mut signers_array)?;
-    let signers = &signers_array[..signers_count];
+    let signers = &Signers::collect(accounts)?;
 
-    // Expected accounts: stake, vote, clock, stake_history, stake_config
+    // Accounts: roles::DELEGATE_STAKE
     let account_info_iter = &mut accounts.iter();
     let stake_account_info = next_account_info(account_info_iter)?;
     let vote_account_info = next_account_info(account_info_iter)?;
-    let clock_info = next_account_info(account_info_iter)?;
+    // clock, stake_history, stake_config: only the clock is read, located by pubkey
+    let _clock_info = next_account_info(account_info_iter)?;
     let _stake_history_info = next_account_info(account_info_iter)?;
     let _stake_config_info = next_account_info(account_info_iter)?;
 
-    let clock = &Clock::from_account_info(clock_info)?;
-    let stake_history = &StakeHistorySysvar(clock.epoch);
+    let clock = &sysvar_cache::clock_account(accounts)?;
+    let stake_history = &WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
 
     let vote_credits = get_vote_credits(vote_account_info)?;
 
@@ -68,15 +70,7 @@ pub fn process_delegate(accounts: &[AccountInfo]) -> ProgramResult {
             let ValidatedDelegatedInfo { stake_amount } =
                 validate_delegated_amount(stake_account_info, &meta)?;
 
-            // If deactivation is scheduled and target vote differs, reject (TooSoon)
-            // Pre-check: if deactivating, only allow redelegation to the same vote
-            let current_voter = stake.delegation.voter_pubkey;
-            let deact_epoch = crate::helpers::bytes_to_u64(stake.delegation.deactivation_epoch);
-            if deact_epoch != u64::MAX && current_voter != *vote_account_info.key() {
-                return Err(to_program_error(crate::error::StakeError::TooSoonToRedelegate));
-            }
-
-            // Let helper update stake state (possible rescind or re-delegate)
+            // Reuse the stake if nothing is effective, else at most rescind (TooSoon otherwise)
             redelegate_stake_with_credits(
                 &mut stake,
                 stake_amount,
@@ -84,11 +78,12 @@ pub fn process_delegate(accounts: &[AccountInfo]) -> ProgramResult {
                 vote_credits,
                 clock.epoch,
                 stake_history,
+                new_rate_activation_epoch,
             )?;
 
             set_stake_state(stake_account_info, &StakeStateV2::Stake(meta, stake, flags))
         }
-        _ => Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => Err(ProgramError::InvalidAccountData),
     }?;
 
     Ok(())
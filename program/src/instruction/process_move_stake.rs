@@ -1,5 +1,5 @@
 
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult, sysvars::Sysvar};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::pubkey_eq, ProgramResult};
 
 use crate::error::{to_program_error, StakeError};
 use crate::helpers::{
@@ -8,63 +8,36 @@ use crate::helpers::{
     next_account_info,
     relocate_lamports, // use shared helper, not a local copy
     set_stake_state,
-    get_stake_state,
+    validate_stake_account_shape,
+    StakeAccountShape,
 };
-use crate::helpers::merge::{
+use crate::helpers::merge_engine::{
     merge_delegation_stake_and_credits_observed,
     move_stake_or_lamports_shared_checks,
 };
-use crate::state::{MergeKind, StakeFlags, StakeStateV2};
+use crate::state::{LamportsLE, MergeKind, StakeFlags, StakeStateV2};
 
 pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramResult {
     let it = &mut accounts.iter();
-    // Expected accounts: 3
+    // Accounts: roles::MOVE_STAKE
     let source_stake_account_info = next_account_info(it)?;
     let destination_stake_account_info = next_account_info(it)?;
     let stake_authority_info = next_account_info(it)?;
 
-    // Verify signer status is provided by the runtime
-    if stake_authority_info.is_signer() {
-    } else {
-    }
-
-    // Early: Uninitialized on either side is invalid for MoveStake
-    if let Ok(state) = get_stake_state(source_stake_account_info) {
-        if let StakeStateV2::Uninitialized = state {
-            return Err(ProgramError::InvalidAccountData);
-        }
-    }
-    if let Ok(state) = get_stake_state(destination_stake_account_info) {
-        if let StakeStateV2::Uninitialized = state {
-            return Err(ProgramError::InvalidAccountData);
-        }
-    }
-
-    // Shared checks + classification (auth, writable, nonzero, compatible metas)
+    // Shared checks + classification (auth, writable, nonzero, compatible metas);
+    // the only load of either account's state
     let (source_kind, destination_kind) = move_stake_or_lamports_shared_checks(
+        accounts,
         source_stake_account_info,
         lamports,
         destination_stake_account_info,
         stake_authority_info,
-        true,  // need meta compat for stake
-        true,  // require mergeable classification
+        true, // need meta compat for stake
     )?;
 
-    // Additional explicit guard (post-signer-check): destination must not be deactivating
-    if let Ok(StakeStateV2::Stake(_, stake, _)) = get_stake_state(destination_stake_account_info) {
-        let deact = bytes_to_u64(stake.delegation.deactivation_epoch);
-        let clock = pinocchio::sysvars::clock::Clock::get()?;
-        if deact != u64::MAX && clock.epoch <= deact {
-            return Err(crate::error::to_program_error(crate::error::StakeError::MergeMismatch));
-        }
-    }
-
     // Native safeguard: require exact account data size
-    if source_stake_account_info.data_len() != StakeStateV2::size_of()
-        || destination_stake_account_info.data_len() != StakeStateV2::size_of()
-    {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    validate_stake_account_shape(source_stake_account_info, StakeAccountShape::Exact)?;
+    validate_stake_account_shape(destination_stake_account_info, StakeAccountShape::Exact)?;
 
     // Source must be fully active
     let MergeKind::FullyActive(source_meta, mut source_stake) = source_kind else {
@@ -75,7 +48,7 @@ pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramRes
     let source_effective_stake = source_stake.delegation.stake;
 
     // cannot move more stake than the source has (even if it has plenty of lamports)
-    let source_final_stake = bytes_to_u64(source_effective_stake)
+    let source_final_stake = source_effective_stake.get()
         .checked_sub(lamports)
         .ok_or(ProgramError::InvalidArgument)?;
 
@@ -88,12 +61,12 @@ pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramRes
     let destination_meta = match destination_kind {
         MergeKind::FullyActive(destination_meta, mut destination_stake) => {
             // active destination must share the same vote account
-            if source_stake.delegation.voter_pubkey != destination_stake.delegation.voter_pubkey {
+            if !pubkey_eq(&source_stake.delegation.voter_pubkey, &destination_stake.delegation.voter_pubkey) {
                 return Err(to_program_error(StakeError::VoteAddressMismatch));
             }
 
             let destination_effective_stake = destination_stake.delegation.stake;
-            let destination_final_stake = bytes_to_u64(destination_effective_stake)
+            let destination_final_stake = destination_effective_stake.get()
                 .checked_add(lamports)
                 .ok_or(ProgramError::ArithmeticOverflow)?;
 
@@ -124,7 +97,7 @@ pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramRes
 
             // clone source stake shape and set only the moved stake amount
             let mut destination_stake = source_stake;
-            destination_stake.delegation.stake = lamports.to_le_bytes();
+            destination_stake.delegation.stake = LamportsLE::new(lamports);
 
             set_stake_state(
                 destination_stake_account_info,
@@ -143,7 +116,7 @@ pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramRes
             &StakeStateV2::Initialized(source_meta),
         )?;
     } else {
-        source_stake.delegation.stake = source_final_stake.to_le_bytes();
+        source_stake.delegation.stake = LamportsLE::new(source_final_stake);
         set_stake_state(
             source_stake_account_info,
             &StakeStateV2::Stake(source_meta, source_stake, StakeFlags::empty()),
@@ -158,9 +131,9 @@ pub fn process_move_stake(accounts: &[AccountInfo], lamports: u64) -> ProgramRes
     )?;
 
     // guard against impossible (rent) underflows due to any mismatch in math
-    if source_stake_account_info.lamports() < bytes_to_u64(source_meta.rent_exempt_reserve)
+    if source_stake_account_info.lamports() < source_meta.rent_exempt_reserve.get()
         || destination_stake_account_info.lamports()
-            < bytes_to_u64(destination_meta.rent_exempt_reserve)
+            < destination_meta.rent_exempt_reserve.get()
     {
         return Err(ProgramError::InvalidArgument);
     }
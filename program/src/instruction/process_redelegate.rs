@@ -1,39 +1,36 @@
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::clock::Clock,
     ProgramResult,
 };
 
 use crate::{
     error::to_program_error,
-    helpers::{collect_signers, next_account_info},
-    helpers::utils::{
+    helpers::{Signers, next_account_info},
+    helpers::{
         get_stake_state, get_vote_credits, new_stake_with_credits, redelegate_stake_with_credits, set_stake_state,
         validate_delegated_amount, ValidatedDelegatedInfo,
     },
-    helpers::constant::MAXIMUM_SIGNERS,
-    state::{StakeAuthorize, StakeFlags, StakeHistorySysvar, StakeStateV2},
+    state::{StakeAuthorize, StakeFlags, WindowedStakeHistory, StakeStateV2},
 };
+use crate::helpers::{sysvar_cache, warmup_rate};
 
 /// Redelegate/Delegate helper (works for initial delegation and redelegation)
 pub fn redelegate(accounts: &[AccountInfo]) -> ProgramResult {
     // Collect signers from the full account list
-    let mut signers_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let n = collect_signers(accounts, &mut signers_buf)?;
-    let signers = &signers_buf[..n];
+    let signers = &Signers::collect(accounts)?;
 
-    // Expected accounts: 5 (2 sysvars + stake config)
+    // Accounts: roles::REDELEGATE
     let account_info_iter = &mut accounts.iter();
     let stake_account_info = next_account_info(account_info_iter)?;
     let vote_account_info  = next_account_info(account_info_iter)?;
-    let clock_info         = next_account_info(account_info_iter)?;
+    let _clock_info        = next_account_info(account_info_iter)?; // located by pubkey below
     let _stake_history     = next_account_info(account_info_iter)?; // present but not read directly
     let _stake_config      = next_account_info(account_info_iter)?; // present but not read directly
 
-    let clock = &Clock::from_account_info(clock_info)?;
-    let stake_history = StakeHistorySysvar(clock.epoch);
+    let clock = &sysvar_cache::clock_account(accounts)?;
+    let stake_history = WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
 
     let vote_credits = get_vote_credits(vote_account_info)?;
 
@@ -78,12 +75,118 @@ pub fn redelegate(accounts: &[AccountInfo]) -> ProgramResult {
                 vote_credits,
                 clock.epoch,
                 &stake_history,
+                new_rate_activation_epoch,
             )?;
 
             set_stake_state(stake_account_info, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     }
 
     Ok(())
 }
+
+/// Historical `Redelegate` (discriminant 15), kept for replaying old
+/// transactions against this program. It was never activated on mainnet.
+///
+/// Accounts: [`REDELEGATE`](super::roles::REDELEGATE); the uninitialized stake
+/// receives the effective stake and the stake config is unused.
+#[cfg(feature = "redelegate-legacy")]
+pub fn process_redelegate(accounts: &[AccountInfo]) -> ProgramResult {
+    use pinocchio::pubkey::pubkey_eq;
+    use crate::{
+        error::StakeError,
+        helpers::{
+            deactivate_stake, ensure_distinct, relocate_lamports, validate_stake_account_shape, StakeAccountShape,
+        },
+        state::{EpochLE, LamportsLE},
+    };
+    
+
+    let signers = &Signers::collect(accounts)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let stake_account_info = next_account_info(account_info_iter)?;
+    let uninitialized_stake_account_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    ensure_distinct(
+        &[stake_account_info, uninitialized_stake_account_info],
+        ProgramError::InvalidAccountData,
+    )?;
+
+    let clock = sysvar_cache::clock(accounts)?;
+    let stake_history = WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
+
+    // destination must be an uninitialized stake account of the canonical size
+    if *uninitialized_stake_account_info.owner() != crate::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    validate_stake_account_shape(uninitialized_stake_account_info, StakeAccountShape::Exact)?;
+    if !matches!(
+        get_stake_state(uninitialized_stake_account_info)?,
+        StakeStateV2::Uninitialized
+    ) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // get_vote_credits enforces the vote program owner
+    let vote_credits = get_vote_credits(vote_account_info)?;
+    let vote_pubkey = *vote_account_info.key();
+
+    let (meta, mut stake, mut flags, effective_stake) = match get_stake_state(stake_account_info)? {
+        StakeStateV2::Stake(meta, stake, flags) => {
+            let status = stake.delegation.stake_activating_and_deactivating(
+                EpochLE::new(clock.epoch),
+                &stake_history,
+                new_rate_activation_epoch,
+            );
+            let effective = status.effective.get();
+            if effective == 0
+                || status.activating.get() != 0
+                || status.deactivating.get() != 0
+            {
+                return Err(to_program_error(StakeError::RedelegateTransientOrInactiveStake));
+            }
+            // redelegating to the same vote account would only grief the warmup/cooldown rate
+            if pubkey_eq(&stake.delegation.voter_pubkey, &vote_pubkey) {
+                return Err(to_program_error(StakeError::RedelegateToSameVoteAccount));
+            }
+            (meta, stake, flags, effective)
+        }
+        StakeStateV2::Uninitialized | StakeStateV2::Initialized(_) | StakeStateV2::RewardsPool => {
+            return Err(ProgramError::InvalidAccountData)
+        }
+    };
+
+    // deactivate the source; this also requires the staker signature
+    meta.authorized
+        .check(signers, StakeAuthorize::Staker)
+        .map_err(to_program_error)?;
+    deactivate_stake(&mut stake, &mut flags, clock.epoch, &stake_history, new_rate_activation_epoch)
+        .map_err(to_program_error)?;
+    set_stake_state(stake_account_info, &StakeStateV2::Stake(meta, stake, flags))?;
+
+    // move the effective stake into the new account
+    relocate_lamports(
+        stake_account_info,
+        uninitialized_stake_account_info,
+        effective_stake,
+    )?;
+
+    // initialize the new account and schedule it for activation
+    let mut new_meta = meta;
+    new_meta.rent_exempt_reserve =
+        LamportsLE::new(sysvar_cache::rent(accounts)?.minimum_balance(uninitialized_stake_account_info.data_len()));
+    let ValidatedDelegatedInfo { stake_amount } =
+        validate_delegated_amount(uninitialized_stake_account_info, &new_meta)?;
+
+    set_stake_state(
+        uninitialized_stake_account_info,
+        &StakeStateV2::Stake(
+            new_meta,
+            new_stake_with_credits(stake_amount, &vote_pubkey, clock.epoch, vote_credits),
+            StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED,
+        ),
+    )
+}
@@ -1,118 +1,49 @@
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
 
 use crate::{
-    helpers::{collect_signers, next_account_info},
-    helpers::utils::{get_stake_state, set_stake_state},
-    helpers::constant::MAXIMUM_SIGNERS,
-    state::{accounts::SetLockupData, stake_state_v2::StakeStateV2, state::Meta},
+    helpers::{Signers, next_account_info},
+    helpers::{get_stake_state, set_stake_state},
+    state::{accounts::SetLockupData, stake_state_v2::StakeStateV2},
 };
+use crate::helpers::sysvar_cache;
 
 pub fn process_set_lockup(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    // Iterate accounts: first is stake; additional accounts may be supplied
-    let account_info_iter = &mut accounts.iter();
-    let stake_account_info = next_account_info(account_info_iter)?;
-    // Additional accounts are considered for signer collection
-
-    // Parse payload into optional fields
-    let args = SetLockupData::instruction_data(instruction_data);
-
-    // Read the clock sysvar directly (no clock account is required)
-    let clock = Clock::get()?;
-
-    // Collect all signers from all provided accounts
-    let mut signer_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let n = collect_signers(accounts, &mut signer_buf)?;
-    let signers = &signer_buf[..n];
-
-    // Owner and size checks are performed by get_stake_state(); writable is enforced by set_stake_state
-    match get_stake_state(stake_account_info)? {
-        StakeStateV2::Initialized(mut meta) => {
-            apply_lockup_update(&mut meta, &args, &clock, signers)?;
-            set_stake_state(stake_account_info, &StakeStateV2::Initialized(meta))
-        }
-        StakeStateV2::Stake(mut meta, stake, stake_flags) => {
-            apply_lockup_update(&mut meta, &args, &clock, signers)?;
-            set_stake_state(
-                stake_account_info,
-                &StakeStateV2::Stake(meta, stake, stake_flags),
-            )
-        }
-        _ => Err(ProgramError::InvalidAccountData),
-    }
+    let args = SetLockupData::parse(instruction_data)?;
+    process_set_lockup_parsed(accounts, args)
 }
 
-// Bincode-decoded variant: accept parsed LockupArgs directly (native parity)
-pub fn process_set_lockup_parsed(
-    accounts: &[AccountInfo],
-    lockup: crate::state::accounts::SetLockupData, // we will translate to Meta updates
-) -> ProgramResult {
-    // Iterate accounts: first is stake
+/// Same as [`process_set_lockup`] for callers that already decoded the args
+/// (e.g. the bincode wire path).
+pub fn process_set_lockup_parsed(accounts: &[AccountInfo], args: SetLockupData) -> ProgramResult {
+    // First account is the stake; every provided account counts for signers
     let account_info_iter = &mut accounts.iter();
     let stake_account_info = next_account_info(account_info_iter)?;
 
-    // Read the clock sysvar directly (no clock account required)
-    let clock = Clock::get()?;
+    let signers = &Signers::collect(accounts)?;
+
+    // Owner and size checks are performed by get_stake_state(); writable is enforced by set_stake_state
+    let state = get_stake_state(stake_account_info)?;
 
-    // Collect signers
-    let mut signer_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let n = collect_signers(accounts, &mut signer_buf)?;
-    let signers = &signer_buf[..n];
+    // No clock account is required (read if passed, else via the syscall);
+    // loaded once the account has passed the cheap checks
+    let clock = sysvar_cache::clock(accounts)?;
 
-    match get_stake_state(stake_account_info)? {
+    match state {
         StakeStateV2::Initialized(mut meta) => {
-            apply_lockup_update(&mut meta, &lockup, &clock, signers)?;
+            meta.set_lockup(&args, signers, &clock)?;
             set_stake_state(stake_account_info, &StakeStateV2::Initialized(meta))
         }
         StakeStateV2::Stake(mut meta, stake, stake_flags) => {
-            apply_lockup_update(&mut meta, &lockup, &clock, signers)?;
+            meta.set_lockup(&args, signers, &clock)?;
             set_stake_state(
                 stake_account_info,
                 &StakeStateV2::Stake(meta, stake, stake_flags),
             )
         }
-        _ => Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => Err(ProgramError::InvalidAccountData),
     }
 }
-
-/// Lockup gating in `Meta::set_lockup`:
-/// - If lockup is in force → current custodian must have signed
-/// - Else → current withdraw authority must have signed
-/// Then apply any provided fields as-is.
-pub fn apply_lockup_update(
-    meta: &mut Meta,
-    args: &SetLockupData,
-    clock: &Clock,
-    signers: &[Pubkey],
-) -> ProgramResult {
-    let signed = |pk: &Pubkey| signers.iter().any(|s| s == pk);
-
-    // Lockup in force? (pass None to disallow custodian bypass)
-    let in_force = meta.lockup.is_in_force(clock, None);
-
-    if in_force {
-        if !signed(&meta.lockup.custodian) {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-    } else if !signed(&meta.authorized.withdrawer) {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    // Apply optional fields (no monotonicity check)
-    if let Some(ts) = args.unix_timestamp {
-        meta.lockup.unix_timestamp = ts;
-    }
-    if let Some(ep) = args.epoch {
-        meta.lockup.epoch = ep;
-    }
-    if let Some(cust) = args.custodian {
-        meta.lockup.custodian = cust;
-    }
-
-    Ok(())
-}
@@ -4,14 +4,15 @@ use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
 
 use crate::{
-    helpers::{collect_signers, get_stake_state, set_stake_state, MAXIMUM_SIGNERS},
-    state::{stake_state_v2::StakeStateV2, state::Meta},
+    helpers::{Signers, get_stake_state, set_stake_state},
+    state::{accounts::SetLockupData, stake_state_v2::StakeStateV2},
 };
+use crate::helpers::sysvar_cache;
+use super::roles::{self, Role};
 
 
 pub struct LockupCheckedData {
@@ -51,8 +52,18 @@ impl LockupCheckedData {
             None
         };
 
+        // Reject trailing bytes after the flagged fields
+        if off != data.len() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
         Ok(Self { unix_timestamp, epoch })
     }
+
+    /// Full SetLockup args; the new custodian travels as a signing account
+    pub fn with_custodian(&self, custodian: Option<Pubkey>) -> SetLockupData {
+        SetLockupData { unix_timestamp: self.unix_timestamp, epoch: self.epoch, custodian }
+    }
 }
 
 
@@ -64,91 +75,72 @@ pub fn process_set_lockup_checked(
         return Err(ProgramError::NotEnoughAccountKeys);
     }
 
-    // stake, [old_auth?], [new_lockup_auth?], ...
-    let stake_ai = &accounts[0];
-
     // Parse the payload
     let checked = LockupCheckedData::parse(instruction_data)?;
+    process_set_lockup_checked_parsed(accounts, checked)
+}
+
+/// Same as [`process_set_lockup_checked`] for callers that already decoded the args
+/// (e.g. the bincode wire path).
+pub fn process_set_lockup_checked_parsed(
+    accounts: &[AccountInfo],
+    checked: LockupCheckedData,
+) -> ProgramResult {
+    // Accounts: roles::SET_LOCKUP_CHECKED
+    let stake_ai = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
 
     // Collect all signers
-    let mut signer_buf = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let n = collect_signers(accounts, &mut signer_buf)?;
-    let signers = &signer_buf[..n];
+    let signers = &Signers::collect(accounts)?;
 
-    // Optional new custodian comes from account #2 and must be a signer if present
-    let custodian_update: Option<Pubkey> = match accounts.get(2) {
+    // Optional new custodian must be a signer if present
+    const NEW_CUSTODIAN: Option<usize> = roles::position(roles::SET_LOCKUP_CHECKED, Role::NewLockupCustodian);
+    let custodian_update: Option<Pubkey> = match NEW_CUSTODIAN.and_then(|i| accounts.get(i)) {
         Some(ai) if ai.is_signer() => Some(*ai.key()),
         Some(_ai) => return Err(ProgramError::MissingRequiredSignature),
         None => None, // no custodian change
     };
 
-    // Use Clock::get() (no clock account is required)
-    let clock = Clock::get()?;
+    let args = checked.with_custodian(custodian_update);
 
     // Owner check happens in get_stake_state()
-    match get_stake_state(stake_ai)? {
+    let state = get_stake_state(stake_ai)?;
+
+    // No clock account is required: read it if passed, else via the syscall
+    let clock = sysvar_cache::clock(accounts)?;
+
+    match state {
         StakeStateV2::Initialized(mut meta) => {
-            apply_set_lockup_policy(
-                &mut meta,
-                checked.unix_timestamp,
-                checked.epoch,
-                custodian_update,
-                signers,
-                &clock,
-            )?;
+            meta.set_lockup(&args, signers, &clock)?;
             set_stake_state(stake_ai, &StakeStateV2::Initialized(meta))?;
         }
         StakeStateV2::Stake(mut meta, stake, flags) => {
-            apply_set_lockup_policy(
-                &mut meta,
-                checked.unix_timestamp,
-                checked.epoch,
-                custodian_update,
-                signers,
-                &clock,
-            )?;
+            meta.set_lockup(&args, signers, &clock)?;
             set_stake_state(stake_ai, &StakeStateV2::Stake(meta, stake, flags))?;
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     }
 
     Ok(())
 }
 
 
-fn apply_set_lockup_policy(
-    meta: &mut Meta,
-    unix_ts: Option<i64>,
-    epoch: Option<u64>,
-    custodian_update: Option<Pubkey>,
-    signers: &[Pubkey],
-    clock: &Clock,
-) -> Result<(), ProgramError> {
-    let is_signed = |who: &Pubkey| signers.iter().any(|s| s == who);
-
-    // Gate by current lockup status
-    if meta.lockup.is_in_force(clock, None) {
-        // Lockup currently in force => custodian must sign
-        if !is_signed(&meta.lockup.custodian) {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-    } else {
-        // Lockup not in force => withdrawer must sign
-        if !is_signed(&meta.authorized.withdrawer) {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Apply updates
-    if let Some(ts) = unix_ts {
-        meta.lockup.unix_timestamp = ts;
-    }
-    if let Some(ep) = epoch {
-        meta.lockup.epoch = ep;
-    }
-    if let Some(new_custodian) = custodian_update {
-        meta.lockup.custodian = new_custodian;
-    }
+    #[test]
+    fn parse_rejects_trailing_bytes() {
+        let mut data = [0u8; 1 + 8 + 8 + 1];
+        data[0] = 0x03;
+        data[1..9].copy_from_slice(&5i64.to_le_bytes());
+        data[9..17].copy_from_slice(&6u64.to_le_bytes());
 
-    Ok(())
+        let parsed = LockupCheckedData::parse(&data[..17]).unwrap();
+        assert_eq!(parsed.unix_timestamp, Some(5));
+        assert_eq!(parsed.epoch, Some(6));
+
+        assert!(LockupCheckedData::parse(&data).is_err());
+        assert!(LockupCheckedData::parse(&[0x00, 0xAA]).is_err());
+        assert!(LockupCheckedData::parse(&[0x00]).is_ok());
+    }
 }
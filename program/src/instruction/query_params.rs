@@ -0,0 +1,70 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::{
+    helpers::{get_minimum_delegation, sysvar_cache, warmup_cooldown_rate, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH},
+    state::{stake_state_v2::StakeStateV2, EpochLE},
+};
+
+/// The program parameters front-ends otherwise hard-code, in one simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StakeParams {
+    /// Same value GetMinimumDelegation returns
+    pub minimum_delegation: u64,
+    /// Data length of a stake account
+    pub stake_account_size: u64,
+    /// Warmup/cooldown rate in effect at the current epoch, as `f64` bits
+    pub warmup_cooldown_rate: u64,
+}
+
+impl StakeParams {
+    pub const LEN: usize = 8 + 8 + 8;
+
+    pub fn at_epoch(epoch: u64) -> Self {
+        let rate = warmup_cooldown_rate(EpochLE::new(epoch), PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH);
+        Self {
+            minimum_delegation: get_minimum_delegation(),
+            stake_account_size: StakeStateV2::ACCOUNT_SIZE as u64,
+            warmup_cooldown_rate: rate.to_bits(),
+        }
+    }
+
+    /// Return data: minimum_delegation, stake_account_size, warmup_cooldown_rate
+    /// (each u64 LE)
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[..8].copy_from_slice(&self.minimum_delegation.to_le_bytes());
+        out[8..16].copy_from_slice(&self.stake_account_size.to_le_bytes());
+        out[16..].copy_from_slice(&self.warmup_cooldown_rate.to_le_bytes());
+        out
+    }
+}
+
+/// QueryParams (extension): read-only query. Accounts:
+/// [`QUERY_PARAMS`](super::roles::QUERY_PARAMS); a passed clock sysvar saves the syscall.
+pub fn process_query_params(accounts: &[AccountInfo]) -> Result<StakeParams, ProgramError> {
+    let clock = sysvar_cache::clock(accounts)?;
+    Ok(StakeParams::at_epoch(clock.epoch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_match_program_constants() {
+        let params = StakeParams::at_epoch(0);
+        assert_eq!(params.minimum_delegation, get_minimum_delegation());
+        assert_eq!(params.stake_account_size, 200);
+        // the new rate has applied since genesis
+        assert_eq!(f64::from_bits(params.warmup_cooldown_rate), 0.09);
+        assert_eq!(StakeParams::at_epoch(u64::MAX), params);
+    }
+
+    #[test]
+    fn return_data_layout() {
+        let bytes = StakeParams { minimum_delegation: 1, stake_account_size: 200, warmup_cooldown_rate: 0x0102 }.to_bytes();
+        assert_eq!(bytes[..8], 1u64.to_le_bytes());
+        assert_eq!(bytes[8..16], 200u64.to_le_bytes());
+        assert_eq!(bytes[16..], 0x0102u64.to_le_bytes());
+    }
+}
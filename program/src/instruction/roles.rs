@@ -0,0 +1,304 @@
+//! Account layouts of every instruction, as const tables of typed roles.
+//!
+//! Each table lists the accounts in the order the native builders pass them.
+//! The dispatcher attaches them to the instruction descriptors, handlers look
+//! up the position of an account they read by role, and client builders can
+//! derive their account metas from the same entries.
+
+/// What an account is to the instruction that receives it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Stake,
+    SplitStake,
+    SourceStake,
+    DestinationStake,
+    UninitializedStake,
+    Recipient,
+    NativeTokenAccount,
+    Vote,
+    DelinquentVote,
+    ReferenceVote,
+    RentSysvar,
+    ClockSysvar,
+    StakeHistorySysvar,
+    StakeConfig,
+    SystemProgram,
+    TokenProgram,
+    Authority,
+    AuthorityBase,
+    Base,
+    NewAuthority,
+    StakeAuthority,
+    WithdrawAuthority,
+    LockupOrWithdrawAuthority,
+    Staker,
+    Withdrawer,
+    LockupCustodian,
+    NewLockupCustodian,
+    RentDestination,
+}
+
+impl Role {
+    /// snake_case name, as doc generators and IDLs print it
+    pub const fn name(self) -> &'static str {
+        match self {
+            Role::Stake => "stake",
+            Role::SplitStake => "split_stake",
+            Role::SourceStake => "source_stake",
+            Role::DestinationStake => "destination_stake",
+            Role::UninitializedStake => "uninitialized_stake",
+            Role::Recipient => "recipient",
+            Role::NativeTokenAccount => "native_token_account",
+            Role::Vote => "vote",
+            Role::DelinquentVote => "delinquent_vote",
+            Role::ReferenceVote => "reference_vote",
+            Role::RentSysvar => "rent_sysvar",
+            Role::ClockSysvar => "clock_sysvar",
+            Role::StakeHistorySysvar => "stake_history_sysvar",
+            Role::StakeConfig => "stake_config",
+            Role::SystemProgram => "system_program",
+            Role::TokenProgram => "token_program",
+            Role::Authority => "authority",
+            Role::AuthorityBase => "authority_base",
+            Role::Base => "base",
+            Role::NewAuthority => "new_authority",
+            Role::StakeAuthority => "stake_authority",
+            Role::WithdrawAuthority => "withdraw_authority",
+            Role::LockupOrWithdrawAuthority => "lockup_or_withdraw_authority",
+            Role::Staker => "staker",
+            Role::Withdrawer => "withdrawer",
+            Role::LockupCustodian => "lockup_custodian",
+            Role::NewLockupCustodian => "new_lockup_custodian",
+            Role::RentDestination => "rent_destination",
+        }
+    }
+}
+
+/// One account in an instruction's layout, as the native builders pass it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountRole {
+    pub role: Role,
+    pub writable: bool,
+    pub signer: bool,
+    /// May be left out (e.g. a custodian when no lockup is in force)
+    pub optional: bool,
+}
+
+impl AccountRole {
+    pub const fn readonly(role: Role) -> Self {
+        Self { role, writable: false, signer: false, optional: false }
+    }
+
+    pub const fn writable(role: Role) -> Self {
+        Self { writable: true, ..Self::readonly(role) }
+    }
+
+    pub const fn signer(role: Role) -> Self {
+        Self { signer: true, ..Self::readonly(role) }
+    }
+
+    pub const fn optional(self) -> Self {
+        Self { optional: true, ..self }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.role.name()
+    }
+}
+
+/// Position of `role` in `layout`, if the instruction takes such an account
+pub const fn position(layout: &[AccountRole], role: Role) -> Option<usize> {
+    let mut i = 0;
+    while i < layout.len() {
+        if layout[i].role as u8 == role as u8 {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+use AccountRole as A;
+use Role::*;
+
+pub const INITIALIZE: &[AccountRole] = &[A::writable(Stake), A::readonly(RentSysvar)];
+
+pub const AUTHORIZE: &[AccountRole] = &[
+    A::writable(Stake),
+    A::readonly(ClockSysvar),
+    A::signer(Authority),
+    A::signer(LockupCustodian).optional(),
+];
+
+pub const DELEGATE_STAKE: &[AccountRole] = &[
+    A::writable(Stake),
+    A::readonly(Vote),
+    A::readonly(ClockSysvar),
+    A::readonly(StakeHistorySysvar),
+    A::readonly(StakeConfig),
+    A::signer(StakeAuthority),
+];
+
+pub const SPLIT: &[AccountRole] = &[A::writable(Stake), A::writable(SplitStake), A::signer(StakeAuthority)];
+
+/// Also the layout of the extensions that withdraw
+pub const WITHDRAW: &[AccountRole] = &[
+    A::writable(Stake),
+    A::writable(Recipient),
+    A::readonly(ClockSysvar),
+    A::readonly(StakeHistorySysvar),
+    A::signer(WithdrawAuthority),
+    A::signer(LockupCustodian).optional(),
+];
+
+pub const DEACTIVATE: &[AccountRole] = &[A::writable(Stake), A::readonly(ClockSysvar), A::signer(StakeAuthority)];
+
+pub const SET_LOCKUP: &[AccountRole] = &[A::writable(Stake), A::signer(LockupOrWithdrawAuthority)];
+
+pub const MERGE: &[AccountRole] = &[
+    A::writable(DestinationStake),
+    A::writable(SourceStake),
+    A::readonly(ClockSysvar),
+    A::readonly(StakeHistorySysvar),
+    A::signer(StakeAuthority),
+    // `extensions`: takes the source's rent-exempt reserve
+    #[cfg(feature = "extensions")]
+    A::writable(RentDestination).optional(),
+];
+
+pub const AUTHORIZE_WITH_SEED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::signer(AuthorityBase),
+    A::readonly(ClockSysvar),
+    A::signer(LockupCustodian).optional(),
+];
+
+pub const INITIALIZE_CHECKED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::readonly(RentSysvar),
+    A::readonly(Staker),
+    A::signer(Withdrawer),
+];
+
+pub const AUTHORIZE_CHECKED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::readonly(ClockSysvar),
+    A::signer(Authority),
+    A::signer(NewAuthority),
+    A::signer(LockupCustodian).optional(),
+];
+
+pub const AUTHORIZE_CHECKED_WITH_SEED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::signer(AuthorityBase),
+    A::readonly(ClockSysvar),
+    A::signer(NewAuthority),
+    A::signer(LockupCustodian).optional(),
+];
+
+pub const SET_LOCKUP_CHECKED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::signer(LockupOrWithdrawAuthority),
+    A::signer(NewLockupCustodian).optional(),
+];
+
+pub const GET_MINIMUM_DELEGATION: &[AccountRole] = &[];
+
+pub const DEACTIVATE_DELINQUENT: &[AccountRole] =
+    &[A::writable(Stake), A::readonly(DelinquentVote), A::readonly(ReferenceVote)];
+
+pub const REDELEGATE: &[AccountRole] = &[
+    A::writable(Stake),
+    A::writable(UninitializedStake),
+    A::readonly(Vote),
+    A::readonly(StakeConfig),
+    A::signer(StakeAuthority),
+];
+
+pub const MOVE_STAKE: &[AccountRole] =
+    &[A::writable(SourceStake), A::writable(DestinationStake), A::signer(StakeAuthority)];
+
+pub const MOVE_LAMPORTS: &[AccountRole] = MOVE_STAKE;
+
+pub const DEACTIVATE_PARTIAL: &[AccountRole] = &[
+    A::writable(Stake),
+    A::writable(SplitStake),
+    A::readonly(ClockSysvar),
+    A::signer(StakeAuthority),
+];
+
+pub const WITHDRAW_EXCESS: &[AccountRole] = WITHDRAW;
+
+pub const SPLIT_WITH_SEED: &[AccountRole] = &[
+    A::writable(Stake),
+    A::writable(SplitStake),
+    A::signer(Base),
+    A::readonly(SystemProgram),
+    A::signer(StakeAuthority),
+];
+
+pub const GET_DELEGATION_PREVIEW: &[AccountRole] = &[A::readonly(Stake), A::readonly(ClockSysvar).optional()];
+
+pub const CLOSE_STAKE_ACCOUNT: &[AccountRole] = WITHDRAW;
+
+/// Followed by up to `MAX_MERGE_SOURCES` writable source stakes
+pub const MERGE_MANY: &[AccountRole] = &[
+    A::writable(DestinationStake),
+    A::readonly(ClockSysvar),
+    A::readonly(StakeHistorySysvar),
+    A::signer(StakeAuthority),
+    A::writable(SourceStake),
+];
+
+pub const QUERY_PARAMS: &[AccountRole] = &[A::readonly(ClockSysvar).optional()];
+
+pub const GET_VERSION: &[AccountRole] = &[];
+
+pub const WITHDRAW_TO_TOKEN_ACCOUNT: &[AccountRole] = &[
+    A::writable(Stake),
+    A::writable(NativeTokenAccount),
+    A::readonly(ClockSysvar),
+    A::readonly(StakeHistorySysvar),
+    A::signer(WithdrawAuthority),
+    A::readonly(TokenProgram),
+    A::signer(LockupCustodian).optional(),
+];
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::Instruction, pubkey::Pubkey, stake::instruction as sdk, stake::state as sdk_state};
+
+    fn assert_matches_sdk(layout: &[AccountRole], ix: &Instruction) {
+        assert_eq!(layout.len(), ix.accounts.len(), "{ix:?}");
+        for (spec, meta) in layout.iter().zip(&ix.accounts) {
+            assert_eq!((spec.writable, spec.signer), (meta.is_writable, meta.is_signer), "{}", spec.name());
+        }
+    }
+
+    #[test]
+    fn layouts_match_the_sdk_builders() {
+        let [a, b, c, d] = [(); 4].map(|_| Pubkey::new_unique());
+        assert_matches_sdk(DELEGATE_STAKE, &sdk::delegate_stake(&a, &b, &c));
+        assert_matches_sdk(WITHDRAW, &sdk::withdraw(&a, &b, &c, 1, Some(&d)));
+        assert_matches_sdk(DEACTIVATE, &sdk::deactivate_stake(&a, &b));
+        assert_matches_sdk(&MERGE[..5], &sdk::merge(&a, &b, &c)[0]);
+        assert_matches_sdk(
+            AUTHORIZE_CHECKED,
+            &sdk::authorize_checked(&a, &b, &c, sdk_state::StakeAuthorize::Staker, Some(&d)),
+        );
+        let lockup = sdk::LockupArgs { custodian: Some(d), ..Default::default() };
+        assert_matches_sdk(SET_LOCKUP_CHECKED, &sdk::set_lockup_checked(&a, &lockup, &b));
+        assert_matches_sdk(MOVE_STAKE, &sdk::move_stake(&a, &b, &c, 1));
+        assert_matches_sdk(DEACTIVATE_DELINQUENT, &sdk::deactivate_delinquent_stake(&a, &b, &c));
+    }
+
+    #[test]
+    fn positions_by_role() {
+        assert_eq!(position(WITHDRAW, LockupCustodian), Some(5));
+        assert_eq!(position(SET_LOCKUP_CHECKED, NewLockupCustodian), Some(2));
+        assert_eq!(position(GET_MINIMUM_DELEGATION, Stake), None);
+        assert_eq!(A::signer(StakeAuthority).name(), "stake_authority");
+    }
+}
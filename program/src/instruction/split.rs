@@ -1,59 +1,62 @@
+use crate::helpers::log::{log_debug, log_error};
 use crate::{
     error::*, helpers::*, state::accounts::StakeAuthorize, state::stake_state_v2::StakeStateV2,
-    state::StakeHistorySysvar,
+    state::{EpochLE, LamportsLE, WindowedStakeHistory},
 };
 use pinocchio::{
     account_info::AccountInfo,
-    msg,
     program_error::ProgramError,
-    pubkey::Pubkey,
-    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
+use crate::helpers::sysvar_cache;
 
 pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramResult {
-    msg!("Split: begin");
-    let mut arr_of_signers = [Pubkey::default(); MAXIMUM_SIGNERS];
-    let _ = collect_signers(accounts, &mut arr_of_signers)?;
+    log_debug!("Split: begin");
+    let signers = &Signers::collect(accounts)?;
 
-    let [source_stake_account_info, destination_stake_account_info, _] = accounts else {
+    // stake, split destination, authority (found by signer scan), extra accounts ignored
+    let [source_stake_account_info, destination_stake_account_info, _, ..] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
-    msg!("Split: destructured accounts");
+    log_debug!("Split: destructured accounts");
+    // Native fails the Uninitialized destination check for an aliased split
+    ensure_distinct(
+        &[source_stake_account_info, destination_stake_account_info],
+        ProgramError::InvalidAccountData,
+    )?;
     // Trace key account flags
-    if source_stake_account_info.is_signer() { msg!("Split: src signer=1"); } else { msg!("Split: src signer=0"); }
-    if source_stake_account_info.is_writable() { msg!("Split: src writable=1"); } else { msg!("Split: src writable=0"); }
-    if destination_stake_account_info.is_signer() { msg!("Split: dst signer=1"); } else { msg!("Split: dst signer=0"); }
-    if destination_stake_account_info.is_writable() { msg!("Split: dst writable=1"); } else { msg!("Split: dst writable=0"); }
-    if *source_stake_account_info.owner() == crate::ID { msg!("Split: src owner ok"); } else { msg!("Split: src owner mismatch"); return Err(ProgramError::InvalidAccountOwner); }
-    if *destination_stake_account_info.owner() == crate::ID { msg!("Split: dst owner ok"); } else { msg!("Split: dst owner mismatch"); return Err(ProgramError::InvalidAccountOwner); }
-
+    #[cfg(feature = "log-debug")]
+    {
+        if source_stake_account_info.is_signer() { log_debug!("Split: src signer=1"); } else { log_debug!("Split: src signer=0"); }
+        if source_stake_account_info.is_writable() { log_debug!("Split: src writable=1"); } else { log_debug!("Split: src writable=0"); }
+        if destination_stake_account_info.is_signer() { log_debug!("Split: dst signer=1"); } else { log_debug!("Split: dst signer=0"); }
+        if destination_stake_account_info.is_writable() { log_debug!("Split: dst writable=1"); } else { log_debug!("Split: dst writable=0"); }
+    }
+    if *source_stake_account_info.owner() == crate::ID { log_debug!("Split: src owner ok"); } else { log_error!("Split: src owner mismatch"); return Err(ProgramError::InvalidAccountOwner); }
 
-    let clock = Clock::get()?;
-    msg!("Split: got Clock");
-    let stake_history = &StakeHistorySysvar(clock.epoch);
+    let clock = sysvar_cache::clock(accounts)?;
+    log_debug!("Split: got Clock");
+    let stake_history = &WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
 
-    let source_data_len = source_stake_account_info.data_len();
     let destination_data_len = destination_stake_account_info.data_len();
-    if source_data_len == 0 { msg!("Split: src len=0"); }
-    if destination_data_len == 0 { msg!("Split: dest len=0"); }
-    let min = StakeStateV2::size_of();
-    if destination_data_len == 0 { msg!("Split: dest len=0"); }
-    else if destination_data_len < min { msg!("Split: dest len<min"); }
-    else { msg!("Split: dest len>=min"); }
-    if destination_data_len < StakeStateV2::size_of() {
-        msg!("Split: dest size too small");
+    if validate_stake_account_shape(destination_stake_account_info, StakeAccountShape::Exact).is_err() {
+        log_error!("Split: dest size mismatch");
         return Err(ProgramError::InvalidAccountData);
     }
+    // after the size check, as in native: a program account is InvalidAccountData
+    if *destination_stake_account_info.owner() == crate::ID { log_debug!("Split: dst owner ok"); } else { log_error!("Split: dst owner mismatch"); return Err(ProgramError::InvalidAccountOwner); }
 
     // Be tolerant of account data alignment for destination Uninitialized check.
     // Only require that the destination deserializes to Uninitialized.
     {
-        let data = unsafe { destination_stake_account_info.borrow_data_unchecked() };
-        match StakeStateV2::deserialize(&data) {
-            Ok(StakeStateV2::Uninitialized) => { msg!("Split: dest Uninitialized OK"); }
-            Ok(_) => { msg!("Split: dest not Uninitialized"); return Err(ProgramError::InvalidAccountData); }
-            Err(_) => { msg!("Split: dest deserialize error"); return Err(ProgramError::InvalidAccountData); }
+        match get_stake_state(destination_stake_account_info) {
+            Ok(StakeStateV2::Uninitialized) => { log_debug!("Split: dest Uninitialized OK"); }
+            Ok(StakeStateV2::Initialized(_) | StakeStateV2::Stake(..) | StakeStateV2::RewardsPool) => {
+                log_error!("Split: dest not Uninitialized");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            Err(_) => { log_error!("Split: dest deserialize error"); return Err(ProgramError::InvalidAccountData); }
         }
     }
 
@@ -66,29 +69,28 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
 
     match get_stake_state(source_stake_account_info)? {
         StakeStateV2::Stake(source_meta, mut source_stake, stake_flags) => {
-            msg!("Split: source=Stake");
+            log_debug!("Split: source=Stake");
             source_meta
                 .authorized
-                .check(&arr_of_signers, StakeAuthorize::Staker)
+                .check(signers, StakeAuthorize::Staker)
                 .map_err(to_program_error)?;
 
             let minimum_delegation = get_minimum_delegation();
 
             let status = source_stake.delegation.stake_activating_and_deactivating(
-                clock.epoch.to_le_bytes(),
+                EpochLE::new(clock.epoch),
                 stake_history,
-                PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
+                new_rate_activation_epoch,
             );
 
-            let is_active = bytes_to_u64(status.effective) > 0;
+            let is_active = status.effective.get() > 0;
 
-            // NOTE this function also internally summons Rent via syscall
             let validated_split_info = validate_split_amount(
                 source_lamport_balance,
                 destination_lamport_balance,
                 split_lamports,
                 &source_meta,
-                destination_data_len,
+                sysvar_cache::rent(accounts)?.minimum_balance(destination_data_len),
                 minimum_delegation,
                 is_active,
             )?;
@@ -111,16 +113,16 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
                     // to prevent magic activation of stake by splitting between accounts of
                     // different sizes.
                     let remaining_stake_delta = split_lamports
-                        .saturating_sub(bytes_to_u64(source_meta.rent_exempt_reserve));
+                        .saturating_sub(source_meta.rent_exempt_reserve.get());
                     (remaining_stake_delta, remaining_stake_delta)
                 } else {
                     // Otherwise, the new split stake should reflect the entire split
                     // requested, less any lamports needed to cover the
                     // split_rent_exempt_reserve.
-                    if bytes_to_u64(source_stake.delegation.stake).saturating_sub(split_lamports)
+                    if source_stake.delegation.stake.get().saturating_sub(split_lamports)
                         < minimum_delegation
                     {
-                        return Err(to_program_error(StakeError::InsufficientDelegation.into()));
+                        return Err(to_program_error(StakeError::InsufficientDelegation));
                     }
 
                     (
@@ -134,7 +136,7 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
                 };
 
             if split_stake_amount < minimum_delegation {
-                return Err(to_program_error(StakeError::InsufficientDelegation.into()));
+                return Err(to_program_error(StakeError::InsufficientDelegation));
             }
 
             let destination_stake = source_stake
@@ -142,9 +144,7 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
                 .map_err(to_program_error)?;
 
             let mut destination_meta = source_meta;
-            destination_meta.rent_exempt_reserve = validated_split_info
-                .destination_rent_exempt_reserve
-                .to_le_bytes();
+            destination_meta.rent_exempt_reserve = LamportsLE::new(validated_split_info.destination_rent_exempt_reserve);
 
             set_stake_state(
                 source_stake_account_info,
@@ -157,27 +157,30 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
             )?;
         }
         StakeStateV2::Initialized(source_meta) => {
-            msg!("Split: source=Initialized");
+            log_debug!("Split: source=Initialized");
             source_meta
                 .authorized
-                .check(&arr_of_signers, StakeAuthorize::Staker)
+                .check(signers, StakeAuthorize::Staker)
                 .map_err(to_program_error)?;
 
-            // NOTE this function also internally summons Rent via syscall
+            let additional_required_lamports = if FEATURE_SPLIT_INITIALIZED_REQUIRES_MINIMUM_DELEGATION {
+                get_minimum_delegation()
+            } else {
+                0
+            };
+
             let validated_split_info = validate_split_amount(
                 source_lamport_balance,
                 destination_lamport_balance,
                 split_lamports,
                 &source_meta,
-                destination_data_len,
-                0,     // additional_required_lamports
+                sysvar_cache::rent(accounts)?.minimum_balance(destination_data_len),
+                additional_required_lamports,
                 false, // is_active
             )?;
 
             let mut destination_meta = source_meta;
-            destination_meta.rent_exempt_reserve = validated_split_info
-                .destination_rent_exempt_reserve
-                .to_le_bytes();
+            destination_meta.rent_exempt_reserve = LamportsLE::new(validated_split_info.destination_rent_exempt_reserve);
 
             set_stake_state(
                 destination_stake_account_info,
@@ -185,26 +188,176 @@ pub fn process_split(accounts: &[AccountInfo], split_lamports: u64) -> ProgramRe
             )?;
         }
         StakeStateV2::Uninitialized => {
-            msg!("Split: source=Uninitialized");
+            log_debug!("Split: source=Uninitialized");
+            // as native, only the source's own signature: no authority, reserve
+            // or minimum, and neither state is written beyond the deinit below
             if !source_stake_account_info.is_signer() {
                 return Err(ProgramError::MissingRequiredSignature);
             }
         }
-        _ => { msg!("Split: source invalid state"); return Err(ProgramError::InvalidAccountData) },
+        StakeStateV2::RewardsPool => { log_error!("Split: source is RewardsPool"); return Err(ProgramError::InvalidAccountData) },
     }
 
-    // Deinitialize state upon zero balance
+    // Deinitialize state upon zero balance (only a fully drained source)
     if split_lamports == source_lamport_balance {
         set_stake_state(source_stake_account_info, &StakeStateV2::Uninitialized)?;
     }
 
-    msg!("Split: relocating lamports");
+    log_debug!("Split: relocating lamports");
     relocate_lamports(
         source_stake_account_info,
         destination_stake_account_info,
         split_lamports,
     )?;
 
-    msg!("Split: done");
+    log_debug!("Split: done");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+    use crate::helpers::test_accounts::{self, info, RawAccount};
+    use crate::state::{
+        migration::{ActiveMigration, StateMigration},
+        Authorized, Delegation, Meta, Stake, StakeFlags,
+    };
+    use pinocchio::sysvars::{clock::CLOCK_ID, rent::RENT_ID};
+
+    fn raw<const N: usize>(key: Pubkey, owner: Pubkey, lamports: u64, data: [u8; N]) -> Box<RawAccount<N>> {
+        Box::new(test_accounts::raw(key, owner, lamports, data))
+    }
+
+    /// Rent::default(): 3480 lamports per byte-year, two years exempt
+    fn minimum_balance(data_len: usize) -> u64 {
+        (128 + data_len as u64) * 3480 * 2
+    }
+
+    /// A bootstrap (fully active) delegation of `stake` in an `N`-byte account
+    /// holding its rent-exempt reserve on top
+    fn active_source<const N: usize>(stake: u64) -> Box<RawAccount<N>> {
+        let reserve = minimum_balance(N);
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(reserve),
+            authorized: Authorized { staker: [7; 32], withdrawer: [7; 32] },
+            ..Meta::default()
+        };
+        let delegation = Delegation {
+            deactivation_epoch: EpochLE::new(u64::MAX),
+            ..Delegation::new(&[9; 32], stake, EpochLE::new(u64::MAX))
+        };
+        let state = StakeStateV2::Stake(meta, Stake { delegation, ..Stake::default() }, StakeFlags::empty());
+        let mut data = [0u8; N];
+        ActiveMigration::store(&state, &mut data).unwrap();
+        #[cfg(feature = "integrity")]
+        crate::helpers::checksum::write_checksum(&mut data);
+        raw([1; 32], crate::ID, reserve + stake, data)
+    }
+
+    /// Split `lamports` of `source` into `destination`, signed by the [7; 32]
+    /// authority
+    fn split_into<const N: usize, const M: usize>(
+        source: &mut RawAccount<N>,
+        destination: &mut RawAccount<M>,
+        lamports: u64,
+    ) -> ProgramResult {
+        let mut authority = raw([7; 32], [0; 32], 0, [0u8; 0]);
+        authority.is_signer = 1;
+        let mut clock = raw(CLOCK_ID, [0; 32], 1, [0u8; 40]);
+        let mut rent_data = [0u8; 17];
+        rent_data[..8].copy_from_slice(&3480u64.to_le_bytes());
+        rent_data[8..16].copy_from_slice(&2.0f64.to_le_bytes());
+        rent_data[16] = 50;
+        let mut rent = raw(RENT_ID, [0; 32], 1, rent_data);
+        let accounts = [info(source), info(destination), info(&mut authority), info(&mut clock), info(&mut rent)];
+        process_split(&accounts, lamports)
+    }
+
+    /// Split all of `source` into an unfunded `M`-byte destination; returns the
+    /// destination's state and lamports, checking the source is left empty
+    fn split_all<const N: usize, const M: usize>(source: &mut RawAccount<N>) -> Result<(StakeStateV2, u64), ProgramError> {
+        let mut destination = raw([2; 32], crate::ID, 0, [0u8; M]);
+        let lamports = source.lamports;
+        split_into(source, &mut destination, lamports)?;
+        assert_eq!(source.lamports, 0);
+        assert_eq!(get_stake_state(&info(source)), Ok(StakeStateV2::Uninitialized));
+        Ok((get_stake_state(&info(&mut destination))?, destination.lamports))
+    }
+
+    fn delegated_stake(state: &StakeStateV2) -> (u64, u64) {
+        let StakeStateV2::Stake(meta, stake, _) = state else {
+            panic!("expected Stake, got {state:?}");
+        };
+        (stake.delegation.stake.get(), meta.rent_exempt_reserve.get())
+    }
+
+    #[test]
+    fn split_destination_must_be_exactly_stake_sized() {
+        // native rejects a destination of any other size, larger or truncated
+        let mut source = active_source::<200>(50_000_000);
+        assert_eq!(split_all::<200, 4096>(&mut source), Err(ProgramError::InvalidAccountData));
+        assert_eq!(split_all::<200, 201>(&mut source), Err(ProgramError::InvalidAccountData));
+        assert_eq!(split_all::<200, 199>(&mut source), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn full_split_moves_the_delegation_into_a_smaller_account() {
+        let stake = 50_000_000;
+        let mut source = active_source::<4096>(stake);
+        let (state, lamports) = split_all::<4096, 200>(&mut source).unwrap();
+        // the reserve freed by the smaller account does not activate as stake
+        assert_eq!(delegated_stake(&state), (stake, minimum_balance(200)));
+        assert_eq!(lamports, minimum_balance(4096) + stake);
+    }
+
+    /// Uninitialized stake data with a stray byte past the tag
+    fn uninitialized<const N: usize>(key: Pubkey, lamports: u64) -> Box<RawAccount<N>> {
+        let mut data = [0u8; N];
+        data[100] = 7;
+        raw(key, crate::ID, lamports, data)
+    }
+
+    #[test]
+    fn uninitialized_split_moves_lamports_and_writes_no_state() {
+        let reserve = minimum_balance(200);
+        let mut source = uninitialized::<200>([1; 32], reserve + 1_000);
+        source.is_signer = 1;
+        let mut destination = uninitialized::<200>([2; 32], reserve);
+        split_into(&mut source, &mut destination, 400).unwrap();
+        assert_eq!((source.lamports, destination.lamports), (reserve + 600, reserve + 400));
+        // as native: neither account is rewritten, so the stray bytes stay
+        assert_eq!((source.data[100], destination.data[100]), (7, 7));
+        assert_eq!(get_stake_state(&info(&mut source)), Ok(StakeStateV2::Uninitialized));
+
+        split_into(&mut source, &mut destination, 0).unwrap();
+        assert_eq!(source.lamports, reserve + 600);
+        assert_eq!(split_into(&mut source, &mut destination, reserve + 601), Err(ProgramError::InsufficientFunds));
+
+        // the drained source is deinitialized, which leaves Uninitialized data alone
+        split_into(&mut source, &mut destination, reserve + 600).unwrap();
+        assert_eq!((source.lamports, destination.lamports), (0, 2 * reserve + 1_000));
+        assert_eq!(get_stake_state(&info(&mut source)), Ok(StakeStateV2::Uninitialized));
+        assert_eq!(destination.data[100], 7);
+    }
+
+    #[test]
+    fn uninitialized_split_needs_the_source_signature() {
+        let reserve = minimum_balance(200);
+        // the [7; 32] authority signs, but as another account than the source
+        let mut source = uninitialized::<200>([1; 32], reserve + 1_000);
+        let mut destination = uninitialized::<200>([2; 32], reserve);
+        assert_eq!(split_into(&mut source, &mut destination, 400), Err(ProgramError::MissingRequiredSignature));
+
+        source.is_signer = 1;
+        split_into(&mut source, &mut destination, 400).unwrap();
+
+        // the destination constraints still apply first
+        let mut initialized = active_source::<200>(1_000);
+        initialized.key = [2; 32];
+        assert_eq!(split_into(&mut source, &mut initialized, 400), Err(ProgramError::InvalidAccountData));
+        let mut wrong_size = uninitialized::<201>([2; 32], reserve);
+        assert_eq!(split_into(&mut source, &mut wrong_size, 400), Err(ProgramError::InvalidAccountData));
+        assert_eq!(source.lamports, reserve + 600);
+    }
+}
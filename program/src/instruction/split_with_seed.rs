@@ -0,0 +1,49 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+use pinocchio_system::instructions::AllocateWithSeed;
+
+use crate::helpers::log::log_debug;
+use crate::{
+    helpers::{create_with_seed, ensure_distinct},
+    instruction::split::process_split,
+    state::{accounts::SplitWithSeedData, stake_state_v2::StakeStateV2},
+};
+
+/// SplitWithSeed (extension): Split into a destination at
+/// `create_with_seed(base, seed, stake program)`, allocating it through the
+/// system program first, so no separate create step is needed.
+///
+/// Accounts: [`SPLIT_WITH_SEED`](super::roles::SPLIT_WITH_SEED).
+///
+/// The destination may be a fresh (even unfunded) system account, or one
+/// already allocated to this program; Split's own checks then require it to
+/// be Uninitialized and to end up rent exempt.
+pub fn process_split_with_seed(accounts: &[AccountInfo], args: SplitWithSeedData) -> ProgramResult {
+    let [stake_ai, split_ai, base_ai, system_ai, authority_ai, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if *system_ai.key() != pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    ensure_distinct(&[stake_ai, split_ai], ProgramError::InvalidAccountData)?;
+
+    if !base_ai.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *split_ai.key() != create_with_seed(base_ai.key(), args.seed.as_bytes(), &crate::ID)? {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if *split_ai.owner() == pinocchio_system::ID {
+        log_debug!("SplitWithSeed: allocate destination");
+        AllocateWithSeed {
+            account: split_ai,
+            base: base_ai,
+            seed: args.seed,
+            space: StakeStateV2::size_of() as u64,
+            owner: &crate::ID,
+        }
+        .invoke()?;
+    }
+
+    process_split(&[*stake_ai, *split_ai, *authority_ai], args.lamports)
+}
@@ -1,118 +1,181 @@
 use pinocchio::{
     account_info::AccountInfo,
-    msg,
     program_error::ProgramError,
-    sysvars::clock::Clock,
     ProgramResult,
 };
 
 use crate::{
     error::{to_program_error, StakeError},
-    helpers::{checked_add, get_stake_state, next_account_info, relocate_lamports, set_stake_state},
-    state::{Lockup, StakeAuthorize, StakeHistorySysvar, StakeStateV2},
+    helpers::{
+        checked_add, Signers, get_stake_state, next_account_info,
+        presented_custodian, require_signed_custodian_slot, relocate_lamports, set_stake_state, warmup_rate,
+    },
+    state::{
+        Delegation, EpochLE, Lockup, StakeAuthorize, StakeHistoryGetEntry, WindowedStakeHistory,
+        StakeStateV2,
+    },
 
 };
-use pinocchio::pubkey::Pubkey;
+use pinocchio::pubkey::pubkey_eq;
+use crate::helpers::log::log_debug;
+use crate::helpers::sysvar_cache;
 
-//
+/// How much a withdrawal takes out of the source account
+#[derive(Clone, Copy)]
+enum WithdrawAmount {
+    Exact(u64),
+    /// Everything above the rent reserve and still-locked stake (WithdrawExcess,
+    /// or Withdraw of `WITHDRAW_MAX` under `withdraw-max`)
+    #[cfg(any(feature = "extensions", feature = "withdraw-max"))]
+    Excess,
+    /// The whole balance (CloseStakeAccount)
+    #[cfg(feature = "extensions")]
+    All,
+}
+
+impl WithdrawAmount {
+    /// Withdraw's lamports argument; `WITHDRAW_MAX` is only a sentinel under `withdraw-max`
+    fn requested(lamports: u64) -> Self {
+        #[cfg(feature = "withdraw-max")]
+        if lamports == WITHDRAW_MAX {
+            return WithdrawAmount::Excess;
+        }
+        WithdrawAmount::Exact(lamports)
+    }
+
+    #[cfg_attr(not(any(feature = "extensions", feature = "withdraw-max")), allow(unused_variables))]
+    fn resolve(self, account_lamports: u64, reserve: u64) -> u64 {
+        match self {
+            WithdrawAmount::Exact(lamports) => lamports,
+            #[cfg(any(feature = "extensions", feature = "withdraw-max"))]
+            WithdrawAmount::Excess => account_lamports.saturating_sub(reserve),
+            #[cfg(feature = "extensions")]
+            WithdrawAmount::All => account_lamports,
+        }
+    }
+}
+
+/// `withdraw-max`: Withdraw of this many lamports takes the maximum
+/// withdrawable amount (balance minus rent reserve minus locked stake) as of
+/// execution, so rewards credited after simulation do not fail the withdrawal.
+/// Native has no sentinel and rejects the amount with `InsufficientFunds`.
+#[cfg(feature = "withdraw-max")]
+pub const WITHDRAW_MAX: u64 = u64::MAX;
+
+/// Withdraw's optional lockup custodian, after the withdraw authority
+const CUSTODIAN_SLOT: usize = 5;
 
 pub fn process_withdraw(accounts: &[AccountInfo], withdraw_lamports: u64) -> ProgramResult {
-    msg!("Withdraw: enter");
+    withdraw(accounts, WithdrawAmount::requested(withdraw_lamports), CUSTODIAN_SLOT)
+}
+
+/// WithdrawExcess (extension): Withdraw with the amount computed on-chain as
+/// everything above the rent reserve plus locked stake, so callers do not
+/// race rewards crediting the account. Same accounts as Withdraw.
+#[cfg(feature = "extensions")]
+pub fn process_withdraw_excess(accounts: &[AccountInfo]) -> ProgramResult {
+    withdraw(accounts, WithdrawAmount::Excess, CUSTODIAN_SLOT)
+}
+
+/// CloseStakeAccount (extension): a full Withdraw followed by zeroing the
+/// account data, so the account is left empty for the runtime to reclaim.
+/// Same accounts and authority rules as Withdraw: the stake account signs
+/// when Uninitialized, otherwise the withdrawer (plus the custodian while
+/// locked up), and any delegation must have fully cooled down.
+#[cfg(feature = "extensions")]
+pub fn process_close_stake_account(accounts: &[AccountInfo]) -> ProgramResult {
+    let [stake_account_info, destination_info, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    // closing into itself would keep the lamports on a wiped account
+    crate::helpers::ensure_distinct(&[stake_account_info, destination_info], ProgramError::InvalidArgument)?;
+
+    withdraw(accounts, WithdrawAmount::All, CUSTODIAN_SLOT)?;
+    crate::helpers::wipe_stake_account(stake_account_info)
+}
+
+/// WithdrawToTokenAccount (`withdraw-to-token` extension): Withdraw into a
+/// native-mint token account, then SyncNative through the token program so
+/// the lamports arrive as wSOL. Accounts:
+/// [`WITHDRAW_TO_TOKEN_ACCOUNT`](super::roles::WITHDRAW_TO_TOKEN_ACCOUNT), the token
+/// program located by key.
+#[cfg(feature = "withdraw-to-token")]
+pub fn process_withdraw_to_token_account(accounts: &[AccountInfo], withdraw_lamports: u64) -> ProgramResult {
+    use crate::helpers::token;
+
+    let [_, token_account_info, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    token::find_token_program(accounts)?;
+    token::check_native_token_account(token_account_info)?;
+
+    // the token program sits before the custodian
+    withdraw(accounts, WithdrawAmount::requested(withdraw_lamports), CUSTODIAN_SLOT + 1)?;
+    token::sync_native(token_account_info)
+}
+
+fn withdraw(accounts: &[AccountInfo], amount: WithdrawAmount, custodian_slot: usize) -> ProgramResult {
+    log_debug!("Withdraw: enter");
     let account_info_iter = &mut accounts.iter();
 
-    // Expected accounts: 5 (including 2 sysvars)
+    // Accounts: roles::WITHDRAW. Stake, destination and the custodian are
+    // positional; the clock is located by pubkey and the withdrawer by signer scan
+    if accounts.len() < 5 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
     let source_stake_account_info = next_account_info(account_info_iter)?;
     let destination_info = next_account_info(account_info_iter)?;
-    let clock_info = next_account_info(account_info_iter)?;
-    let _stake_history_info = next_account_info(account_info_iter)?;
-    let withdraw_authority_info = next_account_info(account_info_iter)?;
-    // other accounts (optional)
-    let option_lockup_authority_info = next_account_info(account_info_iter).ok();
+    // as in native, a custodian that is passed must sign, whatever the state
+    require_signed_custodian_slot(accounts, custodian_slot)?;
 
     // Fast path: Uninitialized source with source signer — no sysvars needed
-    match get_stake_state(source_stake_account_info) {
-        Ok(StakeStateV2::Uninitialized) => {
-            msg!("Withdraw: source=Uninitialized fast path");
-            if !source_stake_account_info.is_signer() {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            relocate_lamports(
-                source_stake_account_info,
-                destination_info,
-                withdraw_lamports,
-            )?;
-            return Ok(());
+    if let Ok(StakeStateV2::Uninitialized) = get_stake_state(source_stake_account_info) {
+        log_debug!("Withdraw: source=Uninitialized fast path");
+        if !source_stake_account_info.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
         }
-        _ => {}
+        let withdraw_lamports = amount.resolve(source_stake_account_info.lamports(), 0);
+        check_withdraw_amount(withdraw_lamports, source_stake_account_info.lamports(), 0, false)?;
+        return move_withdrawn_lamports(
+            accounts,
+            source_stake_account_info,
+            destination_info,
+            withdraw_lamports,
+        );
     }
 
-    msg!("Withdraw: load clock");
-    let clock = &Clock::from_account_info(clock_info)?;
-    let stake_history = &StakeHistorySysvar(clock.epoch);
+    log_debug!("Withdraw: load clock");
+    let clock = &sysvar_cache::clock_account(accounts)?;
+    let stake_history = &WindowedStakeHistory::new(clock.epoch);
+    let new_rate_activation_epoch = warmup_rate::new_warmup_cooldown_rate_epoch();
 
-    // Require withdraw authority signer; if custodian account is supplied it must also be a signer
-    msg!("Withdraw: gather signers");
-    let mut signer_keys: [Pubkey; 2] = [Pubkey::default(); 2];
-    let mut n = 0usize;
-    if withdraw_authority_info.is_signer() {
-        signer_keys[n] = *withdraw_authority_info.key();
-        n += 1;
-    } else {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-    let custodian: Option<&Pubkey> = match option_lockup_authority_info {
-        Some(ai) => {
-            if ai.is_signer() {
-                signer_keys[n] = *ai.key();
-                n += 1;
-                Some(ai.key())
-            } else {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-        }
-        None => None,
-    };
-    let signers_slice: &[Pubkey] = &signer_keys[..n];
+    log_debug!("Withdraw: gather signers");
+    let signers = &Signers::collect(accounts)?;
 
     // Decide withdrawal constraints based on current stake state
-    msg!("Withdraw: read state");
+    log_debug!("Withdraw: read state");
     let (lockup, reserve_u64, is_staked) = match get_stake_state(source_stake_account_info)? {
         StakeStateV2::Stake(meta, stake, _stake_flags) => {
-            msg!("Withdraw: state=Stake");
+            log_debug!("Withdraw: state=Stake");
             // Must have withdraw authority
             meta.authorized
-                .check(signers_slice, StakeAuthorize::Withdrawer)
+                .check(signers, StakeAuthorize::Withdrawer)
                 .map_err(to_program_error)?;
 
-            // Convert little-endian fields to u64
-            let deact_epoch = u64::from_le_bytes(stake.delegation.deactivation_epoch);
-            // During the deactivation epoch, stake is still fully effective for withdrawal rules
-            let staked: u64 = if deact_epoch != u64::MAX && clock.epoch == deact_epoch {
-                u64::from_le_bytes(stake.delegation.stake)
-            } else if deact_epoch != u64::MAX && clock.epoch > deact_epoch {
-                // After deactivation epoch, consult history to compute remaining effective
-                stake.delegation.stake(
-                    clock.epoch.to_le_bytes(),
-                    stake_history,
-                    crate::helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
-                )
-            } else {
-                // Not deactivating
-                u64::from_le_bytes(stake.delegation.stake)
-            };
-
-            let rent_reserve = u64::from_le_bytes(meta.rent_exempt_reserve);
+            let staked = withdraw_locked_stake(&stake.delegation, clock.epoch, stake_history, new_rate_activation_epoch);
+
+            let rent_reserve = meta.rent_exempt_reserve.get();
             let staked_plus_reserve = checked_add(staked, rent_reserve)?;
             (meta.lockup, staked_plus_reserve, staked != 0)
         }
         StakeStateV2::Initialized(meta) => {
-            msg!("Withdraw: state=Initialized");
+            log_debug!("Withdraw: state=Initialized");
             // Must have withdraw authority
             meta.authorized
-                .check(signers_slice, StakeAuthorize::Withdrawer)
+                .check(signers, StakeAuthorize::Withdrawer)
                 .map_err(to_program_error)?;
 
-            let rent_reserve = u64::from_le_bytes(meta.rent_exempt_reserve);
+            let rent_reserve = meta.rent_exempt_reserve.get();
             (meta.lockup, rent_reserve, false)
         }
         StakeStateV2::Uninitialized => {
@@ -122,42 +185,263 @@ pub fn process_withdraw(accounts: &[AccountInfo], withdraw_lamports: u64) -> Pro
             }
             (Lockup::default(), 0u64, false)
         }
-        _ => return Err(ProgramError::InvalidAccountData),
+        StakeStateV2::RewardsPool => return Err(ProgramError::InvalidAccountData),
     };
 
-    // Lockup must be expired or bypassed by a custodian signer
-    msg!("Withdraw: check lockup");
+    // Lockup must be expired or bypassed by the custodian signing in its slot
+    log_debug!("Withdraw: check lockup");
+    let custodian = presented_custodian(accounts, custodian_slot);
     if lockup.is_in_force(clock, custodian) {
         return Err(to_program_error(StakeError::LockupInForce));
     }
 
     let stake_account_lamports = source_stake_account_info.lamports();
+    let withdraw_lamports = amount.resolve(stake_account_lamports, reserve_u64);
+    check_withdraw_amount(withdraw_lamports, stake_account_lamports, reserve_u64, is_staked)?;
 
     if withdraw_lamports == stake_account_lamports {
-        msg!("Withdraw: full");
-        // Full withdrawal: can't close if still staked
-        if is_staked {
-            return Err(ProgramError::InsufficientFunds);
-        }
+        log_debug!("Withdraw: full");
         // Deinitialize state upon zero balance
         set_stake_state(source_stake_account_info, &StakeStateV2::Uninitialized)?;
-    } else {
-        msg!("Withdraw: partial");
-        // Partial withdrawal must not deplete the reserve
-        let withdraw_plus_reserve = checked_add(withdraw_lamports, reserve_u64)?;
-        if withdraw_plus_reserve > stake_account_lamports {
-            return Err(ProgramError::InsufficientFunds);
-        }
     }
 
     // Move lamports after state update
-    msg!("Withdraw: relocate lamports");
-    relocate_lamports(
+    log_debug!("Withdraw: relocate lamports");
+    move_withdrawn_lamports(
+        accounts,
         source_stake_account_info,
         destination_info,
         withdraw_lamports,
     )?;
 
-    msg!("Withdraw: ok");
+    log_debug!("Withdraw: ok");
+    Ok(())
+}
+
+/// Stake that still blocks withdrawal at `epoch`, as in native `withdraw`.
+/// Once deactivation has started, only the part that stake history says is
+/// still effective stays locked. Before that, the full delegation is used,
+/// because warmup could expose more of it later.
+pub(crate) fn withdraw_locked_stake<T: StakeHistoryGetEntry>(
+    delegation: &Delegation,
+    epoch: u64,
+    stake_history: &T,
+    new_rate_activation_epoch: Option<EpochLE>,
+) -> u64 {
+    if epoch >= delegation.deactivation_epoch.get() {
+        delegation.stake(
+            EpochLE::new(epoch),
+            stake_history,
+            new_rate_activation_epoch,
+        )
+    } else {
+        delegation.stake.get()
+    }
+}
+
+/// Native debits and credits the same account when destination == source, so
+/// after all checks have passed the withdrawal nets to zero (a full one still
+/// deinitializes the state). Skip the move rather than borrowing the account twice.
+fn move_withdrawn_lamports(
+    accounts: &[AccountInfo],
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    lamports: u64,
+) -> ProgramResult {
+    if pubkey_eq(source.key(), destination.key()) {
+        log_debug!("Withdraw: destination is source");
+        return Ok(());
+    }
+    relocate_lamports(source, destination, lamports)?;
+    #[cfg(feature = "recipient-rent")]
+    {
+        let minimum = sysvar_cache::rent(accounts)?.minimum_balance(destination.data_len());
+        check_recipient_rent(destination.lamports(), minimum)?;
+    }
+    #[cfg(not(feature = "recipient-rent"))]
+    let _ = accounts;
+    Ok(())
+}
+
+/// `recipient-rent`: the destination must end up rent-exempt for its own data
+/// length. An account left with zero lamports does not exist, so is accepted.
+#[cfg(any(test, feature = "recipient-rent"))]
+fn check_recipient_rent(destination_lamports: u64, minimum_balance: u64) -> ProgramResult {
+    if destination_lamports != 0 && destination_lamports < minimum_balance {
+        crate::helpers::log::log_error!("Withdraw: destination below its rent-exempt minimum");
+        return Err(ProgramError::InsufficientFunds);
+    }
+    Ok(())
+}
+
+/// A full withdrawal closes the account and needs zero locked stake; a partial
+/// one must leave `reserve` (locked stake + rent reserve) in place. As native,
+/// the sum is taken first, so an overflowing one fails either way.
+fn check_withdraw_amount(
+    withdraw_lamports: u64,
+    account_lamports: u64,
+    reserve: u64,
+    is_staked: bool,
+) -> ProgramResult {
+    let withdraw_lamports_and_reserve = checked_add(withdraw_lamports, reserve)?;
+    if withdraw_lamports == account_lamports {
+        if is_staked {
+            return Err(ProgramError::InsufficientFunds);
+        }
+    } else if withdraw_lamports_and_reserve > account_lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH, state::{LamportsLE, StakeHistoryEntry}};
+
+    struct History(&'static [(u64, u64, u64, u64)]);
+
+    impl StakeHistoryGetEntry for History {
+        fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+            self.0.iter().find(|e| e.0 == epoch).map(|&(_, effective, activating, deactivating)| {
+                StakeHistoryEntry {
+                    effective: LamportsLE::new(effective),
+                    activating: LamportsLE::new(activating),
+                    deactivating: LamportsLE::new(deactivating),
+                }
+            })
+        }
+    }
+
+    const STAKE: u64 = 1_000;
+    const RESERVE: u64 = 100;
+
+    fn deactivating_delegation(deactivation_epoch: u64) -> Delegation {
+        let mut delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(u64::MAX));
+        delegation.deactivation_epoch = EpochLE::new(deactivation_epoch);
+        delegation
+    }
+
+    #[test]
+    fn withdraw_blocked_until_cooldown_completes() {
+        // cluster cools 9% per epoch: this delegation is still partly effective at 11,
+        // and the large cluster at 11 lets the remainder cool by 12
+        let history = History(&[(10, 1_000, 0, 1_000), (11, 100_000, 0, 910)]);
+        let delegation = deactivating_delegation(10);
+        let lamports = STAKE + RESERVE;
+
+        for (epoch, expected_locked) in [(9, STAKE), (10, STAKE), (11, 910), (12, 0)] {
+            let locked = withdraw_locked_stake(&delegation, epoch, &history, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH);
+            assert_eq!(locked, expected_locked, "epoch {epoch}");
+
+            let full = check_withdraw_amount(lamports, lamports, locked + RESERVE, locked != 0);
+            assert_eq!(full.is_ok(), locked == 0, "full withdraw at epoch {epoch}");
+        }
+
+        // a partial withdrawal of the already-cooled part succeeds mid-cooldown
+        let locked = withdraw_locked_stake(&delegation, 11, &history, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH);
+        assert!(check_withdraw_amount(STAKE - locked, lamports, locked + RESERVE, true).is_ok());
+        assert!(check_withdraw_amount(STAKE - locked + 1, lamports, locked + RESERVE, true).is_err());
+    }
+
+    #[test]
+    fn withdraw_uses_effective_stake_on_deactivation_epoch() {
+        // activated at 5 and deactivated before warmup finished: only the
+        // effective part is locked on the deactivation epoch itself
+        let history = History(&[(5, 10_000, 1_000, 0)]);
+        let mut delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(5));
+        delegation.deactivation_epoch = EpochLE::new(6);
+
+        assert_eq!(withdraw_locked_stake(&delegation, 5, &history, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH), STAKE);
+        assert_eq!(withdraw_locked_stake(&delegation, 6, &history, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH), 900);
+    }
+
+    #[test]
+    fn locked_stake_matches_native_around_deactivation() {
+        use solana_sdk::stake::state::Delegation as NativeDelegation;
+        use solana_sdk::stake_history::{StakeHistory as NativeHistory, StakeHistoryEntry as NativeEntry};
+
+        const ENTRIES: &[(u64, u64, u64, u64)] = &[
+            (3, 10_000, 2_000, 0),
+            (4, 10_000, 0, 1_000),
+            (5, 10_000, 500, 1_000),
+            (6, 10_000, 0, 500),
+            (7, 10_000, 0, 0),
+        ];
+        let history = History(ENTRIES);
+        let mut native_history = NativeHistory::default();
+        for &(epoch, effective, activating, deactivating) in ENTRIES.iter().rev() {
+            native_history.add(epoch, NativeEntry { effective, activating, deactivating });
+        }
+
+        for activation_epoch in [3, 4, u64::MAX] {
+            for deactivation_epoch in (activation_epoch.min(4)..=7).chain([u64::MAX]) {
+                let mut delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(activation_epoch));
+                delegation.deactivation_epoch = EpochLE::new(deactivation_epoch);
+                let native = NativeDelegation { stake: STAKE, activation_epoch, deactivation_epoch, ..NativeDelegation::default() };
+                for epoch in 2..10 {
+                    // native withdraw: `clock.epoch >= deactivation_epoch` switches to effective stake
+                    let expected = if epoch >= deactivation_epoch {
+                        native.stake(epoch, &native_history, Some(0))
+                    } else {
+                        STAKE
+                    };
+                    let locked = withdraw_locked_stake(&delegation, epoch, &history, PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH);
+                    assert_eq!(locked, expected, "activation {activation_epoch}, deactivation {deactivation_epoch}, epoch {epoch}");
+                }
+            }
+        }
+    }
+
+    #[cfg(any(feature = "extensions", feature = "withdraw-max"))]
+    #[test]
+    fn excess_leaves_exactly_the_reserve() {
+        let lamports = STAKE + RESERVE + 42;
+        let excess = WithdrawAmount::Excess.resolve(lamports, STAKE + RESERVE);
+        assert_eq!(excess, 42);
+        assert!(check_withdraw_amount(excess, lamports, STAKE + RESERVE, true).is_ok());
+
+        // nothing spare, or an account already below its reserve
+        assert_eq!(WithdrawAmount::Excess.resolve(RESERVE, RESERVE), 0);
+        assert_eq!(WithdrawAmount::Excess.resolve(RESERVE - 1, RESERVE), 0);
+        // no reserve at all (Uninitialized) takes the whole balance
+        assert_eq!(WithdrawAmount::Excess.resolve(lamports, 0), lamports);
+    }
+
+    #[test]
+    fn max_sentinel_is_feature_gated() {
+        let lamports = STAKE + RESERVE + 42;
+        let max = WithdrawAmount::requested(u64::MAX).resolve(lamports, STAKE + RESERVE);
+        if cfg!(feature = "withdraw-max") {
+            assert_eq!(max, 42);
+        } else {
+            // as in native: an exact amount no account can cover
+            assert_eq!(max, u64::MAX);
+            assert_eq!(check_withdraw_amount(max, lamports, STAKE + RESERVE, true), Err(ProgramError::InsufficientFunds));
+        }
+        assert_eq!(WithdrawAmount::requested(u64::MAX - 1).resolve(lamports, 0), u64::MAX - 1);
+    }
+
+    #[test]
+    fn amounts_near_u64_max_fail_without_overflow() {
+        // lamports + reserve overflows: InsufficientFunds, as native's checked_add
+        assert_eq!(check_withdraw_amount(1, u64::MAX, u64::MAX, false), Err(ProgramError::InsufficientFunds));
+        assert_eq!(check_withdraw_amount(u64::MAX, u64::MAX - 1, 1, false), Err(ProgramError::InsufficientFunds));
+        assert!(check_withdraw_amount(u64::MAX - 1, u64::MAX, 1, true).is_ok());
+        // the sum is checked before the full-withdrawal branch
+        assert_eq!(check_withdraw_amount(u64::MAX, u64::MAX, u64::MAX, false), Err(ProgramError::InsufficientFunds));
+        assert_eq!(check_withdraw_amount(u64::MAX, u64::MAX, 1, false), Err(ProgramError::InsufficientFunds));
+        assert!(check_withdraw_amount(u64::MAX, u64::MAX, 0, false).is_ok());
+        assert_eq!(WithdrawAmount::Exact(u64::MAX).resolve(0, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn recipient_must_end_up_rent_exempt() {
+        let minimum = 890_880;
+        assert!(check_recipient_rent(minimum, minimum).is_ok());
+        assert!(check_recipient_rent(minimum + 1, minimum).is_ok());
+        assert_eq!(check_recipient_rent(minimum - 1, minimum), Err(ProgramError::InsufficientFunds));
+        // an empty account (zero-lamport withdrawal to a fresh address) is not created
+        assert!(check_recipient_rent(0, minimum).is_ok());
+    }
+}
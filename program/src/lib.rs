@@ -1,16 +1,49 @@
+//! Pinocchio port of the native stake program.
+//!
+//! Feature matrix (see Cargo.toml for the full list):
+//!
+//! | build | features | compiles |
+//! |---|---|---|
+//! | on-chain | `sbf` | no_std, `entrypoint`, `instruction` |
+//! | on-chain, smallest | `sbf,minimal` | the same, refusing every non-essential feature below |
+//! | host (default) | `std,no-entrypoint,program` | `instruction`, `wire`, `builders`, `client`; no entrypoint |
+//! | state only (wasm) | `std` | `state`, `error`, shared `helpers` math, `client` |
+//!
+//! On top of those: `extensions` (and `withdraw-to-token`) add program-local
+//! instructions; `log-error`/`log-debug`/`trace-state` add logging and
+//! `metrics` a counters line per instruction;
+//! `wire_bincode` decodes SDK instruction data first; `lenient-vote`,
+//! `loose-merge` and `state-v3` are test-fixture fallbacks, never for chain.
+
 // Only go no_std when building for SBF.
 #![cfg_attr(feature = "sbf", no_std)]
 
+// `minimal` strips everything a deployment does not need; say so rather than
+// silently building a bigger program
+#[cfg(all(feature = "minimal", any(feature = "wire_bincode", feature = "log-error", feature = "trace-state", feature = "metrics")))]
+compile_error!("`minimal` builds carry no wire decoding and no logging: drop `wire_bincode`, `log-error`/`log-debug`, `trace-state` and `metrics`");
+#[cfg(all(feature = "minimal", any(feature = "lenient-vote", feature = "loose-merge", feature = "state-v3")))]
+compile_error!("`minimal` builds carry no test-fixture fallbacks: drop `lenient-vote`, `loose-merge` and `state-v3`");
+
 #[cfg(feature = "std")]
 extern crate std;
 
-#[cfg(not(feature = "no-entrypoint"))]
+#[cfg(all(feature = "program", not(feature = "no-entrypoint")))]
 pub mod entrypoint;
 
+#[cfg(all(feature = "std", feature = "program"))]
+pub mod builders;
+#[cfg(feature = "std")]
+pub mod client;
 pub mod error;
 pub mod helpers;
+#[cfg(feature = "program")]
 pub mod instruction;
 pub mod state;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(all(feature = "std", feature = "program"))]
+pub mod wire;
 
 pinocchio_pubkey::declare_id!("Stake11111111111111111111111111111111111111");
 
@@ -1,7 +1,10 @@
 use crate::{error::StakeError, state::Lockup};
 
-use core::mem::size_of;
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{pubkey_eq, Pubkey},
+};
 
 
 // Constants for fixed-size arrays
@@ -9,11 +12,15 @@ pub const MAX_AUTHORITY_SEED_LEN: usize = 32;
 
 #[repr(C)]
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Authorized {
     /// Authority to manage the stake account (delegate, deactivate, split, merge)
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::pubkey"))]
     pub staker: Pubkey,
 
     /// Authority to withdraw funds from the stake account
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::pubkey"))]
     pub withdrawer: Pubkey,
 }
 
@@ -27,11 +34,11 @@ impl Authorized {
     }
 
     pub fn is_staker(&self, pubkey: &Pubkey) -> bool {
-        self.staker == *pubkey
+        pubkey_eq(&self.staker, pubkey)
     }
 
     pub fn is_withdrawer(&self, pubkey: &Pubkey) -> bool {
-        self.withdrawer == *pubkey
+        pubkey_eq(&self.withdrawer, pubkey)
     }
 
     pub fn get_account_info(accounts: &AccountInfo) -> Result<&Self, ProgramError> {
@@ -42,6 +49,7 @@ impl Authorized {
         Ok(unsafe { &*(accounts.borrow_data_unchecked().as_ptr() as *const Self) })
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_account_info_mut(accounts: &AccountInfo) -> Result<&mut Self, ProgramError> {
         if accounts.data_len() < Self::size() {
             return Err(ProgramError::InvalidAccountData);
@@ -53,7 +61,7 @@ impl Authorized {
     // verify required signature is present
     pub fn check(
         &self,
-        signers: &[Pubkey],
+        signers: &(impl crate::helpers::SignerSet + ?Sized),
         stake_authorize: StakeAuthorize,
     ) -> Result<(), StakeError> {
         let required = match stake_authorize {
@@ -96,7 +104,7 @@ impl Authorized {
 
 //     /// Check if the lockup is active for the given timestamp and epoch
 //     pub fn is_active(&self, current_timestamp: i64, current_epoch: u64) -> bool {
-//         current_timestamp < self.unix_timestamp || current_epoch < bytes_to_u64(self.epoch)
+//         current_timestamp < self.unix_timestamp || current_epoch < self.epoch.get()
 //     }
 
 //     pub fn get_account_info(account: &AccountInfo) -> Result<&Self, ProgramError> {
@@ -128,46 +136,6 @@ impl Authorized {
 //     }
 // }
 
-#[derive(Debug, Clone, PartialEq)]
-#[repr(C)]
-pub struct Stake {
-    /// Delegation information
-    pub delegation: Delegation,
-    /// Credits observed during the epoch
-    pub credits_observed: u64,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-#[repr(C)]
-pub struct Delegation {
-    /// To whom the stake is delegated
-    pub voter_pubkey: Pubkey,
-    /// Amount of stake delegated, in lamports
-    pub stake: u64,
-    /// Epoch at which this delegation was activated
-    pub activation_epoch: u64,
-    /// Epoch at which this delegation was deactivated, or u64::MAX if never deactivated
-    pub deactivation_epoch: u64,
-    /// How much stake we can activate per-epoch as a fraction of currently effective stake
-    pub warmup_cooldown_rate: f64,
-}
-
-impl Delegation {
-    pub fn size() -> usize {
-        size_of::<Delegation>()
-    }
-
-    /// Check if the delegation is active
-    pub fn is_active(&self) -> bool {
-        self.deactivation_epoch == u64::MAX
-    }
-
-    /// Check if the delegation is fully activated
-    pub fn is_fully_activated(&self, current_epoch: u64) -> bool {
-        current_epoch >= self.activation_epoch
-    }
-}
-
 /// Configuration parameters for the stake program
 #[derive(Debug, Clone, PartialEq)]
 #[repr(C)]
@@ -251,7 +219,7 @@ impl AuthorizeData {
 }
 
 /// Types of stake authorization
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
 pub enum StakeAuthorize {
     Staker = 0,
@@ -295,11 +263,12 @@ impl<'a> AuthorizeWithSeedData<'a> {
 
         let seed_len = data[33] as usize;
 
-        if seed_len > 32 {
-            return Err(ProgramError::InvalidInstructionData);
+        if seed_len > pinocchio::pubkey::MAX_SEED_LEN {
+            return Err(ProgramError::MaxSeedLengthExceeded);
         }
 
-        if data.len() < 34 + seed_len + 32 {
+        // Exact length: trailing bytes after the owner are rejected
+        if data.len() != 34 + seed_len + 32 {
             return Err(ProgramError::InvalidInstructionData);
         }
 
@@ -316,6 +285,39 @@ impl<'a> AuthorizeWithSeedData<'a> {
     }
 }
 
+/// SplitWithSeed (extension) instruction data
+#[cfg(feature = "extensions")]
+pub struct SplitWithSeedData<'a> {
+    pub lamports: u64,
+    /// Seed of the destination, `create_with_seed(base, seed, stake program)`
+    pub seed: &'a str,
+}
+
+#[cfg(feature = "extensions")]
+impl<'a> SplitWithSeedData<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, ProgramError> {
+        // Expected format:
+        // [0..8] - lamports (u64 LE)
+        // [8] - seed length
+        // [9..9+seed_len] - seed (UTF-8, as the system program takes it)
+        if data.len() < 9 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let lamports = u64::from_le_bytes(data[0..8].try_into().unwrap());
+
+        let seed_len = data[8] as usize;
+        if seed_len > pinocchio::pubkey::MAX_SEED_LEN {
+            return Err(ProgramError::MaxSeedLengthExceeded);
+        }
+        if data.len() != 9 + seed_len {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let seed = core::str::from_utf8(&data[9..]).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        Ok(Self { lamports, seed })
+    }
+}
+
 #[repr(C)]
 pub struct AuthorizeCheckedWithSeedData<'a> {
     pub new_authorized: Pubkey,
@@ -352,11 +354,12 @@ impl<'a> AuthorizeCheckedWithSeedData<'a> {
 
         let seed_len = data[33] as usize;
 
-        if seed_len > 32 {
-            return Err(ProgramError::InvalidInstructionData);
+        if seed_len > pinocchio::pubkey::MAX_SEED_LEN {
+            return Err(ProgramError::MaxSeedLengthExceeded);
         }
 
-        if data.len() < 34 + seed_len + 32 {
+        // Exact length: trailing bytes after the owner are rejected
+        if data.len() != 34 + seed_len + 32 {
             return Err(ProgramError::InvalidInstructionData);
         }
 
@@ -383,7 +386,123 @@ pub struct SetLockupData {
 impl SetLockupData {
     pub const LEN: usize = 1 + 8 + 1 + 8 + 1 + 32; // flags + timestamp + flag + epoch + flag + pubkey
 
-    pub fn instruction_data(data: &[u8]) -> &mut Self {
-        unsafe { &mut *(data.as_ptr() as *mut Self) }
+    /// Parse bincode-style `LockupArgs`: each field is a 1-byte Option tag
+    /// (0 = None, 1 = Some) followed by the value when present. The payload
+    /// must be consumed exactly.
+    pub fn parse(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut rest = data;
+
+        let unix_timestamp = take_option::<8>(&mut rest)?.map(i64::from_le_bytes);
+        let epoch = take_option::<8>(&mut rest)?.map(u64::from_le_bytes);
+        let custodian = take_option::<32>(&mut rest)?;
+
+        if !rest.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            unix_timestamp,
+            epoch,
+            custodian,
+        })
+    }
+}
+
+fn take_option<const N: usize>(data: &mut &[u8]) -> Result<Option<[u8; N]>, ProgramError> {
+    let (tag, rest) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match tag {
+        0 => {
+            *data = rest;
+            Ok(None)
+        }
+        1 => {
+            if rest.len() < N {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let (value, rest) = rest.split_at(N);
+            *data = rest;
+            Ok(Some(value.try_into().unwrap()))
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed_payload(seed: &[u8]) -> [u8; 98] {
+        let mut buf = [0u8; 98];
+        buf[..32].copy_from_slice(&[7u8; 32]);
+        buf[32] = 1;
+        buf[33] = seed.len() as u8;
+        buf[34..34 + seed.len()].copy_from_slice(seed);
+        buf[34 + seed.len()..66 + seed.len()].copy_from_slice(&[9u8; 32]);
+        buf
+    }
+
+    #[test]
+    fn seed_parsers_reject_trailing_bytes() {
+        let seed = b"seed";
+        let buf = seed_payload(seed);
+        let exact = 66 + seed.len();
+
+        let args = AuthorizeWithSeedData::parse(&buf[..exact]).unwrap();
+        assert_eq!(args.authority_seed, seed);
+        assert_eq!(args.authority_owner, [9u8; 32]);
+        assert!(AuthorizeCheckedWithSeedData::parse(&buf[..exact]).is_ok());
+
+        assert_eq!(
+            AuthorizeWithSeedData::parse(&buf[..exact + 1]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            AuthorizeCheckedWithSeedData::parse(&buf[..exact + 1]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn split_with_seed_parse_is_exact() {
+        let mut data = [0u8; 8 + 1 + 5];
+        data[..8].copy_from_slice(&77u64.to_le_bytes());
+        data[8] = 4;
+        data[9..13].copy_from_slice(b"dest");
+
+        let args = SplitWithSeedData::parse(&data[..13]).unwrap();
+        assert_eq!(args.lamports, 77);
+        assert_eq!(args.seed, "dest");
+
+        assert!(SplitWithSeedData::parse(&data).is_err());
+        assert!(SplitWithSeedData::parse(&data[..12]).is_err());
+        data[9] = 0xFF;
+        assert!(SplitWithSeedData::parse(&data[..13]).is_err());
+        data[8] = 33;
+        assert_eq!(SplitWithSeedData::parse(&data).err(), Some(ProgramError::MaxSeedLengthExceeded));
+    }
+
+    #[test]
+    fn set_lockup_parse_is_exact() {
+        // Some(ts), None, Some(custodian)
+        let mut data = [0u8; 1 + 8 + 1 + 1 + 32 + 1];
+        data[0] = 1;
+        data[1..9].copy_from_slice(&42i64.to_le_bytes());
+        data[9] = 0;
+        data[10] = 1;
+        data[11..43].copy_from_slice(&[3u8; 32]);
+
+        let args = SetLockupData::parse(&data[..43]).unwrap();
+        assert_eq!(args.unix_timestamp, Some(42));
+        assert_eq!(args.epoch, None);
+        assert_eq!(args.custodian, Some([3u8; 32]));
+
+        data[43] = 0xFF;
+        assert!(SetLockupData::parse(&data).is_err());
+        // truncated value and invalid option tag
+        assert!(SetLockupData::parse(&data[..40]).is_err());
+        assert!(SetLockupData::parse(&[2, 0, 0]).is_err());
     }
 }
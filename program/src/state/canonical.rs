@@ -0,0 +1,240 @@
+//! Canonical hash of a stake account's state, for off-chain reconcilers
+//! comparing large sets of accounts across implementations or layouts.
+//!
+//! The hash is `sha256(CANONICAL_DOMAIN || canonical_bytes(state))`, where the
+//! canonical bytes are the state's fields in a fixed order, little-endian:
+//!
+//! - tag: `u8` (0 Uninitialized, 1 Initialized, 2 Stake, 3 RewardsPool)
+//! - Meta: rent_exempt_reserve, staker, withdrawer, lockup unix_timestamp,
+//!   epoch, custodian
+//! - Stake: voter, stake, activation_epoch, deactivation_epoch,
+//!   credits_observed, then the flags as their bit set (`u8`)
+//!
+//! Only semantic content is covered: not the account padding (nor the
+//! `integrity` seal kept there), not the layout's tag width or version (a
+//! `state-v3` account hashes as the V2 account it upgraded from), and not the
+//! deprecated `warmup_cooldown_rate`, which no logic reads. Lamports are not
+//! part of the state; compare them separately.
+//!
+//! On-chain the digest comes from the `sol_sha256` syscall
+//! ([`canonical_hash_syscall`]), elsewhere from `sha2-const-stable`
+//! ([`canonical_hash_host`]); [`canonical_hash`] picks the one for the target.
+
+use pinocchio::program_error::ProgramError;
+
+use crate::state::{
+    migration::{ActiveMigration, StateMigration},
+    stake_state_v2::StakeStateV2,
+    state::Meta,
+};
+
+/// Prefix of every hashed message; bumped with any change to the encoding
+pub const CANONICAL_DOMAIN: &[u8] = b"pinocchio-stake:canonical-state:v1";
+
+/// Canonical bytes of a Stake state, the longest variant
+pub const MAX_CANONICAL_LEN: usize = 1 + 120 + 32 + 8 * 4 + 1;
+
+struct Writer<'a> {
+    out: &'a mut [u8; MAX_CANONICAL_LEN],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.out[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn meta(&mut self, meta: &Meta) {
+        self.put(&meta.rent_exempt_reserve.to_le_bytes());
+        self.put(&meta.authorized.staker);
+        self.put(&meta.authorized.withdrawer);
+        self.put(&meta.lockup.unix_timestamp.to_le_bytes());
+        self.put(&meta.lockup.epoch.to_le_bytes());
+        self.put(&meta.lockup.custodian);
+    }
+}
+
+/// Write the canonical bytes of `state` into `out`, returning their length
+pub fn canonical_bytes(state: &StakeStateV2, out: &mut [u8; MAX_CANONICAL_LEN]) -> usize {
+    let mut w = Writer { out, len: 0 };
+    match state {
+        StakeStateV2::Uninitialized => w.put(&[0]),
+        StakeStateV2::Initialized(meta) => {
+            w.put(&[1]);
+            w.meta(meta);
+        }
+        StakeStateV2::Stake(meta, stake, flags) => {
+            w.put(&[2]);
+            w.meta(meta);
+            let delegation = stake.delegation;
+            w.put(&delegation.voter_pubkey);
+            w.put(&delegation.stake.to_le_bytes());
+            w.put(&delegation.activation_epoch.to_le_bytes());
+            w.put(&delegation.deactivation_epoch.to_le_bytes());
+            w.put(&stake.credits_observed);
+            w.put(&[flags.bits]);
+        }
+        StakeStateV2::RewardsPool => w.put(&[3]),
+    }
+    w.len
+}
+
+/// [`canonical_hash`] through the `sol_sha256` syscall
+#[cfg(target_os = "solana")]
+pub fn canonical_hash_syscall(state: &StakeStateV2) -> [u8; 32] {
+    let mut bytes = [0u8; MAX_CANONICAL_LEN];
+    let len = canonical_bytes(state, &mut bytes);
+    let vals: [&[u8]; 2] = [CANONICAL_DOMAIN, &bytes[..len]];
+    let mut hash = [0u8; 32];
+    // SAFETY: `vals` is an array of slices, the layout the syscall reads, and
+    // `hash` has room for the 32-byte digest
+    unsafe {
+        pinocchio::syscalls::sol_sha256(vals.as_ptr() as *const u8, vals.len() as u64, hash.as_mut_ptr());
+    }
+    hash
+}
+
+/// [`canonical_hash`] through `sha2-const-stable`, for hosts and wasm
+#[cfg(not(target_os = "solana"))]
+pub fn canonical_hash_host(state: &StakeStateV2) -> [u8; 32] {
+    let mut bytes = [0u8; MAX_CANONICAL_LEN];
+    let len = canonical_bytes(state, &mut bytes);
+    sha2_const_stable::Sha256::new().update(CANONICAL_DOMAIN).update(&bytes[..len]).finalize()
+}
+
+/// Canonical hash of `state`
+#[inline]
+pub fn canonical_hash(state: &StakeStateV2) -> [u8; 32] {
+    #[cfg(target_os = "solana")]
+    {
+        canonical_hash_syscall(state)
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        canonical_hash_host(state)
+    }
+}
+
+/// Canonical hash of stake account data in any layout the program loads
+pub fn canonical_account_hash(data: &[u8]) -> Result<[u8; 32], ProgramError> {
+    Ok(canonical_hash(&ActiveMigration::load(data)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        accounts::Authorized,
+        delegation::{Delegation, Stake},
+        stake_flag::StakeFlags,
+        state::Lockup,
+        EpochLE, LamportsLE,
+    };
+
+    fn meta() -> Meta {
+        Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            lockup: Lockup { unix_timestamp: -5, epoch: 600, custodian: [3; 32] },
+        }
+    }
+
+    fn staked() -> StakeStateV2 {
+        let mut delegation = Delegation::new(&[4; 32], 5_000_000_000, EpochLE::new(7));
+        delegation.deactivation_epoch = EpochLE::new(9);
+        let stake = Stake { delegation, credits_observed: 42u64.to_le_bytes() };
+        StakeStateV2::Stake(meta(), stake, StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED)
+    }
+
+    fn states() -> [StakeStateV2; 4] {
+        [StakeStateV2::Uninitialized, StakeStateV2::Initialized(meta()), staked(), StakeStateV2::RewardsPool]
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn host_hash_matches_the_sdk_hashv() {
+        // solana-sdk's hashv is what sol_sha256 computes on-chain
+        for state in states() {
+            let mut bytes = [0u8; MAX_CANONICAL_LEN];
+            let len = canonical_bytes(&state, &mut bytes);
+            let expected = solana_sdk::hash::hashv(&[CANONICAL_DOMAIN, &bytes[..len]]).to_bytes();
+            assert_eq!(canonical_hash_host(&state), expected, "{state:?}");
+            assert_eq!(canonical_hash(&state), expected);
+        }
+        assert_eq!(canonical_bytes(&staked(), &mut [0; MAX_CANONICAL_LEN]), MAX_CANONICAL_LEN);
+    }
+
+    #[test]
+    fn encoding_is_pinned() {
+        // reconcilers compare against hashes computed elsewhere: any change here
+        // needs a new CANONICAL_DOMAIN
+        assert_eq!(hex(&canonical_hash(&staked())), "9149c68b07d82d08628ab61d21dcbdd0fdf63ee06c3a040552aa6e46c459abaa");
+    }
+
+    #[test]
+    fn every_semantic_field_is_covered() {
+        #[allow(deprecated)]
+        let variants: [fn(&mut Meta, &mut Stake, &mut StakeFlags); 12] = [
+            |m, _, _| m.rent_exempt_reserve = LamportsLE::new(1),
+            |m, _, _| m.authorized.staker[31] ^= 1,
+            |m, _, _| m.authorized.withdrawer[0] ^= 1,
+            |m, _, _| m.lockup.unix_timestamp = 5,
+            |m, _, _| m.lockup.epoch += 1,
+            |m, _, _| m.lockup.custodian[9] ^= 1,
+            |_, s, _| s.delegation.voter_pubkey[3] ^= 1,
+            |_, s, _| s.delegation.stake = LamportsLE::new(1),
+            |_, s, _| s.delegation.activation_epoch = EpochLE::new(8),
+            |_, s, _| s.delegation.deactivation_epoch = EpochLE::MAX,
+            |_, s, _| s.credits_observed = 43u64.to_le_bytes(),
+            |_, _, f| *f = StakeFlags::empty(),
+        ];
+        let StakeStateV2::Stake(meta, stake, flags) = staked() else { unreachable!() };
+        let base = canonical_hash(&staked());
+        for (i, change) in variants.iter().enumerate() {
+            let (mut m, mut s, mut f) = (meta, stake, flags);
+            change(&mut m, &mut s, &mut f);
+            assert_ne!(canonical_hash(&StakeStateV2::Stake(m, s, f)), base, "field {i}");
+        }
+
+        // the deprecated warmup rate is layout only
+        let mut s = stake;
+        #[allow(deprecated)]
+        {
+            s.delegation.warmup_cooldown_rate = 0.25f64.to_le_bytes();
+        }
+        assert_eq!(canonical_hash(&StakeStateV2::Stake(meta, s, flags)), base);
+
+        // variants with the same Meta still differ
+        let hashes = states().map(|state| canonical_hash(&state));
+        assert!(hashes.iter().enumerate().all(|(i, h)| !hashes[..i].contains(h)));
+    }
+
+    #[test]
+    fn account_padding_is_not_hashed() {
+        for state in states() {
+            let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+            ActiveMigration::store(&state, &mut data).unwrap();
+            let hash = canonical_account_hash(&data).unwrap();
+            assert_eq!(hash, canonical_hash(&state));
+
+            // whatever trails the state (padding, the integrity seal) is ignored
+            let end = match state {
+                StakeStateV2::Stake(..) => StakeStateV2::FLAGS_OFFSET + 1,
+                StakeStateV2::Initialized(_) => StakeStateV2::STAKE_OFFSET,
+                _ => StakeStateV2::TAG_LEN,
+            };
+            data[end..].fill(0xa5);
+            assert_eq!(canonical_account_hash(&data).unwrap(), hash, "{state:?}");
+
+            // and a V2 account hashes the same under any active layout
+            let mut v2 = [0u8; StakeStateV2::ACCOUNT_SIZE];
+            state.serialize(&mut v2).unwrap();
+            assert_eq!(canonical_account_hash(&v2).unwrap(), hash);
+        }
+        assert_eq!(canonical_account_hash(&[9, 0, 0, 0]), Err(ProgramError::InvalidAccountData));
+    }
+}
@@ -1,44 +1,54 @@
 use crate::error::StakeError;
 use crate::helpers::{
-    bytes_to_u64, warmup_cooldown_rate, Epoch, DEFAULT_WARMUP_COOLDOWN_RATE,
+    warmup_cooldown_rate, Binary64, DEFAULT_WARMUP_COOLDOWN_RATE,
+};
+use crate::state::{
+    stake_history::{StakeHistoryEntry, StakeHistoryGetEntry},
+    EpochLE, LamportsLE,
 };
-use crate::state::stake_history::{StakeHistoryEntry, StakeHistoryGetEntry, StakeHistorySysvar};
 use pinocchio::pubkey::Pubkey;
 
 pub type StakeActivationStatus = StakeHistoryEntry;
 
 #[repr(C, packed)]
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Delegation {
     /// to whom the stake is delegated
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::pubkey"))]
     pub voter_pubkey: Pubkey,
     /// activated stake amount, set at delegate() time
-    pub stake: [u8; 8],
+    pub stake: LamportsLE,
     /// epoch at which this stake was activated, `u64::MAX` if bootstrap stake
-    pub activation_epoch: Epoch,
+    pub activation_epoch: EpochLE,
     /// epoch the stake was deactivated, `u64::MAX` if not deactivated
-    pub deactivation_epoch: Epoch,
+    pub deactivation_epoch: EpochLE,
     /// kept for layout compatibility only; not used by logic
     #[deprecated(
         since = "1.16.7",
         note = "Use global warmup_cooldown_rate() instead"
     )]
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::le_f64"))]
     pub warmup_cooldown_rate: [u8; 8],
 }
 
 #[repr(C)]
 #[derive(Debug, Default, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stake {
     pub delegation: Delegation,
     /// credits observed is credits from vote account state when delegated or redeemed
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::le_u64"))]
     pub credits_observed: [u8; 8],
 }
 
 impl Delegation {
-    pub fn new(voter_pubkey: &Pubkey, stake: u64, activation_epoch: Epoch) -> Self {
+    pub fn new(voter_pubkey: &Pubkey, stake: u64, activation_epoch: EpochLE) -> Self {
         Self {
             voter_pubkey: *voter_pubkey,
-            stake: stake.to_le_bytes(),
+            stake: LamportsLE::new(stake),
             activation_epoch,
             ..Delegation::default()
         }
@@ -46,14 +56,14 @@ impl Delegation {
 
     #[inline]
     pub fn is_bootstrap(&self) -> bool {
-        bytes_to_u64(self.activation_epoch) == u64::MAX
+        self.activation_epoch == EpochLE::MAX
     }
 
     pub fn stake<T: StakeHistoryGetEntry>(
         &self,
-        epoch: Epoch,
+        epoch: EpochLE,
         history: &T,
-        new_rate_activation_epoch: Option<Epoch>,
+        new_rate_activation_epoch: Option<EpochLE>,
     ) -> u64 {
         self.stake_activating_and_deactivating(epoch, history, new_rate_activation_epoch).effective_u64()
     }
@@ -61,13 +71,13 @@ impl Delegation {
     #[allow(clippy::comparison_chain)]
     pub fn stake_activating_and_deactivating<T: StakeHistoryGetEntry>(
         &self,
-        target_epoch: Epoch,
+        target_epoch: EpochLE,
         history: &T,
-        new_rate_activation_epoch: Option<Epoch>,
+        new_rate_activation_epoch: Option<EpochLE>,
     ) -> StakeActivationStatus {
         // Convert epochs to numeric before any comparisons
-        let tgt = bytes_to_u64(target_epoch);
-        let deact = bytes_to_u64(self.deactivation_epoch);
+        let tgt = target_epoch.get();
+        let deact = self.deactivation_epoch.get();
 
         // first, calculate an effective and activating stake
         let (effective_stake, activating_stake) =
@@ -85,7 +95,7 @@ impl Delegation {
             // can only deactivate what's activated
             StakeActivationStatus::with_deactivating(effective_stake)
         } else if let Some((history, mut prev_epoch, mut prev_cluster_stake)) = history
-            .get_entry(bytes_to_u64(self.deactivation_epoch))
+            .get_entry(self.deactivation_epoch.get())
             .map(|cluster_stake_at_deactivation_epoch| {
                 (history, self.deactivation_epoch, cluster_stake_at_deactivation_epoch)
             })
@@ -93,26 +103,26 @@ impl Delegation {
             // target_epoch > self.deactivation_epoch
             let mut current_effective_stake = effective_stake;
             loop {
-                let current_epoch_u64 = bytes_to_u64(prev_epoch) + 1;
+                let current_epoch_u64 = prev_epoch.get() + 1;
 
                 // if there is no deactivating stake at prev epoch, we should have been fully undelegated
-                if bytes_to_u64(prev_cluster_stake.deactivating) == 0 {
+                if prev_cluster_stake.deactivating.get() == 0 {
                     break;
                 }
 
                 // proportion of newly non-effective cluster stake this account is entitled to take
-                let weight = current_effective_stake as f64
-                    / bytes_to_u64(prev_cluster_stake.deactivating) as f64;
+                let weight = Binary64::from_u64(current_effective_stake)
+                    / Binary64::from_u64(prev_cluster_stake.deactivating.get());
                 let rate = warmup_cooldown_rate(
-                    current_epoch_u64.to_le_bytes(),
+                    EpochLE::new(current_epoch_u64),
                     new_rate_activation_epoch,
                 );
 
                 // newly not-effective cluster stake at current epoch
                 let newly_not_effective_cluster_stake =
-                    bytes_to_u64(prev_cluster_stake.effective) as f64 * rate;
+                    Binary64::from_u64(prev_cluster_stake.effective.get()) * rate;
                 let newly_not_effective_stake =
-                    ((weight * newly_not_effective_cluster_stake) as u64).max(1);
+                    (weight * newly_not_effective_cluster_stake).to_u64().max(1);
 
                 current_effective_stake = current_effective_stake.saturating_sub(newly_not_effective_stake);
                 if current_effective_stake == 0 {
@@ -123,7 +133,7 @@ impl Delegation {
                     break;
                 }
                 if let Some(current_cluster_stake) = history.get_entry(current_epoch_u64) {
-                    prev_epoch = current_epoch_u64.to_le_bytes();
+                    prev_epoch = EpochLE::new(current_epoch_u64);
                     prev_cluster_stake = current_cluster_stake;
                 } else {
                     break;
@@ -141,26 +151,26 @@ impl Delegation {
     // returns (effective, activating)
     fn stake_and_activating<T: StakeHistoryGetEntry>(
         &self,
-        target_epoch: Epoch,
+        target_epoch: EpochLE,
         history: &T,
-        new_rate_activation_epoch: Option<Epoch>,
+        new_rate_activation_epoch: Option<EpochLE>,
     ) -> (u64, u64) {
         let delegated_stake = self.stake;
 
-        let tgt = bytes_to_u64(target_epoch);
-        let act = bytes_to_u64(self.activation_epoch);
-        let deact = bytes_to_u64(self.deactivation_epoch);
+        let tgt = target_epoch.get();
+        let act = self.activation_epoch.get();
+        let deact = self.deactivation_epoch.get();
 
         if self.is_bootstrap() {
-            (bytes_to_u64(delegated_stake), 0)
+            (delegated_stake.get(), 0)
         } else if self.activation_epoch == self.deactivation_epoch {
             (0, 0)
         } else if tgt == act {
-            (0, bytes_to_u64(delegated_stake))
+            (0, delegated_stake.get())
         } else if tgt < act {
             (0, 0)
         } else if let Some((history, mut prev_epoch, mut prev_cluster_stake)) = history
-            .get_entry(bytes_to_u64(self.activation_epoch))
+            .get_entry(self.activation_epoch.get())
             .map(|cluster_stake_at_activation_epoch| {
                 (history, self.activation_epoch, cluster_stake_at_activation_epoch)
             })
@@ -168,26 +178,26 @@ impl Delegation {
             // tgt > act
             let mut current_effective_stake = 0u64;
             loop {
-                let current_epoch_u64 = bytes_to_u64(prev_epoch) + 1;
+                let current_epoch_u64 = prev_epoch.get() + 1;
 
-                if bytes_to_u64(prev_cluster_stake.activating) == 0 {
+                if prev_cluster_stake.activating.get() == 0 {
                     break;
                 }
 
                 // entitlement to newly-effective cluster stake at current epoch
-                let delegated_stake_u64 = bytes_to_u64(delegated_stake);
+                let delegated_stake_u64 = delegated_stake.get();
                 let remaining_activating_stake = delegated_stake_u64 - current_effective_stake;
-                let weight = remaining_activating_stake as f64
-                    / bytes_to_u64(prev_cluster_stake.activating) as f64;
+                let weight = Binary64::from_u64(remaining_activating_stake)
+                    / Binary64::from_u64(prev_cluster_stake.activating.get());
                 let rate = warmup_cooldown_rate(
-                    current_epoch_u64.to_le_bytes(),
+                    EpochLE::new(current_epoch_u64),
                     new_rate_activation_epoch,
                 );
 
                 let newly_effective_cluster_stake =
-                    bytes_to_u64(prev_cluster_stake.effective) as f64 * rate;
+                    Binary64::from_u64(prev_cluster_stake.effective.get()) * rate;
                 let newly_effective_stake =
-                    ((weight * newly_effective_cluster_stake) as u64).max(1);
+                    (weight * newly_effective_cluster_stake).to_u64().max(1);
 
                 current_effective_stake = current_effective_stake.saturating_add(newly_effective_stake);
                 if current_effective_stake >= delegated_stake_u64 {
@@ -199,16 +209,16 @@ impl Delegation {
                     break;
                 }
                 if let Some(current_cluster_stake) = history.get_entry(current_epoch_u64) {
-                    prev_epoch = current_epoch_u64.to_le_bytes();
+                    prev_epoch = EpochLE::new(current_epoch_u64);
                     prev_cluster_stake = current_cluster_stake;
                 } else {
                     break;
                 }
             }
 
-            (current_effective_stake, bytes_to_u64(delegated_stake) - current_effective_stake)
+            (current_effective_stake, delegated_stake.get() - current_effective_stake)
         } else {
-            (bytes_to_u64(delegated_stake), 0)
+            (delegated_stake.get(), 0)
         }
     }
 }
@@ -218,10 +228,10 @@ impl Default for Delegation {
         #[allow(deprecated)]
         Self {
             voter_pubkey: Pubkey::default(),
-            stake: 0u64.to_le_bytes(),
-            activation_epoch: 0u64.to_le_bytes(),
-            deactivation_epoch: u64::MAX.to_le_bytes(),
-            warmup_cooldown_rate: DEFAULT_WARMUP_COOLDOWN_RATE.to_le_bytes(),
+            stake: LamportsLE::ZERO,
+            activation_epoch: EpochLE::ZERO,
+            deactivation_epoch: EpochLE::MAX,
+            warmup_cooldown_rate: DEFAULT_WARMUP_COOLDOWN_RATE.to_bits().to_le_bytes(),
         }
     }
 }
@@ -229,9 +239,9 @@ impl Default for Delegation {
 impl Stake {
     /// Whether this stake is considered active for the given epoch
     /// (simple window check; the effective check is done via `Stake::stake`)
-    pub fn is_active(&self, current_epoch: u64, _stake_history: &StakeHistorySysvar) -> bool {
-        let act = bytes_to_u64(self.delegation.activation_epoch);
-        let deact = bytes_to_u64(self.delegation.deactivation_epoch);
+    pub fn is_active<T: StakeHistoryGetEntry>(&self, current_epoch: u64, _stake_history: &T) -> bool {
+        let act = self.delegation.activation_epoch.get();
+        let deact = self.delegation.deactivation_epoch.get();
         act <= current_epoch && current_epoch < deact
     }
 
@@ -241,9 +251,9 @@ impl Stake {
 
     pub fn stake<T: StakeHistoryGetEntry>(
         &self,
-        epoch: Epoch,
+        epoch: EpochLE,
         history: &T,
-        new_rate_activation_epoch: Option<Epoch>,
+        new_rate_activation_epoch: Option<EpochLE>,
     ) -> u64 {
         self.delegation
             .stake(epoch, history, new_rate_activation_epoch)
@@ -254,14 +264,13 @@ impl Stake {
         remaining_stake_delta: u64,
         split_stake_amount: u64,
     ) -> Result<Self, StakeError> {
-        let current = bytes_to_u64(self.delegation.stake);
-        if remaining_stake_delta > current {
-            return Err(StakeError::InsufficientStake);
-        }
-        self.delegation.stake = current.saturating_sub(remaining_stake_delta).to_le_bytes();
+        let remaining = self.delegation.stake.get()
+            .checked_sub(remaining_stake_delta)
+            .ok_or(StakeError::InsufficientStake)?;
+        self.delegation.stake = LamportsLE::new(remaining);
         let new = Self {
             delegation: Delegation {
-                stake: split_stake_amount.to_le_bytes(),
+                stake: LamportsLE::new(split_stake_amount),
                 ..self.delegation
             },
             ..*self
@@ -269,8 +278,8 @@ impl Stake {
         Ok(new)
     }
 
-    pub fn deactivate(&mut self, epoch: Epoch) -> Result<(), StakeError> {
-        if bytes_to_u64(self.delegation.deactivation_epoch) != u64::MAX {
+    pub fn deactivate(&mut self, epoch: EpochLE) -> Result<(), StakeError> {
+        if self.delegation.deactivation_epoch != EpochLE::MAX {
             Err(StakeError::AlreadyDeactivated)
         } else {
             self.delegation.deactivation_epoch = epoch;
@@ -279,18 +288,271 @@ impl Stake {
     }
 }
 
+impl Delegation {
+    /// What-if projection of [`Self::stake_activating_and_deactivating`] at
+    /// `target_epoch`, which may lie past the end of `history`.
+    ///
+    /// `history` must hold entries for every epoch before `current_epoch`, as
+    /// the StakeHistory sysvar does at that clock. Later entries are projected
+    /// assuming the rest of the cluster neither delegates nor deactivates
+    /// anything new: the cluster's pending activating and deactivating stake
+    /// keeps warming up and cooling down at the warmup/cooldown rate, and this
+    /// delegation's own activation or deactivation is added in the epoch it
+    /// takes effect. Up to `current_epoch` the result equals the plain status.
+    /// Cost grows with the square of `target_epoch - current_epoch`.
+    pub fn projected_activation<T: StakeHistoryGetEntry>(
+        &self,
+        target_epoch: EpochLE,
+        current_epoch: EpochLE,
+        history: &T,
+        new_rate_activation_epoch: Option<EpochLE>,
+    ) -> StakeActivationStatus {
+        let projected = ProjectedHistory {
+            history,
+            current_epoch: current_epoch.get(),
+            delegation: self,
+            new_rate_activation_epoch,
+        };
+        self.stake_activating_and_deactivating(target_epoch, &projected, new_rate_activation_epoch)
+    }
+}
+
+/// `history` extended past `current_epoch - 1` for [`Delegation::projected_activation`]
+struct ProjectedHistory<'a, T> {
+    history: &'a T,
+    current_epoch: u64,
+    delegation: &'a Delegation,
+    new_rate_activation_epoch: Option<EpochLE>,
+}
+
+impl<T: StakeHistoryGetEntry> ProjectedHistory<'_, T> {
+    /// Cluster stake at `epoch` moved on one epoch from `prev`
+    fn step(&self, prev: &StakeHistoryEntry, epoch: u64) -> StakeHistoryEntry {
+        let effective = prev.effective.get();
+        let activating = prev.activating.get();
+        let deactivating = prev.deactivating.get();
+
+        let rate = warmup_cooldown_rate(EpochLE::new(epoch), self.new_rate_activation_epoch);
+        let moved = (Binary64::from_u64(effective) * rate).to_u64().max(1);
+        let newly_effective = activating.min(moved);
+        let newly_not_effective = deactivating.min(moved);
+
+        let mut activating = activating - newly_effective;
+        let mut deactivating = deactivating - newly_not_effective;
+        if epoch == self.delegation.activation_epoch.get() {
+            activating = activating.saturating_add(self.delegation.stake.get());
+        }
+        if epoch == self.delegation.deactivation_epoch.get() {
+            // only entries before `epoch` are needed, so this does not recurse further
+            let own = self.delegation.stake_activating_and_deactivating(
+                EpochLE::new(epoch),
+                self,
+                self.new_rate_activation_epoch,
+            );
+            deactivating = deactivating.saturating_add(own.deactivating.get());
+        }
+
+        StakeHistoryEntry {
+            // saturating: history entries come from the sysvar, not from our own math
+            effective: LamportsLE::new(effective.saturating_add(newly_effective).saturating_sub(newly_not_effective)),
+            activating: LamportsLE::new(activating),
+            deactivating: LamportsLE::new(deactivating),
+        }
+    }
+}
+
+impl<T: StakeHistoryGetEntry> StakeHistoryGetEntry for ProjectedHistory<'_, T> {
+    fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+        if epoch < self.current_epoch {
+            return self.history.get_entry(epoch);
+        }
+        // past both of its own events a settled cluster no longer changes
+        let last_event = [self.delegation.activation_epoch, self.delegation.deactivation_epoch]
+            .map(EpochLE::get)
+            .into_iter()
+            .filter(|&e| e != u64::MAX)
+            .max()
+            .unwrap_or(0);
+        let mut entry = self.history.get_entry(self.current_epoch.checked_sub(1)?)?;
+        for e in self.current_epoch..=epoch {
+            let settled = entry.activating.get() == 0 && entry.deactivating.get() == 0;
+            if settled && e > last_event {
+                break;
+            }
+            entry = self.step(&entry, e);
+        }
+        Some(entry)
+    }
+}
+
 // small helper to keep public API consistent
 impl StakeActivationStatus {
     #[inline]
     fn effective_u64(&self) -> u64 {
-        // Expect StakeHistoryEntry to expose `effective` as [u8;8] in Pinocchio
-        bytes_to_u64(self.effective)
+        self.effective.get()
     }
 }
 
 // helper: set stake amount
 impl Delegation {
     pub fn set_stake_amount(&mut self, amount: u64) {
-        self.stake = amount.to_le_bytes();
+        self.stake = LamportsLE::new(amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH as NEW_RATE;
+
+    const CURRENT: u64 = 10;
+    const STAKE: u64 = 180_000;
+
+    /// The same cluster entry for every epoch before `CURRENT`
+    struct Flat(StakeHistoryEntry);
+
+    impl StakeHistoryGetEntry for Flat {
+        fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+            (epoch < CURRENT).then(|| self.0.clone())
+        }
+    }
+
+    fn status(effective: u64, activating: u64, deactivating: u64) -> StakeActivationStatus {
+        StakeActivationStatus {
+            effective: LamportsLE::new(effective),
+            activating: LamportsLE::new(activating),
+            deactivating: LamportsLE::new(deactivating),
+        }
+    }
+
+    fn project(delegation: &Delegation, target: u64, history: &Flat) -> StakeActivationStatus {
+        delegation.projected_activation(EpochLE::new(target), EpochLE::new(CURRENT), history, NEW_RATE)
+    }
+
+    #[test]
+    fn rate_switches_at_new_rate_activation_epoch_like_native() {
+        use solana_sdk::stake::state::Delegation as NativeDelegation;
+        use solana_sdk::stake_history::{StakeHistory as NativeHistory, StakeHistoryEntry as NativeEntry};
+
+        // a cluster far larger than what is warming up, so warmup spans many epochs
+        let mut native_history = NativeHistory::default();
+        for epoch in 0..400 {
+            native_history.add(epoch, NativeEntry { effective: 1_000_000, activating: 4_000_000, deactivating: 4_000_000 });
+        }
+        struct Every(StakeHistoryEntry);
+        impl StakeHistoryGetEntry for Every {
+            fn get_entry(&self, _epoch: u64) -> Option<StakeHistoryEntry> {
+                Some(self.0.clone())
+            }
+        }
+        let history = Every(StakeHistoryEntry {
+            effective: LamportsLE::new(1_000_000),
+            activating: LamportsLE::new(4_000_000),
+            deactivating: LamportsLE::new(4_000_000),
+        });
+
+        let mut delegation = Delegation::new(&[1; 32], 4_000_000, EpochLE::new(240));
+        delegation.deactivation_epoch = EpochLE::new(262);
+        let native = NativeDelegation {
+            stake: 4_000_000,
+            activation_epoch: 240,
+            deactivation_epoch: 262,
+            ..NativeDelegation::default()
+        };
+        // epochs past 255 do not order like numbers as little-endian bytes
+        for rate_epoch in [Some(0), Some(250), Some(256), Some(258), None] {
+            for target in 235u64..290 {
+                let ours = delegation.stake_activating_and_deactivating(
+                    EpochLE::new(target),
+                    &history,
+                    rate_epoch.map(EpochLE::new),
+                );
+                let theirs = native.stake_activating_and_deactivating(target, &native_history, rate_epoch);
+                assert_eq!(
+                    (ours.effective.get(), ours.activating.get(), ours.deactivating.get()),
+                    (theirs.effective, theirs.activating, theirs.deactivating),
+                    "rate epoch {rate_epoch:?}, target {target}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn projection_matches_status_within_history() {
+        let history = Flat(StakeHistoryEntry::with_effective_and_activating(1_000_000, 1_000_000));
+        let delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(5));
+        for target in 0..=CURRENT {
+            assert_eq!(
+                project(&delegation, target, &history),
+                delegation.stake_activating_and_deactivating(EpochLE::new(target), &history, NEW_RATE),
+                "epoch {target}"
+            );
+        }
+    }
+
+    #[test]
+    fn projection_warms_up_past_history() {
+        // 9% of a 1M effective cluster comes online per epoch
+        let history = Flat(StakeHistoryEntry::with_effective(1_000_000));
+        let delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(CURRENT));
+
+        assert_eq!(project(&delegation, CURRENT, &history), status(0, STAKE, 0));
+        assert_eq!(project(&delegation, CURRENT + 1, &history), status(90_000, 90_000, 0));
+        assert_eq!(project(&delegation, CURRENT + 2, &history), status(STAKE, 0, 0));
+        assert_eq!(project(&delegation, CURRENT + 1_000, &history), status(STAKE, 0, 0));
+
+        // without projection the missing entries count as fully warmed up
+        let plain = delegation.stake_activating_and_deactivating(EpochLE::new(CURRENT + 1), &history, NEW_RATE);
+        assert_eq!(plain, status(STAKE, 0, 0));
+    }
+
+    #[test]
+    fn projection_cools_down_past_history() {
+        let history = Flat(StakeHistoryEntry::with_effective(1_000_000));
+        let mut delegation = Delegation::new(&[1; 32], STAKE, EpochLE::new(u64::MAX));
+        delegation.deactivation_epoch = EpochLE::new(CURRENT);
+
+        assert_eq!(project(&delegation, CURRENT, &history), status(STAKE, 0, STAKE));
+        assert_eq!(project(&delegation, CURRENT + 1, &history), status(90_000, 0, 90_000));
+        // the cluster shrank to 910k effective, so 81.9k more cools down
+        assert_eq!(project(&delegation, CURRENT + 2, &history), status(8_100, 0, 8_100));
+        assert_eq!(project(&delegation, CURRENT + 3, &history), status(0, 0, 0));
+    }
+
+    #[test]
+    fn projection_includes_pending_cluster_activation() {
+        // another 910k is already queued ahead of this delegation
+        let history = Flat(StakeHistoryEntry::with_effective_and_activating(1_000_000, 910_000));
+        let delegation = Delegation::new(&[1; 32], 90_000, EpochLE::new(CURRENT));
+
+        // by epoch CURRENT 90k of the queue is online (1.09M effective) and
+        // 820k + 90k is activating, so it gets 9/91 of the next 98.1k
+        let first = project(&delegation, CURRENT + 1, &history);
+        assert_eq!(first, status(9_702, 80_298, 0));
+        let later = project(&delegation, CURRENT + 2, &history);
+        assert!(later.effective.get() > first.effective.get());
+    }
+
+    #[test]
+    fn projection_near_u64_max_saturates() {
+        let history = Flat(StakeHistoryEntry::with_effective_and_activating(u64::MAX - 1, u64::MAX));
+        let delegation = Delegation::new(&[1; 32], u64::MAX, EpochLE::new(CURRENT));
+        // no overflow panic, and the delegation keeps warming up
+        let mut effective = 0;
+        for target in CURRENT..CURRENT + 4 {
+            let status = project(&delegation, target, &history);
+            assert!(status.effective.get() >= effective);
+            effective = status.effective.get();
+        }
+    }
+
+    #[test]
+    fn split_takes_exactly_the_delta() {
+        let mut stake = Stake { delegation: Delegation::new(&[1; 32], u64::MAX, EpochLE::ZERO), credits_observed: [0; 8] };
+        let split = stake.split(u64::MAX - 1, 5).unwrap();
+        assert_eq!(stake.delegation.stake.get(), 1);
+        assert_eq!(split.delegation.stake.get(), 5);
+        assert_eq!(stake.split(2, 2), Err(StakeError::InsufficientStake));
+        assert_eq!(stake.delegation.stake.get(), 1);
     }
 }
@@ -0,0 +1,117 @@
+//! Byte offsets of the fields in a serialized `StakeStateV2` account.
+//!
+//! The layout is native's bincode encoding and will not change, so indexers can
+//! build RPC `memcmp` filters from these (e.g. every Stake account delegated to
+//! a vote account: `TAG_STAKE` at [`TAG`] and the vote pubkey at
+//! [`VOTER_PUBKEY`]). Meta fields are present in Initialized and Stake
+//! accounts, delegation fields only in Stake accounts.
+//!
+//! The accessors read a single field straight from account data without
+//! deserializing the whole state, returning `None` if the account is too short
+//! or its variant does not carry that field.
+
+use pinocchio::pubkey::Pubkey;
+
+use crate::state::StakeStateV2;
+
+/// Length of a stake account
+pub const ACCOUNT_SIZE: usize = StakeStateV2::ACCOUNT_SIZE;
+
+/// Variant index, little-endian `u32`
+pub const TAG: usize = 0;
+pub const TAG_UNINITIALIZED: u32 = 0;
+pub const TAG_INITIALIZED: u32 = 1;
+pub const TAG_STAKE: u32 = 2;
+pub const TAG_REWARDS_POOL: u32 = 3;
+
+pub const RENT_EXEMPT_RESERVE: usize = StakeStateV2::META_OFFSET;
+pub const STAKER: usize = RENT_EXEMPT_RESERVE + 8;
+pub const WITHDRAWER: usize = STAKER + 32;
+/// Lockup unix timestamp, little-endian `i64`
+pub const LOCKUP_UNIX_TIMESTAMP: usize = WITHDRAWER + 32;
+pub const LOCKUP_EPOCH: usize = LOCKUP_UNIX_TIMESTAMP + 8;
+pub const CUSTODIAN: usize = LOCKUP_EPOCH + 8;
+
+pub const VOTER_PUBKEY: usize = StakeStateV2::STAKE_OFFSET;
+/// Delegated stake, little-endian `u64`
+pub const STAKE: usize = VOTER_PUBKEY + 32;
+pub const ACTIVATION_EPOCH: usize = STAKE + 8;
+pub const DEACTIVATION_EPOCH: usize = ACTIVATION_EPOCH + 8;
+/// Deprecated per-delegation rate, `f64` bits
+pub const WARMUP_COOLDOWN_RATE: usize = DEACTIVATION_EPOCH + 8;
+pub const CREDITS_OBSERVED: usize = WARMUP_COOLDOWN_RATE + 8;
+
+/// `StakeFlags` bits, one byte
+pub const STAKE_FLAGS: usize = StakeStateV2::FLAGS_OFFSET;
+
+const _: () = assert!(CUSTODIAN + 32 == StakeStateV2::STAKE_OFFSET);
+const _: () = assert!(CREDITS_OBSERVED + 8 == StakeStateV2::FLAGS_OFFSET);
+const _: () = assert!(STAKE_FLAGS < ACCOUNT_SIZE);
+
+/// Variant index of the account
+pub fn tag(data: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(*array(data, TAG)?))
+}
+
+fn array<const N: usize>(data: &[u8], offset: usize) -> Option<&[u8; N]> {
+    data.get(offset..offset + N)?.try_into().ok()
+}
+
+fn meta_field<const N: usize>(data: &[u8], offset: usize) -> Option<&[u8; N]> {
+    match tag(data)? {
+        TAG_INITIALIZED | TAG_STAKE => array(data, offset),
+        _ => None,
+    }
+}
+
+fn stake_field<const N: usize>(data: &[u8], offset: usize) -> Option<&[u8; N]> {
+    match tag(data)? {
+        TAG_STAKE => array(data, offset),
+        _ => None,
+    }
+}
+
+pub fn rent_exempt_reserve(data: &[u8]) -> Option<u64> {
+    meta_field(data, RENT_EXEMPT_RESERVE).copied().map(u64::from_le_bytes)
+}
+
+pub fn staker(data: &[u8]) -> Option<&Pubkey> {
+    meta_field(data, STAKER)
+}
+
+pub fn withdrawer(data: &[u8]) -> Option<&Pubkey> {
+    meta_field(data, WITHDRAWER)
+}
+
+pub fn lockup_unix_timestamp(data: &[u8]) -> Option<i64> {
+    meta_field(data, LOCKUP_UNIX_TIMESTAMP).copied().map(i64::from_le_bytes)
+}
+
+pub fn lockup_epoch(data: &[u8]) -> Option<u64> {
+    meta_field(data, LOCKUP_EPOCH).copied().map(u64::from_le_bytes)
+}
+
+pub fn custodian(data: &[u8]) -> Option<&Pubkey> {
+    meta_field(data, CUSTODIAN)
+}
+
+pub fn voter_pubkey(data: &[u8]) -> Option<&Pubkey> {
+    stake_field(data, VOTER_PUBKEY)
+}
+
+/// Delegated stake (not the effective amount)
+pub fn delegated_stake(data: &[u8]) -> Option<u64> {
+    stake_field(data, STAKE).copied().map(u64::from_le_bytes)
+}
+
+pub fn activation_epoch(data: &[u8]) -> Option<u64> {
+    stake_field(data, ACTIVATION_EPOCH).copied().map(u64::from_le_bytes)
+}
+
+pub fn deactivation_epoch(data: &[u8]) -> Option<u64> {
+    stake_field(data, DEACTIVATION_EPOCH).copied().map(u64::from_le_bytes)
+}
+
+pub fn credits_observed(data: &[u8]) -> Option<u64> {
+    stake_field(data, CREDITS_OBSERVED).copied().map(u64::from_le_bytes)
+}
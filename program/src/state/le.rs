@@ -0,0 +1,177 @@
+//! Little-endian `u64` newtypes for the epoch and lamport fields of account
+//! state.
+//!
+//! The state structs are read straight out of account data, so these fields
+//! stay 8 raw bytes with alignment 1 ([`EpochLE`] and [`LamportsLE`] are
+//! `repr(transparent)` over `[u8; 8]`). Unlike the bare arrays they compare
+//! and add as numbers: comparing the little-endian bytes misorders values
+//! once they pass 255.
+
+use core::{cmp::Ordering, fmt};
+
+macro_rules! le_u64 {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[repr(transparent)]
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+        pub struct $name([u8; 8]);
+
+        impl $name {
+            pub const ZERO: Self = Self::new(0);
+            pub const MAX: Self = Self::new(u64::MAX);
+
+            #[inline(always)]
+            pub const fn new(value: u64) -> Self {
+                Self(value.to_le_bytes())
+            }
+
+            #[inline(always)]
+            pub const fn get(self) -> u64 {
+                u64::from_le_bytes(self.0)
+            }
+
+            #[inline(always)]
+            pub const fn from_le_bytes(bytes: [u8; 8]) -> Self {
+                Self(bytes)
+            }
+
+            #[inline(always)]
+            pub const fn to_le_bytes(self) -> [u8; 8] {
+                self.0
+            }
+
+            #[inline(always)]
+            pub const fn checked_add(self, rhs: u64) -> Option<Self> {
+                match self.get().checked_add(rhs) {
+                    Some(value) => Some(Self::new(value)),
+                    None => None,
+                }
+            }
+
+            #[inline(always)]
+            pub const fn checked_sub(self, rhs: u64) -> Option<Self> {
+                match self.get().checked_sub(rhs) {
+                    Some(value) => Some(Self::new(value)),
+                    None => None,
+                }
+            }
+
+            #[inline(always)]
+            pub const fn saturating_add(self, rhs: u64) -> Self {
+                Self::new(self.get().saturating_add(rhs))
+            }
+
+            #[inline(always)]
+            pub const fn saturating_sub(self, rhs: u64) -> Self {
+                Self::new(self.get().saturating_sub(rhs))
+            }
+        }
+
+        impl From<u64> for $name {
+            #[inline(always)]
+            fn from(value: u64) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for u64 {
+            #[inline(always)]
+            fn from(value: $name) -> Self {
+                value.get()
+            }
+        }
+
+        impl PartialEq<u64> for $name {
+            #[inline(always)]
+            fn eq(&self, other: &u64) -> bool {
+                self.get() == *other
+            }
+        }
+
+        impl PartialOrd for $name {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            #[inline(always)]
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.get().cmp(&other.get())
+            }
+        }
+
+        impl PartialOrd<u64> for $name {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &u64) -> Option<Ordering> {
+                Some(self.get().cmp(other))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.get(), f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.get(), f)
+            }
+        }
+
+        /// A number in human-readable formats (JSON), the raw bytes in binary
+        /// ones so bincode still matches the account layout
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::state::serde_fields::le_u64::serialize(&self.0, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                crate::state::serde_fields::le_u64::deserialize(deserializer).map(Self)
+            }
+        }
+    };
+}
+
+le_u64! {
+    /// An epoch, stored as little-endian bytes
+    EpochLE
+}
+
+le_u64! {
+    /// A lamport amount (stake, reserves, cluster totals), stored as
+    /// little-endian bytes
+    LamportsLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_as_numbers() {
+        // 256 is [0, 1, ..] and 255 is [255, 0, ..]: the bytes order them wrongly
+        assert!(EpochLE::new(255).to_le_bytes() > EpochLE::new(256).to_le_bytes());
+        assert!(EpochLE::new(255) < EpochLE::new(256));
+        assert!(LamportsLE::new(1 << 8) > 255);
+        assert_eq!(EpochLE::MAX, u64::MAX);
+        assert_eq!(core::mem::align_of::<LamportsLE>(), 1);
+    }
+
+    #[test]
+    fn arithmetic_is_explicit_about_overflow() {
+        assert_eq!(LamportsLE::new(5).checked_add(7), Some(LamportsLE::new(12)));
+        assert_eq!(LamportsLE::MAX.checked_add(1), None);
+        assert_eq!(LamportsLE::ZERO.checked_sub(1), None);
+        assert_eq!(EpochLE::MAX.saturating_add(1), EpochLE::MAX);
+        assert_eq!(LamportsLE::new(3).saturating_sub(5), LamportsLE::ZERO);
+        assert_eq!(u64::from(EpochLE::from(42)), 42);
+    }
+}
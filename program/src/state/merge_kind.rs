@@ -1,18 +1,16 @@
-use pinocchio::{program_error::ProgramError, sysvars::clock::Clock, ProgramResult};
+use pinocchio::{program_error::ProgramError, pubkey::pubkey_eq, sysvars::clock::Clock, ProgramResult};
 use crate::error::{to_program_error, StakeError};
+use crate::helpers::log::log_error;
 
-use crate::helpers::{
-    bytes_to_u64,
-    checked_add,
-    constant::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
-};
-use crate::helpers::merge::merge_delegation_stake_and_credits_observed;
+use crate::helpers::{bytes_to_u64, checked_add};
+use crate::helpers::merge_engine::merge_delegation_stake_and_credits_observed;
 use crate::state::{
     delegation::Stake as DelegationStake,
     stake_flag::StakeFlags,
     stake_history::StakeHistoryGetEntry,
     stake_state_v2::StakeStateV2,
     state::Meta,
+    EpochLE, LamportsLE,
 };
 /// Classification of stake accounts for merge compatibility
 #[derive(Clone, Debug, PartialEq)]
@@ -46,87 +44,81 @@ impl MergeKind {
         }
     }
 
-    /// Classification helper
+    /// Classify an account for Merge/MoveStake/MoveLamports exactly as native
+    /// does, from the delegation's effective/activating/deactivating stake at
+    /// `clock.epoch`: all zero is `Inactive`, only activating is
+    /// `ActivationEpoch`, only effective is `FullyActive`, and anything else is
+    /// transient (`MergeTransientStake`).
     pub fn get_if_mergeable<T: StakeHistoryGetEntry>(
         stake_state: &StakeStateV2,
         stake_lamports: u64,
         clock: &Clock,
         stake_history: &T,
+        new_rate_activation_epoch: Option<EpochLE>,
     ) -> Result<Self, ProgramError> {
+        #[cfg(feature = "loose-merge")]
+        if let Some(kind) = Self::get_if_mergeable_loose(stake_state, stake_lamports, clock) {
+            return Ok(kind);
+        }
+
         match stake_state {
             StakeStateV2::Stake(meta, stake, flags) => {
-                // Fast path: if delegated > 0, no deactivation scheduled, and activation epoch reached,
-                // treat as FullyActive even if stake history can't inform effective/activating metrics.
-                let delegated    = crate::helpers::bytes_to_u64(stake.delegation.stake);
-                let act_epoch    = crate::helpers::bytes_to_u64(stake.delegation.activation_epoch);
-                let deact_epoch  = crate::helpers::bytes_to_u64(stake.delegation.deactivation_epoch);
-                // If a deactivation has been scheduled and we're at or before that epoch,
-                // this account is considered deactivating and not mergeable for move/merge.
-                if deact_epoch != u64::MAX {
-                    if clock.epoch <= deact_epoch {
-                        return Err(to_program_error(StakeError::MergeMismatch));
-                    } else {
-                    }
-                } else {
-                }
-                if delegated > 0 && deact_epoch == u64::MAX && clock.epoch > act_epoch {
-                    return Ok(Self::FullyActive(*meta, *stake));
-                }
                 let status = stake.delegation.stake_activating_and_deactivating(
-                    clock.epoch.to_le_bytes(),
+                    EpochLE::new(clock.epoch),
                     stake_history,
-                    crate::helpers::constant::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
+                    new_rate_activation_epoch,
                 );
-                let effective    = crate::helpers::bytes_to_u64(status.effective);
-                let activating   = crate::helpers::bytes_to_u64(status.activating);
-                let deactivating = crate::helpers::bytes_to_u64(status.deactivating);
-                // If any stake is deactivating, treat as not mergeable for move/merge ops
-                if deactivating > 0 {
-                    return Err(to_program_error(StakeError::MergeMismatch));
-                }
-
-                match (effective, activating, deactivating) {
-                    (0, 0, 0) => {
-                        // History yielded zeros; decide based on epochs.
-                        let deact_epoch = bytes_to_u64(stake.delegation.deactivation_epoch);
-                        if delegated > 0 && deact_epoch == u64::MAX {
-                            Ok(Self::FullyActive(*meta, *stake))
-                        } else {
-                            // Either no delegation, or delegation but fully deactivated in the past
-                            Ok(Self::Inactive(*meta, stake_lamports, *flags))
-                        }
-                    }
-                    (0, _, _) => {
-                        // Fallback: if activation is in the past and there's no deactivation scheduled,
-                        // but history doesn't report progress, consider it FullyActive for classification.
-                        let act_epoch = bytes_to_u64(stake.delegation.activation_epoch);
-                        let deact_epoch = bytes_to_u64(stake.delegation.deactivation_epoch);
-                        if delegated > 0 && deact_epoch == u64::MAX && clock.epoch > act_epoch {
-                            Ok(Self::FullyActive(*meta, *stake))
-                        } else {
-                            // Only classify as ActivationEpoch when truly activating (not deactivating)
-                            if activating > 0 {
-                                Ok(Self::ActivationEpoch(*meta, *stake, *flags))
-                            } else {
-                                Err(to_program_error(StakeError::MergeMismatch))
-                            }
-                        }
+                match (
+                    status.effective.get(),
+                    status.activating.get(),
+                    status.deactivating.get(),
+                ) {
+                    (0, 0, 0) => Ok(Self::Inactive(*meta, stake_lamports, *flags)),
+                    (0, _, _) => Ok(Self::ActivationEpoch(*meta, *stake, *flags)),
+                    (_, 0, 0) => Ok(Self::FullyActive(*meta, *stake)),
+                    _ => {
+                        log_error!("merge: stake is transient");
+                        Err(to_program_error(StakeError::MergeTransientStake))
                     }
-                    (_, 0, 0) if effective == delegated => Ok(Self::FullyActive(*meta, *stake)),
-                    _ => Err(to_program_error(StakeError::MergeMismatch)),
                 }
             }
             StakeStateV2::Initialized(meta) => {
-                Ok(Self::Inactive(*meta, stake_lamports, crate::state::stake_flag::StakeFlags::empty()))
+                Ok(Self::Inactive(*meta, stake_lamports, StakeFlags::empty()))
             }
-            _ => Err(to_program_error(StakeError::MergeMismatch)),
+            StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// `loose-merge` (test fixtures only): classify a non-deactivating
+    /// delegation from its epochs alone, so fixtures whose stake history never
+    /// records their delegation still merge. Transient or deactivating stake
+    /// falls through to the exact classification.
+    #[cfg(feature = "loose-merge")]
+    fn get_if_mergeable_loose(stake_state: &StakeStateV2, stake_lamports: u64, clock: &Clock) -> Option<Self> {
+        let StakeStateV2::Stake(meta, stake, flags) = stake_state else {
+            return None;
+        };
+        let delegated = stake.delegation.stake.get();
+        let activation_epoch = stake.delegation.activation_epoch.get();
+        let deactivation_epoch = stake.delegation.deactivation_epoch.get();
+        if deactivation_epoch != u64::MAX {
+            return None;
+        }
+        if delegated == 0 {
+            Some(Self::Inactive(*meta, stake_lamports, *flags))
+        } else if clock.epoch > activation_epoch {
+            Some(Self::FullyActive(*meta, *stake))
+        } else {
+            None
         }
     }
 
     /// Metadata compatibility check for merge
     pub fn metas_can_merge(dest: &Meta, source: &Meta, clock: &Clock) -> ProgramResult {
         // Authorities must match exactly
-        if dest.authorized != source.authorized {
+        if !pubkey_eq(&dest.authorized.staker, &source.authorized.staker)
+            || !pubkey_eq(&dest.authorized.withdrawer, &source.authorized.withdrawer)
+        {
             return Err(to_program_error(StakeError::MergeMismatch));
         }
 
@@ -147,11 +139,10 @@ impl MergeKind {
         dest: &crate::state::delegation::Delegation,
         source: &crate::state::delegation::Delegation,
     ) -> ProgramResult {
-        if dest.voter_pubkey != source.voter_pubkey {
+        if !pubkey_eq(&dest.voter_pubkey, &source.voter_pubkey) {
             return Err(to_program_error(StakeError::MergeMismatch));
         }
-        let max_epoch = u64::MAX.to_le_bytes();
-        if dest.deactivation_epoch == max_epoch && source.deactivation_epoch == max_epoch {
+        if dest.deactivation_epoch == EpochLE::MAX && source.deactivation_epoch == EpochLE::MAX {
             Ok(())
         } else {
             Err(to_program_error(StakeError::MergeMismatch))
@@ -184,8 +175,8 @@ impl MergeKind {
              Self::Inactive(_, src_lamports, src_flags)) =>
             {
                 let new_stake =
-                    checked_add(bytes_to_u64(stake.delegation.stake), src_lamports)?;
-                stake.delegation.stake = new_stake.to_le_bytes();
+                    checked_add(stake.delegation.stake.get(), src_lamports)?;
+                stake.delegation.stake = LamportsLE::new(new_stake);
 
                 let merged_flags = dst_flags.union(src_flags);
                 Some(StakeStateV2::Stake(meta, stake, merged_flags))
@@ -196,8 +187,8 @@ impl MergeKind {
              Self::ActivationEpoch(src_meta, src_stake, src_flags)) =>
             {
                 let src_stake_lamports = checked_add(
-                    bytes_to_u64(src_meta.rent_exempt_reserve),
-                    bytes_to_u64(src_stake.delegation.stake),
+                    src_meta.rent_exempt_reserve.get(),
+                    src_stake.delegation.stake.get(),
                 )?;
                 merge_delegation_stake_and_credits_observed(
                     &mut stake,
@@ -215,7 +206,7 @@ impl MergeKind {
             {
                 merge_delegation_stake_and_credits_observed(
                     &mut stake,
-                    bytes_to_u64(src_stake.delegation.stake),
+                    src_stake.delegation.stake.get(),
                     bytes_to_u64(src_stake.credits_observed),
                 )?;
                 Some(StakeStateV2::Stake(meta, stake, StakeFlags::empty()))
@@ -227,4 +218,133 @@ impl MergeKind {
 
         Ok(merged)
     }
-}   
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{delegation::Delegation, stake_history::StakeHistoryEntry};
+    use solana_sdk::{
+        pubkey::Pubkey as SdkPubkey,
+        stake::state::Delegation as NativeDelegation,
+        stake_history::{StakeHistory as NativeHistory, StakeHistoryEntry as NativeEntry},
+    };
+
+    /// Both sides read the same cluster history
+    struct History(NativeHistory);
+
+    impl StakeHistoryGetEntry for History {
+        fn get_entry(&self, epoch: u64) -> Option<StakeHistoryEntry> {
+            self.0.get(epoch).map(|e| StakeHistoryEntry {
+                effective: LamportsLE::new(e.effective),
+                activating: LamportsLE::new(e.activating),
+                deactivating: LamportsLE::new(e.deactivating),
+            })
+        }
+    }
+
+    /// A cluster that keeps warming and cooling, so a delegation takes several
+    /// epochs to activate or deactivate
+    fn history() -> History {
+        let mut history = NativeHistory::default();
+        for epoch in 0..32 {
+            history.add(epoch, NativeEntry { effective: 1_000_000, activating: 500_000, deactivating: 400_000 });
+        }
+        History(history)
+    }
+
+    fn clock(epoch: u64) -> Clock {
+        Clock { slot: 0, epoch_start_timestamp: 0, epoch, leader_schedule_epoch: epoch, unix_timestamp: 0 }
+    }
+
+    const NEW_RATE: Option<EpochLE> = crate::helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH;
+
+    /// Native `MergeKind::get_if_mergeable` on native activation math:
+    /// 0 = Inactive, 1 = ActivationEpoch, 2 = FullyActive
+    fn native_kind(delegation: &NativeDelegation, epoch: u64, history: &History) -> Result<u8, ProgramError> {
+        let status = delegation.stake_activating_and_deactivating(epoch, &history.0, Some(0));
+        match (status.effective, status.activating, status.deactivating) {
+            (0, 0, 0) => Ok(0),
+            (0, _, _) => Ok(1),
+            (_, 0, 0) => Ok(2),
+            _ => Err(to_program_error(StakeError::MergeTransientStake)),
+        }
+    }
+
+    fn kind(stake_amount: u64, activation_epoch: u64, deactivation_epoch: u64, epoch: u64, history: &History) -> Result<u8, ProgramError> {
+        let mut delegation = Delegation::new(&[7; 32], stake_amount, EpochLE::new(activation_epoch));
+        delegation.deactivation_epoch = EpochLE::new(deactivation_epoch);
+        let state = StakeStateV2::Stake(
+            Meta::default(),
+            DelegationStake { delegation, credits_observed: [0; 8] },
+            StakeFlags::empty(),
+        );
+        MergeKind::get_if_mergeable(&state, stake_amount, &clock(epoch), history, NEW_RATE).map(|kind| match kind {
+            MergeKind::Inactive(..) => 0,
+            MergeKind::ActivationEpoch(..) => 1,
+            MergeKind::FullyActive(..) => 2,
+        })
+    }
+
+    #[test]
+    #[cfg_attr(feature = "loose-merge", ignore = "loose-merge bypasses stake history for active delegations")]
+    fn matches_native_across_activation_epochs() {
+        let history = history();
+        let (mut transient, mut activating) = (0, 0);
+        for stake_amount in [0, 1, 300_000, 5_000_000] {
+            for activation_epoch in (0..12).chain([u64::MAX]) {
+                let deactivations = (activation_epoch.min(12)..16).chain([u64::MAX]);
+                for deactivation_epoch in deactivations {
+                    for epoch in 0..24 {
+                        let native = NativeDelegation {
+                            voter_pubkey: SdkPubkey::new_from_array([7; 32]),
+                            stake: stake_amount,
+                            activation_epoch,
+                            deactivation_epoch,
+                            ..NativeDelegation::default()
+                        };
+                        let expected = native_kind(&native, epoch, &history);
+                        let actual = kind(stake_amount, activation_epoch, deactivation_epoch, epoch, &history);
+                        assert_eq!(
+                            actual, expected,
+                            "stake {stake_amount}, activation {activation_epoch}, deactivation {deactivation_epoch}, epoch {epoch}"
+                        );
+                        transient += expected.is_err() as u32;
+                        activating += (expected == Ok(1)) as u32;
+                    }
+                }
+            }
+        }
+        // the sweep must reach the transient and activation-epoch cases, not only the easy ones
+        assert!(transient > 0 && activating > 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "loose-merge", ignore = "loose-merge bypasses stake history for active delegations")]
+    fn partially_warmed_stake_is_transient() {
+        // the old fallback treated this as FullyActive because activation was in the past
+        let history = history();
+        assert_eq!(kind(300_000, 3, u64::MAX, 3, &history), Ok(1));
+        assert_eq!(
+            kind(300_000, 3, u64::MAX, 5, &history),
+            Err(to_program_error(StakeError::MergeTransientStake))
+        );
+    }
+
+    #[test]
+    fn initialized_is_inactive_and_others_are_invalid() {
+        let history = history();
+        let lamports = 42;
+        assert_eq!(
+            MergeKind::get_if_mergeable(&StakeStateV2::Initialized(Meta::default()), lamports, &clock(1), &history, NEW_RATE),
+            Ok(MergeKind::Inactive(Meta::default(), lamports, StakeFlags::empty()))
+        );
+        for state in [StakeStateV2::Uninitialized, StakeStateV2::RewardsPool] {
+            assert_eq!(
+                MergeKind::get_if_mergeable(&state, lamports, &clock(1), &history, NEW_RATE),
+                Err(ProgramError::InvalidAccountData)
+            );
+        }
+    }
+}
+
@@ -0,0 +1,141 @@
+//! Layout versioning hook for stake account data.
+//!
+//! Every state load and store in `helpers::account_io` goes through
+//! [`ActiveMigration`]. Today that is [`NoMigration`], which reads and writes
+//! the native `StakeStateV2` layout unchanged. A future layout is rolled out by
+//! implementing [`StateMigration`] for it: `load` recognizes both the old and
+//! the new tags, `store` always writes the new one, so accounts upgrade the
+//! first time an instruction writes them and the program never has to reject
+//! an account sitting in the older layout.
+//!
+//! The `state-v3` feature swaps in [`StakeStateV3Preview`], a hypothetical V3
+//! layout, to exercise that upgrade path in tests. It changes the account
+//! encoding away from native's and must never be deployed.
+
+use pinocchio::{program_error::ProgramError, ProgramResult};
+
+use crate::state::StakeStateV2;
+
+pub trait StateMigration {
+    /// Decode account data in any layout this migration understands
+    fn load(data: &[u8]) -> Result<StakeStateV2, ProgramError> {
+        StakeStateV2::deserialize(data)
+    }
+
+    /// Encode `state` in the layout new writes use
+    fn store(state: &StakeStateV2, data: &mut [u8]) -> ProgramResult {
+        state.serialize(data)
+    }
+}
+
+/// The native layout, read and written as is
+pub struct NoMigration;
+
+impl StateMigration for NoMigration {}
+
+#[cfg(not(feature = "state-v3"))]
+pub type ActiveMigration = NoMigration;
+#[cfg(feature = "state-v3")]
+pub type ActiveMigration = StakeStateV3Preview;
+
+/// Hypothetical V3 layout: V2's bytes with the variant tag offset by
+/// [`StakeStateV3Preview::TAG_BASE`]. Uninitialized stays tag 0 so zeroed
+/// accounts keep reading as Uninitialized.
+#[cfg(feature = "state-v3")]
+pub struct StakeStateV3Preview;
+
+#[cfg(feature = "state-v3")]
+impl StakeStateV3Preview {
+    pub const TAG_BASE: u32 = 0x100;
+
+    fn v2_tag(tag: u32) -> Option<u32> {
+        match tag {
+            0..=3 => Some(tag),
+            t if t > Self::TAG_BASE && t <= Self::TAG_BASE + 3 => Some(t - Self::TAG_BASE),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "state-v3")]
+impl StateMigration for StakeStateV3Preview {
+    fn load(data: &[u8]) -> Result<StakeStateV2, ProgramError> {
+        let tag_bytes = data.get(..StakeStateV2::TAG_LEN).ok_or(ProgramError::InvalidAccountData)?;
+        let tag = u32::from_le_bytes(tag_bytes.try_into().unwrap());
+        let v2_tag = Self::v2_tag(tag).ok_or(ProgramError::InvalidAccountData)?;
+        if v2_tag == tag {
+            return StakeStateV2::deserialize(data);
+        }
+        // V3 bodies are V2's, so decode a copy carrying the V2 tag
+        let mut v2 = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        let len = data.len().min(v2.len());
+        v2[..len].copy_from_slice(&data[..len]);
+        v2[..StakeStateV2::TAG_LEN].copy_from_slice(&v2_tag.to_le_bytes());
+        StakeStateV2::deserialize(&v2[..len])
+    }
+
+    fn store(state: &StakeStateV2, data: &mut [u8]) -> ProgramResult {
+        state.serialize(data)?;
+        if !matches!(state, StakeStateV2::Uninitialized) {
+            let tag = u32::from_le_bytes(data[..StakeStateV2::TAG_LEN].try_into().unwrap());
+            data[..StakeStateV2::TAG_LEN].copy_from_slice(&(tag + Self::TAG_BASE).to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Authorized, LamportsLE, Meta};
+
+    fn initialized() -> StakeStateV2 {
+        StakeStateV2::Initialized(Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            ..Meta::default()
+        })
+    }
+
+    #[test]
+    fn no_migration_keeps_the_native_layout() {
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        NoMigration::store(&initialized(), &mut data).unwrap();
+        let mut native = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        initialized().serialize(&mut native).unwrap();
+        assert_eq!(data, native);
+        assert_eq!(NoMigration::load(&data).unwrap(), initialized());
+
+        // a later layout's tag is not guessed at
+        data[..4].copy_from_slice(&0x101u32.to_le_bytes());
+        assert_eq!(NoMigration::load(&data), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[cfg(feature = "state-v3")]
+    #[test]
+    fn v2_accounts_upgrade_to_v3_on_write() {
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        initialized().serialize(&mut data).unwrap();
+        // an untouched V2 account still loads
+        assert_eq!(StakeStateV3Preview::load(&data).unwrap(), initialized());
+
+        let state = StakeStateV3Preview::load(&data).unwrap();
+        StakeStateV3Preview::store(&state, &mut data).unwrap();
+        assert_eq!(data[..4], 0x101u32.to_le_bytes());
+        assert_eq!(StakeStateV3Preview::load(&data).unwrap(), initialized());
+        // the V2 reader cannot parse it any more
+        assert!(StakeStateV2::deserialize(&data).is_err());
+    }
+
+    #[cfg(feature = "state-v3")]
+    #[test]
+    fn v3_keeps_zeroed_accounts_uninitialized() {
+        let mut data = [0xAAu8; StakeStateV2::ACCOUNT_SIZE];
+        StakeStateV3Preview::store(&StakeStateV2::Uninitialized, &mut data).unwrap();
+        assert_eq!(data, [0u8; StakeStateV2::ACCOUNT_SIZE]);
+        assert_eq!(StakeStateV3Preview::load(&data).unwrap(), StakeStateV2::Uninitialized);
+
+        data[..4].copy_from_slice(&StakeStateV3Preview::TAG_BASE.to_le_bytes());
+        assert_eq!(StakeStateV3Preview::load(&data), Err(ProgramError::InvalidAccountData));
+    }
+}
@@ -1,19 +1,29 @@
 pub mod accounts;
+pub mod canonical;
 
 pub mod delegation;
+pub mod layout;
+pub mod le;
 pub mod merge_kind;
+pub mod migration;
+#[cfg(feature = "serde")]
+pub mod serde_fields;
 pub mod stake;
 pub mod stake_flag;
 pub mod stake_history;
 pub mod stake_state_v2;
+#[allow(clippy::module_inception)]
 pub mod state;
 pub mod vote_state;
 
 pub use accounts::*;
 
 pub use delegation::*;
+pub use le::*;
 pub use merge_kind::*;
 pub use stake_flag::*;
+// Both sysvar modules declare `ID`/`id`/`check_id`; reach them by module path.
+#[allow(ambiguous_glob_reexports)]
 pub use stake_history::*;
 pub use stake_state_v2::*;
 pub use state::*;
@@ -0,0 +1,96 @@
+//! `serde(with = ...)` adapters for the raw account fields.
+//!
+//! The state types keep pubkeys as `[u8; 32]` and most integers as
+//! little-endian `[u8; 8]` so they can be cast straight from account data.
+//! Human-readable formats (JSON) get base58 strings and plain numbers instead;
+//! binary formats keep the raw arrays, so bincode still matches the account layout.
+
+extern crate alloc;
+use alloc::string::String;
+
+use pinocchio::pubkey::Pubkey;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// `Pubkey` as a base58 string
+pub mod pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&bs58::encode(key).into_string())
+        } else {
+            key.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Pubkey::deserialize(deserializer);
+        }
+        let encoded = String::deserialize(deserializer)?;
+        let mut key = Pubkey::default();
+        match bs58::decode(&encoded).onto(&mut key) {
+            Ok(32) => Ok(key),
+            _ => Err(D::Error::custom("invalid base58 pubkey")),
+        }
+    }
+}
+
+/// `Option<Pubkey>` as a base58 string or null
+pub mod option_pubkey {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct Wrapped(#[serde(with = "super::pubkey")] Pubkey);
+
+    pub fn serialize<S: Serializer>(key: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        key.map(Wrapped).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pubkey>, D::Error> {
+        Ok(Option::<Wrapped>::deserialize(deserializer)?.map(|Wrapped(key)| key))
+    }
+}
+
+/// Little-endian `[u8; 8]` as a `u64`
+pub mod le_u64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_u64(u64::from_le_bytes(*bytes))
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 8], D::Error> {
+        if deserializer.is_human_readable() {
+            u64::deserialize(deserializer).map(u64::to_le_bytes)
+        } else {
+            <[u8; 8]>::deserialize(deserializer)
+        }
+    }
+}
+
+/// Little-endian `[u8; 8]` holding `f64` bits, as an `f64`
+pub mod le_f64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_f64(f64::from_le_bytes(*bytes))
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 8], D::Error> {
+        if deserializer.is_human_readable() {
+            f64::deserialize(deserializer).map(f64::to_le_bytes)
+        } else {
+            <[u8; 8]>::deserialize(deserializer)
+        }
+    }
+}
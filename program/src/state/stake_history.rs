@@ -1,5 +1,6 @@
-use crate::helpers::get_sysvar;
-use core::mem::size_of;
+use crate::helpers::{get_sysvar, log::log_debug};
+use crate::state::LamportsLE;
+use core::{cell::RefCell, mem::size_of};
 use pinocchio::sysvars::clock::Epoch;
 
 // Stake History sysvar id on Solana
@@ -13,15 +14,24 @@ pub const MAX_STAKE_HISTORY_ENTRIES: usize = 512;
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
 pub struct StakeHistoryEntry {
-    pub effective: [u8; 8],    // effective stake at this epoch
-    pub activating: [u8; 8],   // sum of portion of stakes not fully warmed up
-    pub deactivating: [u8; 8], // requested to be cooled down, not fully deactivated yet
+    pub effective: LamportsLE,    // effective stake at this epoch
+    pub activating: LamportsLE,   // sum of portion of stakes not fully warmed up
+    pub deactivating: LamportsLE, // requested to be cooled down, not fully deactivated yet
 }
 
+/// Source of stake history entries. Handlers build a [`StakeHistorySysvar`] at
+/// the entrypoint and pass it down as `&T`, so the activation math can also run
+/// against fixture-backed histories (e.g. `client::StakeHistorySnapshot`).
 pub trait StakeHistoryGetEntry {
     fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry>;
 }
 
+impl<T: StakeHistoryGetEntry + ?Sized> StakeHistoryGetEntry for &T {
+    fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        (**self).get_entry(epoch)
+    }
+}
+
 #[macro_export]
 macro_rules! impl_sysvar_id {
     ($type:ty) => {
@@ -51,23 +61,23 @@ impl StakeHistoryEntry {
     }
     pub fn with_effective(effective: u64) -> Self {
         Self {
-            effective: effective.to_le_bytes(),
+            effective: LamportsLE::new(effective),
             ..Self::default()
         }
     }
 
     pub fn with_effective_and_activating(effective: u64, activating: u64) -> Self {
         Self {
-            effective: effective.to_le_bytes(),
-            activating: activating.to_le_bytes(),
+            effective: LamportsLE::new(effective),
+            activating: LamportsLE::new(activating),
             ..Self::default()
         }
     }
 
     pub fn with_deactivating(deactivating: u64) -> Self {
         Self {
-            effective: deactivating.to_le_bytes(),
-            deactivating: deactivating.to_le_bytes(),
+            effective: LamportsLE::new(deactivating),
+            deactivating: LamportsLE::new(deactivating),
             ..Self::default()
         }
     }
@@ -83,13 +93,19 @@ pub struct StakeHistory {
     pub len: usize,
 }
 
+impl Default for StakeHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StakeHistory {
     pub fn new() -> Self {
         Self {
             entries: core::array::from_fn(|_| StakeHistoryEntry {
-                effective: [0u8; 8],
-                activating: [0u8; 8],
-                deactivating: [0u8; 8],
+                effective: LamportsLE::ZERO,
+                activating: LamportsLE::ZERO,
+                deactivating: LamportsLE::ZERO,
             }),
             len: 0,
         }
@@ -117,51 +133,166 @@ impl StakeHistory {
 }
 const EPOCH_AND_ENTRY_SERIALIZED_SIZE: u64 = 32;
 
+/// Entries a [`WindowedStakeHistory`] fetches per `sol_get_sysvar` call
+pub const STAKE_HISTORY_FETCH_WINDOW: usize = 8;
+
+/// Byte range of the sysvar holding `target_epoch` and up to `window - 1`
+/// newer entries, as (offset, entry count). The sysvar is a `Vec` sorted
+/// newest first, so the newer entries sit just before the target.
+///
+/// None when the target is current, in the future, or fell off history.
+pub fn stake_history_window(current_epoch: Epoch, target_epoch: Epoch, window: usize) -> Option<(u64, u64)> {
+    // if current epoch is zero this returns None because there is no history yet
+    let newest_historical_epoch = current_epoch.checked_sub(1)?;
+    let oldest_historical_epoch = current_epoch.saturating_sub(MAX_STAKE_HISTORY_ENTRIES as u64);
+
+    // target epoch is old enough to have fallen off history; presume fully active/deactive
+    if target_epoch < oldest_historical_epoch {
+        return None;
+    }
+
+    // epoch delta is how many epoch-entries we offset in the stake history vector, which may be zero
+    // None means target epoch is current or in the future; this is a user error
+    let epoch_delta = newest_historical_epoch.checked_sub(target_epoch)?;
+    let first = epoch_delta.saturating_sub((window as u64).saturating_sub(1));
+
+    // offset is the number of bytes to the first entry, including eight for vector length
+    let offset = first
+        .checked_mul(EPOCH_AND_ENTRY_SERIALIZED_SIZE)?
+        .checked_add(core::mem::size_of::<u64>() as u64)?;
+    Some((offset, epoch_delta - first + 1))
+}
+
+/// Binary search serialized `(epoch, entry)` records sorted newest first, the
+/// sysvar's own layout, for `epoch`
+pub fn search_stake_history(data: &[u8], epoch: Epoch) -> Option<StakeHistoryEntry> {
+    let mut lo = 0;
+    let mut hi = data.len() / EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let record = &data[mid * EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize..][..EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize];
+        let record_epoch = u64::from_le_bytes(record[0..8].try_into().unwrap());
+        match record_epoch.cmp(&epoch) {
+            core::cmp::Ordering::Equal => return Some(decode_entry(record)),
+            // newest first: a newer record means the target is further along
+            core::cmp::Ordering::Greater => lo = mid + 1,
+            core::cmp::Ordering::Less => hi = mid,
+        }
+    }
+    None
+}
+
+fn decode_entry(record: &[u8]) -> StakeHistoryEntry {
+    StakeHistoryEntry {
+        effective: LamportsLE::from_le_bytes(record[8..16].try_into().unwrap()),
+        activating: LamportsLE::from_le_bytes(record[16..24].try_into().unwrap()),
+        deactivating: LamportsLE::from_le_bytes(record[24..32].try_into().unwrap()),
+    }
+}
+
+/// Fetch `target_epoch` and its newer neighbours into `buf` with one syscall,
+/// returning the bytes read
+fn fetch_window(buf: &mut [u8], current_epoch: Epoch, target_epoch: Epoch) -> Option<&[u8]> {
+    let window = buf.len() / EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize;
+    let (offset, count) = stake_history_window(current_epoch, target_epoch, window)?;
+    let len = count * EPOCH_AND_ENTRY_SERIALIZED_SIZE;
+    let buf = &mut buf[..len as usize];
+    log_debug!("stake_history: fetch window");
+    // Use this module's Sysvar ID (not the program ID)
+    get_sysvar(buf, &ID, offset, len).ok()?;
+
+    // the target is the window's oldest record; this would only fail if stake
+    // history skipped an epoch or the binary format of the sysvar changed
+    let target = &buf[buf.len() - EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize..];
+    assert_eq!(u64::from_le_bytes(target[0..8].try_into().unwrap()), target_epoch);
+    Some(buf)
+}
+
 impl StakeHistoryGetEntry for StakeHistorySysvar {
     fn get_entry(&self, target_epoch: Epoch) -> Option<StakeHistoryEntry> {
-        let current_epoch = self.0;
+        let mut entry_buf = [0; EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize];
+        fetch_window(&mut entry_buf, self.0, target_epoch).map(decode_entry)
+    }
+}
 
-        // if current epoch is zero this returns None because there is no history yet
-        let newest_historical_epoch = current_epoch.checked_sub(1)?;
-        let oldest_historical_epoch =
-            current_epoch.saturating_sub(MAX_STAKE_HISTORY_ENTRIES as u64);
+/// Stake history read `N` entries per syscall.
+///
+/// The activation math walks consecutive epochs upward from an activation or
+/// deactivation epoch, so each fetch takes the target plus the `N - 1` epochs
+/// after it and later lookups are answered from that window until one falls
+/// outside it. A warmup spanning `k` epochs costs `ceil(k / N)` syscalls
+/// instead of `k`, each a little over the ~100 CU base cost.
+pub struct StakeHistoryWindow<const N: usize> {
+    current_epoch: Epoch,
+    cache: RefCell<([[u8; EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize]; N], usize)>,
+}
 
-        // target epoch is old enough to have fallen off history; presume fully active/deactive
-        if target_epoch < oldest_historical_epoch {
-            return None;
+/// The window handlers read stake history through
+pub type WindowedStakeHistory = StakeHistoryWindow<STAKE_HISTORY_FETCH_WINDOW>;
+
+impl<const N: usize> StakeHistoryWindow<N> {
+    pub const fn new(current_epoch: Epoch) -> Self {
+        Self { current_epoch, cache: RefCell::new(([[0; EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize]; N], 0)) }
+    }
+}
+
+impl<const N: usize> StakeHistoryGetEntry for StakeHistoryWindow<N> {
+    fn get_entry(&self, target_epoch: Epoch) -> Option<StakeHistoryEntry> {
+        let mut cache = self.cache.borrow_mut();
+        let (records, len) = &mut *cache;
+        let cached = &records.as_flattened()[..*len];
+        if let Some(entry) = search_stake_history(cached, target_epoch) {
+            return Some(entry);
         }
+        let fetched = fetch_window(records.as_flattened_mut(), self.current_epoch, target_epoch);
+        *len = fetched.map_or(0, <[u8]>::len);
+        fetched.map(|window| decode_entry(&window[window.len() - EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize..]))
+    }
+}
 
-        // epoch delta is how many epoch-entries we offset in the stake history vector, which may be zero
-        // None means target epoch is current or in the future; this is a user error
-        let epoch_delta = newest_historical_epoch.checked_sub(target_epoch)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // offset is the number of bytes to our desired entry, including eight for vector length
-        let offset = epoch_delta
-            .checked_mul(EPOCH_AND_ENTRY_SERIALIZED_SIZE)?
-            .checked_add(core::mem::size_of::<u64>() as u64)?;
+    /// Sysvar bytes (without the length prefix) for epochs `newest` down to 0
+    fn records(newest: Epoch) -> ([u8; 32 * 20], usize) {
+        let mut data = [0u8; 32 * 20];
+        for (i, epoch) in (0..=newest).rev().enumerate() {
+            let record = &mut data[i * 32..][..32];
+            record[0..8].copy_from_slice(&epoch.to_le_bytes());
+            record[8..16].copy_from_slice(&(epoch * 10).to_le_bytes());
+        }
+        (data, (newest as usize + 1) * 32)
+    }
 
-        let mut entry_buf = [0; EPOCH_AND_ENTRY_SERIALIZED_SIZE as usize];
-        // Use this module's Sysvar ID (not the program ID)
-        let result = get_sysvar(&mut entry_buf, &ID, offset, EPOCH_AND_ENTRY_SERIALIZED_SIZE);
-
-        match result {
-            Ok(()) => {
-                // All safe because `entry_buf` is a 32-length array
-                let entry_epoch = u64::from_le_bytes(entry_buf[0..8].try_into().unwrap());
-                let effective = u64::from_le_bytes(entry_buf[8..16].try_into().unwrap());
-                let activating = u64::from_le_bytes(entry_buf[16..24].try_into().unwrap());
-                let deactivating = u64::from_le_bytes(entry_buf[24..32].try_into().unwrap());
-
-                // this would only fail if stake history skipped an epoch or the binary format of the sysvar changed
-                assert_eq!(entry_epoch, target_epoch);
-
-                Some(StakeHistoryEntry {
-                    effective: effective.to_le_bytes(),
-                    activating: activating.to_le_bytes(),
-                    deactivating: deactivating.to_le_bytes(),
-                })
-            }
-            _ => None,
+    #[test]
+    fn window_covers_the_target_and_newer_epochs() {
+        // current epoch 10: entries 9 (offset 8) down to 0
+        assert_eq!(stake_history_window(10, 9, 1), Some((8, 1)));
+        assert_eq!(stake_history_window(10, 2, 1), Some((8 + 7 * 32, 1)));
+        // epochs 2..=5, ending with the target
+        assert_eq!(stake_history_window(10, 2, 4), Some((8 + 4 * 32, 4)));
+        // clipped at the newest entry
+        assert_eq!(stake_history_window(10, 8, 4), Some((8, 2)));
+
+        assert_eq!(stake_history_window(0, 0, 4), None);
+        assert_eq!(stake_history_window(10, 10, 4), None);
+        assert_eq!(stake_history_window(600, 600 - 513, 4), None);
+        assert_eq!(stake_history_window(600, 600 - 512, 4), Some((8 + 508 * 32, 4)));
+    }
+
+    #[test]
+    fn binary_search_finds_every_epoch() {
+        let (data, len) = records(12);
+        for epoch in 0..=12 {
+            assert_eq!(search_stake_history(&data[..len], epoch), Some(StakeHistoryEntry::with_effective(epoch * 10)));
         }
+        assert_eq!(search_stake_history(&data[..len], 13), None);
+        assert_eq!(search_stake_history(&[], 0), None);
+
+        // a window from the middle of the sysvar, with gaps around it
+        assert_eq!(search_stake_history(&data[3 * 32..6 * 32], 8), Some(StakeHistoryEntry::with_effective(80)));
+        assert_eq!(search_stake_history(&data[3 * 32..6 * 32], 10), None);
+        assert_eq!(search_stake_history(&data[3 * 32..6 * 32], 6), None);
     }
 }
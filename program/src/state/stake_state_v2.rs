@@ -1,13 +1,14 @@
-use crate::state::delegation::Stake;
+use crate::state::accounts::Authorized;
+use crate::state::delegation::{Delegation, Stake};
 use crate::state::stake_flag::StakeFlags;
-use crate::state::state::Meta;
+use crate::state::state::{Lockup, Meta};
+use crate::state::{EpochLE, LamportsLE};
 
-use crate::ID;
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
+use pinocchio::program_error::ProgramError;
 
 #[repr(C)]
 #[derive(Debug, PartialEq, Clone, Copy)]
-
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StakeStateV2 {
     Uninitialized,
     Initialized(Meta),
@@ -16,7 +17,19 @@ pub enum StakeStateV2 {
 }
 
 impl StakeStateV2 {
-    pub const ACCOUNT_SIZE: usize = core::mem::size_of::<Self>();
+    /// Serialized size of a stake account, identical to native
+    /// `StakeStateV2::size_of()` (bincode layout padded to 200 bytes).
+    pub const ACCOUNT_SIZE: usize = 200;
+
+    /// bincode writes the enum variant index as a little-endian u32
+    pub const TAG_LEN: usize = 4;
+    /// Serialized Meta: reserve, staker, withdrawer, lockup timestamp, epoch, custodian
+    const META_LEN: usize = 8 + 32 + 32 + 8 + 8 + 32;
+    /// Serialized Stake: voter, stake, activation, deactivation, warmup rate, credits
+    const STAKE_LEN: usize = 32 + 8 + 8 + 8 + 8 + 8;
+    pub const META_OFFSET: usize = Self::TAG_LEN;
+    pub const STAKE_OFFSET: usize = Self::META_OFFSET + Self::META_LEN;
+    pub const FLAGS_OFFSET: usize = Self::STAKE_OFFSET + Self::STAKE_LEN;
 
     /// The fixed number of bytes used to serialize each stake account
     pub const fn size_of() -> usize {
@@ -24,26 +37,25 @@ impl StakeStateV2 {
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, ProgramError> {
-        if data.is_empty() {
+        if data.len() < Self::TAG_LEN {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        let discriminant = data[0];
+        let discriminant = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
 
         match discriminant {
             0 => Ok(StakeStateV2::Uninitialized),
             1 => {
-                let meta = Self::deserialize_meta(&data[1..])?;
+                let meta = Self::deserialize_meta(&data[Self::META_OFFSET..])?;
                 Ok(StakeStateV2::Initialized(meta))
             }
             2 => {
-                let meta = Self::deserialize_meta(&data[1..])?;
-                let stake = Self::deserialize_stake(&data[1 + core::mem::size_of::<Meta>()..])?;
+                let meta = Self::deserialize_meta(&data[Self::META_OFFSET..])?;
+                let stake = Self::deserialize_stake(&data[Self::STAKE_OFFSET..])?;
 
-                let flags_offset = 1 + core::mem::size_of::<Meta>() + core::mem::size_of::<Stake>();
-                let stake_flags = if data.len() > flags_offset && data[flags_offset] != 0 {
+                let stake_flags = if data.len() > Self::FLAGS_OFFSET {
                     StakeFlags {
-                        bits: data[flags_offset],
+                        bits: data[Self::FLAGS_OFFSET],
                     }
                 } else {
                     StakeFlags::empty()
@@ -56,6 +68,11 @@ impl StakeStateV2 {
         }
     }
 
+    /// Write the state in native's layout. The byte image is a pure function of
+    /// the state: the whole account is zeroed first and every field is written
+    /// explicitly as little-endian bytes, so no struct padding or prior
+    /// contents reach the account and equal states always hash equally (as the
+    /// `integrity` checksum and `trace-state` digests rely on).
     pub fn serialize(&self, data: &mut [u8]) -> Result<(), ProgramError> {
         if data.len() < Self::ACCOUNT_SIZE {
             return Err(ProgramError::AccountDataTooSmall);
@@ -63,98 +80,168 @@ impl StakeStateV2 {
 
         data.iter_mut().for_each(|byte| *byte = 0);
 
-        match self {
-            StakeStateV2::Uninitialized => {
-                data[0] = 0;
-            }
+        let tag: u32 = match self {
+            StakeStateV2::Uninitialized => 0,
             StakeStateV2::Initialized(meta) => {
-                data[0] = 1;
-                Self::serialize_meta(meta, &mut data[1..])?;
+                Self::serialize_meta(meta, &mut data[Self::META_OFFSET..])?;
+                1
             }
             StakeStateV2::Stake(meta, stake, stake_flags) => {
-                data[0] = 2;
-                Self::serialize_meta(meta, &mut data[1..])?;
-                Self::serialize_stake(stake, &mut data[1 + core::mem::size_of::<Meta>()..])?;
-
-                let flags_offset = 1 + core::mem::size_of::<Meta>() + core::mem::size_of::<Stake>();
-                data[flags_offset] = stake_flags.bits;
-            }
-            StakeStateV2::RewardsPool => {
-                data[0] = 3;
+                Self::serialize_meta(meta, &mut data[Self::META_OFFSET..])?;
+                Self::serialize_stake(stake, &mut data[Self::STAKE_OFFSET..])?;
+                data[Self::FLAGS_OFFSET] = stake_flags.bits;
+                2
             }
-        }
+            StakeStateV2::RewardsPool => 3,
+        };
+        data[..Self::TAG_LEN].copy_from_slice(&tag.to_le_bytes());
 
         Ok(())
     }
 
     fn deserialize_meta(data: &[u8]) -> Result<Meta, ProgramError> {
-        if data.len() < core::mem::size_of::<Meta>() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let meta = unsafe { core::ptr::read_unaligned(data.as_ptr() as *const Meta) };
-
-        Ok(meta)
+        let mut fields = FieldReader::new(data, Self::META_LEN).ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Meta {
+            rent_exempt_reserve: LamportsLE::from_le_bytes(fields.take()),
+            authorized: Authorized { staker: fields.take(), withdrawer: fields.take() },
+            lockup: Lockup {
+                unix_timestamp: i64::from_le_bytes(fields.take()),
+                epoch: u64::from_le_bytes(fields.take()),
+                custodian: fields.take(),
+            },
+        })
     }
 
     fn serialize_meta(meta: &Meta, data: &mut [u8]) -> Result<(), ProgramError> {
-        if data.len() < core::mem::size_of::<Meta>() {
-            return Err(ProgramError::AccountDataTooSmall);
-        }
-        unsafe { core::ptr::write_unaligned(data.as_mut_ptr() as *mut Meta, meta.clone()) };
-
+        let mut fields = FieldWriter::new(data, Self::META_LEN).ok_or(ProgramError::AccountDataTooSmall)?;
+        fields.put(&meta.rent_exempt_reserve.to_le_bytes());
+        fields.put(&meta.authorized.staker);
+        fields.put(&meta.authorized.withdrawer);
+        fields.put(&meta.lockup.unix_timestamp.to_le_bytes());
+        fields.put(&meta.lockup.epoch.to_le_bytes());
+        fields.put(&meta.lockup.custodian);
         Ok(())
     }
 
+    #[allow(deprecated)]
     fn deserialize_stake(data: &[u8]) -> Result<Stake, ProgramError> {
-        if data.len() < core::mem::size_of::<Stake>() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let stake = unsafe { core::ptr::read_unaligned(data.as_ptr() as *const Stake) };
-
-        Ok(stake)
+        let mut fields = FieldReader::new(data, Self::STAKE_LEN).ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Stake {
+            delegation: Delegation {
+                voter_pubkey: fields.take(),
+                stake: LamportsLE::from_le_bytes(fields.take()),
+                activation_epoch: EpochLE::from_le_bytes(fields.take()),
+                deactivation_epoch: EpochLE::from_le_bytes(fields.take()),
+                warmup_cooldown_rate: fields.take(),
+            },
+            credits_observed: fields.take(),
+        })
     }
 
+    #[allow(deprecated)]
     fn serialize_stake(stake: &Stake, data: &mut [u8]) -> Result<(), ProgramError> {
-        if data.len() < core::mem::size_of::<Stake>() {
-            return Err(ProgramError::AccountDataTooSmall);
-        }
-        unsafe {
-            core::ptr::write_unaligned(data.as_mut_ptr() as *mut Stake, stake.clone());
-        }
-
+        let mut fields = FieldWriter::new(data, Self::STAKE_LEN).ok_or(ProgramError::AccountDataTooSmall)?;
+        let delegation = stake.delegation;
+        fields.put(&delegation.voter_pubkey);
+        fields.put(&delegation.stake.to_le_bytes());
+        fields.put(&delegation.activation_epoch.to_le_bytes());
+        fields.put(&delegation.deactivation_epoch.to_le_bytes());
+        fields.put(&delegation.warmup_cooldown_rate);
+        fields.put(&stake.credits_observed);
         Ok(())
     }
-    #[inline]
-    pub fn try_from_account_info_mut_raw(
-        account_info: &AccountInfo,
-    ) -> Result<&mut Self, ProgramError> {
-        let expected_size = Self::size_of();
-        let data = account_info.try_borrow_mut_data()?; //  returns RefMut<[u8]>
+}
 
-        if data.len() != expected_size {
-            return Err(ProgramError::InvalidAccountData);
-        }
+// the in-memory structs have no padding either, so `Meta::size()` and friends
+// agree with the serialized lengths
+const _: () = assert!(core::mem::size_of::<Meta>() == StakeStateV2::META_LEN);
+const _: () = assert!(core::mem::size_of::<Stake>() == StakeStateV2::STAKE_LEN);
 
-        let ptr = data.as_ptr() as usize;
-        if ptr % core::mem::align_of::<Self>() != 0 {
-            return Err(ProgramError::InvalidAccountData); // misaligned
-        }
+/// Sequential field writes over exactly `len` bytes of account data
+struct FieldWriter<'a> {
+    data: &'a mut [u8],
+    at: usize,
+}
+
+impl<'a> FieldWriter<'a> {
+    fn new(data: &'a mut [u8], len: usize) -> Option<Self> {
+        Some(Self { data: data.get_mut(..len)?, at: 0 })
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.data[self.at..self.at + bytes.len()].copy_from_slice(bytes);
+        self.at += bytes.len();
+    }
+}
+
+impl Drop for FieldWriter<'_> {
+    fn drop(&mut self) {
+        // every byte of the range is a field: nothing is left to chance
+        debug_assert_eq!(self.at, self.data.len(), "fields do not cover the layout");
+    }
+}
+
+/// Sequential field reads over exactly `len` bytes of account data
+struct FieldReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
 
-        let ptr = data.as_ptr() as *mut Self;
-        // SAFETY:
-        // - `data` is mutable and of correct length
-        // - Alignment has been checked
-        // - Memory is assumed to contain a valid StakeStateV2
-        Ok(unsafe { &mut *ptr })
+impl<'a> FieldReader<'a> {
+    fn new(data: &'a [u8], len: usize) -> Option<Self> {
+        Some(Self { data: data.get(..len)?, at: 0 })
     }
 
-    pub fn get_stake_state(
-        stake_account_info: &AccountInfo,
-    ) -> Result<&mut StakeStateV2, ProgramError> {
-        if *stake_account_info.owner() != ID {
-            return Err(ProgramError::InvalidAccountOwner);
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let bytes = self.data[self.at..self.at + N].try_into().unwrap();
+        self.at += N;
+        bytes
+    }
+}
+
+/// Borsh encoding with the variant index as a little-endian u32, as bincode
+/// (and native's own borsh impl) writes it, so the bytes equal the account data
+/// minus its zero padding. Decoding reads from the front: pass a whole account
+/// to `BorshDeserialize::deserialize(&mut data)`, since `try_from_slice` rejects
+/// the trailing padding. The inherent `serialize`/`deserialize` shadow the trait
+/// methods, so call these through the trait or `borsh::to_vec`.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for StakeStateV2 {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        match self {
+            StakeStateV2::Uninitialized => 0u32.serialize(writer),
+            StakeStateV2::Initialized(meta) => {
+                1u32.serialize(writer)?;
+                meta.serialize(writer)
+            }
+            StakeStateV2::Stake(meta, stake, stake_flags) => {
+                2u32.serialize(writer)?;
+                meta.serialize(writer)?;
+                stake.serialize(writer)?;
+                stake_flags.serialize(writer)
+            }
+            StakeStateV2::RewardsPool => 3u32.serialize(writer),
+        }
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for StakeStateV2 {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        match u32::deserialize_reader(reader)? {
+            0 => Ok(StakeStateV2::Uninitialized),
+            1 => Ok(StakeStateV2::Initialized(Meta::deserialize_reader(reader)?)),
+            2 => Ok(StakeStateV2::Stake(
+                Meta::deserialize_reader(reader)?,
+                Stake::deserialize_reader(reader)?,
+                StakeFlags::deserialize_reader(reader)?,
+            )),
+            3 => Ok(StakeStateV2::RewardsPool),
+            _ => Err(borsh::io::Error::new(
+                borsh::io::ErrorKind::InvalidData,
+                "invalid StakeStateV2 variant",
+            )),
         }
-        Self::try_from_account_info_mut_raw(stake_account_info)
     }
 }
 
@@ -172,10 +259,8 @@ mod tests {
         log!("Meta size: {}", Meta::size());
         log!("Stake size: {}", core::mem::size_of::<Stake>());
         log!("StakeFlags size: {}", core::mem::size_of::<StakeFlags>());
-        assert_eq!(
-            StakeStateV2::size_of(),
-            core::mem::size_of::<StakeStateV2>()
-        );
+        assert_eq!(StakeStateV2::size_of(), 200);
+        assert_eq!(StakeStateV2::FLAGS_OFFSET, 196);
     }
 
     // test Check alignment
@@ -203,4 +288,147 @@ mod tests {
             "Memory is not properly aligned for StakeStateV2"
         );
     }
+
+    #[test]
+    fn equal_states_serialize_to_equal_bytes() {
+        use crate::state::{layout, Delegation, EpochLE, LamportsLE, Lockup};
+
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            lockup: Lockup { unix_timestamp: -5, epoch: 600, custodian: [3; 32] },
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&[4; 32], 5_000_000_000, EpochLE::new(7)),
+            credits_observed: 42u64.to_le_bytes(),
+        };
+        let flags = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+
+        for state in [
+            StakeStateV2::Uninitialized,
+            StakeStateV2::Initialized(meta),
+            StakeStateV2::Stake(meta, stake, flags),
+            StakeStateV2::RewardsPool,
+        ] {
+            // whatever the buffer held before, the image only depends on the state
+            let mut images = [0x00u8, 0xff, 0xa5].map(|fill| {
+                let mut data = [fill; StakeStateV2::ACCOUNT_SIZE + 8];
+                state.serialize(&mut data).unwrap();
+                data
+            });
+            // and a copy of the state (a fresh stack value) writes the same bytes
+            let copy = StakeStateV2::deserialize(&images[0]).unwrap();
+            copy.serialize(&mut images[1]).unwrap();
+            assert!(images.iter().all(|image| image == &images[0]), "{state:?}");
+            assert_eq!(StakeStateV2::deserialize(&images[2]).unwrap(), state);
+        }
+
+        // fields land at the documented offsets
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        StakeStateV2::Stake(meta, stake, flags).serialize(&mut data).unwrap();
+        assert_eq!(data[layout::LOCKUP_UNIX_TIMESTAMP..][..8], (-5i64).to_le_bytes());
+        assert_eq!(data[layout::CUSTODIAN..][..32], [3; 32]);
+        assert_eq!(data[layout::CREDITS_OBSERVED..][..8], 42u64.to_le_bytes());
+        assert_eq!(data[layout::STAKE_FLAGS], flags.bits);
+        assert!(data[layout::STAKE_FLAGS + 1..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn short_buffers_are_rejected() {
+        let meta = Meta::default();
+        let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE - 1];
+        assert_eq!(StakeStateV2::Initialized(meta).serialize(&mut data), Err(ProgramError::AccountDataTooSmall));
+        // a Meta cut short reads as invalid rather than as zeroed fields
+        let mut full = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        StakeStateV2::Initialized(meta).serialize(&mut full).unwrap();
+        assert_eq!(StakeStateV2::deserialize(&full[..StakeStateV2::STAKE_OFFSET - 1]), Err(ProgramError::InvalidAccountData));
+        assert_eq!(StakeStateV2::deserialize(&full[..StakeStateV2::STAKE_OFFSET]), Ok(StakeStateV2::Initialized(meta)));
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn borsh_matches_account_layout() {
+        use crate::state::{Authorized, Delegation, EpochLE, LamportsLE, Lockup};
+        use borsh::BorshDeserialize;
+
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: [1; 32], withdrawer: [2; 32] },
+            lockup: Lockup { unix_timestamp: -5, epoch: 600, custodian: [3; 32] },
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&[4; 32], 5_000_000_000, EpochLE::new(7)),
+            credits_observed: 42u64.to_le_bytes(),
+        };
+        let flags = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+
+        for state in [
+            StakeStateV2::Uninitialized,
+            StakeStateV2::Initialized(meta),
+            StakeStateV2::Stake(meta, stake, flags),
+            StakeStateV2::RewardsPool,
+        ] {
+            let mut account = [0u8; StakeStateV2::ACCOUNT_SIZE];
+            state.serialize(&mut account).unwrap();
+
+            let encoded = borsh::to_vec(&state).unwrap();
+            assert_eq!(encoded, account[..encoded.len()], "{state:?}");
+            assert!(account[encoded.len()..].iter().all(|&b| b == 0));
+
+            assert_eq!(<StakeStateV2 as BorshDeserialize>::try_from_slice(&encoded).unwrap(), state);
+            assert_eq!(<StakeStateV2 as BorshDeserialize>::deserialize(&mut &account[..]).unwrap(), state);
+        }
+
+        let mut bad_tag = [0u8; StakeStateV2::ACCOUNT_SIZE];
+        bad_tag[0] = 4;
+        assert!(<StakeStateV2 as BorshDeserialize>::deserialize(&mut &bad_tag[..]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_is_readable_and_bincode_matches_account_layout() {
+        use crate::state::{Authorized, Delegation, EpochLE, LamportsLE, Lockup};
+
+        let meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(2_282_880),
+            authorized: Authorized { staker: crate::ID, withdrawer: [0; 32] },
+            lockup: Lockup { unix_timestamp: -5, epoch: 600, custodian: [3; 32] },
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&[4; 32], 5_000_000_000, EpochLE::new(7)),
+            credits_observed: 42u64.to_le_bytes(),
+        };
+        let state = StakeStateV2::Stake(meta, stake, StakeFlags::empty());
+
+        let json: serde_json::Value = serde_json::to_value(state).unwrap();
+        let meta_json = &json["Stake"][0];
+        assert_eq!(meta_json["authorized"]["staker"], "Stake11111111111111111111111111111111111111");
+        assert_eq!(meta_json["authorized"]["withdrawer"], "11111111111111111111111111111111");
+        assert_eq!(meta_json["rent_exempt_reserve"], 2_282_880);
+        let delegation_json = &json["Stake"][1]["delegation"];
+        assert_eq!(delegation_json["stake"], 5_000_000_000u64);
+        assert_eq!(delegation_json["deactivation_epoch"], u64::MAX);
+        assert_eq!(delegation_json["warmup_cooldown_rate"], 0.25);
+
+        for state in [
+            StakeStateV2::Uninitialized,
+            StakeStateV2::Initialized(meta),
+            state,
+            StakeStateV2::RewardsPool,
+        ] {
+            let text = serde_json::to_string(&state).unwrap();
+            assert_eq!(serde_json::from_str::<StakeStateV2>(&text).unwrap(), state, "{text}");
+
+            let mut account = [0u8; StakeStateV2::ACCOUNT_SIZE];
+            state.serialize(&mut account).unwrap();
+            let encoded = bincode::serialize(&state).unwrap();
+            assert_eq!(encoded, account[..encoded.len()], "{state:?}");
+            assert_eq!(bincode::deserialize::<StakeStateV2>(&account).unwrap(), state);
+        }
+
+        let bad_key = r#"{"staker":"not-base58!","withdrawer":"11111111111111111111111111111111"}"#;
+        assert!(serde_json::from_str::<Authorized>(bad_key).is_err());
+        let short_key = r#"{"staker":"1111","withdrawer":"11111111111111111111111111111111"}"#;
+        assert!(serde_json::from_str::<Authorized>(short_key).is_err());
+    }
 }
@@ -1,4 +1,6 @@
-use crate::state::accounts::Authorized;
+use crate::helpers::SignerSet;
+use crate::state::accounts::{Authorized, SetLockupData};
+use crate::state::LamportsLE;
 use pinocchio::{
     account_info::AccountInfo,
     program_error::ProgramError,
@@ -8,6 +10,8 @@ use pinocchio::{
 
 #[repr(C)]
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lockup {
     /// UnixTimestamp at which this stake will allow withdrawal, unless
     /// the transaction is signed by the custodian
@@ -16,13 +20,16 @@ pub struct Lockup {
     /// the transaction is signed by the custodian
     pub epoch: Epoch,                  // u64
     /// Custodian whose signature exempts the operation from lockup constraints
+    #[cfg_attr(feature = "serde", serde(with = "crate::state::serde_fields::pubkey"))]
     pub custodian: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize, borsh::BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Meta {
-    pub rent_exempt_reserve: [u8; 8],
+    pub rent_exempt_reserve: LamportsLE,
     pub authorized: Authorized,
     pub lockup: Lockup,
 }
@@ -32,6 +39,40 @@ impl Meta {
         core::mem::size_of::<Meta>()
     }
 
+    /// SetLockup / SetLockupChecked policy, as native `Meta::set_lockup`:
+    /// while the lockup is in force only the current custodian may change it
+    /// (the custodian signature does not lift the lockup for this check);
+    /// otherwise the withdrawer may. Each provided field is then set as-is,
+    /// so the custodian can also hand over to a new custodian, or set the
+    /// default key for no custodian; a lockup in force without one can't be
+    /// changed by anyone until it expires.
+    pub fn set_lockup(
+        &mut self,
+        args: &SetLockupData,
+        signers: &(impl SignerSet + ?Sized),
+        clock: &Clock,
+    ) -> Result<(), ProgramError> {
+        let allowed = if self.lockup.is_in_force(clock, None) {
+            self.lockup.has_custodian() && signers.contains(&self.lockup.custodian)
+        } else {
+            signers.contains(&self.authorized.withdrawer)
+        };
+        if !allowed {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if let Some(unix_timestamp) = args.unix_timestamp {
+            self.lockup.unix_timestamp = unix_timestamp;
+        }
+        if let Some(epoch) = args.epoch {
+            self.lockup.epoch = epoch;
+        }
+        if let Some(custodian) = args.custodian {
+            self.lockup.custodian = custodian;
+        }
+        Ok(())
+    }
+
     pub fn get_account_info(account: &AccountInfo) -> Result<&Self, ProgramError> {
         if account.data_len() < core::mem::size_of::<Meta>() {
             return Err(ProgramError::InvalidAccountData);
@@ -45,6 +86,7 @@ impl Meta {
         Ok(unsafe { &*(account.borrow_data_unchecked().as_ptr() as *const Self) })
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_account_info_mut(account: &AccountInfo) -> Result<&mut Self, ProgramError> {
         if account.data_len() < core::mem::size_of::<Meta>() {
             return Err(ProgramError::InvalidAccountData);
@@ -73,12 +115,11 @@ impl Lockup {
         }
     }
 
-    /// Check if lockup is active at the given wall time and epoch
+    /// Check if lockup is active at the given wall time and epoch: in force
+    /// while *either* constraint hasn't passed yet. A zero field is not special
+    /// (native compares it like any other value).
     pub fn is_active(&self, current_timestamp: i64, current_epoch: u64) -> bool {
-        // In force if *either* constraint hasn't passed yet (0 means "no constraint")
-        let time_in_force  = self.unix_timestamp != 0 && current_timestamp < self.unix_timestamp;
-        let epoch_in_force = self.epoch          != 0 && current_epoch   < self.epoch;
-        time_in_force || epoch_in_force
+        self.unix_timestamp > current_timestamp || self.epoch > current_epoch
     }
 
     pub fn get_account_info(account: &AccountInfo) -> Result<&Self, ProgramError> {
@@ -91,6 +132,7 @@ impl Lockup {
         Ok(unsafe { &*(account.borrow_data_unchecked().as_ptr() as *const Self) })
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_account_info_mut(account: &AccountInfo) -> Result<&mut Self, ProgramError> {
         if account.data_len() < Self::size() {
             return Err(ProgramError::InvalidAccountData);
@@ -104,19 +146,138 @@ impl Lockup {
         Ok(unsafe { &mut *(account.borrow_mut_data_unchecked().as_ptr() as *mut Self) })
     }
 
-    /// Custodian signature bypasses lockup
+    /// Whether a custodian is set; the default (all-zero) key means none
+    #[inline(always)]
+    pub fn has_custodian(&self) -> bool {
+        self.custodian != Pubkey::default()
+    }
+
+    /// Custodian signature bypasses lockup. Without a custodian nothing does: a
+    /// signer with the all-zero key is not the custodian. Natively no one can
+    /// sign for that key, so results agree for every real transaction.
     #[inline(always)]
     pub fn is_in_force(&self, clock: &Clock, custodian_signer: Option<&Pubkey>) -> bool {
         // Bypass if the configured custodian signed
-        if let Some(sig) = custodian_signer {
-            if *sig == self.custodian {
-                return false;
+        if self.has_custodian() && custodian_signer == Some(&self.custodian) {
+            return false;
+        }
+        self.is_active(clock.unix_timestamp, clock.epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        clock::Clock as SdkClock,
+        pubkey::Pubkey as SdkPubkey,
+        stake::{instruction::LockupArgs, state as native},
+    };
+    use std::collections::HashSet;
+
+    const NOW_TS: i64 = 1_000;
+    const NOW_EPOCH: u64 = 10;
+
+    fn clock() -> Clock {
+        Clock { slot: 0, epoch_start_timestamp: 0, epoch: NOW_EPOCH, leader_schedule_epoch: NOW_EPOCH, unix_timestamp: NOW_TS }
+    }
+
+    /// Every combination of lockup status, signer set and provided fields
+    /// must give native's result and post-state
+    #[test]
+    fn set_lockup_matches_native_meta() {
+        let withdrawer = [1u8; 32];
+        let custodian = [2u8; 32];
+        let new_custodian = [3u8; 32];
+        // (unix_timestamp, epoch): in force by time, by epoch, both, expired, expiring now
+        let lockups = [(NOW_TS + 1, 0), (0, NOW_EPOCH + 1), (NOW_TS + 1, NOW_EPOCH + 1), (NOW_TS - 1, NOW_EPOCH - 1), (NOW_TS, NOW_EPOCH)];
+        let signer_sets: [&[Pubkey]; 4] = [&[], &[withdrawer], &[custodian], &[withdrawer, custodian]];
+        let sdk_clock = SdkClock { epoch: NOW_EPOCH, unix_timestamp: NOW_TS, ..SdkClock::default() };
+
+        for (ts, epoch) in lockups {
+            for signers in signer_sets {
+                for fields in 0..8u8 {
+                    let args = SetLockupData {
+                        unix_timestamp: (fields & 1 != 0).then_some(NOW_TS + 500),
+                        epoch: (fields & 2 != 0).then_some(NOW_EPOCH + 5),
+                        custodian: (fields & 4 != 0).then_some(new_custodian),
+                    };
+                    let mut meta = Meta {
+                        rent_exempt_reserve: LamportsLE::new(0),
+                        authorized: Authorized { staker: withdrawer, withdrawer },
+                        lockup: Lockup { unix_timestamp: ts, epoch, custodian },
+                    };
+                    let mut expected = native::Meta {
+                        rent_exempt_reserve: 0,
+                        authorized: native::Authorized {
+                            staker: SdkPubkey::new_from_array(withdrawer),
+                            withdrawer: SdkPubkey::new_from_array(withdrawer),
+                        },
+                        lockup: native::Lockup { unix_timestamp: ts, epoch, custodian: SdkPubkey::new_from_array(custodian) },
+                    };
+                    let native_args = LockupArgs {
+                        unix_timestamp: args.unix_timestamp,
+                        epoch: args.epoch,
+                        custodian: args.custodian.map(SdkPubkey::new_from_array),
+                    };
+                    let native_signers: HashSet<SdkPubkey> = signers.iter().map(|s| SdkPubkey::new_from_array(*s)).collect();
+
+                    let case = format!("lockup ({ts}, {epoch}), {} signers, fields {fields:03b}", signers.len());
+                    let result = meta.set_lockup(&args, signers, &clock());
+                    let native_result = expected.set_lockup(&native_args, &native_signers, &sdk_clock);
+                    assert_eq!(result.is_ok(), native_result.is_ok(), "{case}");
+                    if result.is_err() {
+                        assert_eq!(result, Err(ProgramError::MissingRequiredSignature), "{case}");
+                    }
+                    assert_eq!(meta.lockup.unix_timestamp, expected.lockup.unix_timestamp, "{case}");
+                    assert_eq!(meta.lockup.epoch, expected.lockup.epoch, "{case}");
+                    assert_eq!(meta.lockup.custodian, expected.lockup.custodian.to_bytes(), "{case}");
+                }
             }
         }
+    }
+
+    #[test]
+    fn custodian_signature_lifts_lockup_only_when_passed() {
+        let custodian = [2u8; 32];
+        let lockup = Lockup { unix_timestamp: NOW_TS + 1, epoch: 0, custodian };
+        assert!(lockup.is_in_force(&clock(), None));
+        assert!(lockup.is_in_force(&clock(), Some(&[9u8; 32])));
+        assert!(!lockup.is_in_force(&clock(), Some(&custodian)));
+        // a lockup ending exactly now is no longer in force
+        assert!(!Lockup { unix_timestamp: NOW_TS, epoch: NOW_EPOCH, custodian }.is_in_force(&clock(), None));
+    }
+
+    #[test]
+    fn default_custodian_is_no_custodian() {
+        let zero = Pubkey::default();
+        let lockup = Lockup { unix_timestamp: NOW_TS + 1, epoch: 0, custodian: zero };
+        assert!(!lockup.has_custodian());
+        // a zero-key signer is not a custodian
+        assert!(lockup.is_in_force(&clock(), Some(&zero)));
+
+        let withdrawer = [1u8; 32];
+        let mut meta = Meta {
+            rent_exempt_reserve: LamportsLE::new(0),
+            authorized: Authorized { staker: withdrawer, withdrawer },
+            lockup,
+        };
+        let extend = SetLockupData { unix_timestamp: Some(NOW_TS + 500), epoch: None, custodian: None };
+        for signers in [&[zero][..], &[withdrawer, zero]] {
+            assert_eq!(meta.set_lockup(&extend, signers, &clock()), Err(ProgramError::MissingRequiredSignature));
+        }
 
-        let time_in_force  = self.unix_timestamp != 0 && clock.unix_timestamp < self.unix_timestamp;
-        let epoch_in_force = self.epoch          != 0 && clock.epoch          < self.epoch;
+        // the custodian can hand over to no custodian, which then bypasses nothing
+        let custodian = [2u8; 32];
+        meta.lockup.custodian = custodian;
+        let release = SetLockupData { unix_timestamp: None, epoch: None, custodian: Some(zero) };
+        meta.set_lockup(&release, &[custodian], &clock()).unwrap();
+        assert!(!meta.lockup.has_custodian());
+        assert!(meta.lockup.is_in_force(&clock(), Some(&zero)));
+        assert_eq!(meta.set_lockup(&extend, &[zero], &clock()), Err(ProgramError::MissingRequiredSignature));
 
-        time_in_force || epoch_in_force
+        // once expired, the withdrawer alone may set it again
+        let later = Clock { unix_timestamp: NOW_TS + 1, ..clock() };
+        meta.set_lockup(&extend, &[withdrawer], &later).unwrap();
     }
 }
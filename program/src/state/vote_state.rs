@@ -13,6 +13,12 @@ pub struct EpochCreditsList {
     items: [EpochCredits; MAX_EPOCH_CREDITS],
 }
 
+impl Default for EpochCreditsList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EpochCreditsList {
     #[inline]
     pub const fn new() -> Self {
@@ -43,14 +49,17 @@ pub struct VoteState {
 }
 
 impl VoteState {
-      #[inline]
+    /// Latest credits (the newest `epoch_credits` entry), as native
+    /// `VoteState::credits`; 0 for a vote account that never earned any
+    #[inline]
     pub fn credits(&self) -> u64 {
         match self.epoch_credits.as_slice().last() {
             Some((_, credits, _prev)) => *credits,
             None => 0,
         }
     }
-       #[inline]
+
+    #[inline]
     pub fn credits_for_epoch(&self, epoch: u64) -> Option<u64> {
         self.epoch_credits
             .as_slice()
@@ -69,49 +78,302 @@ impl VoteState {
         Self::from_bytes(&data)
     }
 
+    /// Vote account data, checked as [`EpochCreditsView::parse`]; keeps the
+    /// newest [`MAX_EPOCH_CREDITS`] entries
     #[inline]
     pub fn from_bytes(data: &[u8]) -> Result<Self, ProgramError> {
-        let list = parse_epoch_credits(data).ok_or(ProgramError::InvalidAccountData)?;
+        let view = EpochCreditsView::parse(data)?;
+        let mut list = EpochCreditsList::new();
+        for i in view.len().saturating_sub(MAX_EPOCH_CREDITS)..view.len() {
+            if let Some(entry) = view.get(i) {
+                list.push(entry);
+            }
+        }
         Ok(Self { epoch_credits: list })
     }
 }
 
-#[inline]
-pub fn parse_epoch_credits(data: &[u8]) -> Option<EpochCreditsList> {
-    if data.len() < 4 {
-        return None;
+/// `lenient-vote`: also accept the light-weight test layout (u32 count, then
+/// the epoch credits entries) when data is not a serialized vote state
+pub const LENIENT_VOTE: bool = cfg!(feature = "lenient-vote");
+
+/// `(Pubkey, Epoch, Epoch)` x 32, then `idx: u64` and `is_empty: bool`
+const PRIOR_VOTERS_LEN: usize = 32 * (32 + 8 + 8) + 8 + 1;
+const EPOCH_CREDITS_ENTRY_LEN: usize = 24;
+
+/// Smallest serialized vote state (V1_14_11 or Current with no votes, root,
+/// authorized voters or epoch credits)
+pub const MIN_VOTE_STATE_LEN: usize = 4 + 32 + 32 + 1 + 8 + 1 + 8 + PRIOR_VOTERS_LEN + 8 + 16;
+
+/// The `epoch_credits` entries of a vote account, read in place (oldest first)
+/// so the handlers never build a vote state on the SBF stack
+#[derive(Debug, Clone, Copy)]
+pub struct EpochCreditsView<'a> {
+    entries: &'a [u8],
+}
+
+impl<'a> EpochCreditsView<'a> {
+    /// Walk a serialized `VoteStateVersions` the way native's deserializer
+    /// does: V1_14_11 (1) and Current (2) only (V0_23_5 is rejected on-chain),
+    /// every length- or tag-prefixed field bounds-checked. Anything else is
+    /// `InvalidAccountData` unless `lenient-vote` falls back to the test layout.
+    pub fn parse(data: &'a [u8]) -> Result<Self, ProgramError> {
+        match Self::parse_vote_state(data) {
+            Some(view) => Ok(view),
+            None if LENIENT_VOTE => Self::parse_test_layout(data).ok_or(ProgramError::InvalidAccountData),
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Raw `(epoch, credits, prev_credits)` entries, 24 bytes each
+    pub fn from_entries(entries: &'a [u8]) -> Self {
+        let whole = entries.len() - entries.len() % EPOCH_CREDITS_ENTRY_LEN;
+        Self { entries: &entries[..whole] }
+    }
+
+    fn parse_vote_state(data: &'a [u8]) -> Option<Self> {
+        if data.len() < MIN_VOTE_STATE_LEN {
+            return None;
+        }
+        let mut r = Reader { data, offset: 0 };
+        let vote_len = match r.u32()? {
+            1 => 8 + 4,     // Lockout
+            2 => 1 + 8 + 4, // LandedVote: latency + Lockout
+            _ => return None,
+        };
+        r.skip(32 + 32 + 1)?; // node_pubkey, authorized_withdrawer, commission
+        let votes = r.u64()?;
+        r.skip_n(votes, vote_len)?;
+        match r.u8()? {
+            0 => {}
+            1 => r.skip(8)?,
+            _ => return None,
+        }
+        let authorized_voters = r.u64()?;
+        r.skip_n(authorized_voters, 8 + 32)?;
+        r.skip(PRIOR_VOTERS_LEN - 1)?;
+        if r.u8()? > 1 {
+            return None;
+        }
+        let count = r.u64()?;
+        let start = r.offset;
+        r.skip_n(count, EPOCH_CREDITS_ENTRY_LEN)?;
+        let entries = &data[start..r.offset];
+        r.skip(8 + 8)?; // last_timestamp
+        Some(Self { entries })
+    }
+
+    fn parse_test_layout(data: &'a [u8]) -> Option<Self> {
+        let mut r = Reader { data, offset: 0 };
+        let count = r.u32()?;
+        r.skip_n(count as u64, EPOCH_CREDITS_ENTRY_LEN)?;
+        Some(Self { entries: &data[4..r.offset] })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len() / EPOCH_CREDITS_ENTRY_LEN
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<EpochCredits> {
+        let off = index.checked_mul(EPOCH_CREDITS_ENTRY_LEN)?;
+        let entry = self.entries.get(off..off + EPOCH_CREDITS_ENTRY_LEN)?;
+        let word = |i: usize| {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(&entry[i * 8..i * 8 + 8]);
+            u64::from_le_bytes(b)
+        };
+        Some((word(0), word(1), word(2)))
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<EpochCredits> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// Latest credits, as native `VoteState::credits`
+    #[inline]
+    pub fn credits(&self) -> u64 {
+        self.last().map_or(0, |(_, credits, _)| credits)
+    }
+}
+
+/// Bounds-checked little-endian cursor
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.offset.checked_add(n)?;
+        let bytes = self.data.get(self.offset..end)?;
+        self.offset = end;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, n: usize) -> Option<()> {
+        self.take(n).map(|_| ())
+    }
+
+    /// Skip `count` items of `item_len` bytes
+    fn skip_n(&mut self, count: u64, item_len: usize) -> Option<()> {
+        let n = usize::try_from(count).ok()?.checked_mul(item_len)?;
+        self.skip(n)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
     }
-    let mut n_bytes = [0u8; 4];
-    n_bytes.copy_from_slice(&data[0..4]);
-    let n = u32::from_le_bytes(n_bytes) as usize;
 
-    let need = 4 + n * (8 * 3);
-    if data.len() < need {
-        return None;
+    fn u32(&mut self) -> Option<u32> {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(self.take(4)?);
+        Some(u32::from_le_bytes(b))
     }
 
+    fn u64(&mut self) -> Option<u64> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Some(u64::from_le_bytes(b))
+    }
+}
+
+/// `lenient-vote`: the entries of the light-weight test layout, whatever the
+/// data holds otherwise; real vote accounts go through [`EpochCreditsView::parse`]
+#[cfg(feature = "lenient-vote")]
+pub fn parse_epoch_credits(data: &[u8]) -> Option<EpochCreditsList> {
+    let view = EpochCreditsView::parse_test_layout(data)?;
     let mut list = EpochCreditsList::new();
-    let mut off = 4;
-    for _ in 0..n {
-        let mut e = [0u8; 8];
-        let mut c = [0u8; 8];
-        let mut p = [0u8; 8];
-        e.copy_from_slice(&data[off..off + 8]); off += 8;
-        c.copy_from_slice(&data[off..off + 8]); off += 8;
-        p.copy_from_slice(&data[off..off + 8]); off += 8;
-        let _ = list.push((u64::from_le_bytes(e), u64::from_le_bytes(c), u64::from_le_bytes(p)));
+    for i in 0..view.len().min(MAX_EPOCH_CREDITS) {
+        list.push(view.get(i)?);
     }
     Some(list)
 }
 
+#[cfg(feature = "lenient-vote")]
 #[inline]
 pub fn parse_epoch_credits_slice(data: &[u8]) -> Option<EpochCreditsList> {
     parse_epoch_credits(data)
 }
 
-#[inline]
 declare_id!("Vote111111111111111111111111111111111111111");
 
 pub fn vote_program_id() -> Pubkey {
     Pubkey::try_from(&ID[..]).unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::vote::state::{
+        LandedVote, Lockout, VoteState1_14_11, VoteStateV3, VoteStateVersions,
+    };
+    use solana_sdk::{clock::Clock, pubkey::Pubkey as SdkPubkey, vote::state::VoteInit};
+
+    fn vote_state(credits: usize) -> VoteStateV3 {
+        let init = VoteInit {
+            node_pubkey: SdkPubkey::new_unique(),
+            authorized_voter: SdkPubkey::new_unique(),
+            authorized_withdrawer: SdkPubkey::new_unique(),
+            commission: 7,
+        };
+        let mut state = VoteStateV3::new(&init, &Clock::default());
+        for slot in 1..=5 {
+            state.votes.push_back(LandedVote { latency: 1, lockout: Lockout::new(slot) });
+        }
+        state.root_slot = Some(1);
+        state.epoch_credits = (0..credits as u64).map(|e| (e, e * 10 + 10, e * 10)).collect();
+        state
+    }
+
+    fn serialize(versioned: &VoteStateVersions, padded: bool) -> Vec<u8> {
+        let mut data = bincode::serialize(versioned).unwrap();
+        if padded {
+            data.resize(VoteStateV3::size_of(), 0);
+        }
+        data
+    }
+
+    #[test]
+    fn reads_epoch_credits_of_every_supported_version() {
+        for credits in [0, 1, 64, 70] {
+            let state = vote_state(credits);
+            let versions = [
+                VoteStateVersions::new_current(state.clone()),
+                VoteStateVersions::V1_14_11(Box::new(VoteState1_14_11::from(state.clone()))),
+            ];
+            for versioned in versions {
+                for padded in [false, true] {
+                    let data = serialize(&versioned, padded);
+                    let view = EpochCreditsView::parse(&data).unwrap();
+                    let entries: Vec<_> = (0..view.len()).map(|i| view.get(i).unwrap()).collect();
+                    assert_eq!(entries, state.epoch_credits);
+                    assert_eq!(view.credits(), state.credits());
+
+                    let kept = VoteState::from_bytes(&data).unwrap();
+                    let tail = &state.epoch_credits[credits.saturating_sub(MAX_EPOCH_CREDITS)..];
+                    assert_eq!(kept.epoch_credits_as_slice(), tail);
+                    assert_eq!(kept.credits(), state.credits());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smallest_vote_state_is_accepted() {
+        let data = serialize(&VoteStateVersions::new_current(VoteStateV3::default()), false);
+        assert_eq!(data.len(), MIN_VOTE_STATE_LEN);
+        assert!(EpochCreditsView::parse(&data).unwrap().is_empty());
+    }
+
+    // the lenient fallback would read these as the test layout
+    #[cfg(not(feature = "lenient-vote"))]
+    #[test]
+    fn truncated_vote_state_is_rejected() {
+        let data = serialize(&VoteStateVersions::new_current(vote_state(3)), false);
+        for len in 0..data.len() {
+            assert_eq!(
+                EpochCreditsView::parse(&data[..len]).err(),
+                Some(ProgramError::InvalidAccountData),
+                "{len} of {} bytes",
+                data.len()
+            );
+        }
+    }
+
+    #[cfg(not(feature = "lenient-vote"))]
+    #[test]
+    fn unsupported_versions_and_bad_tags_are_rejected() {
+        let mut data = serialize(&VoteStateVersions::new_current(vote_state(3)), true);
+        for version in [0u32, 3, u32::MAX] {
+            let mut bad = data.clone();
+            bad[..4].copy_from_slice(&version.to_le_bytes());
+            assert!(EpochCreditsView::parse(&bad).is_err(), "version {version}");
+        }
+        // root_slot option tag: after the tag, keys, commission and the 5 votes
+        let root_tag = 4 + 32 + 32 + 1 + 8 + 5 * 13;
+        assert_eq!(data[root_tag], 1);
+        data[root_tag] = 2;
+        assert!(EpochCreditsView::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_layout_needs_lenient_vote() {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend([5u64, 50, 40].iter().flat_map(|w| w.to_le_bytes()));
+        let parsed = EpochCreditsView::parse(&data);
+        if LENIENT_VOTE {
+            assert_eq!(parsed.unwrap().last(), Some((5, 50, 40)));
+            #[cfg(feature = "lenient-vote")]
+            assert_eq!(parse_epoch_credits(&data).unwrap().as_slice(), &[(5, 50, 40)]);
+        } else {
+            assert_eq!(parsed.err(), Some(ProgramError::InvalidAccountData));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Stake account fixtures for tests (`test-utils` feature).
+//!
+//! [`StakeStateBuilder`] assembles any `StakeStateV2` variant from plain
+//! values and encodes it exactly as the program would store it, so a test can
+//! `add_account` the state it needs instead of running the create, initialize,
+//! delegate and warp transactions that lead there.
+
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use pinocchio::pubkey::Pubkey;
+
+use crate::state::{
+    migration::{ActiveMigration, StateMigration},
+    Authorized, Delegation, EpochLE, LamportsLE, Lockup, Meta, Stake, StakeFlags, StakeStateV2,
+};
+
+/// Rent-exempt reserve of a stake account under the default rent
+/// (`Rent::default().minimum_balance(200)`), the value program-test banks use
+pub const DEFAULT_RESERVE: u64 = 2_282_880;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variant {
+    Uninitialized,
+    Initialized,
+    Stake,
+    RewardsPool,
+}
+
+/// Builds a [`StakeStateV2`] and its account data.
+///
+/// Initialized and Stake accounts start with [`DEFAULT_RESERVE`] and no
+/// lockup. Setting a delegation turns the state into Stake; a delegation is
+/// bootstrap (fully active from genesis) and never deactivated unless the
+/// epochs are set.
+#[derive(Debug, Clone, Copy)]
+pub struct StakeStateBuilder {
+    variant: Variant,
+    meta: Meta,
+    stake: Stake,
+    flags: StakeFlags,
+}
+
+impl StakeStateBuilder {
+    fn new(variant: Variant) -> Self {
+        Self {
+            variant,
+            meta: Meta { rent_exempt_reserve: LamportsLE::new(DEFAULT_RESERVE), ..Meta::default() },
+            stake: Stake::default(),
+            flags: StakeFlags::empty(),
+        }
+    }
+
+    pub fn uninitialized() -> Self {
+        Self::new(Variant::Uninitialized)
+    }
+
+    pub fn rewards_pool() -> Self {
+        Self::new(Variant::RewardsPool)
+    }
+
+    pub fn initialized(staker: Pubkey, withdrawer: Pubkey) -> Self {
+        let mut builder = Self::new(Variant::Initialized);
+        builder.meta.authorized = Authorized { staker, withdrawer };
+        builder
+    }
+
+    pub fn rent_exempt_reserve(mut self, lamports: u64) -> Self {
+        self.meta.rent_exempt_reserve = LamportsLE::new(lamports);
+        self
+    }
+
+    pub fn lockup(mut self, unix_timestamp: i64, epoch: u64, custodian: Pubkey) -> Self {
+        self.meta.lockup = Lockup { unix_timestamp, epoch, custodian };
+        self
+    }
+
+    /// Delegate `stake` lamports to `voter`
+    pub fn delegation(mut self, voter: Pubkey, stake: u64) -> Self {
+        self.variant = Variant::Stake;
+        self.stake.delegation = Delegation {
+            deactivation_epoch: EpochLE::MAX,
+            ..Delegation::new(&voter, stake, EpochLE::MAX)
+        };
+        self
+    }
+
+    pub fn activation_epoch(mut self, epoch: u64) -> Self {
+        self.stake.delegation.activation_epoch = EpochLE::new(epoch);
+        self
+    }
+
+    pub fn deactivation_epoch(mut self, epoch: u64) -> Self {
+        self.stake.delegation.deactivation_epoch = EpochLE::new(epoch);
+        self
+    }
+
+    pub fn credits_observed(mut self, credits: u64) -> Self {
+        self.stake.credits_observed = credits.to_le_bytes();
+        self
+    }
+
+    pub fn flags(mut self, flags: StakeFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn build(&self) -> StakeStateV2 {
+        match self.variant {
+            Variant::Uninitialized => StakeStateV2::Uninitialized,
+            Variant::Initialized => StakeStateV2::Initialized(self.meta),
+            Variant::Stake => StakeStateV2::Stake(self.meta, self.stake, self.flags),
+            Variant::RewardsPool => StakeStateV2::RewardsPool,
+        }
+    }
+
+    /// Account data as the program stores it, including the feature-dependent
+    /// encodings (the `integrity` seal, the `state-v3` layout)
+    pub fn account_data(&self) -> Vec<u8> {
+        let mut data = vec![0; StakeStateV2::ACCOUNT_SIZE];
+        ActiveMigration::store(&self.build(), &mut data).expect("stake account size");
+        #[cfg(feature = "integrity")]
+        crate::helpers::checksum::write_checksum(&mut data);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_every_variant() {
+        assert_eq!(StakeStateBuilder::uninitialized().build(), StakeStateV2::Uninitialized);
+        assert_eq!(StakeStateBuilder::rewards_pool().build(), StakeStateV2::RewardsPool);
+
+        let StakeStateV2::Initialized(meta) = StakeStateBuilder::initialized([1; 32], [2; 32]).lockup(-1, 5, [3; 32]).build() else {
+            panic!("expected Initialized");
+        };
+        assert_eq!(meta.authorized, Authorized { staker: [1; 32], withdrawer: [2; 32] });
+        assert_eq!(meta.lockup, Lockup { unix_timestamp: -1, epoch: 5, custodian: [3; 32] });
+        assert_eq!(meta.rent_exempt_reserve, DEFAULT_RESERVE);
+
+        let flags = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+        let builder = StakeStateBuilder::initialized([1; 32], [1; 32])
+            .delegation([4; 32], 42)
+            .activation_epoch(2)
+            .credits_observed(7)
+            .flags(flags);
+        let StakeStateV2::Stake(_, stake, stake_flags) = builder.build() else {
+            panic!("expected Stake");
+        };
+        let delegation = stake.delegation;
+        assert_eq!(delegation.voter_pubkey, [4; 32]);
+        assert_eq!(delegation.stake, 42u64);
+        assert_eq!(delegation.activation_epoch, 2u64);
+        assert_eq!(delegation.deactivation_epoch, u64::MAX);
+        assert_eq!(stake.credits_observed, 7u64.to_le_bytes());
+        assert_eq!(stake_flags, flags);
+
+        assert_eq!(ActiveMigration::load(&builder.account_data()).unwrap(), builder.build());
+    }
+}
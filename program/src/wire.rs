@@ -0,0 +1,187 @@
+//! Bincode wire format of `StakeInstruction` as built by the Solana SDK (std only).
+//!
+//! Mirrors the SDK enum variant-for-variant so SDK-built transactions can be
+//! decoded without pulling in solana-program.
+
+extern crate alloc;
+use alloc::string::String;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{instruction::process_set_lockup_checked::LockupCheckedData, state::accounts};
+
+pub type WirePubkey = [u8; 32];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Authorized { pub staker: WirePubkey, pub withdrawer: WirePubkey }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockup { pub unix_timestamp: i64, pub epoch: u64, pub custodian: WirePubkey }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StakeAuthorize { Staker, Withdrawer }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockupArgs { pub unix_timestamp: Option<i64>, pub epoch: Option<u64>, pub custodian: Option<WirePubkey> }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockupCheckedArgs { pub unix_timestamp: Option<i64>, pub epoch: Option<u64> }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizeWithSeedArgs { pub new_authorized_pubkey: WirePubkey, pub stake_authorize: StakeAuthorize, pub authority_seed: String, pub authority_owner: WirePubkey }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizeCheckedWithSeedArgs { pub stake_authorize: StakeAuthorize, pub authority_seed: String, pub authority_owner: WirePubkey }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StakeInstruction {
+    Initialize(Authorized, Lockup),
+    Authorize(WirePubkey, StakeAuthorize),
+    DelegateStake,
+    Split(u64),
+    Withdraw(u64),
+    Deactivate,
+    SetLockup(LockupArgs),
+    Merge,
+    AuthorizeWithSeed(AuthorizeWithSeedArgs),
+    InitializeChecked,
+    AuthorizeChecked(StakeAuthorize),
+    AuthorizeCheckedWithSeed(AuthorizeCheckedWithSeedArgs),
+    SetLockupChecked(LockupCheckedArgs),
+    GetMinimumDelegation,
+    DeactivateDelinquent,
+    #[deprecated]
+    Redelegate,
+    MoveStake(u64),
+    MoveLamports(u64),
+}
+
+impl StakeInstruction {
+    /// Decode the SDK's bincode encoding (fixed-width integers), rejecting
+    /// data left over after the instruction like every other decoding path.
+    /// The SDK's own `limited_deserialize` would ignore it.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        use bincode::Options;
+        bincode::DefaultOptions::new().with_fixint_encoding().reject_trailing_bytes().deserialize(data).ok()
+    }
+
+    /// The single-byte discriminant of the same instruction: the SDK's
+    /// variant index
+    #[allow(deprecated)]
+    pub fn discriminant(&self) -> u8 {
+        match self {
+            Self::Initialize(..) => 0,
+            Self::Authorize(..) => 1,
+            Self::DelegateStake => 2,
+            Self::Split(_) => 3,
+            Self::Withdraw(_) => 4,
+            Self::Deactivate => 5,
+            Self::SetLockup(_) => 6,
+            Self::Merge => 7,
+            Self::AuthorizeWithSeed(_) => 8,
+            Self::InitializeChecked => 9,
+            Self::AuthorizeChecked(_) => 10,
+            Self::AuthorizeCheckedWithSeed(_) => 11,
+            Self::SetLockupChecked(_) => 12,
+            Self::GetMinimumDelegation => 13,
+            Self::DeactivateDelinquent => 14,
+            Self::Redelegate => 15,
+            Self::MoveStake(_) => 16,
+            Self::MoveLamports(_) => 17,
+        }
+    }
+}
+
+impl From<StakeAuthorize> for accounts::StakeAuthorize {
+    fn from(which: StakeAuthorize) -> Self {
+        match which {
+            StakeAuthorize::Staker => accounts::StakeAuthorize::Staker,
+            StakeAuthorize::Withdrawer => accounts::StakeAuthorize::Withdrawer,
+        }
+    }
+}
+
+impl From<LockupCheckedArgs> for LockupCheckedData {
+    fn from(args: LockupCheckedArgs) -> Self {
+        Self { unix_timestamp: args.unix_timestamp, epoch: args.epoch }
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{accounts::Authorized as ProgramAuthorized, state::Meta},
+    };
+    use pinocchio::sysvars::clock::Clock;
+    use solana_sdk::{pubkey::Pubkey as SdkPubkey, stake::instruction as sdk};
+
+    fn decode_checked(ix: &solana_sdk::instruction::Instruction) -> LockupCheckedData {
+        match bincode::deserialize::<StakeInstruction>(&ix.data).unwrap() {
+            StakeInstruction::SetLockupChecked(args) => args.into(),
+            other => panic!("unexpected instruction {other:?}"),
+        }
+    }
+
+    fn clock() -> Clock {
+        Clock { slot: 0, epoch_start_timestamp: 0, epoch: 3, leader_schedule_epoch: 3, unix_timestamp: 100 }
+    }
+
+    #[test]
+    fn decode_rejects_trailing_bytes() {
+        let ix = sdk::withdraw(&SdkPubkey::new_unique(), &SdkPubkey::new_unique(), &SdkPubkey::new_unique(), 5, None);
+        assert_eq!(StakeInstruction::decode(&ix.data), Some(StakeInstruction::Withdraw(5)));
+        let mut data = ix.data.clone();
+        data.push(0);
+        assert_eq!(StakeInstruction::decode(&data), None);
+    }
+
+    #[test]
+    fn sdk_set_lockup_checked_args_round_trip() {
+        let stake = SdkPubkey::new_unique();
+        let withdrawer = SdkPubkey::new_unique();
+        let args = sdk::LockupArgs { unix_timestamp: Some(-7), epoch: Some(u64::MAX), custodian: None };
+        let checked = decode_checked(&sdk::set_lockup_checked(&stake, &args, &withdrawer));
+        assert_eq!((checked.unix_timestamp, checked.epoch), (Some(-7), Some(u64::MAX)));
+
+        let args = sdk::LockupArgs { unix_timestamp: None, epoch: Some(9), custodian: None };
+        let checked = decode_checked(&sdk::set_lockup_checked(&stake, &args, &withdrawer));
+        assert_eq!((checked.unix_timestamp, checked.epoch), (None, Some(9)));
+    }
+
+    #[test]
+    fn sdk_set_lockup_checked_applies_args_and_custodian_co_signer() {
+        let stake = SdkPubkey::new_unique();
+        let withdrawer = SdkPubkey::new_unique();
+        let new_custodian = SdkPubkey::new_unique();
+        let args = sdk::LockupArgs {
+            unix_timestamp: Some(1_000),
+            epoch: Some(10),
+            custodian: Some(new_custodian),
+        };
+        let ix = sdk::set_lockup_checked(&stake, &args, &withdrawer);
+
+        // the new custodian travels as a signing account, not in the payload
+        let custodian_meta = &ix.accounts[2];
+        assert_eq!(custodian_meta.pubkey, new_custodian);
+        assert!(custodian_meta.is_signer);
+
+        let mut meta = Meta {
+            authorized: ProgramAuthorized { staker: [1; 32], withdrawer: withdrawer.to_bytes() },
+            ..Meta::default()
+        };
+        let signers: Vec<[u8; 32]> = ix
+            .accounts
+            .iter()
+            .filter(|m| m.is_signer)
+            .map(|m| m.pubkey.to_bytes())
+            .collect();
+        let args = decode_checked(&ix).with_custodian(Some(custodian_meta.pubkey.to_bytes()));
+        meta.set_lockup(&args, &signers[..], &clock()).unwrap();
+
+        assert_eq!(meta.lockup.unix_timestamp, 1_000);
+        assert_eq!(meta.lockup.epoch, 10);
+        assert_eq!(meta.lockup.custodian, new_custodian.to_bytes());
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -7,9 +9,9 @@ use solana_sdk::{system_instruction, message::Message};
 
 #[tokio::test]
 async fn authorize_harness_boots() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
-    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
+    let _program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     let ix = ixn::get_minimum_delegation();
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
@@ -20,8 +22,8 @@ async fn authorize_harness_boots() {
 #[tokio::test]
 async fn authorize_checked_staker_success() {
     // Build context
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Create stake account owned by our program, rent-exempt and correct size
@@ -87,3 +89,196 @@ async fn authorize_checked_staker_success() {
         other => panic!("expected Initialized/Stake, got {:?}", other),
     }
 }
+
+#[tokio::test]
+async fn authorize_withdrawer_under_lockup_with_native_custodian() {
+    use pinocchio_stake::state as pstate;
+    use solana_sdk::{account::Account, instruction::InstructionError, transaction::TransactionError};
+
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    let withdrawer = Keypair::new();
+    let custodian = Keypair::new();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: withdrawer.pubkey().to_bytes(),
+            withdrawer: withdrawer.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup {
+            unix_timestamp: 0,
+            epoch: u64::MAX,
+            custodian: custodian.pubkey().to_bytes(),
+        },
+    };
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports: reserve,
+            data: ixn::encode_program_stake_state(&pstate::StakeStateV2::Initialized(meta)),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let ctx = pt.start_with_context().await;
+    let new_withdrawer = Pubkey::new_unique();
+    let authorize = |custodian_key: Option<&Pubkey>| {
+        ixn::authorize(
+            &stake_key,
+            &withdrawer.pubkey(),
+            &new_withdrawer,
+            solana_sdk::stake::state::StakeAuthorize::Withdrawer,
+            custodian_key,
+        )
+    };
+
+    // lockup in force and no custodian: rejected
+    let tx = Transaction::new_signed_with_payer(&[authorize(None)], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(pinocchio_stake::error::StakeError::CustodianMissing as u32))
+    );
+
+    // a custodian slot that did not sign is no custodian; any other signer there is the wrong one
+    let mut unsigned = authorize(Some(&custodian.pubkey()));
+    unsigned.accounts[3].is_signer = false;
+    let tx = Transaction::new_signed_with_payer(&[unsigned], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(pinocchio_stake::error::StakeError::CustodianMissing as u32))
+    );
+    let intruder = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[authorize(Some(&intruder.pubkey()))],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &withdrawer, &intruder],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(pinocchio_stake::error::StakeError::LockupInForce as u32))
+    );
+
+    // the wrong authority signing: the lockup error comes first, as native;
+    // only with the custodian presented is the missing signature reported
+    let wrong_authority = |custodian_key: Option<&Pubkey>| {
+        ixn::authorize(
+            &stake_key,
+            &intruder.pubkey(),
+            &new_withdrawer,
+            solana_sdk::stake::state::StakeAuthorize::Withdrawer,
+            custodian_key,
+        )
+    };
+    let tx = Transaction::new_signed_with_payer(&[wrong_authority(None)], Some(&ctx.payer.pubkey()), &[&ctx.payer, &intruder], ctx.last_blockhash);
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(pinocchio_stake::error::StakeError::CustodianMissing as u32))
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[wrong_authority(Some(&intruder.pubkey()))],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &intruder],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(pinocchio_stake::error::StakeError::LockupInForce as u32))
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[wrong_authority(Some(&custodian.pubkey()))],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &intruder, &custodian],
+        ctx.last_blockhash,
+    );
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+
+    // native layout [stake, clock, authority, custodian]: the custodian is found after the authority
+    let ix = authorize(Some(&custodian.pubkey()));
+    assert_eq!(ix.accounts[3].pubkey, custodian.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer, &custodian], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let acct = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    match pstate::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pstate::StakeStateV2::Initialized(meta) => assert_eq!(meta.authorized.withdrawer, new_withdrawer.to_bytes()),
+        other => panic!("expected Initialized, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn authorize_checked_enforces_each_signer_role() {
+    use pinocchio_stake::{error::StakeError, state as pstate};
+    use solana_sdk::{account::Account, instruction::InstructionError, transaction::TransactionError};
+
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    let withdrawer = Keypair::new();
+    let custodian = Keypair::new();
+    let new_withdrawer = Keypair::new();
+    let intruder = Keypair::new();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: withdrawer.pubkey().to_bytes(),
+            withdrawer: withdrawer.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup { unix_timestamp: 0, epoch: u64::MAX, custodian: custodian.pubkey().to_bytes() },
+    };
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports: reserve,
+            data: ixn::encode_program_stake_state(&pstate::StakeStateV2::Initialized(meta)),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let ctx = pt.start_with_context().await;
+
+    // [stake, clock, old authority, new authority, custodian?]; `unsigned` clears one signer flag
+    let run = |custodian_key: Option<Pubkey>, unsigned: Option<usize>, signers: Vec<&Keypair>| {
+        let mut ix = ixn::authorize_checked(
+            &stake_key,
+            &withdrawer.pubkey(),
+            &new_withdrawer.pubkey(),
+            solana_sdk::stake::state::StakeAuthorize::Withdrawer,
+            custodian_key.as_ref(),
+        );
+        if let Some(position) = unsigned {
+            ix.accounts[position].is_signer = false;
+        }
+        let mut all = vec![&ctx.payer];
+        all.extend(signers);
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &all, ctx.last_blockhash);
+        async { ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap()) }
+    };
+    let custom = |e: StakeError| TransactionError::InstructionError(0, InstructionError::Custom(e as u32));
+    let missing = TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature);
+
+    // old authority
+    let err = run(Some(custodian.pubkey()), Some(2), vec![&new_withdrawer, &custodian]).await.unwrap_err();
+    assert_eq!(err, missing);
+    // new authority
+    let err = run(Some(custodian.pubkey()), Some(3), vec![&withdrawer, &custodian]).await.unwrap_err();
+    assert_eq!(err, missing);
+    // custodian: absent, passed without signing, or the wrong key
+    let err = run(None, None, vec![&withdrawer, &new_withdrawer]).await.unwrap_err();
+    assert_eq!(err, custom(StakeError::CustodianMissing));
+    let err = run(Some(custodian.pubkey()), Some(4), vec![&withdrawer, &new_withdrawer]).await.unwrap_err();
+    assert_eq!(err, custom(StakeError::CustodianMissing));
+    let err = run(Some(intruder.pubkey()), None, vec![&withdrawer, &new_withdrawer, &intruder]).await.unwrap_err();
+    assert_eq!(err, custom(StakeError::LockupInForce));
+
+    run(Some(custodian.pubkey()), None, vec![&withdrawer, &new_withdrawer, &custodian]).await.unwrap();
+}
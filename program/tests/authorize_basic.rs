@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -11,8 +13,8 @@ use solana_sdk::{
 
 #[tokio::test]
 async fn authorize_nonchecked_staker_success() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Create and initialize stake account with initial authorities
@@ -67,8 +69,8 @@ async fn authorize_nonchecked_staker_success() {
 
 #[tokio::test]
 async fn authorize_nonchecked_withdrawer_success() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     let stake = Keypair::new();
@@ -104,7 +106,6 @@ async fn authorize_nonchecked_withdrawer_success() {
     // Simulate missing old-authority signature by removing it from metas
     ix.accounts.retain(|am| am.pubkey != withdrawer.pubkey());
     // Ensure withdrawer appears as a signer meta (some SDK builders can omit when reordered)
-    let mut ix = ix;
     if let Some(pos) = ix.accounts.iter().position(|am| am.pubkey == withdrawer.pubkey()) {
         ix.accounts[pos].is_signer = true;
     } else {
@@ -132,8 +133,8 @@ async fn authorize_nonchecked_withdrawer_success() {
 
 #[tokio::test]
 async fn authorize_nonchecked_missing_old_signer_fails() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     let stake = Keypair::new();
@@ -179,7 +180,6 @@ async fn authorize_nonchecked_missing_old_signer_fails() {
             use solana_sdk::transaction::TransactionError;
             match te {
                 TransactionError::InstructionError(_, InstructionError::MissingRequiredSignature) => {}
-                TransactionError::InstructionError(_, InstructionError::Custom(_)) => {}
                 other => panic!("unexpected error: {:?}", other),
             }
         }
@@ -1,4 +1,5 @@
 #![cfg(feature = "seed")]
+#![allow(deprecated)]
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -13,8 +14,8 @@ use solana_sdk::instruction::{Instruction, AccountMeta};
 // AuthorizeCheckedWithSeed: staker authority is a derived PDA (base+seed+owner). Base signs; new staker signs.
 #[tokio::test]
 async fn authorize_checked_with_seed_staker_success() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Accounts
@@ -42,10 +43,10 @@ async fn authorize_checked_with_seed_staker_success() {
     tx.try_sign(&[&ctx.payer, &stake_acc], ctx.last_blockhash).unwrap();
     ctx.banks_client.process_transaction(tx).await.unwrap();
 
-    // InitializeChecked with base as current staker and real withdrawer (withdrawer signs)
+    // InitializeChecked with the derived key as current staker and real withdrawer (withdrawer signs)
     let init_ix = ixn::initialize_checked(
         &stake_acc.pubkey(),
-        &Authorized { staker: base.pubkey(), withdrawer: withdrawer.pubkey() },
+        &Authorized { staker: derived_staker, withdrawer: withdrawer.pubkey() },
     );
     let msg = Message::new(&[init_ix], Some(&ctx.payer.pubkey()));
     let mut tx = Transaction::new_unsigned(msg);
@@ -90,8 +91,8 @@ async fn authorize_checked_with_seed_staker_success() {
 // Non-checked variant: base signs; new authority does NOT need to sign.
 #[tokio::test]
 async fn authorize_with_seed_staker_success() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Stake account and authorities
@@ -118,13 +119,13 @@ async fn authorize_with_seed_staker_success() {
     tx.try_sign(&[&ctx.payer, &stake_acc], ctx.last_blockhash).unwrap();
     ctx.banks_client.process_transaction(tx).await.unwrap();
 
-    // InitializeChecked with base as current staker
+    // InitializeChecked with the derived key as current staker
     let init_ix = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(stake_acc.pubkey(), false),
             AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
-            AccountMeta::new_readonly(base.pubkey(), false),
+            AccountMeta::new_readonly(derived_staker, false),
             AccountMeta::new_readonly(withdrawer.pubkey(), true),
         ],
         data: vec![9u8],
@@ -168,3 +169,98 @@ async fn authorize_with_seed_staker_success() {
     }
 }
 
+
+/// Initialized stake whose withdrawer is the seed-derived key of `base`, locked
+/// far in the future under `custodian`
+async fn locked_seed_stake(base: &Keypair, seed: &str, custodian: &Pubkey) -> (ProgramTestContext, Pubkey) {
+    use pinocchio_stake::state as pstate;
+
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    let owner = solana_sdk::system_program::id();
+    let derived = Pubkey::create_with_seed(&base.pubkey(), seed, &owner).unwrap();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let state = pstate::StakeStateV2::Initialized(pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: Pubkey::new_unique().to_bytes(),
+            withdrawer: derived.to_bytes(),
+        },
+        lockup: pstate::Lockup { unix_timestamp: 0, epoch: u64::MAX, custodian: custodian.to_bytes() },
+    });
+    pt.add_account(
+        stake_key,
+        solana_sdk::account::Account {
+            lamports: reserve,
+            data: ixn::encode_program_stake_state(&state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    (pt.start_with_context().await, stake_key)
+}
+
+// Lockup in force: the custodian's signature lets the seed withdrawer rotate.
+#[tokio::test]
+async fn authorize_with_seed_withdrawer_under_lockup_with_custodian() {
+    let base = Keypair::new();
+    let custodian = Keypair::new();
+    let seed = "seed-for-withdrawer";
+    let (ctx, stake_key) = locked_seed_stake(&base, seed, &custodian.pubkey()).await;
+
+    let new_withdrawer = Pubkey::new_unique();
+    let ix = ixn::authorize_with_seed(
+        &stake_key,
+        &base.pubkey(),
+        seed.to_string(),
+        &solana_sdk::system_program::id(),
+        &new_withdrawer,
+        StakeAuthorize::Withdrawer,
+        Some(&custodian.pubkey()),
+    );
+    let msg = Message::new(&[ix], Some(&ctx.payer.pubkey()));
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_sign(&[&ctx.payer, &base, &custodian], ctx.last_blockhash).unwrap();
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let acct = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    match pinocchio_stake::state::stake_state_v2::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pinocchio_stake::state::stake_state_v2::StakeStateV2::Initialized(meta) => {
+            assert_eq!(meta.authorized.withdrawer, new_withdrawer.to_bytes());
+        }
+        other => panic!("unexpected state after authorize_with_seed: {:?}", other),
+    }
+}
+
+// Same rotation without the custodian is rejected.
+#[tokio::test]
+async fn authorize_with_seed_withdrawer_under_lockup_without_custodian() {
+    let base = Keypair::new();
+    let custodian = Keypair::new();
+    let seed = "seed-for-withdrawer";
+    let (ctx, stake_key) = locked_seed_stake(&base, seed, &custodian.pubkey()).await;
+
+    let ix = ixn::authorize_with_seed(
+        &stake_key,
+        &base.pubkey(),
+        seed.to_string(),
+        &solana_sdk::system_program::id(),
+        &Pubkey::new_unique(),
+        StakeAuthorize::Withdrawer,
+        None,
+    );
+    let msg = Message::new(&[ix], Some(&ctx.payer.pubkey()));
+    let mut tx = Transaction::new_unsigned(msg);
+    tx.try_sign(&[&ctx.payer, &base], ctx.last_blockhash).unwrap();
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        solana_sdk::transaction::TransactionError::InstructionError(
+            0,
+            solana_sdk::instruction::InstructionError::Custom(
+                pinocchio_stake::error::StakeError::CustodianMissing as u32
+            )
+        )
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -119,8 +121,8 @@ async fn bench_pinocchio_vs_native() {
     let auth = Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() };
     let ix_init_pin = ixn::initialize_checked(&stake_a.pubkey(), &auth);
     let ix_init_nat = sdk_stake_ixn::initialize_checked(&stake_a.pubkey(), &auth);
-    let units_pin = simulate(&mut ctx_pin, &[ix_init_pin.clone()], &[&withdrawer]).await;
-    let units_nat = simulate(&mut ctx_nat, &[ix_init_nat.clone()], &[&withdrawer]).await;
+    let units_pin = simulate(&mut ctx_pin, std::slice::from_ref(&ix_init_pin), &[&withdrawer]).await;
+    let units_nat = simulate(&mut ctx_nat, std::slice::from_ref(&ix_init_nat), &[&withdrawer]).await;
 
     println!("name,pin,native");
     println!("initialize_checked,{units_pin},{units_nat}");
@@ -150,8 +152,8 @@ async fn bench_pinocchio_vs_native() {
     );
     // authorize_checked requires the current authority AND the new authorized
     // signer to both sign
-    let units_pin = simulate(&mut ctx_pin, &[ix_auth_pin.clone()], &[&withdrawer, &new_withdrawer]).await;
-    let units_nat = simulate(&mut ctx_nat, &[ix_auth_nat.clone()], &[&withdrawer, &new_withdrawer]).await;
+    let units_pin = simulate(&mut ctx_pin, std::slice::from_ref(&ix_auth_pin), &[&withdrawer, &new_withdrawer]).await;
+    let units_nat = simulate(&mut ctx_nat, std::slice::from_ref(&ix_auth_nat), &[&withdrawer, &new_withdrawer]).await;
     println!("authorize_checked,{units_pin},{units_nat}");
 
     // Apply authorize_checked so subsequent lockup_checked can be signed by the new withdrawer
@@ -290,8 +292,8 @@ async fn bench_pinocchio_vs_native() {
     let withdraw_lamports = 500_000_000u64; // 0.5 SOL
     let ix_w_pin = ixn::withdraw(&stake_w.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), withdraw_lamports, None);
     let ix_w_nat = sdk_stake_ixn::withdraw(&stake_w.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), withdraw_lamports, None);
-    let units_pin = simulate(&mut ctx_pin, &[ix_w_pin.clone()], &[&withdrawer]).await;
-    let units_nat = simulate(&mut ctx_nat, &[ix_w_nat.clone()], &[&withdrawer]).await;
+    let units_pin = simulate(&mut ctx_pin, std::slice::from_ref(&ix_w_pin), &[&withdrawer]).await;
+    let units_nat = simulate(&mut ctx_nat, std::slice::from_ref(&ix_w_nat), &[&withdrawer]).await;
     println!("withdraw,{units_pin},{units_nat}");
     for (ctx, ix) in [(&mut ctx_pin, ix_w_pin), (&mut ctx_nat, ix_w_nat)] {
         let msg = Message::new(&[ix], Some(&ctx.payer.pubkey()));
@@ -355,8 +357,8 @@ async fn bench_pinocchio_vs_native() {
     // Move from stake_w (Initialized) to stake_c (Initialized)
     let ix_move_pin = ixn::move_lamports(&stake_w.pubkey(), &stake_c.pubkey(), &staker.pubkey(), 100_000_000);
     let ix_move_nat = sdk_stake_ixn::move_lamports(&stake_w.pubkey(), &stake_c.pubkey(), &staker.pubkey(), 100_000_000);
-    let units_pin = simulate(&mut ctx_pin, &[ix_move_pin.clone()], &[&staker]).await;
-    let units_nat = simulate(&mut ctx_nat, &[ix_move_nat.clone()], &[&staker]).await;
+    let units_pin = simulate(&mut ctx_pin, std::slice::from_ref(&ix_move_pin), &[&staker]).await;
+    let units_nat = simulate(&mut ctx_nat, std::slice::from_ref(&ix_move_nat), &[&staker]).await;
     println!("move_lamports,{units_pin},{units_nat}");
     // Apply move_lamports
     for (ctx, ix) in [(&mut ctx_pin, ix_move_pin), (&mut ctx_nat, ix_move_nat)] {
@@ -373,3 +375,255 @@ async fn bench_pinocchio_vs_native() {
     let units_nat = simulate(&mut ctx_nat, &[ix_min_nat], &[]).await;
     println!("get_minimum_delegation,{units_pin},{units_nat}");
 }
+
+// ---------- CU regression scenarios (compared against tests/fixtures/cu_baseline.json) ----------
+
+mod scenarios {
+    use super::common::cu_bench::{add_stake_account, CuRecorder};
+    use super::*;
+    use pinocchio_stake::state as pstate;
+    use solana_sdk::pubkey::Pubkey;
+
+    const STAKE: u64 = 5_000_000_000;
+
+    fn reserve() -> u64 {
+        solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+    }
+
+    fn meta(staker: &Keypair, withdrawer: &Keypair, lockup: pstate::Lockup) -> pstate::Meta {
+        pstate::Meta {
+            rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+            authorized: pstate::Authorized {
+                staker: staker.pubkey().to_bytes(),
+                withdrawer: withdrawer.pubkey().to_bytes(),
+            },
+            lockup,
+        }
+    }
+
+    /// Bootstrap activation (u64::MAX) is fully effective without stake history
+    fn active(meta: pstate::Meta, voter: &Pubkey, credits: u64) -> pstate::StakeStateV2 {
+        let stake = pstate::Stake {
+            delegation: pstate::Delegation::new(&voter.to_bytes(), STAKE, pstate::EpochLE::MAX),
+            credits_observed: credits.to_le_bytes(),
+        };
+        pstate::StakeStateV2::Stake(meta, stake, pstate::StakeFlags::empty())
+    }
+
+    pub struct Accounts {
+        staker: Keypair,
+        withdrawer: Keypair,
+        custodian: Keypair,
+        split_source: Pubkey,
+        split_dest: Pubkey,
+        merge_dest: Pubkey,
+        merge_source: Pubkey,
+        locked: Pubkey,
+        undelegated: Pubkey,
+        vote: Pubkey,
+    }
+
+    pub fn seed(pt: &mut solana_program_test::ProgramTest) -> Accounts {
+        let accounts = Accounts {
+            staker: Keypair::new(),
+            withdrawer: Keypair::new(),
+            custodian: Keypair::new(),
+            split_source: Pubkey::new_unique(),
+            split_dest: Pubkey::new_unique(),
+            merge_dest: Pubkey::new_unique(),
+            merge_source: Pubkey::new_unique(),
+            locked: Pubkey::new_unique(),
+            undelegated: Pubkey::new_unique(),
+            vote: Pubkey::new_unique(),
+        };
+        let voter = Pubkey::new_unique();
+        let open = meta(&accounts.staker, &accounts.withdrawer, pstate::Lockup::default());
+
+        add_stake_account(pt, accounts.split_source, reserve() + STAKE, &active(open, &voter, 0));
+        add_stake_account(pt, accounts.split_dest, reserve(), &pstate::StakeStateV2::Uninitialized);
+        add_stake_account(pt, accounts.merge_dest, reserve() + STAKE, &active(open, &voter, 10));
+        add_stake_account(pt, accounts.merge_source, reserve() + STAKE, &active(open, &voter, 20));
+
+        let lockup = pstate::Lockup {
+            unix_timestamp: 0,
+            epoch: u64::MAX,
+            custodian: accounts.custodian.pubkey().to_bytes(),
+        };
+        add_stake_account(
+            pt,
+            accounts.locked,
+            reserve() + STAKE,
+            &pstate::StakeStateV2::Initialized(meta(&accounts.staker, &accounts.withdrawer, lockup)),
+        );
+        add_stake_account(pt, accounts.undelegated, reserve() + STAKE, &pstate::StakeStateV2::Initialized(open));
+        pt.add_account(accounts.vote, vote_account());
+        accounts
+    }
+
+    fn vote_account() -> solana_sdk::account::Account {
+        use solana_sdk::vote::{program::id as vote_program_id, state::{VoteStateV3, VoteStateVersions}};
+        let vote_state = VoteStateV3 { epoch_credits: vec![(0, 42, 0)], ..VoteStateV3::default() };
+        let mut data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+        data.resize(VoteStateV3::size_of(), 0);
+        solana_sdk::account::Account { lamports: STAKE, data, owner: vote_program_id(), executable: false, rent_epoch: 0 }
+    }
+
+    /// Authority checks: the staker hands over its own role
+    pub async fn authorize_staker(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let ix = ixn::authorize(
+            &a.split_source,
+            &a.staker.pubkey(),
+            &Pubkey::new_unique(),
+            solana_sdk::stake::state::StakeAuthorize::Staker,
+            None,
+        );
+        rec.measure("authorize_staker", ctx, &[ix], &[&a.staker]).await;
+    }
+
+    pub async fn delegate_initialized(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let ix = ixn::delegate_stake(&a.undelegated, &a.staker.pubkey(), &a.vote);
+        rec.measure("delegate_initialized", ctx, &[ix], &[&a.staker]).await;
+    }
+
+    pub async fn split_active_stake(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let ixs: Vec<_> = ixn::split(&a.split_source, &a.staker.pubkey(), 2_000_000_000, &a.split_dest)
+            .into_iter()
+            .filter(|ix| ix.program_id == solana_sdk::stake::program::id())
+            .collect();
+        rec.measure("split_active_stake", ctx, &ixs, &[&a.staker]).await;
+    }
+
+    pub async fn merge_fully_active(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let ixs = ixn::merge(&a.merge_dest, &a.merge_source, &a.staker.pubkey());
+        rec.measure("merge_fully_active", ctx, &ixs, &[&a.staker]).await;
+    }
+
+    pub async fn withdraw_with_custodian(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let ix = ixn::withdraw(
+            &a.locked,
+            &a.withdrawer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000_000_000,
+            Some(&a.custodian.pubkey()),
+        );
+        rec.measure("withdraw_with_custodian", ctx, &[ix], &[&a.withdrawer, &a.custodian]).await;
+    }
+
+    /// Signer collection: the two cases above with extra signing accounts
+    /// appended, which every authority check has to look past
+    pub async fn extra_signers(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        let extra: Vec<Keypair> = (0..8).map(|_| Keypair::new()).collect();
+        let with_extra = |mut ix: Instruction| {
+            ix.accounts.extend(extra.iter().map(|k| solana_sdk::instruction::AccountMeta::new_readonly(k.pubkey(), true)));
+            ix
+        };
+
+        let authorize = ixn::authorize(
+            &a.split_source,
+            &a.staker.pubkey(),
+            &Pubkey::new_unique(),
+            solana_sdk::stake::state::StakeAuthorize::Staker,
+            None,
+        );
+        let mut signers: Vec<&Keypair> = extra.iter().collect();
+        signers.push(&a.staker);
+        rec.measure("authorize_staker_extra_signers", ctx, &[with_extra(authorize)], &signers).await;
+
+        let withdraw = ixn::withdraw(
+            &a.locked,
+            &a.withdrawer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000_000_000,
+            Some(&a.custodian.pubkey()),
+        );
+        signers.pop();
+        signers.extend([&a.withdrawer, &a.custodian]);
+        rec.measure("withdraw_with_custodian_extra_signers", ctx, &[with_extra(withdraw)], &signers).await;
+    }
+
+    /// `ix` with no signers, so only the payer has to sign the transaction
+    fn unsigned(mut ix: Instruction) -> Instruction {
+        ix.accounts.iter_mut().for_each(|m| m.is_signer = false);
+        ix
+    }
+
+    /// Malformed instructions fail in the dispatcher before any handler work
+    pub async fn rejected_early(rec: &mut CuRecorder, ctx: &mut ProgramTestContext, a: &Accounts) {
+        use solana_sdk::instruction::InstructionError;
+
+        let unknown = Instruction { program_id: solana_sdk::stake::program::id(), accounts: vec![], data: vec![0xff] };
+        rec.measure_rejected("reject_unknown_discriminant", ctx, unknown, InstructionError::InvalidInstructionData)
+            .await;
+
+        let mut split = ixn::split(&a.split_source, &a.staker.pubkey(), 2_000_000_000, &a.split_dest)
+            .into_iter()
+            .find(|ix| ix.program_id == solana_sdk::stake::program::id())
+            .unwrap();
+        split.data.truncate(5);
+        rec.measure_rejected("reject_split_short_payload", ctx, unsigned(split), InstructionError::InvalidInstructionData)
+            .await;
+
+        let mut delegate = ixn::delegate_stake(&a.merge_dest, &a.staker.pubkey(), &Pubkey::new_unique());
+        delegate.accounts.truncate(1);
+        rec.measure_rejected("reject_delegate_missing_accounts", ctx, delegate, InstructionError::NotEnoughAccountKeys)
+            .await;
+
+        let mut withdraw = ixn::withdraw(&a.locked, &a.withdrawer.pubkey(), &Pubkey::new_unique(), 1, None);
+        withdraw.accounts.truncate(2);
+        rec.measure_rejected("reject_withdraw_missing_accounts", ctx, unsigned(withdraw), InstructionError::NotEnoughAccountKeys)
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn cu_regressions() {
+    let mut pt = program_test();
+    let accounts = scenarios::seed(&mut pt);
+    let mut ctx = pt.start_with_context().await;
+
+    let mut rec = common::cu_bench::CuRecorder::new();
+    scenarios::authorize_staker(&mut rec, &mut ctx, &accounts).await;
+    scenarios::delegate_initialized(&mut rec, &mut ctx, &accounts).await;
+    scenarios::split_active_stake(&mut rec, &mut ctx, &accounts).await;
+    scenarios::merge_fully_active(&mut rec, &mut ctx, &accounts).await;
+    scenarios::withdraw_with_custodian(&mut rec, &mut ctx, &accounts).await;
+    scenarios::extra_signers(&mut rec, &mut ctx, &accounts).await;
+    scenarios::rejected_early(&mut rec, &mut ctx, &accounts).await;
+    rec.finish();
+}
+
+#[test]
+fn regression_threshold_is_relative_to_baseline() {
+    use common::cu_bench::{regressions, Regression, Units};
+
+    let baseline: Units = [("split".to_string(), 1_000), ("merge".to_string(), 2_000)].into();
+    let current: Units = [
+        ("split".to_string(), 1_050),
+        ("merge".to_string(), 2_101),
+        ("new_scenario".to_string(), 9_999),
+    ]
+    .into();
+
+    assert_eq!(
+        regressions(&baseline, &current, 5.0),
+        vec![Regression { name: "merge".into(), baseline: 2_000, current: 2_101 }]
+    );
+    assert!(regressions(&baseline, &current, 10.0).is_empty());
+    // improvements never fail
+    assert!(regressions(&current, &baseline, 0.0).is_empty());
+}
+
+#[test]
+fn baseline_round_trips_through_json() {
+    use common::cu_bench::{load_baseline, save_baseline, Units};
+
+    let path = std::env::temp_dir().join(format!("cu_baseline_{}.json", std::process::id()));
+    let units: Units = [("withdraw".to_string(), 1_234u64)].into();
+    save_baseline(&path, &units);
+    assert_eq!(load_baseline(&path), units);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(load_baseline(&path).is_empty());
+    // the checked-in baseline must always parse
+    load_baseline(&common::cu_bench::baseline_path());
+}
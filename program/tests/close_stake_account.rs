@@ -0,0 +1,121 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{state as pstate, test_utils::StakeStateBuilder};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const STAKE: u64 = 10_000_000_000;
+const EXTRA: u64 = 1_234_567;
+const RECIPIENT_START: u64 = 1_000_000_000;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn initialized(withdrawer: &Keypair, lockup_epoch: u64) -> StakeStateBuilder {
+    let withdrawer = withdrawer.pubkey().to_bytes();
+    StakeStateBuilder::initialized(withdrawer, withdrawer).lockup(0, lockup_epoch, [0; 32])
+}
+
+/// Fully active (bootstrap) delegation of `STAKE`
+fn delegated(withdrawer: &Keypair) -> StakeStateBuilder {
+    initialized(withdrawer, 0).delegation(Pubkey::new_unique().to_bytes(), STAKE)
+}
+
+async fn setup(stake_key: Pubkey, state: &StakeStateBuilder, lamports: u64) -> (ProgramTestContext, Pubkey) {
+    let mut pt = common::program_test();
+    let recipient = Pubkey::new_unique();
+    pt.add_account(stake_key, fixture_account(lamports, state));
+    pt.add_account(recipient, Account { lamports: RECIPIENT_START, ..Account::default() });
+    (pt.start_with_context().await, recipient)
+}
+
+async fn close(ctx: &ProgramTestContext, stake: &Pubkey, signer: &Keypair, recipient: &Pubkey) -> Result<(), TransactionError> {
+    let ix = ixn::close_stake_account(stake, &signer.pubkey(), recipient, None);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, signer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn lamports(ctx: &ProgramTestContext, key: Pubkey) -> u64 {
+    ctx.banks_client.get_account(key).await.unwrap().map_or(0, |a| a.lamports)
+}
+
+#[tokio::test]
+async fn close_initialized_returns_everything() {
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let (ctx, recipient) = setup(stake_key, &initialized(&withdrawer, 0), reserve() + EXTRA).await;
+
+    close(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap();
+
+    assert!(ctx.banks_client.get_account(stake_key).await.unwrap().is_none());
+    assert_eq!(lamports(&ctx, recipient).await, RECIPIENT_START + reserve() + EXTRA);
+}
+
+#[tokio::test]
+async fn close_uninitialized_needs_stake_signature() {
+    let stake = Keypair::new();
+    let (ctx, recipient) = setup(stake.pubkey(), &StakeStateBuilder::uninitialized(), reserve()).await;
+
+    let intruder = Keypair::new();
+    let err = close(&ctx, &stake.pubkey(), &intruder, &recipient).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+
+    close(&ctx, &stake.pubkey(), &stake, &recipient).await.unwrap();
+    assert_eq!(lamports(&ctx, recipient).await, RECIPIENT_START + reserve());
+}
+
+#[tokio::test]
+async fn close_rejects_active_stake() {
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let (ctx, recipient) = setup(stake_key, &delegated(&withdrawer), reserve() + STAKE).await;
+
+    let err = close(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InsufficientFunds));
+    assert_eq!(lamports(&ctx, stake_key).await, reserve() + STAKE);
+}
+
+#[tokio::test]
+async fn close_requires_withdrawer() {
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let (ctx, recipient) = setup(stake_key, &initialized(&withdrawer, 0), reserve()).await;
+    let intruder = Keypair::new();
+
+    let err = close(&ctx, &stake_key, &intruder, &recipient).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+}
+
+#[tokio::test]
+async fn close_respects_lockup() {
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let (ctx, recipient) = setup(stake_key, &initialized(&withdrawer, u64::MAX), reserve()).await;
+
+    let err = close(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(pinocchio_stake::error::StakeError::LockupInForce as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_into_itself_is_rejected() {
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let (ctx, _) = setup(stake_key, &initialized(&withdrawer, 0), reserve()).await;
+
+    let err = close(&ctx, &stake_key, &withdrawer, &stake_key).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
@@ -0,0 +1,130 @@
+//! Differential conformance harness: each fixture is one stake instruction plus
+//! its pre-state, executed both against the native stake program
+//! (`program_test_native`) and against this program (`program_test`). The
+//! native run is the expected result, so fixtures only record inputs.
+//!
+//! Fixtures are bincode-encoded [`Fixture`]s in `tests/fixtures/conformance/*.bin`.
+//! Instruction data is stored in the native wire format and translated with
+//! `pin_adapter::translate` for the program side.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    clock::Epoch,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    stake::program::id as stake_program_id,
+    transaction::{Transaction, TransactionError},
+};
+
+use super::stake_history::StakeHistoryFixture;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FixtureInstruction {
+    /// (pubkey, is_signer, is_writable)
+    pub accounts: Vec<(Pubkey, bool, bool)>,
+    /// Native (bincode `StakeInstruction`) encoding
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    pub name: String,
+    /// Accounts loaded at genesis; also the accounts compared afterwards
+    pub accounts: Vec<(Pubkey, Account)>,
+    /// Keypair bytes for every signing meta
+    pub signers: Vec<Vec<u8>>,
+    pub instruction: FixtureInstruction,
+}
+
+/// (lamports, owner, data) of an account after execution
+pub type PostState = (u64, Pubkey, Vec<u8>);
+
+/// Result and post-state of the fixture accounts
+#[derive(Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: Result<(), TransactionError>,
+    pub accounts: Vec<(Pubkey, Option<PostState>)>,
+}
+
+pub fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/conformance")
+}
+
+pub fn load_corpus(dir: &Path) -> Vec<Fixture> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default();
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "bin"));
+    paths.sort();
+    paths
+        .iter()
+        .map(|p| {
+            let bytes = std::fs::read(p).unwrap();
+            bincode::deserialize(&bytes).unwrap_or_else(|e| panic!("{}: {e}", p.display()))
+        })
+        .collect()
+}
+
+pub fn write_fixture(dir: &Path, fixture: &Fixture) {
+    std::fs::create_dir_all(dir).unwrap();
+    let path = dir.join(format!("{}.bin", fixture.name));
+    std::fs::write(path, bincode::serialize(fixture).unwrap()).unwrap();
+}
+
+impl Fixture {
+    pub fn native_instruction(&self) -> Instruction {
+        Instruction {
+            program_id: stake_program_id(),
+            accounts: self
+                .instruction
+                .accounts
+                .iter()
+                .map(|&(pubkey, is_signer, is_writable)| AccountMeta { pubkey, is_signer, is_writable })
+                .collect(),
+            data: self.instruction.data.clone(),
+        }
+    }
+
+    /// Run `ix` with the fixture's pre-state loaded into `pt`
+    pub async fn execute(&self, pt: ProgramTest, ix: Instruction) -> Outcome {
+        self.execute_at(pt, ix, None).await
+    }
+
+    /// [`execute`](Self::execute) with `history` injected and the Clock moved
+    /// to `epoch` first, for stake that is partway through warmup or cooldown
+    pub async fn execute_at(&self, mut pt: ProgramTest, ix: Instruction, history: Option<(&StakeHistoryFixture, Epoch)>) -> Outcome {
+        for (key, account) in &self.accounts {
+            pt.add_account(*key, account.clone());
+        }
+        let mut ctx = pt.start_with_context().await;
+        if let Some((history, epoch)) = history {
+            history.inject(&mut ctx, epoch).await;
+        }
+
+        let signers: Vec<Keypair> = self
+            .signers
+            .iter()
+            .map(|bytes| Keypair::try_from(bytes.as_slice()).unwrap())
+            .collect();
+        let mut all: Vec<&Keypair> = vec![&ctx.payer];
+        all.extend(signers.iter());
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &all, ctx.last_blockhash);
+        let result = ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .map_err(|e| e.unwrap());
+
+        let mut accounts = Vec::with_capacity(self.accounts.len());
+        for (key, _) in &self.accounts {
+            let post = ctx.banks_client.get_account(*key).await.unwrap();
+            accounts.push((*key, post.map(|a| (a.lamports, a.owner, a.data))));
+        }
+        Outcome { result, accounts }
+    }
+}
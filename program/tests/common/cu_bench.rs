@@ -0,0 +1,197 @@
+// Compute-unit regression harness (bench feature).
+//
+// Scenarios simulate one transaction each and record `units_consumed` under a
+// stable name. `CuRecorder::finish` compares the run against the checked-in
+// JSON baseline and panics when any scenario grew by more than the threshold.
+//
+//   CU_BENCH_THRESHOLD_PCT=2.5   allowed growth per scenario (default 5%)
+//   CU_BENCH_UPDATE=1            rewrite the baseline with this run's numbers
+//
+// Record baselines from a build without `log-error`/`log-debug`: each log line
+// adds 100+ CU and would swamp the threshold.
+//
+// Rejected scenarios (`measure_rejected`) must also stay under
+// `EARLY_BAILOUT_UNITS` outright: malformed instructions fail in the
+// dispatcher, before any handler loads state or sysvars.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account, instruction::{Instruction, InstructionError}, message::Message, pubkey::Pubkey,
+    signature::Keypair, signer::Signer, transaction::{Transaction, TransactionError},
+};
+
+pub const DEFAULT_THRESHOLD_PCT: f64 = 5.0;
+
+/// Ceiling for an instruction turned away by the dispatcher's early checks
+pub const EARLY_BAILOUT_UNITS: u64 = 500;
+
+pub fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cu_baseline.json")
+}
+
+pub fn threshold_pct() -> f64 {
+    std::env::var("CU_BENCH_THRESHOLD_PCT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD_PCT)
+}
+
+/// Scenario name -> compute units, sorted so the JSON diff stays stable
+pub type Units = BTreeMap<String, u64>;
+
+pub fn load_baseline(path: &std::path::Path) -> Units {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text)
+            .unwrap_or_else(|e| panic!("invalid CU baseline {}: {e}", path.display())),
+        Err(_) => Units::new(),
+    }
+}
+
+pub fn save_baseline(path: &std::path::Path, units: &Units) {
+    let mut text = serde_json::to_string_pretty(units).unwrap();
+    text.push('\n');
+    std::fs::write(path, text).unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub name: String,
+    pub baseline: u64,
+    pub current: u64,
+}
+
+/// Scenarios whose usage grew more than `threshold_pct` over the baseline.
+/// Scenarios without a baseline entry are new and never count as regressions.
+pub fn regressions(baseline: &Units, current: &Units, threshold_pct: f64) -> Vec<Regression> {
+    current
+        .iter()
+        .filter_map(|(name, &units)| {
+            let &before = baseline.get(name)?;
+            let limit = before as f64 * (1.0 + threshold_pct / 100.0);
+            (units as f64 > limit).then(|| Regression { name: name.clone(), baseline: before, current: units })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct CuRecorder {
+    units: Units,
+}
+
+impl CuRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, name: &str, units: u64) {
+        assert!(
+            self.units.insert(name.to_string(), units).is_none(),
+            "scenario {name} recorded twice"
+        );
+    }
+
+    /// Simulate `ixs` (payer plus `signers`) and record the units under `name`
+    pub async fn measure(
+        &mut self,
+        name: &str,
+        ctx: &mut ProgramTestContext,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> u64 {
+        let sim = ctx.banks_client.simulate_transaction(transaction(ctx, ixs, signers)).await.unwrap();
+        if let Some(Err(err)) = sim.result {
+            for line in sim.simulation_details.iter().flat_map(|d| &d.logs) {
+                eprintln!("log: {line}");
+            }
+            panic!("scenario {name} failed: {err:?}");
+        }
+        let units = sim.simulation_details.expect("simulation details").units_consumed;
+        self.record(name, units);
+        units
+    }
+
+    /// Simulate a single instruction that must fail with `expected`, record the
+    /// units under `name` and check them against `EARLY_BAILOUT_UNITS`
+    pub async fn measure_rejected(
+        &mut self,
+        name: &str,
+        ctx: &mut ProgramTestContext,
+        ix: Instruction,
+        expected: InstructionError,
+    ) -> u64 {
+        let sim = ctx.banks_client.simulate_transaction(transaction(ctx, &[ix], &[])).await.unwrap();
+        assert_eq!(
+            sim.result,
+            Some(Err(TransactionError::InstructionError(0, expected))),
+            "scenario {name} did not fail as expected"
+        );
+        let units = sim.simulation_details.expect("simulation details").units_consumed;
+        assert!(
+            units <= EARLY_BAILOUT_UNITS,
+            "scenario {name} used {units} CU to fail, limit is {EARLY_BAILOUT_UNITS}"
+        );
+        self.record(name, units);
+        units
+    }
+
+    pub fn units(&self) -> &Units {
+        &self.units
+    }
+
+    /// Compare against the checked-in baseline (or rewrite it with `CU_BENCH_UPDATE=1`)
+    pub fn finish(self) {
+        let path = baseline_path();
+        let baseline = load_baseline(&path);
+
+        println!("scenario,baseline,current");
+        for (name, units) in &self.units {
+            let before = baseline.get(name).map(|b| b.to_string()).unwrap_or_else(|| "-".into());
+            println!("{name},{before},{units}");
+        }
+
+        if std::env::var_os("CU_BENCH_UPDATE").is_some() {
+            let mut merged = baseline;
+            merged.extend(self.units);
+            save_baseline(&path, &merged);
+            return;
+        }
+
+        let threshold = threshold_pct();
+        let regressed = regressions(&baseline, &self.units, threshold);
+        assert!(
+            regressed.is_empty(),
+            "compute units regressed by more than {threshold}%: {regressed:?}"
+        );
+    }
+}
+
+/// `ixs` signed by the payer plus `signers`
+fn transaction(ctx: &ProgramTestContext, ixs: &[Instruction], signers: &[&Keypair]) -> Transaction {
+    let msg = Message::new(ixs, Some(&ctx.payer.pubkey()));
+    let mut tx = Transaction::new_unsigned(msg);
+    let mut all: Vec<&Keypair> = vec![&ctx.payer];
+    all.extend_from_slice(signers);
+    tx.try_sign(&all, ctx.last_blockhash).unwrap();
+    tx
+}
+
+/// Seed a program-owned account holding `state` before the test starts
+pub fn add_stake_account(
+    pt: &mut ProgramTest,
+    key: Pubkey,
+    lamports: u64,
+    state: &pinocchio_stake::state::StakeStateV2,
+) {
+    pt.add_account(
+        key,
+        Account {
+            lamports,
+            data: super::pin_adapter::encode_program_stake_state(state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
@@ -0,0 +1,262 @@
+//! Differential sequences: random but well-formed runs of stake instructions
+//! (create, initialize, delegate, deactivate, warp, split, merge, withdraw),
+//! applied step by step to a native bank (`program_test_native`) and to one
+//! running this program (`program_test`). After every step the transaction
+//! result and every tracked account must match, so divergences that only show
+//! up several instructions into a lifecycle are caught where they start.
+//!
+//! A sequence is a pure function of its seed and is generated up front, so a
+//! failure report (seed plus step index) replays exactly.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    stake::{
+        instruction as sdk_ixn,
+        program::id as stake_program_id,
+        state::{Authorized, Lockup, StakeStateV2},
+    },
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+use super::{conformance::PostState, pin_adapter::translate, refresh_blockhash};
+
+/// Stake accounts a sequence works over
+pub const SLOTS: usize = 5;
+/// Vote accounts a slot can delegate to
+pub const VOTES: usize = 2;
+/// Lamports each created account holds above its rent-exempt reserve
+pub const FUNDING: u64 = 10_000_000_000;
+
+const SPACE: usize = StakeStateV2::size_of();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// System-create a zeroed, stake-owned account
+    Create { slot: usize },
+    Initialize { slot: usize },
+    Delegate { slot: usize, vote: usize },
+    Deactivate { slot: usize },
+    /// Advance the bank by whole epochs
+    Warp { epochs: u64 },
+    /// Split into a created (still Uninitialized) slot
+    Split { source: usize, destination: usize, lamports: u64 },
+    Merge { destination: usize, source: usize },
+    Withdraw { slot: usize, lamports: u64 },
+}
+
+/// Where the generator believes a slot is. Steps can still fail (an inactive
+/// merge partner, too little stake left), which is compared like any other result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Empty,
+    Created,
+    Initialized,
+    Delegated,
+}
+
+/// `len` steps derived from `seed`
+pub fn sequence(seed: u64, len: usize) -> Vec<Step> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut phases = [Phase::Empty; SLOTS];
+    let mut steps = Vec::with_capacity(len);
+
+    // amounts around the interesting edges: dust, part of the funding, all of it
+    let amount = |rng: &mut StdRng| match rng.gen_range(0..4) {
+        0 => 1,
+        1 => FUNDING / 4,
+        2 => FUNDING / 2,
+        _ => FUNDING + Rent::default().minimum_balance(SPACE),
+    };
+
+    while steps.len() < len {
+        if rng.gen_ratio(1, 6) {
+            steps.push(Step::Warp { epochs: rng.gen_range(1..=2) });
+            continue;
+        }
+        let slot = rng.gen_range(0..SLOTS);
+        let in_phase = |phase: Phase| (0..SLOTS).filter(move |&s| s != slot && phases[s] == phase);
+        let step = match phases[slot] {
+            Phase::Empty => {
+                phases[slot] = Phase::Created;
+                Step::Create { slot }
+            }
+            Phase::Created => {
+                phases[slot] = Phase::Initialized;
+                Step::Initialize { slot }
+            }
+            phase => {
+                let created: Vec<usize> = in_phase(Phase::Created).collect();
+                let partners: Vec<usize> = in_phase(phase).collect();
+                match rng.gen_range(0..4) {
+                    0 if phase == Phase::Initialized => {
+                        phases[slot] = Phase::Delegated;
+                        Step::Delegate { slot, vote: rng.gen_range(0..VOTES) }
+                    }
+                    0 => Step::Deactivate { slot },
+                    1 if !created.is_empty() => {
+                        let destination = created[rng.gen_range(0..created.len())];
+                        phases[destination] = phase;
+                        Step::Split { source: slot, destination, lamports: amount(&mut rng) }
+                    }
+                    2 if !partners.is_empty() => {
+                        let source = partners[rng.gen_range(0..partners.len())];
+                        phases[source] = Phase::Empty;
+                        Step::Merge { destination: slot, source }
+                    }
+                    _ => {
+                        let lamports = amount(&mut rng);
+                        if lamports == FUNDING + Rent::default().minimum_balance(SPACE) {
+                            // the whole balance closes the account
+                            phases[slot] = Phase::Empty;
+                        }
+                        Step::Withdraw { slot, lamports }
+                    }
+                }
+            }
+        };
+        steps.push(step);
+    }
+    steps
+}
+
+/// Deterministic keys, disjoint from the conformance corpus
+fn key(n: u8) -> Keypair {
+    Keypair::new_from_array([0x80 | n; 32])
+}
+
+/// One bank plus the keys every sequence shares
+pub struct Side {
+    ctx: ProgramTestContext,
+    native: bool,
+    slots: Vec<Keypair>,
+    votes: Vec<Pubkey>,
+    authority: Keypair,
+    recipient: Pubkey,
+}
+
+impl Side {
+    pub async fn native() -> Self {
+        Self::start(super::program_test_native(), true).await
+    }
+
+    pub async fn program() -> Self {
+        Self::start(super::program_test(), false).await
+    }
+
+    async fn start(mut pt: ProgramTest, native: bool) -> Self {
+        let votes: Vec<Pubkey> = (0..VOTES as u8).map(|n| key(0x20 + n).pubkey()).collect();
+        for vote in &votes {
+            pt.add_account(*vote, vote_account());
+        }
+        let recipient = key(0x30).pubkey();
+        pt.add_account(recipient, Account { lamports: 1_000_000, ..Account::default() });
+        Self {
+            ctx: pt.start_with_context().await,
+            native,
+            slots: (0..SLOTS as u8).map(key).collect(),
+            votes,
+            authority: key(0x10),
+            recipient,
+        }
+    }
+
+    /// The accounts compared after every step
+    pub fn tracked(&self) -> Vec<Pubkey> {
+        let mut keys: Vec<Pubkey> = self.slots.iter().map(|k| k.pubkey()).collect();
+        keys.extend(&self.votes);
+        keys.push(self.recipient);
+        keys
+    }
+
+    pub async fn snapshot(&mut self) -> Vec<(Pubkey, Option<PostState>)> {
+        let mut out = Vec::new();
+        for key in self.tracked() {
+            let post = self.ctx.banks_client.get_account(key).await.unwrap();
+            out.push((key, post.map(|a| (a.lamports, a.owner, a.data))));
+        }
+        out
+    }
+
+    pub async fn apply(&mut self, step: Step) -> Result<(), TransactionError> {
+        let slot = |i: usize| self.slots[i].pubkey();
+        let authority = self.authority.pubkey();
+        let ix = match step {
+            Step::Create { slot: i } => {
+                let lamports = Rent::default().minimum_balance(SPACE) + FUNDING;
+                let payer = self.ctx.payer.pubkey();
+                system_instruction::create_account(&payer, &slot(i), lamports, SPACE as u64, &stake_program_id())
+            }
+            Step::Initialize { slot: i } => {
+                let authorized = Authorized { staker: authority, withdrawer: authority };
+                sdk_ixn::initialize(&slot(i), &authorized, &Lockup::default())
+            }
+            Step::Delegate { slot: i, vote } => sdk_ixn::delegate_stake(&slot(i), &authority, &self.votes[vote]),
+            Step::Deactivate { slot: i } => sdk_ixn::deactivate_stake(&slot(i), &authority),
+            Step::Warp { epochs } => {
+                let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+                self.ctx.warp_to_epoch(clock.epoch + epochs).unwrap();
+                // skip the partitioned reward distribution, which would reject
+                // every stake instruction with EpochRewardsActive
+                self.ctx.warp_forward_force_reward_interval_end().unwrap();
+                return Ok(());
+            }
+            Step::Split { source, destination, lamports } => {
+                // the SDK also allocates and assigns; the destination already exists
+                sdk_ixn::split(&slot(source), &authority, lamports, &slot(destination)).pop().unwrap()
+            }
+            Step::Merge { destination, source } => sdk_ixn::merge(&slot(destination), &slot(source), &authority).remove(0),
+            Step::Withdraw { slot: i, lamports } => sdk_ixn::withdraw(&slot(i), &authority, &self.recipient, lamports, None),
+        };
+        let ix = self.for_side(ix);
+        self.process(ix).await
+    }
+
+    fn for_side(&self, ix: Instruction) -> Instruction {
+        if self.native || ix.program_id != stake_program_id() {
+            ix
+        } else {
+            translate(&ix).expect("sequence steps are all native instructions")
+        }
+    }
+
+    async fn process(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        // a step may repeat an earlier transaction exactly
+        refresh_blockhash(&mut self.ctx).await;
+        let mut signers: Vec<&Keypair> = vec![&self.ctx.payer];
+        let keys = self.slots.iter().chain([&self.authority]);
+        signers.extend(keys.filter(|k| ix.accounts.iter().any(|m| m.is_signer && m.pubkey == k.pubkey())));
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.ctx.payer.pubkey()), &signers, self.ctx.last_blockhash);
+        self.ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+    }
+}
+
+fn vote_account() -> Account {
+    use solana_sdk::vote::{program::id as vote_program_id, state::{VoteStateV3, VoteStateVersions}};
+    let vote_state = VoteStateV3 { epoch_credits: vec![(0, 42, 0)], ..VoteStateV3::default() };
+    let mut data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+    data.resize(VoteStateV3::size_of(), 0);
+    Account { lamports: FUNDING, data, owner: vote_program_id(), executable: false, rent_epoch: 0 }
+}
+
+/// Run `steps` on both sides, panicking at the first step whose result or
+/// post-state differs from native
+pub async fn assert_matches_native(seed: u64, steps: &[Step]) {
+    let mut native = Side::native().await;
+    let mut program = Side::program().await;
+    for (i, &step) in steps.iter().enumerate() {
+        let expected = native.apply(step).await;
+        let actual = program.apply(step).await;
+        assert_eq!(actual, expected, "seed {seed}, step {i}: {step:?} result; sequence {:?}", &steps[..=i]);
+        let expected = native.snapshot().await;
+        let actual = program.snapshot().await;
+        assert_eq!(actual, expected, "seed {seed}, step {i}: {step:?} post-state; sequence {:?}", &steps[..=i]);
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code, deprecated, unused_imports)]
+
 use solana_program_test::{ProgramTest, ProgramTestBanksClientExt};
 // Import ReadableAccount from the standalone crate to match AccountSharedData
 use solana_account::ReadableAccount;
@@ -44,6 +46,28 @@ pub fn program_test_without_features(feature_ids: &[Pubkey]) -> ProgramTest {
 // Shared adapter for instruction translation + state helpers
 pub mod pin_adapter;
 
+// Fixture-backed StakeHistory + Clock injection for multi-epoch scenarios
+pub mod stake_history;
+
+#[cfg(feature = "bench")]
+pub mod cu_bench;
+
+// Fixture corpus executed against both native and this program
+#[cfg(feature = "conformance")]
+pub mod conformance;
+
+// Random instruction sequences stepped against native and this program
+#[cfg(feature = "conformance")]
+pub mod differential;
+
+// Recorded cluster transactions replayed against this program
+#[cfg(feature = "replay")]
+pub mod replay;
+
+// Scenario scripts recorded and diffed as simulation snapshots
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+
 pub async fn refresh_blockhash(ctx: &mut ProgramTestContext) {
     ctx.last_blockhash = ctx
         .banks_client
@@ -52,6 +76,18 @@ pub async fn refresh_blockhash(ctx: &mut ProgramTestContext) {
         .unwrap();
 }
 
+/// A program-owned stake account holding `lamports` and the built state, for
+/// `add_account` in place of setup transactions
+pub fn fixture_account(lamports: u64, state: &pinocchio_stake::test_utils::StakeStateBuilder) -> solana_sdk::account::Account {
+    solana_sdk::account::Account {
+        lamports,
+        data: state.account_data(),
+        owner: Pubkey::new_from_array(pinocchio_stake::ID),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
 pub async fn transfer(ctx: &mut ProgramTestContext, recipient: &Pubkey, amount: u64) {
     let tx = Transaction::new_signed_with_payer(
         &[system_instruction::transfer(&ctx.payer.pubkey(), recipient, amount)],
@@ -85,7 +121,7 @@ pub fn program_test_native() -> ProgramTest {
                 // Ensure a predictable name is available: copy to native_stake.so if needed
                 let target = dir.join("native_stake.so");
                 if !target.exists() {
-                    let _ = std::fs::copy(&p, &target);
+                    let _ = std::fs::copy(p, &target);
                 }
             }
             // Load under canonical stake program ID using a static program name
@@ -122,7 +158,7 @@ pub fn program_test_native() -> ProgramTest {
                 std::env::set_var("BPF_OUT_DIR", dir);
                 let target = dir.join("native_vote.so");
                 if !target.exists() {
-                    let _ = std::fs::copy(&p, &target);
+                    let _ = std::fs::copy(p, &target);
                 }
             }
             pt.add_upgradeable_program_to_genesis("native_vote", &solana_sdk::vote::program::id());
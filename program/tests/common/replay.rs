@@ -0,0 +1,239 @@
+//! Replay of recorded stake-program transactions (e.g. a mainnet snapshot
+//! slice): each [`RecordedTransaction`] holds the accounts the transaction
+//! loaded, its instructions in the native wire format, and the result and
+//! post-state the cluster recorded. Replaying loads the pre-state and sysvars
+//! into a bank running this program and checks it arrives at the same
+//! post-state.
+//!
+//! The recorded signers' keypairs are not available, so every signer is
+//! re-keyed to a fresh keypair: its pubkey is substituted wherever it appears
+//! (account addresses, account data such as a stake account's authorities,
+//! instruction metas and data) and substituted back before comparing.
+//!
+//! Dumps live in `tests/fixtures/replay/` (or `$REPLAY_DIR`), one transaction
+//! per file, as JSON (`.json`) or bincode (`.bin`).
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    stake::program::id as stake_program_id,
+    stake_history::StakeHistory,
+    sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+use super::pin_adapter::translate;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInstruction {
+    pub program_id: Pubkey,
+    /// (pubkey, is_signer, is_writable)
+    pub accounts: Vec<(Pubkey, bool, bool)>,
+    /// Native encoding for stake instructions, as recorded for any other program
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTransaction {
+    /// Cluster signature, only used to name the transaction in reports
+    pub signature: String,
+    /// Clock as of the transaction's slot
+    pub clock: Clock,
+    /// StakeHistory sysvar as of the slot
+    pub stake_history: StakeHistory,
+    pub fee_payer: Pubkey,
+    /// Lamports the fee payer was charged; the replay pays from its own payer
+    pub fee: u64,
+    /// Accounts the transaction loaded, before execution. Sysvars and
+    /// executable accounts are skipped: the bank provides its own.
+    pub pre_accounts: Vec<(Pubkey, Account)>,
+    pub instructions: Vec<RecordedInstruction>,
+    pub result: Result<(), TransactionError>,
+    /// Recorded post-state of the same accounts; `None` once closed
+    pub post_accounts: Vec<(Pubkey, Option<Account>)>,
+}
+
+/// (lamports, owner, data) of an account
+pub type PostState = (u64, Pubkey, Vec<u8>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: Result<(), TransactionError>,
+    pub accounts: Vec<(Pubkey, Option<PostState>)>,
+}
+
+pub fn replay_dir() -> PathBuf {
+    match std::env::var("REPLAY_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay"),
+    }
+}
+
+/// Every dump in `dir`, sorted by file name; empty if the directory is missing
+pub fn load_dir(dir: &Path) -> Vec<(PathBuf, RecordedTransaction)> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let bytes = std::fs::read(&path).unwrap();
+            let recorded = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+                Some("bin") => bincode::deserialize(&bytes).map_err(|e| e.to_string()),
+                _ => return None,
+            };
+            Some((path.clone(), recorded.unwrap_or_else(|e| panic!("{}: {e}", path.display()))))
+        })
+        .collect()
+}
+
+/// Replace every 32-byte occurrence of `from` in `data` with `to`
+pub fn substitute(data: &mut [u8], from: &Pubkey, to: &Pubkey) {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let mut i = 0;
+    while i + 32 <= data.len() {
+        if &data[i..i + 32] == from {
+            data[i..i + 32].copy_from_slice(to);
+            i += 32;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Recorded signer keys and the keypairs standing in for them
+pub struct Rekeying {
+    pairs: Vec<(Pubkey, Keypair)>,
+}
+
+impl Rekeying {
+    pub fn for_signers(recorded: &RecordedTransaction) -> Self {
+        let mut pairs: Vec<(Pubkey, Keypair)> = Vec::new();
+        for ix in &recorded.instructions {
+            for &(key, is_signer, _) in &ix.accounts {
+                if is_signer && !pairs.iter().any(|(k, _)| *k == key) {
+                    pairs.push((key, Keypair::new()));
+                }
+            }
+        }
+        Self { pairs }
+    }
+
+    pub fn keypairs(&self) -> impl Iterator<Item = &Keypair> {
+        self.pairs.iter().map(|(_, kp)| kp)
+    }
+
+    pub fn key(&self, recorded: &Pubkey) -> Pubkey {
+        self.pairs.iter().find(|(k, _)| k == recorded).map_or(*recorded, |(_, kp)| kp.pubkey())
+    }
+
+    /// Recorded keys to stand-ins
+    pub fn forward(&self, data: &mut [u8]) {
+        for (key, kp) in &self.pairs {
+            substitute(data, key, &kp.pubkey());
+        }
+    }
+
+    /// Stand-ins back to the recorded keys
+    pub fn back(&self, data: &mut [u8]) {
+        for (key, kp) in &self.pairs {
+            substitute(data, &kp.pubkey(), key);
+        }
+    }
+}
+
+fn replayed(account: &Account) -> bool {
+    !account.executable && account.owner != sysvar::id()
+}
+
+impl RecordedTransaction {
+    /// The recorded result and post-state, with the fee payer's fee added back
+    pub fn expected(&self) -> Outcome {
+        let accounts = self
+            .post_accounts
+            .iter()
+            .filter(|(key, _)| self.pre_accounts.iter().any(|(k, a)| k == key && replayed(a)))
+            .map(|(key, post)| {
+                let post = post.as_ref().map(|a| {
+                    let fee = if *key == self.fee_payer { self.fee } else { 0 };
+                    (a.lamports + fee, a.owner, a.data.clone())
+                });
+                (*key, post)
+            })
+            .collect();
+        Outcome { result: self.result.clone(), accounts }
+    }
+
+    /// Execute against this program (`program_test()`), reporting in recorded keys
+    pub async fn replay(&self) -> Outcome {
+        self.execute(super::program_test(), true).await
+    }
+
+    /// Execute against the native program instead, to check that a dump and
+    /// the re-keying reproduce the cluster before blaming this program
+    pub async fn replay_native(&self) -> Outcome {
+        self.execute(super::program_test_native(), false).await
+    }
+
+    async fn execute(&self, mut pt: ProgramTest, translate_stake: bool) -> Outcome {
+        let rekeying = Rekeying::for_signers(self);
+        for (key, account) in self.pre_accounts.iter().filter(|(_, a)| replayed(a)) {
+            let mut account = account.clone();
+            rekeying.forward(&mut account.data);
+            pt.add_account(rekeying.key(key), account);
+        }
+        let ctx = pt.start_with_context().await;
+        ctx.set_sysvar(&self.clock);
+        ctx.set_sysvar(&self.stake_history);
+
+        let instructions: Vec<Instruction> = self
+            .instructions
+            .iter()
+            .map(|recorded| {
+                let mut data = recorded.data.clone();
+                rekeying.forward(&mut data);
+                let ix = Instruction {
+                    program_id: recorded.program_id,
+                    accounts: recorded
+                        .accounts
+                        .iter()
+                        .map(|&(key, is_signer, is_writable)| AccountMeta { pubkey: rekeying.key(&key), is_signer, is_writable })
+                        .collect(),
+                    data,
+                };
+                if translate_stake && ix.program_id == stake_program_id() {
+                    translate(&ix).unwrap_or_else(|| panic!("{}: stake instruction does not translate", self.signature))
+                } else {
+                    ix
+                }
+            })
+            .collect();
+
+        let mut signers: Vec<&Keypair> = vec![&ctx.payer];
+        signers.extend(rekeying.keypairs());
+        let tx = Transaction::new_signed_with_payer(&instructions, Some(&ctx.payer.pubkey()), &signers, ctx.last_blockhash);
+        let result = ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap());
+
+        let mut accounts = Vec::new();
+        for (key, _) in self.expected().accounts {
+            let post = ctx.banks_client.get_account(rekeying.key(&key)).await.unwrap();
+            accounts.push((
+                key,
+                post.map(|mut a| {
+                    rekeying.back(&mut a.data);
+                    (a.lamports, a.owner, a.data)
+                }),
+            ));
+        }
+        Outcome { result, accounts }
+    }
+}
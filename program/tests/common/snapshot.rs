@@ -0,0 +1,191 @@
+//! Simulation snapshots (`snapshot` feature): a scenario script of stake
+//! instructions run one transaction per step, recording each step's result,
+//! compute units, return data, log lines and the post-state of the accounts it
+//! writes. The record is checked in as JSON under `tests/snapshots/`, and later
+//! runs diff against it step by step, so a change in behavior, logs or CU
+//! between versions of the program shows up with the step that changed.
+//!
+//!   SNAPSHOT_UPDATE=1   rewrite the snapshot with this run
+//!
+//! A snapshot that does not exist yet is recorded and reported, like a new CU
+//! bench scenario. Steps are written in the native wire format and translated
+//! for this program, so the same script also runs against native.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// One transaction of the script
+pub struct Step {
+    pub name: &'static str,
+    /// Native (bincode `StakeInstruction`) encoding
+    pub instruction: Instruction,
+    /// Signers besides the fee payer
+    pub signers: Vec<Keypair>,
+}
+
+pub struct Scenario {
+    /// Accounts loaded at genesis
+    pub accounts: Vec<(Pubkey, Account)>,
+    /// Run in order against one bank; no two may build the same transaction
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub owner: String,
+    /// Hex
+    pub data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepSnapshot {
+    pub name: String,
+    /// `ok`, or the transaction error
+    pub result: String,
+    pub units: u64,
+    /// Hex, None when the step set none
+    pub return_data: Option<String>,
+    pub logs: Vec<String>,
+    /// The step's writable accounts afterwards, in instruction order; None once closed
+    pub accounts: Vec<(String, Option<AccountState>)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub steps: Vec<StepSnapshot>,
+}
+
+pub fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn account_state(account: Account) -> AccountState {
+    AccountState { lamports: account.lamports, owner: account.owner.to_string(), data: hex(&account.data) }
+}
+
+impl Scenario {
+    /// Run every step against `pt`, each instruction passed through `translate` first
+    pub async fn run(&self, mut pt: ProgramTest, translate: impl Fn(&Instruction) -> Instruction) -> Snapshot {
+        for (key, account) in &self.accounts {
+            pt.add_account(*key, account.clone());
+        }
+        let ctx = pt.start_with_context().await;
+
+        let mut snapshot = Snapshot::default();
+        for step in &self.steps {
+            let ix = translate(&step.instruction);
+            let mut writable: Vec<Pubkey> = ix.accounts.iter().filter(|m| m.is_writable).map(|m| m.pubkey).collect();
+            writable.dedup();
+
+            let mut signers: Vec<&Keypair> = vec![&ctx.payer];
+            signers.extend(step.signers.iter());
+            let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &signers, ctx.last_blockhash);
+            let processed = ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+            let metadata = processed.metadata.unwrap_or_else(|| panic!("step {}: no metadata", step.name));
+
+            let mut accounts = Vec::with_capacity(writable.len());
+            for key in writable {
+                let post = ctx.banks_client.get_account(key).await.unwrap();
+                accounts.push((key.to_string(), post.map(account_state)));
+            }
+            snapshot.steps.push(StepSnapshot {
+                name: step.name.to_string(),
+                result: match processed.result {
+                    Ok(()) => "ok".to_string(),
+                    Err(err) => format!("{err:?}"),
+                },
+                units: metadata.compute_units_consumed,
+                return_data: metadata.return_data.map(|data| hex(&data.data)),
+                logs: metadata.log_messages,
+                accounts,
+            });
+        }
+        snapshot
+    }
+}
+
+/// Every difference from `expected` to `actual`, one line each
+pub fn diff(expected: &Snapshot, actual: &Snapshot) -> Vec<String> {
+    let mut out = Vec::new();
+    for (i, step) in actual.steps.iter().enumerate() {
+        let Some(before) = expected.steps.get(i) else {
+            out.push(format!("step {i} {}: new", step.name));
+            continue;
+        };
+        let at = format!("step {i} {}", step.name);
+        if before.name != step.name {
+            out.push(format!("{at}: was {}", before.name));
+        }
+        if before.result != step.result {
+            out.push(format!("{at}: result {} -> {}", before.result, step.result));
+        }
+        if before.units != step.units {
+            out.push(format!("{at}: units {} -> {}", before.units, step.units));
+        }
+        if before.return_data != step.return_data {
+            out.push(format!("{at}: return data {:?} -> {:?}", before.return_data, step.return_data));
+        }
+        if before.logs != step.logs {
+            let first = before.logs.iter().zip(&step.logs).position(|(a, b)| a != b);
+            let line = first.unwrap_or(before.logs.len().min(step.logs.len()));
+            out.push(format!(
+                "{at}: log line {line} {:?} -> {:?} ({} -> {} lines)",
+                before.logs.get(line),
+                step.logs.get(line),
+                before.logs.len(),
+                step.logs.len(),
+            ));
+        }
+        if before.accounts != step.accounts {
+            for (key, state) in &step.accounts {
+                let old = before.accounts.iter().find(|(k, _)| k == key).map(|(_, s)| s);
+                if old != Some(state) {
+                    out.push(format!("{at}: account {key} {old:?} -> {state:?}"));
+                }
+            }
+        }
+    }
+    for (i, step) in expected.steps.iter().enumerate().skip(actual.steps.len()) {
+        out.push(format!("step {i} {}: removed", step.name));
+    }
+    out
+}
+
+/// Compare `actual` against `tests/snapshots/<name>.json`, or record it there
+/// when it is missing or `SNAPSHOT_UPDATE` is set
+pub fn check(name: &str, actual: &Snapshot) {
+    let path = snapshot_dir().join(format!("{name}.json"));
+    let expected = std::fs::read_to_string(&path)
+        .ok()
+        .map(|json| serde_json::from_str::<Snapshot>(&json).unwrap_or_else(|e| panic!("{}: {e}", path.display())));
+    match expected {
+        Some(expected) if std::env::var_os("SNAPSHOT_UPDATE").is_none() => {
+            let changes = diff(&expected, actual);
+            assert!(
+                changes.is_empty(),
+                "{} differs from this run (rerun with SNAPSHOT_UPDATE=1 if intended):\n{}",
+                path.display(),
+                changes.join("\n")
+            );
+        }
+        _ => {
+            std::fs::create_dir_all(snapshot_dir()).unwrap();
+            std::fs::write(&path, serde_json::to_string_pretty(actual).unwrap() + "\n").unwrap();
+            println!("recorded {}", path.display());
+        }
+    }
+}
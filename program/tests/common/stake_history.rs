@@ -0,0 +1,75 @@
+// Fixture-backed stake history for multi-epoch activation/deactivation tests.
+//
+// `StakeHistoryFixture` replays a set of delegations epoch by epoch using the
+// SDK's activation math, producing the same StakeHistory the cluster would
+// have recorded. `inject` installs it (plus a matching Clock) into a running
+// ProgramTest bank, so the program reads it through the real sysvar path
+// without warping through every epoch.
+
+use pinocchio_stake::client::StakeHistorySnapshot;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::{Clock, Epoch},
+    stake::state::Delegation,
+    stake_history::{StakeHistory, StakeHistoryEntry},
+};
+
+/// The program applies the post-SIMD-0045 rate from epoch 0
+pub const NEW_RATE_ACTIVATION_EPOCH: Option<Epoch> = Some(0);
+
+#[derive(Debug, Clone, Default)]
+pub struct StakeHistoryFixture {
+    history: StakeHistory,
+}
+
+impl StakeHistoryFixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an explicit cluster entry for `epoch`
+    pub fn with_entry(mut self, epoch: Epoch, effective: u64, activating: u64, deactivating: u64) -> Self {
+        self.history.add(epoch, StakeHistoryEntry { effective, activating, deactivating });
+        self
+    }
+
+    /// Cluster history for `epochs` [0, up_to) where `bootstrap` lamports are
+    /// fully effective from genesis and `delegations` warm up / cool down
+    pub fn from_delegations(bootstrap: u64, delegations: &[Delegation], up_to: Epoch) -> Self {
+        let mut history = StakeHistory::default();
+        for epoch in 0..up_to {
+            let mut entry = StakeHistoryEntry { effective: bootstrap, ..Default::default() };
+            for delegation in delegations {
+                let status =
+                    delegation.stake_activating_and_deactivating(epoch, &history, NEW_RATE_ACTIVATION_EPOCH);
+                entry.effective += status.effective;
+                entry.activating += status.activating;
+                entry.deactivating += status.deactivating;
+            }
+            history.add(epoch, entry);
+        }
+        Self { history }
+    }
+
+    pub fn history(&self) -> &StakeHistory {
+        &self.history
+    }
+
+    pub fn entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        self.history.get(epoch).cloned()
+    }
+
+    /// The same history as a program-side `StakeHistoryGetEntry` source
+    pub fn snapshot(&self) -> StakeHistorySnapshot {
+        StakeHistorySnapshot::from_sysvar_data(&bincode::serialize(&self.history).unwrap()).unwrap()
+    }
+
+    /// Install the history and move the bank's Clock to `epoch`
+    pub async fn inject(&self, ctx: &mut ProgramTestContext, epoch: Epoch) {
+        let mut clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+        clock.epoch = epoch;
+        clock.leader_schedule_epoch = epoch + 1;
+        ctx.set_sysvar(&clock);
+        ctx.set_sysvar(&self.history);
+    }
+}
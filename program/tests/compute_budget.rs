@@ -0,0 +1,84 @@
+//! Every recommended compute budget (`builders`) against simulated usage: each
+//! conformance fixture runs with its budget prepended and must end as it does
+//! without one, within the limit.
+//!
+//!   cargo test --features conformance --test compute_budget
+
+#![cfg(feature = "conformance")]
+
+mod common;
+use common::*;
+use common::conformance::{corpus_dir, load_corpus, Fixture};
+use common::pin_adapter as ixn;
+use pinocchio_stake::builders::ComputeBudget;
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    transaction::TransactionError,
+};
+
+fn budget_instructions(budget: &ComputeBudget) -> Vec<Instruction> {
+    budget
+        .instructions()
+        .into_iter()
+        .map(|ix| Instruction { program_id: Pubkey::new_from_array(ix.program_id), accounts: vec![], data: ix.data })
+        .collect()
+}
+
+/// Simulate `ix` over the fixture's accounts, optionally behind `budget`:
+/// the result (instruction index as if unbudgeted) and units consumed
+async fn simulate(pt: ProgramTest, fixture: &Fixture, ix: Instruction, budget: Option<&ComputeBudget>) -> (Result<(), TransactionError>, u64) {
+    let mut pt = pt;
+    for (key, account) in &fixture.accounts {
+        pt.add_account(*key, account.clone());
+    }
+    let ctx = pt.start_with_context().await;
+    let signers: Vec<Keypair> = fixture.signers.iter().map(|bytes| Keypair::try_from(bytes.as_slice()).unwrap()).collect();
+    let mut all: Vec<&Keypair> = vec![&ctx.payer];
+    all.extend(signers.iter());
+
+    let mut ixs = budget.map(budget_instructions).unwrap_or_default();
+    let offset = ixs.len() as u8;
+    ixs.push(ix);
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&ctx.payer.pubkey()), &all, ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    let result = sim.result.unwrap().map_err(|err| match err {
+        TransactionError::InstructionError(index, err) => TransactionError::InstructionError(index - offset, err),
+        err => err,
+    });
+    (result, sim.simulation_details.unwrap().units_consumed)
+}
+
+async fn check_budgets(pt: impl Fn() -> ProgramTest, translate: impl Fn(&Instruction) -> Instruction) {
+    let mut corpus = load_corpus(&corpus_dir());
+    // not in the corpus: a query with no accounts
+    corpus.push(Fixture {
+        name: "get_minimum_delegation".into(),
+        accounts: vec![],
+        signers: vec![],
+        instruction: common::conformance::FixtureInstruction {
+            accounts: vec![],
+            data: solana_sdk::stake::instruction::get_minimum_delegation().data,
+        },
+    });
+    assert!(corpus.len() > 100);
+    for fixture in &corpus {
+        let ix = translate(&fixture.native_instruction());
+        let budget = ComputeBudget::for_stake_instructions([&ix.data[..]], 1).unwrap();
+        let (expected, _) = simulate(pt(), fixture, ix.clone(), None).await;
+        let (result, units) = simulate(pt(), fixture, ix, Some(&budget)).await;
+        assert_eq!(result, expected, "{}: budget {}", fixture.name, budget.unit_limit);
+        assert_ne!(result, Err(TransactionError::InstructionError(0, InstructionError::ComputationalBudgetExceeded)));
+        assert!(units <= budget.unit_limit as u64, "{}: {units} units, budget {}", fixture.name, budget.unit_limit);
+    }
+}
+
+#[tokio::test]
+async fn budgets_cover_native_usage() {
+    check_budgets(common::program_test_native, Clone::clone).await;
+}
+
+#[tokio::test]
+async fn budgets_cover_program_usage() {
+    check_budgets(common::program_test, |ix| ixn::translate(ix).unwrap()).await;
+}
@@ -0,0 +1,1622 @@
+#![cfg(feature = "conformance")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::conformance::{corpus_dir, load_corpus, write_fixture, Fixture, FixtureInstruction};
+use common::pin_adapter as ixn;
+use common::stake_history::{StakeHistoryFixture, NEW_RATE_ACTIVATION_EPOCH};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    rent::Rent,
+    stake::{
+        instruction::{self as sdk_ixn, LockupArgs, StakeError},
+        program::id as stake_program_id,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeActivationStatus, StakeAuthorize, StakeStateV2},
+        stake_flags::StakeFlags,
+    },
+    transaction::TransactionError,
+};
+
+const SPACE: usize = StakeStateV2::size_of();
+const LAMPORTS: u64 = 2_000_000_000;
+
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(SPACE)
+}
+
+/// Deterministic keys so a regenerated corpus is byte-identical
+fn key(n: u8) -> Keypair {
+    Keypair::new_from_array([n; 32])
+}
+
+fn stake_account(state: &StakeStateV2, lamports: u64) -> Account {
+    let mut data = bincode::serialize(state).unwrap();
+    data.resize(SPACE, 0);
+    Account { lamports, data, owner: stake_program_id(), executable: false, rent_epoch: 0 }
+}
+
+fn system_account(lamports: u64) -> Account {
+    Account { lamports, ..Account::default() }
+}
+
+fn vote_account(epoch_credits: &[(u64, u64, u64)]) -> Account {
+    use solana_sdk::vote::{program::id as vote_program_id, state::{VoteStateV3, VoteStateVersions}};
+    let vote_state = VoteStateV3 { epoch_credits: epoch_credits.to_vec(), ..VoteStateV3::default() };
+    let mut data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+    data.resize(VoteStateV3::size_of(), 0);
+    Account { lamports: LAMPORTS, data, owner: vote_program_id(), executable: false, rent_epoch: 0 }
+}
+
+/// One of the mainnet-shaped tests/fixtures/vote accounts (commission, tower,
+/// a full credits history)
+fn vote_fixture_account(name: &str) -> Account {
+    let path = format!("{}/tests/fixtures/vote/{name}.bin", env!("CARGO_MANIFEST_DIR"));
+    Account { data: std::fs::read(path).unwrap(), ..vote_account(&[]) }
+}
+
+/// The light-weight layout older unit fixtures used: u32 count, then the entries
+fn fabricated_vote_account(epoch_credits: &[(u64, u64, u64)]) -> Account {
+    let mut data = (epoch_credits.len() as u32).to_le_bytes().to_vec();
+    data.extend(epoch_credits.iter().flat_map(|&(e, c, p)| [e, c, p]).flat_map(u64::to_le_bytes));
+    Account { data, ..vote_account(epoch_credits) }
+}
+
+fn initialized(staker: &Keypair, withdrawer: &Keypair, lockup: Lockup) -> StakeStateV2 {
+    StakeStateV2::Initialized(Meta {
+        rent_exempt_reserve: reserve(),
+        authorized: Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() },
+        lockup,
+    })
+}
+
+/// `ix` with the authority at `position` swapped for `stand_in` (who also signs)
+/// and the real authority appended as an extra signing account
+fn authority_moved(mut ix: Instruction, position: usize, stand_in: &Keypair) -> Instruction {
+    let authority = std::mem::replace(&mut ix.accounts[position].pubkey, stand_in.pubkey());
+    ix.accounts.push(AccountMeta::new_readonly(authority, true));
+    ix
+}
+
+/// `ix` with the account at `position` no longer signing
+fn unsigned(mut ix: Instruction, position: usize) -> Instruction {
+    ix.accounts[position].is_signer = false;
+    ix
+}
+
+fn fixture(name: &str, accounts: Vec<(Pubkey, Account)>, signers: &[&Keypair], ix: Instruction) -> Fixture {
+    Fixture {
+        name: name.to_string(),
+        accounts,
+        signers: signers.iter().map(|k| k.to_bytes().to_vec()).collect(),
+        instruction: FixtureInstruction {
+            accounts: ix.accounts.iter().map(|m| (m.pubkey, m.is_signer, m.is_writable)).collect(),
+            data: ix.data,
+        },
+    }
+}
+
+/// The checked-in seed corpus. Each case starts from explicit account state,
+/// so it runs identically against a fresh bank on either side.
+fn seed_corpus() -> Vec<Fixture> {
+    let stake = key(1);
+    let staker = key(2);
+    let withdrawer = key(3);
+    let custodian = key(4);
+    let new_authority = key(5);
+    let other = key(6);
+    let recipient = key(7);
+
+    let auth = Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() };
+    let locked = Lockup { unix_timestamp: 0, epoch: u64::MAX, custodian: custodian.pubkey() };
+    let locked_by_timestamp = Lockup { unix_timestamp: i64::MAX, epoch: 0, ..locked };
+    let expired = Lockup { unix_timestamp: 0, epoch: 0, ..locked };
+    let uninit = (stake.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()));
+    let init = |lockup: Lockup, extra: u64| {
+        (stake.pubkey(), stake_account(&initialized(&staker, &withdrawer, lockup), reserve() + extra))
+    };
+    let recipient_account = (recipient.pubkey(), system_account(1_000_000));
+    // Uninitialized stake data with a nonzero byte past the tag
+    let stray_bytes = || {
+        let mut data = stake_account(&StakeStateV2::Uninitialized, 0).data;
+        data[100] = 7;
+        data
+    };
+    let uninit_funded = |extra: u64, stray: bool| {
+        let account = stake_account(&StakeStateV2::Uninitialized, reserve() + extra);
+        (stake.pubkey(), if stray { Account { data: stray_bytes(), ..account } } else { account })
+    };
+
+    let mut unsigned_init = sdk_ixn::initialize_checked(&stake.pubkey(), &auth);
+    unsigned_init.accounts[3].is_signer = false;
+
+    let seed = "conformance";
+    let base = key(8);
+    let seed_withdrawer = Pubkey::create_with_seed(&base.pubkey(), seed, &solana_sdk::system_program::id()).unwrap();
+    let seed_state = StakeStateV2::Initialized(Meta {
+        rent_exempt_reserve: reserve(),
+        authorized: Authorized { staker: staker.pubkey(), withdrawer: seed_withdrawer },
+        lockup: Lockup::default(),
+    });
+    let locked_seed_state = StakeStateV2::Initialized(Meta {
+        rent_exempt_reserve: reserve(),
+        authorized: Authorized { staker: staker.pubkey(), withdrawer: seed_withdrawer },
+        lockup: locked,
+    });
+
+    // genesis runs in epoch 0: activating in 0 is still warming up
+    let delegated = |activation_epoch: u64, deactivation_epoch: u64| {
+        let meta = Meta {
+            rent_exempt_reserve: reserve(),
+            authorized: auth,
+            lockup: Lockup::default(),
+        };
+        let delegation = Delegation {
+            voter_pubkey: other.pubkey(),
+            stake: LAMPORTS,
+            activation_epoch,
+            deactivation_epoch,
+            ..Delegation::default()
+        };
+        let state = StakeStateV2::Stake(meta, Stake { delegation, credits_observed: 0 }, StakeFlags::empty());
+        (stake.pubkey(), stake_account(&state, reserve() + LAMPORTS))
+    };
+
+    // bootstrap (fully active) delegation holding `spare` free lamports
+    let active_with_spare = |spare: u64| {
+        let (key, account) = delegated(u64::MAX, u64::MAX);
+        (key, Account { lamports: account.lamports + spare, ..account })
+    };
+
+    let default_staker_state = StakeStateV2::Initialized(Meta {
+        rent_exempt_reserve: reserve(),
+        authorized: Authorized { staker: Pubkey::default(), withdrawer: withdrawer.pubkey() },
+        lockup: Lockup::default(),
+    });
+
+    let split_dest = key(9);
+    let merge_src = key(10);
+    // the source signs as its own authority
+    let split_uninitialized =
+        |lamports| sdk_ixn::split(&stake.pubkey(), &stake.pubkey(), lamports, &split_dest.pubkey()).pop().unwrap();
+    let second_vote = key(11);
+    let pool = |key: &Keypair| (key.pubkey(), stake_account(&StakeStateV2::RewardsPool, reserve() + LAMPORTS));
+
+    vec![
+        fixture(
+            "initialize",
+            vec![uninit.clone()],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_checked",
+            vec![uninit.clone()],
+            &[&withdrawer],
+            sdk_ixn::initialize_checked(&stake.pubkey(), &auth),
+        ),
+        fixture("initialize_checked_unsigned_withdrawer", vec![uninit.clone()], &[], unsigned_init),
+        // Initialize rejections, in native's order: size, owner, state, then rent
+        // (underfunded accounts keep a lamport so genesis still creates them)
+        fixture(
+            "initialize_already_initialized",
+            vec![init(Lockup::default(), 0)],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_already_delegated",
+            vec![delegated(0, u64::MAX)],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &Lockup::default()),
+        ),
+        fixture(
+            "initialize_checked_already_initialized",
+            vec![init(Lockup::default(), 0)],
+            &[&withdrawer],
+            sdk_ixn::initialize_checked(&stake.pubkey(), &auth),
+        ),
+        fixture(
+            "initialize_underfunded",
+            vec![(stake.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() - 1))],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_checked_underfunded",
+            vec![(stake.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() - 1))],
+            &[&withdrawer],
+            sdk_ixn::initialize_checked(&stake.pubkey(), &auth),
+        ),
+        fixture(
+            "initialize_initialized_underfunded",
+            vec![(stake.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve() - 1))],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_oversized_account",
+            vec![(stake.pubkey(), Account { data: vec![0; SPACE + 1], ..stake_account(&StakeStateV2::Uninitialized, LAMPORTS) })],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_undersized_underfunded_account",
+            vec![(stake.pubkey(), Account { data: vec![0; SPACE - 1], ..stake_account(&StakeStateV2::Uninitialized, 1) })],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "initialize_foreign_underfunded_account",
+            vec![(stake.pubkey(), Account { owner: other.pubkey(), ..stake_account(&StakeStateV2::Uninitialized, 1) })],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &locked),
+        ),
+        fixture(
+            "authorize_staker",
+            vec![init(Lockup::default(), 0)],
+            &[&staker],
+            sdk_ixn::authorize(&stake.pubkey(), &staker.pubkey(), &new_authority.pubkey(), StakeAuthorize::Staker, None),
+        ),
+        fixture(
+            "authorize_withdrawer_by_staker",
+            vec![init(Lockup::default(), 0)],
+            &[&staker],
+            sdk_ixn::authorize(&stake.pubkey(), &staker.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_withdrawer_locked_with_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer, &custodian],
+            sdk_ixn::authorize(
+                &stake.pubkey(),
+                &withdrawer.pubkey(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                Some(&custodian.pubkey()),
+            ),
+        ),
+        fixture(
+            "authorize_checked_withdrawer",
+            vec![init(Lockup::default(), 0)],
+            &[&withdrawer, &new_authority],
+            sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        // AuthorizeChecked signer roles: old authority, new authority, custodian
+        fixture(
+            "authorize_checked_staker_by_withdrawer",
+            vec![init(Lockup::default(), 0)],
+            &[&withdrawer, &new_authority],
+            sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Staker, None),
+        ),
+        fixture(
+            "authorize_with_seed_locked_custodian_not_signer",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base],
+            unsigned(
+                sdk_ixn::authorize_with_seed(
+                    &stake.pubkey(),
+                    &base.pubkey(),
+                    seed.to_string(),
+                    &solana_sdk::system_program::id(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    Some(&custodian.pubkey()),
+                ),
+                3,
+            ),
+        ),
+        fixture(
+            "authorize_checked_old_authority_not_signer",
+            vec![init(Lockup::default(), 0)],
+            &[&new_authority],
+            unsigned(
+                sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+                2,
+            ),
+        ),
+        fixture(
+            "authorize_checked_new_authority_not_signer",
+            vec![init(Lockup::default(), 0)],
+            &[&withdrawer],
+            unsigned(
+                sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+                3,
+            ),
+        ),
+        fixture(
+            "authorize_checked_locked_without_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority],
+            sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_checked_locked_custodian_not_signer",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority],
+            unsigned(
+                sdk_ixn::authorize_checked(
+                    &stake.pubkey(),
+                    &withdrawer.pubkey(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    Some(&custodian.pubkey()),
+                ),
+                4,
+            ),
+        ),
+        fixture(
+            "authorize_checked_locked_wrong_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority, &other],
+            sdk_ixn::authorize_checked(
+                &stake.pubkey(),
+                &withdrawer.pubkey(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                Some(&other.pubkey()),
+            ),
+        ),
+        // only the custodian's signature in slot 4 makes these withdrawer changes valid
+        fixture(
+            "authorize_checked_locked_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority, &custodian],
+            sdk_ixn::authorize_checked(
+                &stake.pubkey(),
+                &withdrawer.pubkey(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                Some(&custodian.pubkey()),
+            ),
+        ),
+        fixture(
+            "authorize_checked_locked_custodian_after_extra_signer",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority, &other, &custodian],
+            {
+                let mut ix = sdk_ixn::authorize_checked(
+                    &stake.pubkey(),
+                    &withdrawer.pubkey(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    Some(&other.pubkey()),
+                );
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        fixture(
+            "authorize_checked_locked_custodian_as_old_authority",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority, &custodian],
+            authority_moved(
+                sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+                2,
+                &custodian,
+            ),
+        ),
+        // the custodian is read from its slot only; signing in another position does not count
+        fixture(
+            "authorize_locked_custodian_after_extra_signer",
+            vec![init(locked, 0)],
+            &[&withdrawer, &other, &custodian],
+            {
+                let mut ix = sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey()));
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        fixture(
+            "authorize_locked_custodian_signed_after_unsigned_slot",
+            vec![init(locked, 0)],
+            &[&withdrawer, &custodian],
+            {
+                let mut ix = unsigned(sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey())), 3);
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        fixture(
+            "authorize_checked_locked_custodian_signed_after_unsigned_slot",
+            vec![init(locked, 0)],
+            &[&withdrawer, &new_authority, &custodian],
+            {
+                let mut ix = unsigned(sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey())), 4);
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        fixture(
+            "authorize_checked_with_seed_locked_custodian_after_extra_signer",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base, &new_authority, &other, &custodian],
+            {
+                let mut ix = sdk_ixn::authorize_checked_with_seed(&stake.pubkey(), &base.pubkey(), seed.to_string(), &solana_sdk::system_program::id(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey()));
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        // the staker role is not subject to the lockup
+        fixture(
+            "authorize_checked_staker_locked_without_custodian",
+            vec![init(locked, 0)],
+            &[&staker, &new_authority],
+            sdk_ixn::authorize_checked(&stake.pubkey(), &staker.pubkey(), &new_authority.pubkey(), StakeAuthorize::Staker, None),
+        ),
+        fixture(
+            "authorize_checked_with_seed_base_not_signer",
+            vec![(stake.pubkey(), stake_account(&seed_state, reserve()))],
+            &[&new_authority],
+            unsigned(
+                sdk_ixn::authorize_checked_with_seed(
+                    &stake.pubkey(),
+                    &base.pubkey(),
+                    seed.to_string(),
+                    &solana_sdk::system_program::id(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    None,
+                ),
+                1,
+            ),
+        ),
+        fixture(
+            "authorize_checked_with_seed_new_authority_not_signer",
+            vec![(stake.pubkey(), stake_account(&seed_state, reserve()))],
+            &[&base],
+            unsigned(
+                sdk_ixn::authorize_checked_with_seed(
+                    &stake.pubkey(),
+                    &base.pubkey(),
+                    seed.to_string(),
+                    &solana_sdk::system_program::id(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    None,
+                ),
+                3,
+            ),
+        ),
+        fixture(
+            "authorize_checked_with_seed_locked_without_custodian",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base, &new_authority],
+            sdk_ixn::authorize_checked_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                None,
+            ),
+        ),
+        fixture(
+            "authorize_checked_with_seed_locked_custodian_not_signer",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base, &new_authority],
+            unsigned(
+                sdk_ixn::authorize_checked_with_seed(
+                    &stake.pubkey(),
+                    &base.pubkey(),
+                    seed.to_string(),
+                    &solana_sdk::system_program::id(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    Some(&custodian.pubkey()),
+                ),
+                4,
+            ),
+        ),
+        fixture(
+            "authorize_checked_with_seed_locked_custodian",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base, &new_authority, &custodian],
+            sdk_ixn::authorize_checked_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                Some(&custodian.pubkey()),
+            ),
+        ),
+        fixture(
+            "authorize_locked_without_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_locked_custodian_not_signer",
+            vec![init(locked, 0)],
+            &[&withdrawer],
+            unsigned(
+                sdk_ixn::authorize(
+                    &stake.pubkey(),
+                    &withdrawer.pubkey(),
+                    &new_authority.pubkey(),
+                    StakeAuthorize::Withdrawer,
+                    Some(&custodian.pubkey()),
+                ),
+                3,
+            ),
+        ),
+        // the lockup only guards the withdrawer, by epoch or by timestamp, and
+        // only until it expires
+        fixture(
+            "authorize_staker_locked_without_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Staker, None),
+        ),
+        fixture(
+            "authorize_locked_by_timestamp_without_custodian",
+            vec![init(locked_by_timestamp, 0)],
+            &[&withdrawer],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_locked_by_timestamp_with_custodian",
+            vec![init(locked_by_timestamp, 0)],
+            &[&withdrawer, &custodian],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&custodian.pubkey())),
+        ),
+        fixture(
+            "authorize_lockup_expired_without_custodian",
+            vec![init(expired, 0)],
+            &[&withdrawer],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_lockup_expired_wrong_custodian",
+            vec![init(expired, 0)],
+            &[&withdrawer, &other],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey())),
+        ),
+        fixture(
+            "authorize_locked_wrong_custodian",
+            vec![init(locked, 0)],
+            &[&withdrawer, &other],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey())),
+        ),
+        // the lockup is checked before the withdrawer's signature
+        fixture(
+            "authorize_locked_wrong_authority_without_custodian",
+            vec![init(locked, 0)],
+            &[&other],
+            sdk_ixn::authorize(&stake.pubkey(), &other.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "authorize_locked_wrong_authority_wrong_custodian",
+            vec![init(locked, 0)],
+            &[&other],
+            sdk_ixn::authorize(&stake.pubkey(), &other.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&other.pubkey())),
+        ),
+        fixture(
+            "authorize_locked_wrong_authority_with_custodian",
+            vec![init(locked, 0)],
+            &[&other, &custodian],
+            sdk_ixn::authorize(&stake.pubkey(), &other.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&custodian.pubkey())),
+        ),
+        // a withdrawer that is also the custodian presents itself in slot 3
+        fixture(
+            "authorize_locked_custodian_is_the_withdrawer",
+            vec![init(Lockup { custodian: withdrawer.pubkey(), ..locked }, 0)],
+            &[&withdrawer],
+            sdk_ixn::authorize(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, Some(&withdrawer.pubkey())),
+        ),
+        fixture(
+            "authorize_with_seed_withdrawer",
+            vec![(stake.pubkey(), stake_account(&seed_state, reserve()))],
+            &[&base],
+            sdk_ixn::authorize_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                None,
+            ),
+        ),
+        fixture(
+            "authorize_with_seed_withdrawer_locked_with_custodian",
+            vec![(stake.pubkey(), stake_account(&locked_seed_state, reserve()))],
+            &[&base, &custodian],
+            sdk_ixn::authorize_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                Some(&custodian.pubkey()),
+            ),
+        ),
+        fixture(
+            "withdraw_free_lamports",
+            vec![init(Lockup::default(), LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS / 2, None),
+        ),
+        fixture(
+            "withdraw_into_reserve",
+            vec![init(Lockup::default(), LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS + 1, None),
+        ),
+        fixture(
+            "withdraw_everything",
+            vec![init(Lockup::default(), LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), reserve() + LAMPORTS, None),
+        ),
+        fixture(
+            "withdraw_locked_without_custodian",
+            vec![init(locked, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, None),
+        ),
+        // the custodian counts only as account 5, after the withdraw authority
+        fixture(
+            "withdraw_locked_with_custodian",
+            vec![init(locked, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer, &custodian],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, Some(&custodian.pubkey())),
+        ),
+        fixture(
+            "withdraw_locked_custodian_unsigned",
+            vec![init(locked, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            unsigned(sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, Some(&custodian.pubkey())), 5),
+        ),
+        fixture(
+            "withdraw_locked_custodian_after_extra_signer",
+            vec![init(locked, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer, &other, &custodian],
+            {
+                let mut ix = sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, Some(&other.pubkey()));
+                ix.accounts.push(AccountMeta::new_readonly(custodian.pubkey(), true));
+                ix
+            },
+        ),
+        // a custodian that is passed must sign, even with no lockup in force
+        fixture(
+            "withdraw_unlocked_custodian_unsigned",
+            vec![init(Lockup { epoch: 0, ..locked }, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer],
+            unsigned(sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, Some(&custodian.pubkey())), 5),
+        ),
+        fixture(
+            "withdraw_locked_custodian_as_withdraw_authority",
+            vec![init(locked, LAMPORTS), recipient_account.clone()],
+            &[&withdrawer, &custodian],
+            authority_moved(sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, None), 4, &custodian),
+        ),
+        fixture(
+            "split_initialized",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve())),
+            ],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey())
+                .pop()
+                .unwrap(),
+        ),
+        // native requires no minimum delegation on either side of an Initialized split
+        fixture(
+            "split_initialized_dust",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve())),
+            ],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), 1, &split_dest.pubkey())
+                .pop()
+                .unwrap(),
+        ),
+        // prefunded destinations either side of the rent-exempt reserve: active
+        // stake needs the reserve already in place, inactive stake (activated
+        // and deactivated in epoch 0) pays the deficit out of the split, and a
+        // surplus is never credited as stake
+        // (dust destinations keep a lamport so genesis still creates them)
+        fixture(
+            "split_active_destination_below_reserve",
+            vec![delegated(u64::MAX, u64::MAX), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() - 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_active_destination_at_reserve",
+            vec![delegated(u64::MAX, u64::MAX), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_active_destination_above_reserve",
+            vec![delegated(u64::MAX, u64::MAX), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() + 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_inactive_destination_below_reserve",
+            vec![delegated(0, 0), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() - 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_inactive_destination_at_reserve",
+            vec![delegated(0, 0), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_inactive_destination_above_reserve",
+            vec![delegated(0, 0), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() + 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        // a 1-lamport destination: the deficit is the reserve minus that lamport
+        // plus the minimum delegation
+        fixture(
+            "split_inactive_exactly_the_deficit",
+            vec![delegated(0, 0), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), reserve(), &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_inactive_one_below_the_deficit",
+            vec![delegated(0, 0), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), reserve() - 1, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_initialized_exactly_the_deficit",
+            vec![init(Lockup::default(), LAMPORTS), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), reserve() - 1, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_initialized_one_below_the_deficit",
+            vec![init(Lockup::default(), LAMPORTS), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), reserve() - 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_initialized_destination_above_reserve",
+            vec![init(Lockup::default(), LAMPORTS), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve() + 1))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), 1, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        // an Uninitialized source only has to sign itself: lamports move, neither
+        // state is written (stray bytes survive), and a drained source is closed
+        fixture(
+            "split_uninitialized",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&stake],
+            split_uninitialized(LAMPORTS / 2),
+        ),
+        fixture(
+            "split_uninitialized_fully",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&stake],
+            split_uninitialized(reserve() + LAMPORTS),
+        ),
+        fixture(
+            "split_uninitialized_nothing",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&stake],
+            split_uninitialized(0),
+        ),
+        fixture(
+            "split_uninitialized_keeps_stray_source_bytes",
+            vec![uninit_funded(LAMPORTS, true), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&stake],
+            split_uninitialized(LAMPORTS),
+        ),
+        fixture(
+            "split_uninitialized_fully_keeps_stray_destination_bytes",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), Account { data: stray_bytes(), ..stake_account(&StakeStateV2::Uninitialized, reserve()) })],
+            &[&stake],
+            split_uninitialized(reserve() + LAMPORTS),
+        ),
+        fixture(
+            "split_uninitialized_more_than_the_balance",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&stake],
+            split_uninitialized(reserve() + LAMPORTS + 1),
+        ),
+        fixture(
+            "split_uninitialized_signed_as_extra_account",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker, &stake],
+            authority_moved(split_uninitialized(LAMPORTS / 2), 2, &staker),
+        ),
+        // a staker signature is not the source's
+        fixture(
+            "split_uninitialized_signed_by_other",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_uninitialized_signed_by_destination",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&split_dest],
+            sdk_ixn::split(&stake.pubkey(), &split_dest.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "split_uninitialized_into_initialized",
+            vec![uninit_funded(LAMPORTS, false), (split_dest.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&stake],
+            split_uninitialized(LAMPORTS / 2),
+        ),
+        fixture(
+            "deactivate_initialized",
+            vec![init(Lockup::default(), 0)],
+            &[&staker],
+            sdk_ixn::deactivate_stake(&stake.pubkey(), &staker.pubkey()),
+        ),
+        fixture(
+            "deactivate_while_activating",
+            vec![delegated(0, u64::MAX)],
+            &[&staker],
+            sdk_ixn::deactivate_stake(&stake.pubkey(), &staker.pubkey()),
+        ),
+        fixture(
+            "deactivate_already_deactivated",
+            vec![delegated(u64::MAX, 0)],
+            &[&staker],
+            sdk_ixn::deactivate_stake(&stake.pubkey(), &staker.pubkey()),
+        ),
+        // the program's own (executable, loader-owned) account is never a stake
+        // account; as a split destination it already fails native's size check
+        fixture(
+            "deactivate_program_account_as_stake",
+            vec![],
+            &[&staker],
+            sdk_ixn::deactivate_stake(&stake_program_id(), &staker.pubkey()),
+        ),
+        fixture(
+            "withdraw_program_account_as_stake",
+            vec![recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake_program_id(), &withdrawer.pubkey(), &recipient.pubkey(), 1, None),
+        ),
+        fixture(
+            "split_into_program_account",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().map(|mut ix| {
+                ix.accounts[1].pubkey = stake_program_id();
+                ix
+            }).unwrap(),
+        ),
+        // vote accounts: owner, length and layout as native deserializes them
+        fixture(
+            "delegate_to_vote_state",
+            vec![init(Lockup::default(), LAMPORTS), (other.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        // credits_observed is the vote account's latest credits, in either version
+        fixture(
+            "delegate_to_long_running_vote_account",
+            vec![init(Lockup::default(), LAMPORTS), (other.pubkey(), vote_fixture_account("current_long_running_validator"))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "delegate_to_v1_14_11_vote_account",
+            vec![init(Lockup::default(), LAMPORTS), (other.pubkey(), vote_fixture_account("v1_14_11_long_running_validator"))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "redelegate_deactivated_to_long_running_vote_account",
+            vec![delegated(0, 0), (second_vote.pubkey(), vote_fixture_account("current_long_running_validator"))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &second_vote.pubkey()),
+        ),
+        fixture(
+            "delegate_to_fabricated_vote_account",
+            vec![init(Lockup::default(), LAMPORTS), (other.pubkey(), fabricated_vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "delegate_to_foreign_owned_vote_state",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (other.pubkey(), Account { owner: Pubkey::new_from_array([9; 32]), ..vote_account(&[(0, 42, 0)]) }),
+            ],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        // re-delegating a Stake account: only stake with nothing effective is
+        // reused; effective stake may only rescind this epoch's deactivation
+        fixture(
+            "redelegate_activating_to_other_vote",
+            vec![delegated(0, u64::MAX), (second_vote.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &second_vote.pubkey()),
+        ),
+        fixture(
+            "redelegate_deactivated_to_other_vote",
+            vec![delegated(0, 0), (second_vote.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &second_vote.pubkey()),
+        ),
+        fixture(
+            "redelegate_deactivated_to_same_vote",
+            vec![delegated(0, 0), (other.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "redelegate_active_to_other_vote",
+            vec![delegated(u64::MAX, u64::MAX), (second_vote.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &second_vote.pubkey()),
+        ),
+        fixture(
+            "redelegate_active_to_same_vote",
+            vec![delegated(u64::MAX, u64::MAX), (other.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "redelegate_deactivating_to_same_vote",
+            vec![delegated(u64::MAX, 0), (other.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "redelegate_deactivating_to_other_vote",
+            vec![delegated(u64::MAX, 0), (second_vote.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &second_vote.pubkey()),
+        ),
+        fixture(
+            "deactivate_delinquent_never_voted",
+            vec![
+                delegated(0, u64::MAX),
+                (other.pubkey(), vote_account(&[])),
+                (recipient.pubkey(), vote_account(&[(0, 1, 0); 5])),
+            ],
+            &[],
+            sdk_ixn::deactivate_delinquent_stake(&stake.pubkey(), &other.pubkey(), &recipient.pubkey()),
+        ),
+        fixture(
+            "deactivate_delinquent_too_early",
+            vec![
+                delegated(0, u64::MAX),
+                (other.pubkey(), vote_account(&[(0, 1, 0)])),
+                (recipient.pubkey(), vote_account(&[(0, 1, 0); 5])),
+            ],
+            &[],
+            sdk_ixn::deactivate_delinquent_stake(&stake.pubkey(), &other.pubkey(), &recipient.pubkey()),
+        ),
+        fixture(
+            "deactivate_delinquent_fabricated_reference",
+            vec![
+                delegated(0, u64::MAX),
+                (other.pubkey(), vote_account(&[])),
+                (recipient.pubkey(), fabricated_vote_account(&[(0, 1, 0); 5])),
+            ],
+            &[],
+            sdk_ixn::deactivate_delinquent_stake(&stake.pubkey(), &other.pubkey(), &recipient.pubkey()),
+        ),
+        fixture(
+            "set_lockup_by_withdrawer",
+            vec![init(Lockup::default(), 0)],
+            &[&withdrawer],
+            sdk_ixn::set_lockup(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: Some(1_000), epoch: Some(10), custodian: Some(custodian.pubkey()) },
+                &withdrawer.pubkey(),
+            ),
+        ),
+        fixture(
+            "set_lockup_checked_by_staker",
+            vec![init(Lockup::default(), 0)],
+            &[&staker],
+            sdk_ixn::set_lockup_checked(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: None, epoch: Some(10), custodian: None },
+                &staker.pubkey(),
+            ),
+        ),
+        fixture(
+            "set_lockup_in_force_by_withdrawer",
+            vec![init(locked, 0)],
+            &[&withdrawer],
+            sdk_ixn::set_lockup(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: None, epoch: Some(0), custodian: None },
+                &withdrawer.pubkey(),
+            ),
+        ),
+        fixture(
+            "set_lockup_in_force_by_custodian",
+            vec![init(locked, 0)],
+            &[&custodian],
+            sdk_ixn::set_lockup(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: Some(1_000), epoch: None, custodian: None },
+                &custodian.pubkey(),
+            ),
+        ),
+        fixture(
+            "set_lockup_checked_in_force_custodian_handover",
+            vec![init(locked, 0)],
+            &[&custodian, &new_authority],
+            sdk_ixn::set_lockup_checked(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: None, epoch: None, custodian: Some(new_authority.pubkey()) },
+                &custodian.pubkey(),
+            ),
+        ),
+        // MergeKind classification; genesis is epoch 0, with no stake history yet
+        fixture(
+            "merge_into_deactivating",
+            vec![delegated(u64::MAX, 0), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "merge_into_activating",
+            vec![delegated(0, u64::MAX), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "merge_into_deactivated_in_activation_epoch",
+            vec![delegated(0, 0), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "move_lamports_from_activating",
+            vec![delegated(0, u64::MAX), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_from_deactivating",
+            vec![delegated(u64::MAX, 0), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        // MoveLamports takes only free lamports: above the reserve, and above delegated stake when active
+        fixture(
+            "move_lamports_inactive_at_reserve",
+            vec![init(Lockup::default(), 0), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_inactive_one_above_reserve",
+            vec![init(Lockup::default(), 1), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_fully_active_free",
+            vec![active_with_spare(1), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_fully_active_into_stake",
+            vec![active_with_spare(1), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 2),
+        ),
+        fixture(
+            "move_lamports_into_underfunded_destination",
+            vec![init(Lockup::default(), 1), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve() - 1))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_stake_from_fully_active",
+            vec![delegated(u64::MAX, u64::MAX), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_stake(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), LAMPORTS / 2),
+        ),
+        fixture(
+            "merge_initialized",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve())),
+            ],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "merge_with_foreign_authority",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (merge_src.pubkey(), stake_account(&initialized(&other, &withdrawer, Lockup::default()), reserve())),
+            ],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        // authorities are found among all signers, not at a fixed position
+        fixture(
+            "withdraw_by_withdrawer_in_extra_account",
+            vec![init(Lockup::default(), LAMPORTS), recipient_account.clone()],
+            &[&new_authority, &withdrawer],
+            authority_moved(sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, None), 4, &new_authority),
+        ),
+        fixture(
+            "split_by_staker_in_extra_account",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve())),
+            ],
+            &[&new_authority, &staker],
+            authority_moved(
+                sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+                2,
+                &new_authority,
+            ),
+        ),
+        fixture(
+            "split_unsigned_default_staker",
+            vec![
+                (stake.pubkey(), stake_account(&default_staker_state, reserve() + LAMPORTS)),
+                (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve())),
+            ],
+            &[&new_authority],
+            sdk_ixn::split(&stake.pubkey(), &new_authority.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "move_lamports_by_staker_in_extra_account",
+            vec![init(Lockup::default(), 1), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&new_authority, &staker],
+            authority_moved(sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1), 2, &new_authority),
+        ),
+        fixture(
+            "move_stake_by_staker_in_extra_account",
+            vec![delegated(u64::MAX, u64::MAX), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&new_authority, &staker],
+            authority_moved(sdk_ixn::move_stake(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), LAMPORTS / 2), 2, &new_authority),
+        ),
+        // one account passed in two writable roles
+        fixture(
+            "split_into_itself",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &stake.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "merge_into_itself",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &stake.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "move_lamports_to_itself",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &stake.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_stake_to_itself",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&staker],
+            sdk_ixn::move_stake(&stake.pubkey(), &stake.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "withdraw_to_itself",
+            vec![init(Lockup::default(), LAMPORTS)],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &stake.pubkey(), LAMPORTS, None),
+        ),
+        // a RewardsPool account in every stake account role
+        fixture(
+            "rewards_pool_initialize",
+            vec![pool(&stake)],
+            &[],
+            sdk_ixn::initialize(&stake.pubkey(), &auth, &Lockup::default()),
+        ),
+        fixture(
+            "rewards_pool_initialize_checked",
+            vec![pool(&stake)],
+            &[&withdrawer],
+            sdk_ixn::initialize_checked(&stake.pubkey(), &auth),
+        ),
+        fixture(
+            "rewards_pool_authorize",
+            vec![pool(&stake)],
+            &[&staker],
+            sdk_ixn::authorize(&stake.pubkey(), &staker.pubkey(), &new_authority.pubkey(), StakeAuthorize::Staker, None),
+        ),
+        fixture(
+            "rewards_pool_authorize_checked",
+            vec![pool(&stake)],
+            &[&withdrawer, &new_authority],
+            sdk_ixn::authorize_checked(&stake.pubkey(), &withdrawer.pubkey(), &new_authority.pubkey(), StakeAuthorize::Withdrawer, None),
+        ),
+        fixture(
+            "rewards_pool_authorize_with_seed",
+            vec![pool(&stake)],
+            &[&base],
+            sdk_ixn::authorize_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                None,
+            ),
+        ),
+        fixture(
+            "rewards_pool_authorize_checked_with_seed",
+            vec![pool(&stake)],
+            &[&base, &new_authority],
+            sdk_ixn::authorize_checked_with_seed(
+                &stake.pubkey(),
+                &base.pubkey(),
+                seed.to_string(),
+                &solana_sdk::system_program::id(),
+                &new_authority.pubkey(),
+                StakeAuthorize::Withdrawer,
+                None,
+            ),
+        ),
+        fixture(
+            "rewards_pool_delegate",
+            vec![pool(&stake), (other.pubkey(), vote_account(&[(0, 42, 0)]))],
+            &[&staker],
+            sdk_ixn::delegate_stake(&stake.pubkey(), &staker.pubkey(), &other.pubkey()),
+        ),
+        fixture(
+            "rewards_pool_split_source",
+            vec![pool(&stake), (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "rewards_pool_split_destination",
+            vec![init(Lockup::default(), LAMPORTS), pool(&split_dest)],
+            &[&staker],
+            sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), LAMPORTS / 2, &split_dest.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "rewards_pool_withdraw",
+            vec![pool(&stake), recipient_account.clone()],
+            &[&withdrawer],
+            sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), LAMPORTS, None),
+        ),
+        fixture(
+            "rewards_pool_deactivate",
+            vec![pool(&stake)],
+            &[&staker],
+            sdk_ixn::deactivate_stake(&stake.pubkey(), &staker.pubkey()),
+        ),
+        fixture(
+            "rewards_pool_set_lockup",
+            vec![pool(&stake)],
+            &[&withdrawer],
+            sdk_ixn::set_lockup(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: None, epoch: Some(10), custodian: None },
+                &withdrawer.pubkey(),
+            ),
+        ),
+        fixture(
+            "rewards_pool_set_lockup_checked",
+            vec![pool(&stake)],
+            &[&withdrawer],
+            sdk_ixn::set_lockup_checked(
+                &stake.pubkey(),
+                &LockupArgs { unix_timestamp: None, epoch: Some(10), custodian: None },
+                &withdrawer.pubkey(),
+            ),
+        ),
+        fixture(
+            "rewards_pool_merge_destination",
+            vec![pool(&stake), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "rewards_pool_merge_source",
+            vec![init(Lockup::default(), LAMPORTS), pool(&merge_src)],
+            &[&staker],
+            sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap(),
+        ),
+        fixture(
+            "rewards_pool_move_stake_source",
+            vec![pool(&stake), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_stake(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), LAMPORTS / 2),
+        ),
+        fixture(
+            "rewards_pool_move_stake_destination",
+            vec![delegated(u64::MAX, u64::MAX), pool(&merge_src)],
+            &[&staker],
+            sdk_ixn::move_stake(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), LAMPORTS / 2),
+        ),
+        fixture(
+            "rewards_pool_move_lamports_source",
+            vec![pool(&stake), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "rewards_pool_move_lamports_destination",
+            vec![init(Lockup::default(), LAMPORTS), pool(&merge_src)],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        // Uninitialized accounts fail classification with InvalidAccountData: the
+        // source before the authority check, the destination after it
+        fixture(
+            "move_lamports_from_uninitialized",
+            vec![uninit.clone(), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_from_uninitialized_by_other",
+            vec![uninit.clone(), (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()))],
+            &[&other],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &other.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_to_uninitialized",
+            vec![init(Lockup::default(), LAMPORTS), (merge_src.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_to_uninitialized_by_other",
+            vec![init(Lockup::default(), LAMPORTS), (merge_src.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&other],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &other.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_between_uninitialized",
+            vec![uninit.clone(), (merge_src.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve()))],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        // each owner is checked as its account is loaded, so a bad source wins
+        fixture(
+            "move_lamports_to_foreign_account",
+            vec![
+                init(Lockup::default(), LAMPORTS),
+                (merge_src.pubkey(), Account { owner: solana_sdk::system_program::id(), ..stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()) }),
+            ],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "move_lamports_from_uninitialized_to_foreign_account",
+            vec![
+                uninit.clone(),
+                (merge_src.pubkey(), Account { owner: solana_sdk::system_program::id(), ..stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()) }),
+            ],
+            &[&staker],
+            sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), 1),
+        ),
+        fixture(
+            "rewards_pool_deactivate_delinquent",
+            vec![
+                pool(&stake),
+                (other.pubkey(), vote_account(&[])),
+                (recipient.pubkey(), vote_account(&[(0, 1, 0); 5])),
+            ],
+            &[],
+            sdk_ixn::deactivate_delinquent_stake(&stake.pubkey(), &other.pubkey(), &recipient.pubkey()),
+        ),
+    ]
+}
+
+/// Epoch the stake history cases run at
+const HISTORY_EPOCH: u64 = 4;
+/// Bootstrap stake, fully effective since genesis, that cases warm up against
+const CLUSTER_STAKE: u64 = 10_000_000_000;
+
+/// The stake history the delegations in a case's accounts would have produced
+/// up to [`HISTORY_EPOCH`]
+fn case_history(fixture: &Fixture) -> StakeHistoryFixture {
+    let delegations: Vec<Delegation> = fixture
+        .accounts
+        .iter()
+        .filter(|(_, account)| account.owner == stake_program_id())
+        .filter_map(|(_, account)| bincode::deserialize::<StakeStateV2>(&account.data).ok()?.delegation())
+        .collect();
+    StakeHistoryFixture::from_delegations(CLUSTER_STAKE, &delegations, HISTORY_EPOCH)
+}
+
+/// Split, merge and move cases over stake partway through warmup or cooldown,
+/// each with native's expected result. They run at [`HISTORY_EPOCH`] over
+/// [`case_history`], which a corpus file can't record, so they are built here
+/// rather than stored. With 2 SOL against the 10 SOL cluster, stake activated
+/// or deactivated in epoch 3 is partly effective in epoch 4, and stake
+/// activated in epoch 4 is in its activation epoch.
+fn history_cases() -> Vec<(Fixture, Result<(), TransactionError>)> {
+    let stake = key(1);
+    let staker = key(2);
+    let withdrawer = key(3);
+    let voter = key(6);
+    let split_dest = key(9);
+    let merge_src = key(10);
+
+    let auth = Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() };
+    let delegated = |key: &Keypair, activation_epoch: u64, deactivation_epoch: u64| {
+        let meta = Meta { rent_exempt_reserve: reserve(), authorized: auth, lockup: Lockup::default() };
+        let delegation = Delegation {
+            voter_pubkey: voter.pubkey(),
+            stake: LAMPORTS,
+            activation_epoch,
+            deactivation_epoch,
+            ..Delegation::default()
+        };
+        let state = StakeStateV2::Stake(meta, Stake { delegation, credits_observed: 0 }, StakeFlags::empty());
+        (key.pubkey(), stake_account(&state, reserve() + LAMPORTS))
+    };
+    let partially_active = |key| delegated(key, 3, u64::MAX);
+    let partially_deactivated = |key| delegated(key, u64::MAX, 3);
+    let activation_epoch = |key| delegated(key, HISTORY_EPOCH, u64::MAX);
+    let fully_active = |key| delegated(key, u64::MAX, u64::MAX);
+    let initialized_src = (merge_src.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve()));
+    let split_into = |lamports| (split_dest.pubkey(), stake_account(&StakeStateV2::Uninitialized, lamports));
+
+    let split = |amount| sdk_ixn::split(&stake.pubkey(), &staker.pubkey(), amount, &split_dest.pubkey()).pop().unwrap();
+    let merge = || sdk_ixn::merge(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey()).pop().unwrap();
+    let move_stake = |amount| sdk_ixn::move_stake(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), amount);
+    let move_lamports = |amount| sdk_ixn::move_lamports(&stake.pubkey(), &merge_src.pubkey(), &staker.pubkey(), amount);
+    let case = |name, accounts, ix| fixture(name, accounts, &[&staker], ix);
+    let stake_error = |e: StakeError| TransactionError::InstructionError(0, InstructionError::Custom(e as u32));
+    let ok = Ok(());
+    let transient = Err(stake_error(StakeError::MergeTransientStake));
+    let mismatch = Err(stake_error(StakeError::MergeMismatch));
+    let insufficient = Err(TransactionError::InstructionError(0, InstructionError::InsufficientFunds));
+    let invalid_data = Err(TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    vec![
+        (case("split_partially_active", vec![partially_active(&stake), split_into(reserve())], split(LAMPORTS / 2)), ok.clone()),
+        (case("split_partially_active_underfunded_destination", vec![partially_active(&stake), split_into(reserve() - 1)], split(LAMPORTS / 2)), insufficient),
+        (case("split_partially_deactivated", vec![partially_deactivated(&stake), split_into(reserve())], split(LAMPORTS / 2)), ok.clone()),
+        (case("split_in_activation_epoch", vec![activation_epoch(&stake), split_into(1)], split(LAMPORTS / 2)), ok.clone()),
+        (case("merge_in_activation_epoch", vec![activation_epoch(&stake), activation_epoch(&merge_src)], merge()), ok.clone()),
+        (case("merge_initialized_into_activation_epoch", vec![activation_epoch(&stake), initialized_src.clone()], merge()), ok.clone()),
+        (case("merge_activation_epoch_into_initialized", vec![(stake.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve() + LAMPORTS)), activation_epoch(&merge_src)], merge()), ok),
+        (case("merge_activation_epoch_into_fully_active", vec![fully_active(&stake), activation_epoch(&merge_src)], merge()), mismatch.clone()),
+        (case("merge_fully_active_into_activation_epoch", vec![activation_epoch(&stake), fully_active(&merge_src)], merge()), mismatch),
+        (case("merge_partially_active_source", vec![activation_epoch(&stake), partially_active(&merge_src)], merge()), transient.clone()),
+        (case("merge_into_partially_active", vec![partially_active(&stake), activation_epoch(&merge_src)], merge()), transient.clone()),
+        (case("merge_partially_deactivated", vec![fully_active(&stake), partially_deactivated(&merge_src)], merge()), transient.clone()),
+        (case("move_stake_from_partially_active", vec![partially_active(&stake), initialized_src.clone()], move_stake(LAMPORTS / 4)), transient.clone()),
+        (case("move_stake_into_partially_active", vec![fully_active(&stake), partially_active(&merge_src)], move_stake(LAMPORTS / 4)), transient.clone()),
+        (case("move_stake_into_activation_epoch", vec![fully_active(&stake), activation_epoch(&merge_src)], move_stake(LAMPORTS / 4)), invalid_data.clone()),
+        (case("move_lamports_from_partially_active", vec![partially_active(&stake), initialized_src.clone()], move_lamports(1)), transient.clone()),
+        (case("move_lamports_from_activation_epoch", vec![activation_epoch(&stake), initialized_src.clone()], move_lamports(1)), invalid_data),
+        (case("move_lamports_into_partially_active", vec![(stake.pubkey(), stake_account(&initialized(&staker, &withdrawer, Lockup::default()), reserve() + 1)), partially_active(&merge_src)], move_lamports(1)), transient),
+    ]
+}
+
+#[test]
+fn history_cases_are_mid_transition() {
+    for (fixture, _) in history_cases() {
+        let history = case_history(&fixture);
+        let statuses: Vec<_> = fixture
+            .accounts
+            .iter()
+            .filter_map(|(_, account)| bincode::deserialize::<StakeStateV2>(&account.data).ok()?.delegation())
+            .map(|d| d.stake_activating_and_deactivating(HISTORY_EPOCH, history.history(), NEW_RATE_ACTIVATION_EPOCH))
+            .collect();
+        let transient = |s: &&StakeActivationStatus| s.activating > 0 || s.deactivating > 0;
+        assert!(statuses.iter().any(|s| transient(&s)), "{}: no stake in warmup or cooldown", fixture.name);
+        if fixture.name.contains("partially") {
+            assert!(statuses.iter().filter(transient).any(|s| s.effective > 0), "{}: not partly effective", fixture.name);
+        }
+    }
+}
+
+#[tokio::test]
+async fn history_cases_match_native() {
+    for (fixture, expected) in history_cases() {
+        let history = case_history(&fixture);
+        let native_ix = fixture.native_instruction();
+        let program_ix = ixn::translate(&native_ix).unwrap();
+        let native = fixture.execute_at(common::program_test_native(), native_ix, Some((&history, HISTORY_EPOCH))).await;
+        assert_eq!(native.result, expected, "fixture {} on native", fixture.name);
+        let actual = fixture.execute_at(common::program_test(), program_ix, Some((&history, HISTORY_EPOCH))).await;
+        assert_eq!(actual, native, "fixture {}", fixture.name);
+    }
+}
+
+/// Rewrite the checked-in seed corpus:
+/// `cargo test --test conformance --features conformance -- --ignored regenerate_seed_corpus`
+#[test]
+#[ignore]
+fn regenerate_seed_corpus() {
+    let dir = corpus_dir();
+    for fixture in seed_corpus() {
+        write_fixture(&dir, &fixture);
+    }
+}
+
+#[test]
+fn seed_corpus_is_current() {
+    let on_disk = load_corpus(&corpus_dir());
+    let expected = seed_corpus();
+    for fixture in &expected {
+        let found = on_disk.iter().find(|f| f.name == fixture.name);
+        let found = found.unwrap_or_else(|| panic!("{} missing; run regenerate_seed_corpus", fixture.name));
+        assert_eq!(bincode::serialize(found).unwrap(), bincode::serialize(fixture).unwrap(), "{}", fixture.name);
+    }
+}
+
+#[test]
+fn corpus_translates() {
+    for fixture in load_corpus(&corpus_dir()) {
+        assert!(ixn::translate(&fixture.native_instruction()).is_some(), "{}", fixture.name);
+    }
+}
+
+#[tokio::test]
+async fn corpus_matches_native() {
+    let corpus = load_corpus(&corpus_dir());
+    assert!(!corpus.is_empty(), "no fixtures in {}", corpus_dir().display());
+
+    for fixture in &corpus {
+        let native_ix = fixture.native_instruction();
+        let program_ix = ixn::translate(&native_ix).unwrap();
+        let expected = fixture.execute(common::program_test_native(), native_ix).await;
+        let actual = fixture.execute(common::program_test(), program_ix).await;
+        assert_eq!(actual, expected, "fixture {}", fixture.name);
+    }
+}
+
+
+
+/// Every writable meta of a fixture native accepts, marked read-only (unless
+/// the same key is writable elsewhere, which keeps the account writable):
+/// native only fails once it writes, mostly with an opaque error, where the
+/// program turns the account away up front with `InvalidAccountData`.
+/// MoveStake and MoveLamports check writability after their authority with
+/// native's `InvalidInstructionData`.
+#[tokio::test]
+async fn read_only_writable_accounts_fail_on_both() {
+    for fixture in &load_corpus(&corpus_dir()) {
+        let native_ix = fixture.native_instruction();
+        if fixture.execute(common::program_test_native(), native_ix.clone()).await.result.is_err() {
+            continue;
+        }
+        for (i, meta) in native_ix.accounts.iter().enumerate() {
+            let writable_elsewhere =
+                native_ix.accounts.iter().enumerate().any(|(j, other)| j != i && other.is_writable && other.pubkey == meta.pubkey);
+            if !meta.is_writable || writable_elsewhere {
+                continue;
+            }
+            let mut read_only = native_ix.clone();
+            read_only.accounts[i].is_writable = false;
+            let native = fixture.execute(common::program_test_native(), read_only.clone()).await.result;
+            let program = fixture.execute(common::program_test(), ixn::translate(&read_only).unwrap()).await.result;
+
+            let name = format!("{} with account {i} read-only", fixture.name);
+            assert!(native.is_err(), "{name}");
+            let expected = match native {
+                Err(TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)) => {
+                    InstructionError::InvalidInstructionData
+                }
+                _ => InstructionError::InvalidAccountData,
+            };
+            assert_eq!(program, Err(TransactionError::InstructionError(0, expected)), "{name}");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -33,8 +35,9 @@ async fn create_dummy_vote_account(ctx: &mut ProgramTestContext, kp: &Keypair) {
 }
 
 #[tokio::test]
+#[cfg_attr(not(feature = "lenient-vote"), ignore = "fabricated vote account needs lenient-vote")]
 async fn deactivate_success_after_delegate() {
-    let mut pt = common::program_test();
+    let pt = common::program_test();
     let mut ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
@@ -94,7 +97,7 @@ async fn deactivate_success_after_delegate() {
     let state = pinocchio_stake::state::stake_state_v2::StakeStateV2::deserialize(&acct.data).unwrap();
     match state {
         pinocchio_stake::state::stake_state_v2::StakeStateV2::Stake(_meta, stake_data, _flags) => {
-            let deact = u64::from_le_bytes(stake_data.delegation.deactivation_epoch);
+            let deact = stake_data.delegation.deactivation_epoch.get();
             assert_eq!(deact, clock.epoch, "deactivation epoch should match clock");
         }
         other => panic!("expected Stake state, got {:?}", other),
@@ -102,8 +105,9 @@ async fn deactivate_success_after_delegate() {
 }
 
 #[tokio::test]
+#[cfg_attr(not(feature = "lenient-vote"), ignore = "fabricated vote account needs lenient-vote")]
 async fn deactivate_missing_staker_signature_fails() {
-    let mut pt = common::program_test();
+    let pt = common::program_test();
     let mut ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
@@ -161,10 +165,151 @@ async fn deactivate_missing_staker_signature_fails() {
             use solana_sdk::instruction::InstructionError;
             match te {
                 TransactionError::InstructionError(_, InstructionError::MissingRequiredSignature) => {}
-                TransactionError::InstructionError(_, InstructionError::Custom(_)) => {}
                 other => panic!("unexpected transaction error: {:?}", other),
             }
         }
         other => panic!("unexpected banks client error: {:?}", other),
     }
 }
+
+// Withdrawer-signed Deactivate: rejected natively, allowed by `lenient-auth` only once the lockup expired
+async fn deactivate_as_withdrawer(lockup_epoch: u64) -> Result<(), solana_sdk::transaction::TransactionError> {
+    use pinocchio_stake::state as pstate;
+
+    let mut pt = common::program_test();
+    let staker = Keypair::new();
+    let withdrawer = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: staker.pubkey().to_bytes(),
+            withdrawer: withdrawer.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup { epoch: lockup_epoch, ..pstate::Lockup::default() },
+    };
+    let delegation = pstate::Delegation::new(&Pubkey::new_unique().to_bytes(), 2_000_000_000, pstate::EpochLE::MAX);
+    let state = pstate::StakeStateV2::Stake(
+        meta,
+        pstate::Stake { delegation, credits_observed: [0; 8] },
+        pstate::StakeFlags::empty(),
+    );
+    pt.add_account(
+        stake_key,
+        solana_sdk::account::Account {
+            lamports: reserve + 2_000_000_000,
+            data: ixn::encode_program_stake_state(&state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let ctx = pt.start_with_context().await;
+
+    let ix = ixn::deactivate_stake(&stake_key, &withdrawer.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+#[cfg(not(feature = "lenient-auth"))]
+#[tokio::test]
+async fn deactivate_by_withdrawer_fails_by_default() {
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+    let missing = TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature);
+    assert_eq!(deactivate_as_withdrawer(0).await.unwrap_err(), missing);
+    assert_eq!(deactivate_as_withdrawer(1_000).await.unwrap_err(), missing);
+}
+
+#[cfg(feature = "lenient-auth")]
+#[tokio::test]
+async fn deactivate_by_withdrawer_after_lockup_with_lenient_auth() {
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+    deactivate_as_withdrawer(0).await.unwrap();
+    assert_eq!(
+        deactivate_as_withdrawer(1_000).await.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+// Staker-signed Deactivate of a prepared delegation; returns the result and the stake afterwards
+async fn deactivate_prepared(
+    activation_epoch: u64,
+    deactivation_epoch: u64,
+    flags: pinocchio_stake::state::StakeFlags,
+) -> (Result<(), solana_sdk::transaction::TransactionError>, pinocchio_stake::state::Stake) {
+    use pinocchio_stake::state as pstate;
+
+    let mut pt = common::program_test();
+    let staker = Keypair::new();
+    let stake_key = Pubkey::new_unique();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: staker.pubkey().to_bytes(),
+            withdrawer: staker.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup::default(),
+    };
+    let mut delegation = pstate::Delegation::new(&Pubkey::new_unique().to_bytes(), 2_000_000_000, pstate::EpochLE::new(activation_epoch));
+    delegation.deactivation_epoch = pstate::EpochLE::new(deactivation_epoch);
+    let state = pstate::StakeStateV2::Stake(meta, pstate::Stake { delegation, credits_observed: [0; 8] }, flags);
+    pt.add_account(
+        stake_key,
+        solana_sdk::account::Account {
+            lamports: reserve + 2_000_000_000,
+            data: ixn::encode_program_stake_state(&state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let ctx = pt.start_with_context().await;
+
+    let ix = ixn::deactivate_stake(&stake_key, &staker.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &staker], ctx.last_blockhash);
+    let result = ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap());
+
+    let acct = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    match pstate::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pstate::StakeStateV2::Stake(_, stake, _) => (result, stake),
+        other => panic!("expected Stake, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn deactivate_twice_fails_with_already_deactivated() {
+    use pinocchio_stake::{error::StakeError, state::StakeFlags};
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    let (result, stake) = deactivate_prepared(u64::MAX, 0, StakeFlags::empty()).await;
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(StakeError::AlreadyDeactivated as u32))
+    );
+    assert_eq!(stake.delegation.deactivation_epoch.get(), 0);
+}
+
+#[tokio::test]
+async fn deactivate_while_activating() {
+    use pinocchio_stake::{error::StakeError, state::StakeFlags};
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    // genesis runs in epoch 0, so a delegation activated in epoch 0 is still warming up
+    let (result, stake) = deactivate_prepared(0, u64::MAX, StakeFlags::empty()).await;
+    result.unwrap();
+    assert_eq!(stake.delegation.deactivation_epoch.get(), 0);
+
+    // a redelegated stake must finish activating first
+    let (result, stake) =
+        deactivate_prepared(0, u64::MAX, StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED).await;
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted as u32)
+        )
+    );
+    assert_eq!(stake.delegation.deactivation_epoch.get(), u64::MAX);
+}
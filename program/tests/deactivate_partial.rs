@@ -0,0 +1,123 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{state as pstate, test_utils::StakeStateBuilder};
+use solana_sdk::{
+    clock::Clock,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const STAKE: u64 = 10_000_000_000;
+const PARTIAL: u64 = 3_000_000_000;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+/// Fully active stake (bootstrap activation) plus an empty, rent-funded split destination
+async fn setup(staker: &Keypair, deactivation_epoch: u64) -> (ProgramTestContext, Pubkey, Pubkey) {
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    let split_key = Pubkey::new_unique();
+
+    let stake = StakeStateBuilder::initialized(staker.pubkey().to_bytes(), staker.pubkey().to_bytes())
+        .delegation(Pubkey::new_unique().to_bytes(), STAKE)
+        .deactivation_epoch(deactivation_epoch);
+    pt.add_account(stake_key, fixture_account(reserve() + STAKE, &stake));
+    pt.add_account(split_key, fixture_account(reserve(), &StakeStateBuilder::uninitialized()));
+    (pt.start_with_context().await, stake_key, split_key)
+}
+
+async fn send(ctx: &ProgramTestContext, ix: solana_sdk::instruction::Instruction, signer: &Keypair) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, signer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn stake_of(ctx: &ProgramTestContext, key: Pubkey) -> (u64, pstate::Stake) {
+    let acct = ctx.banks_client.get_account(key).await.unwrap().unwrap();
+    match pstate::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pstate::StakeStateV2::Stake(_, stake, _) => (acct.lamports, stake),
+        other => panic!("expected Stake, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn deactivate_partial_leaves_remainder_active() {
+    let staker = Keypair::new();
+    let (ctx, stake_key, split_key) = setup(&staker, u64::MAX).await;
+    let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+
+    send(&ctx, ixn::deactivate_partial(&stake_key, &split_key, &staker.pubkey(), PARTIAL), &staker)
+        .await
+        .unwrap();
+
+    let (lamports, source) = stake_of(&ctx, stake_key).await;
+    assert_eq!(lamports, reserve() + STAKE - PARTIAL);
+    assert_eq!(source.delegation.stake.get(), STAKE - PARTIAL);
+    assert_eq!(source.delegation.deactivation_epoch.get(), u64::MAX);
+
+    // the destination already held its reserve, so all split lamports are stake
+    let (lamports, split) = stake_of(&ctx, split_key).await;
+    assert_eq!(lamports, reserve() + PARTIAL);
+    assert_eq!(split.delegation.stake.get(), PARTIAL);
+    assert_eq!(split.delegation.deactivation_epoch.get(), clock.epoch);
+    assert_eq!(split.delegation.voter_pubkey, source.delegation.voter_pubkey);
+}
+
+#[tokio::test]
+async fn deactivate_partial_rejects_the_full_balance() {
+    let staker = Keypair::new();
+    let (ctx, stake_key, split_key) = setup(&staker, u64::MAX).await;
+
+    let err = send(&ctx, ixn::deactivate_partial(&stake_key, &split_key, &staker.pubkey(), reserve() + STAKE), &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
+
+#[tokio::test]
+async fn deactivate_partial_requires_staker() {
+    let staker = Keypair::new();
+    let (ctx, stake_key, split_key) = setup(&staker, u64::MAX).await;
+    let intruder = Keypair::new();
+
+    let err = send(&ctx, ixn::deactivate_partial(&stake_key, &split_key, &intruder.pubkey(), PARTIAL), &intruder)
+        .await
+        .unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+}
+
+#[tokio::test]
+async fn deactivate_partial_rejects_deactivating_stake() {
+    let staker = Keypair::new();
+    let (ctx, stake_key, split_key) = setup(&staker, 0).await;
+
+    let err = send(&ctx, ixn::deactivate_partial(&stake_key, &split_key, &staker.pubkey(), PARTIAL), &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(pinocchio_stake::error::StakeError::AlreadyDeactivated as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn deactivate_partial_rejects_stake_as_its_own_destination() {
+    let staker = Keypair::new();
+    let (ctx, stake_key, _) = setup(&staker, u64::MAX).await;
+
+    let err = send(&ctx, ixn::deactivate_partial(&stake_key, &stake_key, &staker.pubkey(), PARTIAL), &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    let (lamports, stake) = stake_of(&ctx, stake_key).await;
+    assert_eq!(lamports, reserve() + STAKE);
+    assert_eq!(stake.delegation.deactivation_epoch.get(), u64::MAX);
+}
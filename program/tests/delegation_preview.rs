@@ -0,0 +1,83 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{instruction::DelegationPreview, state as pstate};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const STAKE: u64 = 5_000_000_000;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn initialized() -> pstate::StakeStateV2 {
+    let authority = Pubkey::new_unique().to_bytes();
+    pstate::StakeStateV2::Initialized(pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+        authorized: pstate::Authorized { staker: authority, withdrawer: authority },
+        lockup: pstate::Lockup::default(),
+    })
+}
+
+/// Simulate the query against a stake account holding `state` and `lamports`
+async fn preview(state: &pstate::StakeStateV2, lamports: u64) -> Result<Vec<u8>, TransactionError> {
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports,
+            data: ixn::encode_program_stake_state(state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let ctx = pt.start_with_context().await;
+
+    let ix = ixn::get_delegation_preview(&stake_key);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    sim.result.unwrap()?;
+    let ret = sim.simulation_details.and_then(|d| d.return_data).expect("program should return data");
+    assert_eq!(ret.program_id, Pubkey::new_from_array(pinocchio_stake::ID));
+    Ok(ret.data)
+}
+
+fn expected(lamports: u64) -> Vec<u8> {
+    let meta = match initialized() {
+        pstate::StakeStateV2::Initialized(meta) => meta,
+        _ => unreachable!(),
+    };
+    DelegationPreview::new(lamports, &meta).unwrap().to_bytes().to_vec()
+}
+
+#[tokio::test]
+async fn preview_reports_stake_above_reserve() {
+    let data = preview(&initialized(), reserve() + STAKE).await.unwrap();
+    assert_eq!(data, expected(reserve() + STAKE));
+    assert_eq!(data[..8], STAKE.to_le_bytes());
+    assert_eq!(data[8], 1);
+}
+
+#[tokio::test]
+async fn preview_flags_rent_only_account() {
+    let data = preview(&initialized(), reserve()).await.unwrap();
+    assert_eq!(data, expected(reserve()));
+    assert_eq!(data[..8], 0u64.to_le_bytes());
+    assert_eq!(data[8], 0);
+    assert_ne!(data[9..], 0u64.to_le_bytes());
+}
+
+#[tokio::test]
+async fn preview_rejects_uninitialized() {
+    let err = preview(&pstate::StakeStateV2::Uninitialized, reserve() + STAKE).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+}
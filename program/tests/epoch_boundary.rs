@@ -0,0 +1,215 @@
+//! Deactivate, withdraw and merge on either side of an epoch boundary: the
+//! bank is warped to the last slot of an epoch, then to the first slot of the
+//! next (where the epoch rewards period rejects stake writes) and the first
+//! slot after rewards, and every step must land exactly as it does on native:
+//! the epoch a deactivation records, whether the stake still counts as
+//! effective, and how merge classifies it.
+
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    clock::{Clock, Epoch},
+    epoch_rewards::EpochRewards,
+    epoch_schedule::EpochSchedule,
+    instruction::{Instruction, InstructionError},
+    rent::Rent,
+    stake::{
+        instruction::{self as sdk_ixn, StakeError},
+        program::id as stake_program_id,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2},
+        stake_flags::StakeFlags,
+    },
+    transaction::TransactionError,
+    vote::program::id as vote_program_id,
+};
+
+const STAKE: u64 = 2_000_000_000;
+
+/// Deterministic keys, so native and program runs see the same accounts
+fn key(n: u8) -> Keypair {
+    Keypair::new_from_array([n; 32])
+}
+
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(StakeStateV2::size_of())
+}
+
+fn stake_account(state: &StakeStateV2, lamports: u64) -> Account {
+    let mut data = bincode::serialize(state).unwrap();
+    data.resize(StakeStateV2::size_of(), 0);
+    Account { lamports, data, owner: stake_program_id(), executable: false, rent_epoch: 0 }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum At {
+    LastSlotOf(Epoch),
+    FirstSlotOf(Epoch),
+    /// The first slot of the epoch once its rewards have been distributed
+    FirstSlotAfterRewards(Epoch),
+}
+
+struct Step {
+    name: &'static str,
+    at: At,
+    /// Native encoding, built from the first account's lamports at that slot
+    /// (rewards land in between, so a full withdrawal is only known then)
+    instruction: Box<dyn Fn(u64) -> Instruction>,
+    signer: u8,
+}
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    name: &'static str,
+    result: Result<(), TransactionError>,
+    /// Deactivation epoch of the instruction's first account afterwards, if it
+    /// still holds a delegation
+    deactivation_epoch: Option<Epoch>,
+}
+
+struct Scenario {
+    accounts: Vec<(Pubkey, Account)>,
+    steps: Vec<Step>,
+}
+
+fn scenario() -> Scenario {
+    let (stake_a, stake_b, source, vote, recipient) = (key(1), key(2), key(3), key(4), key(5));
+    let (staker, withdrawer) = (key(6), key(7));
+    let meta = Meta {
+        rent_exempt_reserve: reserve(),
+        authorized: Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() },
+        lockup: Lockup::default(),
+    };
+    // bootstrap delegations: fully active from genesis, so only the
+    // deactivations below move them
+    let active = Delegation { voter_pubkey: vote.pubkey(), stake: STAKE, activation_epoch: u64::MAX, ..Delegation::default() };
+    let active = StakeStateV2::Stake(meta, Stake { delegation: active, credits_observed: 0 }, StakeFlags::empty());
+    let vote_data = std::fs::read(format!("{}/tests/fixtures/vote/current_new_validator.bin", env!("CARGO_MANIFEST_DIR"))).unwrap();
+
+    let (a, b, from, staker_key, withdrawer_key, to) =
+        (stake_a.pubkey(), stake_b.pubkey(), source.pubkey(), staker.pubkey(), withdrawer.pubkey(), recipient.pubkey());
+    let withdraw_all = |stake: Pubkey| -> Box<dyn Fn(u64) -> Instruction> {
+        Box::new(move |lamports| sdk_ixn::withdraw(&stake, &withdrawer_key, &to, lamports, None))
+    };
+    let deactivate =
+        |stake: Pubkey| -> Box<dyn Fn(u64) -> Instruction> { Box::new(move |_| sdk_ixn::deactivate_stake(&stake, &staker_key)) };
+    let merge_source =
+        || -> Box<dyn Fn(u64) -> Instruction> { Box::new(move |_| sdk_ixn::merge(&a, &from, &staker_key).remove(0)) };
+    let steps = vec![
+        Step { name: "deactivate_a_last_slot", at: At::LastSlotOf(2), instruction: deactivate(a), signer: 6 },
+        Step { name: "withdraw_a_last_slot", at: At::LastSlotOf(2), instruction: withdraw_all(a), signer: 7 },
+        Step { name: "merge_into_a_last_slot", at: At::LastSlotOf(2), instruction: merge_source(), signer: 6 },
+        Step { name: "deactivate_b_first_slot", at: At::FirstSlotOf(3), instruction: deactivate(b), signer: 6 },
+        Step { name: "withdraw_a_first_slot", at: At::FirstSlotOf(3), instruction: withdraw_all(a), signer: 7 },
+        Step { name: "deactivate_b_after_rewards", at: At::FirstSlotAfterRewards(3), instruction: deactivate(b), signer: 6 },
+        Step { name: "deactivate_a_again", at: At::FirstSlotAfterRewards(3), instruction: deactivate(a), signer: 6 },
+        Step { name: "withdraw_b_after_rewards", at: At::FirstSlotAfterRewards(3), instruction: withdraw_all(b), signer: 7 },
+        Step { name: "merge_into_a_after_rewards", at: At::FirstSlotAfterRewards(3), instruction: merge_source(), signer: 6 },
+        Step { name: "withdraw_a_after_rewards", at: At::FirstSlotAfterRewards(3), instruction: withdraw_all(a), signer: 7 },
+        Step { name: "withdraw_b_last_slot", at: At::LastSlotOf(3), instruction: withdraw_all(b), signer: 7 },
+        Step { name: "withdraw_b_next_epoch", at: At::FirstSlotAfterRewards(4), instruction: withdraw_all(b), signer: 7 },
+    ];
+    Scenario {
+        accounts: vec![
+            (stake_a.pubkey(), stake_account(&active, STAKE + reserve())),
+            (stake_b.pubkey(), stake_account(&active, STAKE + reserve())),
+            (source.pubkey(), stake_account(&StakeStateV2::Initialized(meta), reserve())),
+            (vote.pubkey(), Account { lamports: 1_000_000_000, data: vote_data, owner: vote_program_id(), executable: false, rent_epoch: 0 }),
+            (recipient.pubkey(), Account { lamports: 1_000_000_000, owner: solana_sdk::system_program::id(), ..Account::default() }),
+        ],
+        steps,
+    }
+}
+
+impl Scenario {
+    async fn run(&self, mut pt: ProgramTest, translate: impl Fn(&Instruction) -> Instruction) -> Vec<Outcome> {
+        for (key, account) in &self.accounts {
+            pt.add_account(*key, account.clone());
+        }
+        let mut ctx = pt.start_with_context().await;
+        let schedule: EpochSchedule = ctx.banks_client.get_sysvar().await.unwrap();
+
+        let mut outcomes = Vec::new();
+        for step in &self.steps {
+            let slot = match step.at {
+                At::LastSlotOf(epoch) => schedule.get_last_slot_in_epoch(epoch),
+                At::FirstSlotOf(epoch) | At::FirstSlotAfterRewards(epoch) => schedule.get_first_slot_in_epoch(epoch),
+            };
+            let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+            if clock.slot < slot {
+                ctx.warp_to_slot(slot).unwrap();
+            }
+            if let At::FirstSlotAfterRewards(_) = step.at {
+                while ctx.banks_client.get_sysvar::<EpochRewards>().await.unwrap().active {
+                    let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+                    ctx.warp_to_slot(clock.slot + 1).unwrap();
+                }
+            }
+            let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+            let epoch = match step.at {
+                At::LastSlotOf(epoch) | At::FirstSlotOf(epoch) | At::FirstSlotAfterRewards(epoch) => epoch,
+            };
+            assert_eq!(clock.epoch, epoch, "{}: {:?} at slot {}", step.name, step.at, clock.slot);
+            if !matches!(step.at, At::FirstSlotAfterRewards(_)) {
+                assert_eq!(clock.slot, slot, "{}", step.name);
+            }
+
+            let first = (step.instruction)(0).accounts[0].pubkey;
+            let lamports = ctx.banks_client.get_balance(first).await.unwrap();
+            let ix = translate(&(step.instruction)(lamports));
+            let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &key(step.signer)], ctx.last_blockhash);
+            let result = ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap());
+            let deactivation_epoch = ctx
+                .banks_client
+                .get_account(first)
+                .await
+                .unwrap()
+                .and_then(|account| bincode::deserialize::<StakeStateV2>(&account.data).ok())
+                .and_then(|state| state.delegation())
+                .map(|delegation| delegation.deactivation_epoch);
+            outcomes.push(Outcome { name: step.name, result, deactivation_epoch });
+        }
+        outcomes
+    }
+}
+
+/// What native does at each step
+fn expected() -> Vec<Outcome> {
+    let err = |e: InstructionError| Err(TransactionError::InstructionError(0, e));
+    let stake_err = |e: StakeError| err(InstructionError::Custom(e as u32));
+    let outcome = |name, result, deactivation_epoch| Outcome { name, result, deactivation_epoch };
+    vec![
+        // the last slot of epoch 2 is still epoch 2, and the stake still effective
+        outcome("deactivate_a_last_slot", Ok(()), Some(2)),
+        outcome("withdraw_a_last_slot", err(InstructionError::InsufficientFunds), Some(2)),
+        outcome("merge_into_a_last_slot", stake_err(StakeError::MergeTransientStake), Some(2)),
+        // one slot later epoch 3's rewards are being paid out: no stake writes
+        outcome("deactivate_b_first_slot", stake_err(StakeError::EpochRewardsActive), Some(u64::MAX)),
+        outcome("withdraw_a_first_slot", stake_err(StakeError::EpochRewardsActive), Some(2)),
+        // then A has cooled down, and B deactivates in 3
+        outcome("deactivate_b_after_rewards", Ok(()), Some(3)),
+        outcome("deactivate_a_again", stake_err(StakeError::AlreadyDeactivated), Some(2)),
+        outcome("withdraw_b_after_rewards", err(InstructionError::InsufficientFunds), Some(3)),
+        outcome("merge_into_a_after_rewards", Ok(()), Some(2)),
+        outcome("withdraw_a_after_rewards", Ok(()), None),
+        // still epoch 3 at its last slot: B has not cooled down yet
+        outcome("withdraw_b_last_slot", err(InstructionError::InsufficientFunds), Some(3)),
+        outcome("withdraw_b_next_epoch", Ok(()), None),
+    ]
+}
+
+#[tokio::test]
+async fn native_epoch_boundaries() {
+    let outcomes = scenario().run(common::program_test_native(), Clone::clone).await;
+    assert_eq!(outcomes, expected());
+}
+
+#[tokio::test]
+async fn program_matches_native_at_epoch_boundaries() {
+    let outcomes = scenario().run(common::program_test(), |ix| ixn::translate(ix).unwrap()).await;
+    assert_eq!(outcomes, expected());
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "extensions")]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::instruction::{BuildVersion, INSTRUCTION_SET_REVISION};
+
+#[tokio::test]
+async fn get_version_reports_this_build() {
+    let ctx = common::program_test().start_with_context().await;
+    let tx = Transaction::new_signed_with_payer(&[ixn::get_version()], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    sim.result.unwrap().unwrap();
+    let ret = sim.simulation_details.and_then(|d| d.return_data).expect("program should return data");
+    assert_eq!(ret.program_id, Pubkey::new_from_array(pinocchio_stake::ID));
+
+    let version = BuildVersion::from_bytes(&ret.data).unwrap();
+    let semver = format!("{}.{}.{}", version.major, version.minor, version.patch);
+    assert_eq!(semver, env!("CARGO_PKG_VERSION"));
+    assert_eq!(version.instruction_set_revision, INSTRUCTION_SET_REVISION);
+    // the deployed artifact is built with the extensions this test needs
+    assert!(version.has_feature("extensions"));
+}
@@ -1,15 +1,18 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{instruction::Instruction, stake::state::Authorized, system_instruction};
 
 #[tokio::test]
 async fn initialize_harness_boots() {
     // Sanity: ensure our ProgramTest loads the SBF and can execute a simple query
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
 
-    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let _program_id = Pubkey::new_from_array(pinocchio_stake::ID);
     let ix = ixn::get_minimum_delegation();
 
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
@@ -17,4 +20,114 @@ async fn initialize_harness_boots() {
     assert!(sim.simulation_details.unwrap().return_data.is_some());
 }
 
-// Additional initialize flow tests will be added here after wiring required accounts
+/// Create a program-owned stake account and run `init_ix(stake, authorized)` on it
+async fn initialize_checked_with(init_ix: fn(&Pubkey, &Authorized) -> Instruction) {
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let stake = Keypair::new();
+    let withdrawer = Keypair::new();
+    let auth = Authorized { staker: Pubkey::new_unique(), withdrawer: withdrawer.pubkey() };
+
+    let space = pinocchio_stake::state::stake_state_v2::StakeStateV2::ACCOUNT_SIZE;
+    let lamports = ctx.banks_client.get_rent().await.unwrap().minimum_balance(space);
+    let create = system_instruction::create_account(&ctx.payer.pubkey(), &stake.pubkey(), lamports, space as u64, &program_id);
+    let tx = Transaction::new_signed_with_payer(
+        &[create, init_ix(&stake.pubkey(), &auth)],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &stake, &withdrawer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let acct = ctx.banks_client.get_account(stake.pubkey()).await.unwrap().unwrap();
+    match pinocchio_stake::state::stake_state_v2::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pinocchio_stake::state::stake_state_v2::StakeStateV2::Initialized(meta) => {
+            assert_eq!(meta.authorized.staker, auth.staker.to_bytes());
+            assert_eq!(meta.authorized.withdrawer, auth.withdrawer.to_bytes());
+            assert_eq!(meta.rent_exempt_reserve.get(), lamports);
+        }
+        other => panic!("expected Initialized, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn initialize_checked_with_rent_sysvar() {
+    initialize_checked_with(ixn::initialize_checked).await;
+}
+
+#[tokio::test]
+async fn initialize_checked_without_rent_sysvar() {
+    initialize_checked_with(ixn::initialize_checked_without_rent).await;
+}
+
+/// Initialize `stake` (already created, program-owned and funded) with `ix`
+async fn initialized_data(ix: Instruction, stake: &Keypair) -> Vec<u8> {
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let space = pinocchio_stake::state::stake_state_v2::StakeStateV2::ACCOUNT_SIZE;
+    let lamports = ctx.banks_client.get_rent().await.unwrap().minimum_balance(space);
+    let create = system_instruction::create_account(&ctx.payer.pubkey(), &stake.pubkey(), lamports, space as u64, &program_id);
+    let tx = Transaction::new_signed_with_payer(&[create, ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, stake], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    ctx.banks_client.get_account(stake.pubkey()).await.unwrap().unwrap().data
+}
+
+#[tokio::test]
+async fn initialize_accepts_native_bincode_payload() {
+    use solana_sdk::stake::{instruction as native_ixn, state::Lockup};
+
+    let stake = Keypair::new();
+    let auth = Authorized { staker: Pubkey::new_unique(), withdrawer: Pubkey::new_unique() };
+    let lockup = Lockup { unix_timestamp: 1, epoch: 2, custodian: Pubkey::new_unique() };
+
+    // native's 4-byte enum tag instead of this program's 1-byte discriminator
+    let native = native_ixn::initialize(&stake.pubkey(), &auth, &lockup);
+    assert_eq!(native.data.len(), 4 + 112);
+    let from_native = initialized_data(native, &stake).await;
+    let from_program = initialized_data(ixn::initialize(&stake.pubkey(), &auth, &lockup), &stake).await;
+    assert_eq!(from_native, from_program);
+}
+
+#[tokio::test]
+async fn initialize_rejects_initialized_and_underfunded_accounts() {
+    use solana_sdk::{instruction::InstructionError, stake::state::Lockup, transaction::TransactionError};
+
+    let pt = common::program_test();
+    let mut ctx = pt.start_with_context().await;
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let space = pinocchio_stake::state::stake_state_v2::StakeStateV2::ACCOUNT_SIZE;
+    let reserve = ctx.banks_client.get_rent().await.unwrap().minimum_balance(space);
+    let auth = Authorized { staker: Pubkey::new_unique(), withdrawer: Pubkey::new_unique() };
+
+    // created one lamport short of the reserve, initialized in the same transaction
+    let underfunded = Keypair::new();
+    let create = system_instruction::create_account(&ctx.payer.pubkey(), &underfunded.pubkey(), reserve - 1, space as u64, &program_id);
+    let init = ixn::initialize(&underfunded.pubkey(), &auth, &Lockup::default());
+    let tx = Transaction::new_signed_with_payer(&[create, init], Some(&ctx.payer.pubkey()), &[&ctx.payer, &underfunded], ctx.last_blockhash);
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::InsufficientFunds));
+
+    // a second initialize of a funded account, with other authorities
+    let stake = Keypair::new();
+    let create = system_instruction::create_account(&ctx.payer.pubkey(), &stake.pubkey(), reserve, space as u64, &program_id);
+    let init = ixn::initialize(&stake.pubkey(), &auth, &Lockup::default());
+    let tx = Transaction::new_signed_with_payer(&[create, init], Some(&ctx.payer.pubkey()), &[&ctx.payer, &stake], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    let before = ctx.banks_client.get_account(stake.pubkey()).await.unwrap().unwrap().data;
+
+    refresh_blockhash(&mut ctx).await;
+    let withdrawer = Keypair::new();
+    let other = Authorized { staker: Pubkey::new_unique(), withdrawer: withdrawer.pubkey() };
+    let retries = [
+        (ixn::initialize(&stake.pubkey(), &other, &Lockup::default()), vec![&ctx.payer]),
+        (ixn::initialize_checked(&stake.pubkey(), &other), vec![&ctx.payer, &withdrawer]),
+    ];
+    for (again, signers) in retries {
+        let tx = Transaction::new_signed_with_payer(&[again], Some(&ctx.payer.pubkey()), &signers, ctx.last_blockhash);
+        let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    }
+    assert_eq!(ctx.banks_client.get_account(stake.pubkey()).await.unwrap().unwrap().data, before);
+}
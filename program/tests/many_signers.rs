@@ -0,0 +1,100 @@
+#![allow(deprecated)]
+
+// Transactions carrying many signing accounts, repeated signer metas and long
+// account lists: authority is still taken only from the right keys.
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{state as pstate, test_utils::StakeStateBuilder};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    stake::state::StakeAuthorize,
+    transaction::TransactionError,
+};
+
+/// Signers beyond payer and withdrawer that still fit a legacy transaction
+const EXTRA_SIGNERS: usize = 4;
+/// Read-only non-signers appended after them
+const EXTRA_ACCOUNTS: usize = 10;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn initialized(staker: &Pubkey, withdrawer: &Pubkey) -> Account {
+    fixture_account(reserve() + 1_000_000_000, &StakeStateBuilder::initialized(staker.to_bytes(), withdrawer.to_bytes()))
+}
+
+async fn process(ctx: &mut ProgramTestContext, ix: Instruction, signers: &[&Keypair]) -> Result<(), TransactionError> {
+    refresh_blockhash(ctx).await;
+    let mut all = vec![&ctx.payer];
+    all.extend(signers);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &all, ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn meta(ctx: &mut ProgramTestContext, stake: &Pubkey) -> pstate::Meta {
+    let account = ctx.banks_client.get_account(*stake).await.unwrap().unwrap();
+    match pstate::StakeStateV2::deserialize(&account.data).unwrap() {
+        pstate::StakeStateV2::Initialized(meta) => meta,
+        other => panic!("expected Initialized, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn crowded_and_repeated_signers_keep_authority_exact() {
+    let staker = Keypair::new();
+    let withdrawer = Keypair::new();
+    let stake = Pubkey::new_unique();
+    let mut pt = common::program_test();
+    pt.add_account(stake, initialized(&staker.pubkey(), &withdrawer.pubkey()));
+    let mut ctx = pt.start_with_context().await;
+
+    let bystanders: Vec<Keypair> = (0..EXTRA_SIGNERS).map(|_| Keypair::new()).collect();
+    let crowd = |mut ix: Instruction| {
+        // bystanders sign first, the real authority comes last and repeated
+        let authority = ix.accounts.remove(2);
+        ix.accounts.extend(bystanders.iter().map(|k| AccountMeta::new_readonly(k.pubkey(), true)));
+        ix.accounts.extend((0..EXTRA_ACCOUNTS).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false)));
+        ix.accounts.extend([authority.clone(), authority.clone(), authority]);
+        ix
+    };
+    let mut signers: Vec<&Keypair> = bystanders.iter().collect();
+
+    // many signers, none of them an authority of this account
+    let new_staker = Pubkey::new_unique();
+    let ix = ixn::authorize(&stake, &bystanders[0].pubkey(), &new_staker, StakeAuthorize::Staker, None);
+    let ix = crowd(ix);
+    let missing = TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature);
+    assert_eq!(process(&mut ctx, ix, &signers).await, Err(missing.clone()));
+
+    // the staker can change the staker, but the crowd does not make it the withdrawer
+    signers.push(&staker);
+    let ix = crowd(ixn::authorize(&stake, &staker.pubkey(), &new_staker, StakeAuthorize::Staker, None));
+    process(&mut ctx, ix, &signers).await.unwrap();
+    let ix = crowd(ixn::authorize(&stake, &staker.pubkey(), &new_staker, StakeAuthorize::Withdrawer, None));
+    assert_eq!(process(&mut ctx, ix, &signers).await, Err(missing));
+
+    let meta = meta(&mut ctx, &stake).await;
+    assert_eq!(meta.authorized.staker, new_staker.to_bytes());
+    assert_eq!(meta.authorized.withdrawer, withdrawer.pubkey().to_bytes());
+}
+
+#[tokio::test]
+async fn repeated_withdrawer_meta_withdraws_once() {
+    let withdrawer = Keypair::new();
+    let stake = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let mut pt = common::program_test();
+    pt.add_account(stake, initialized(&withdrawer.pubkey(), &withdrawer.pubkey()));
+    let mut ctx = pt.start_with_context().await;
+
+    let mut ix = ixn::withdraw(&stake, &withdrawer.pubkey(), &recipient, 1_000_000, None);
+    ix.accounts.extend((0..8).map(|_| AccountMeta::new_readonly(withdrawer.pubkey(), true)));
+    process(&mut ctx, ix, &[&withdrawer]).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(recipient).await.unwrap(), 1_000_000);
+    assert_eq!(ctx.banks_client.get_balance(stake).await.unwrap(), reserve() + 999_000_000);
+}
@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -53,7 +55,7 @@ async fn create_initialized_stake(
 
 #[tokio::test]
 async fn merge_inactive_into_inactive_succeeds_and_drains_source() {
-    let mut pt = common::program_test();
+    let pt = common::program_test();
     let mut ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
@@ -92,7 +94,7 @@ async fn merge_inactive_into_inactive_succeeds_and_drains_source() {
 
 #[tokio::test]
 async fn merge_missing_staker_signature_fails() {
-    let mut pt = common::program_test();
+    let pt = common::program_test();
     let mut ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
@@ -122,7 +124,7 @@ async fn merge_missing_staker_signature_fails() {
 
 #[tokio::test]
 async fn merge_authority_mismatch_fails() {
-    let mut pt = common::program_test();
+    let pt = common::program_test();
     let mut ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
@@ -151,3 +153,81 @@ async fn merge_authority_mismatch_fails() {
         other => panic!("unexpected banks client error: {:?}", other),
     }
 }
+
+/// Delegated state activating at `activation_epoch`, with its own credits.
+fn activating_state(
+    staker: &Pubkey,
+    voter: &Pubkey,
+    reserve: u64,
+    stake: u64,
+    credits_observed: u64,
+) -> pinocchio_stake::state::StakeStateV2 {
+    use pinocchio_stake::state as pstate;
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized { staker: staker.to_bytes(), withdrawer: staker.to_bytes() },
+        lockup: pstate::Lockup::default(),
+    };
+    let stake = pstate::Stake {
+        delegation: pstate::Delegation::new(&voter.to_bytes(), stake, pstate::EpochLE::ZERO),
+        credits_observed: credits_observed.to_le_bytes(),
+    };
+    pstate::StakeStateV2::Stake(meta, stake, pstate::StakeFlags::empty())
+}
+
+#[tokio::test]
+async fn merge_activation_epoch_accounts_weights_credits_observed() {
+    let mut pt = common::program_test();
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let staker = Keypair::new();
+    let voter = Pubkey::new_unique();
+    let dst = Pubkey::new_unique();
+    let src = Pubkey::new_unique();
+
+    let space = pinocchio_stake::state::StakeStateV2::size_of();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(space);
+    let (dst_stake, dst_credits) = (3_000_000_000u64, 1_000u64);
+    let (src_stake, src_credits) = (1_000_000_001u64, 1_001u64);
+
+    for (key, stake, credits) in [(dst, dst_stake, dst_credits), (src, src_stake, src_credits)] {
+        pt.add_account(
+            key,
+            solana_sdk::account::Account {
+                lamports: reserve + stake,
+                data: ixn::encode_program_stake_state(&activating_state(
+                    &staker.pubkey(),
+                    &voter,
+                    reserve,
+                    stake,
+                    credits,
+                )),
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+    let mut ctx = pt.start_with_context().await;
+
+    let ix = ixn::merge(&dst, &src, &staker.pubkey()).into_iter().next().unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, &staker],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    // the source's reserve joins the destination's delegation
+    let absorbed = reserve + src_stake;
+    let weighted = u128::from(dst_credits) * u128::from(dst_stake)
+        + u128::from(src_credits) * u128::from(absorbed);
+    let total = u128::from(dst_stake + absorbed);
+    let expected_credits = weighted.div_ceil(total) as u64;
+
+    let (_, merged, lamports) = ixn::get_stake_account(&mut ctx.banks_client, &dst).await;
+    let merged = merged.unwrap();
+    assert_eq!(merged.delegation.stake, dst_stake + absorbed);
+    assert_eq!(merged.credits_observed, expected_credits);
+    assert_eq!(lamports, 2 * reserve + dst_stake + src_stake);
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{instruction::MAX_MERGE_SOURCES, state as pstate};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const EXTRA: u64 = 1_000_000;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn initialized(staker: &Pubkey) -> pstate::StakeStateV2 {
+    pstate::StakeStateV2::Initialized(pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+        authorized: pstate::Authorized { staker: staker.to_bytes(), withdrawer: staker.to_bytes() },
+        lockup: pstate::Lockup::default(),
+    })
+}
+
+/// Destination first, then one stake account per state, each holding `reserve() + EXTRA`
+async fn setup(states: &[pstate::StakeStateV2]) -> (ProgramTestContext, Vec<Pubkey>) {
+    let mut pt = common::program_test();
+    let keys: Vec<Pubkey> = states.iter().map(|_| Pubkey::new_unique()).collect();
+    for (key, state) in keys.iter().zip(states) {
+        pt.add_account(
+            *key,
+            Account {
+                lamports: reserve() + EXTRA,
+                data: ixn::encode_program_stake_state(state),
+                owner: Pubkey::new_from_array(pinocchio_stake::ID),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+    (pt.start_with_context().await, keys)
+}
+
+fn transaction(ctx: &ProgramTestContext, keys: &[Pubkey], staker: &Keypair) -> Transaction {
+    let ix = ixn::merge_many(&keys[0], &keys[1..], &staker.pubkey());
+    Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, staker], ctx.last_blockhash)
+}
+
+/// Simulate first to read the per-source codes, then execute
+async fn merge_many(ctx: &ProgramTestContext, keys: &[Pubkey], staker: &Keypair) -> Result<Vec<u64>, TransactionError> {
+    let sim = ctx.banks_client.simulate_transaction(transaction(ctx, keys, staker)).await.unwrap();
+    sim.result.unwrap()?;
+    let ret = sim.simulation_details.and_then(|d| d.return_data).expect("program should return data");
+    let codes = ret.data.chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
+
+    ctx.banks_client.process_transaction(transaction(ctx, keys, staker)).await.map_err(|e| e.unwrap())?;
+    Ok(codes)
+}
+
+async fn lamports(ctx: &ProgramTestContext, key: Pubkey) -> u64 {
+    ctx.banks_client.get_account(key).await.unwrap().map_or(0, |a| a.lamports)
+}
+
+#[tokio::test]
+async fn merges_every_source_into_destination() {
+    let staker = Keypair::new();
+    let states = vec![initialized(&staker.pubkey()); 4];
+    let (ctx, keys) = setup(&states).await;
+
+    let codes = merge_many(&ctx, &keys, &staker).await.unwrap();
+
+    assert_eq!(codes, vec![0; 3]);
+    assert_eq!(lamports(&ctx, keys[0]).await, 4 * (reserve() + EXTRA));
+    for key in &keys[1..] {
+        assert_eq!(lamports(&ctx, *key).await, 0);
+    }
+}
+
+#[tokio::test]
+async fn unmergeable_source_is_reported_and_left_alone() {
+    let staker = Keypair::new();
+    let stranger = Pubkey::new_unique();
+    let states = vec![
+        initialized(&staker.pubkey()),
+        initialized(&staker.pubkey()),
+        initialized(&stranger),
+        initialized(&staker.pubkey()),
+    ];
+    let (ctx, keys) = setup(&states).await;
+
+    let codes = merge_many(&ctx, &keys, &staker).await.unwrap();
+
+    let mismatch = pinocchio_stake::error::StakeError::MergeMismatch as u64;
+    assert_eq!(codes, vec![0, mismatch, 0]);
+    assert_eq!(lamports(&ctx, keys[0]).await, 3 * (reserve() + EXTRA));
+    assert_eq!(lamports(&ctx, keys[2]).await, reserve() + EXTRA);
+}
+
+#[tokio::test]
+async fn destination_itself_as_source_is_reported() {
+    let staker = Keypair::new();
+    let (ctx, mut keys) = setup(&[initialized(&staker.pubkey()), initialized(&staker.pubkey())]).await;
+    keys.push(keys[0]);
+
+    let codes = merge_many(&ctx, &keys, &staker).await.unwrap();
+
+    let invalid_argument = u64::from(solana_sdk::program_error::ProgramError::InvalidArgument);
+    assert_eq!(codes, vec![0, invalid_argument]);
+    assert_eq!(lamports(&ctx, keys[0]).await, 2 * (reserve() + EXTRA));
+}
+
+#[tokio::test]
+async fn requires_destination_staker() {
+    let staker = Keypair::new();
+    let (ctx, keys) = setup(&[initialized(&staker.pubkey()), initialized(&staker.pubkey())]).await;
+    let intruder = Keypair::new();
+
+    let err = merge_many(&ctx, &keys, &intruder).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+}
+
+#[tokio::test]
+async fn rejects_more_than_max_sources() {
+    let staker = Keypair::new();
+    let states = vec![initialized(&staker.pubkey()); MAX_MERGE_SOURCES + 2];
+    let (ctx, keys) = setup(&states).await;
+
+    let err = merge_many(&ctx, &keys, &staker).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
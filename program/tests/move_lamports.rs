@@ -1,9 +1,10 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
 use solana_sdk::stake::state::Authorized;
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
     message::Message,
     pubkey::Pubkey,
     system_instruction,
@@ -11,8 +12,8 @@ use solana_sdk::{
 
 #[tokio::test]
 async fn move_lamports_from_inactive_source() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Authorities shared by both stake accounts
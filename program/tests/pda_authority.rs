@@ -0,0 +1,195 @@
+#![allow(deprecated)]
+
+// A parent program holding stake authority through a PDA signs for it with
+// invoke_signed. The handlers only see the signer flag on the PDA account,
+// the same as for a keypair, so every authority check must accept it.
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::state as pstate;
+use solana_program_test::processor;
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke_signed,
+    transaction::TransactionError,
+};
+
+const LAMPORTS: u64 = 5_000_000_000;
+const SEED: &[u8] = b"stake-authority";
+
+fn parent_program_id() -> Pubkey {
+    Pubkey::new_from_array([0xA7; 32])
+}
+
+fn stake_program_id() -> Pubkey {
+    Pubkey::new_from_array(pinocchio_stake::ID)
+}
+
+fn pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED], &parent_program_id())
+}
+
+/// Parent program: forwards its data as a stake instruction over accounts
+/// `[stake program, pda, ..]`, with the PDA signing via invoke_signed.
+/// Instruction data is the PDA bump followed by the stake instruction data.
+fn process_parent(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (bump, stake_data) = data.split_first().unwrap();
+    let pda = accounts[1].key;
+    let metas = accounts[2..]
+        .iter()
+        .map(|ai| AccountMeta {
+            pubkey: *ai.key,
+            is_signer: ai.is_signer || ai.key == pda,
+            is_writable: ai.is_writable,
+        })
+        .collect();
+    let ix = Instruction { program_id: *accounts[0].key, accounts: metas, data: stake_data.to_vec() };
+    invoke_signed(&ix, accounts, &[&[SEED, &[*bump]]])
+}
+
+/// `ix` as the parent program would issue it; every occurrence of the PDA in
+/// `ix` arrives at the stake program as a signer
+fn via_pda(ix: Instruction) -> Instruction {
+    let (pda, bump) = pda();
+    let mut accounts = vec![AccountMeta::new_readonly(stake_program_id(), false), AccountMeta::new_readonly(pda, false)];
+    accounts.extend(ix.accounts.into_iter().map(|m| AccountMeta { is_signer: m.is_signer && m.pubkey != pda, ..m }));
+    let mut data = vec![bump];
+    data.extend(ix.data);
+    Instruction { program_id: parent_program_id(), accounts, data }
+}
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn meta(staker: &Pubkey, withdrawer: &Pubkey) -> pstate::Meta {
+    pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+        authorized: pstate::Authorized { staker: staker.to_bytes(), withdrawer: withdrawer.to_bytes() },
+        lockup: pstate::Lockup::default(),
+    }
+}
+
+fn stake_account(state: &pstate::StakeStateV2) -> Account {
+    Account {
+        lamports: reserve() + LAMPORTS,
+        data: ixn::encode_program_stake_state(state),
+        owner: stake_program_id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn vote_account() -> Account {
+    use solana_sdk::vote::{program::id as vote_program_id, state::{VoteStateV3, VoteStateVersions}};
+    let vote_state = VoteStateV3 { epoch_credits: vec![(0, 42, 0)], ..VoteStateV3::default() };
+    let mut data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+    data.resize(VoteStateV3::size_of(), 0);
+    Account { lamports: LAMPORTS, data, owner: vote_program_id(), executable: false, rent_epoch: 0 }
+}
+
+async fn start(accounts: &[(Pubkey, Account)]) -> ProgramTestContext {
+    let mut pt = common::program_test();
+    // the stake program is already in genesis; the parent runs as a builtin
+    pt.prefer_bpf(false);
+    pt.add_program("pda_authority_parent", parent_program_id(), processor!(process_parent));
+    for (key, account) in accounts {
+        pt.add_account(*key, account.clone());
+    }
+    pt.start_with_context().await
+}
+
+async fn process(ctx: &mut ProgramTestContext, ix: Instruction) -> Result<(), TransactionError> {
+    refresh_blockhash(ctx).await;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn state(ctx: &mut ProgramTestContext, key: &Pubkey) -> pstate::StakeStateV2 {
+    let account = ctx.banks_client.get_account(*key).await.unwrap().unwrap();
+    pstate::StakeStateV2::deserialize(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn pda_staker_delegates_and_deactivates() {
+    let (pda, _) = pda();
+    let stake = Pubkey::new_unique();
+    let vote = Pubkey::new_unique();
+    let mut ctx = start(&[
+        (stake, stake_account(&pstate::StakeStateV2::Initialized(meta(&pda, &pda)))),
+        (vote, vote_account()),
+    ])
+    .await;
+
+    // without the parent program nobody can sign for the PDA
+    let missing = TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature);
+    let mut unsigned = ixn::delegate_stake(&stake, &pda, &vote);
+    unsigned.accounts.iter_mut().for_each(|m| m.is_signer = false);
+    assert_eq!(process(&mut ctx, unsigned).await, Err(missing));
+
+    process(&mut ctx, via_pda(ixn::delegate_stake(&stake, &pda, &vote))).await.unwrap();
+    let pstate::StakeStateV2::Stake(_, delegated, _) = state(&mut ctx, &stake).await else {
+        panic!("expected a delegation");
+    };
+    assert_eq!(delegated.delegation.voter_pubkey, vote.to_bytes());
+
+    process(&mut ctx, via_pda(ixn::deactivate_stake(&stake, &pda))).await.unwrap();
+    let pstate::StakeStateV2::Stake(_, deactivated, _) = state(&mut ctx, &stake).await else {
+        panic!("expected a delegation");
+    };
+    assert_ne!(deactivated.delegation.deactivation_epoch, u64::MAX);
+}
+
+#[tokio::test]
+async fn pda_withdrawer_withdraws() {
+    let (pda, _) = pda();
+    let stake = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let mut ctx = start(&[(stake, stake_account(&pstate::StakeStateV2::Initialized(meta(&Pubkey::new_unique(), &pda))))]).await;
+
+    process(&mut ctx, via_pda(ixn::withdraw(&stake, &pda, &recipient, LAMPORTS, None))).await.unwrap();
+
+    let balance = ctx.banks_client.get_balance(recipient).await.unwrap();
+    assert_eq!(balance, LAMPORTS);
+    assert_eq!(ctx.banks_client.get_balance(stake).await.unwrap(), reserve());
+}
+
+#[tokio::test]
+async fn pda_signs_as_new_authority_in_checked_variant() {
+    let (pda, _) = pda();
+    let stake = Pubkey::new_unique();
+    let mut ctx = start(&[(stake, stake_account(&pstate::StakeStateV2::Initialized(meta(&pda, &pda))))]).await;
+
+    // the PDA is both the current withdrawer and the new staker
+    let ix = ixn::authorize_checked(&stake, &pda, &pda, solana_sdk::stake::state::StakeAuthorize::Staker, None);
+    process(&mut ctx, via_pda(ix)).await.unwrap();
+
+    // handing the staker role to a keypair still requires only the PDA
+    let new_staker = Pubkey::new_unique();
+    let ix = ixn::authorize(&stake, &pda, &new_staker, solana_sdk::stake::state::StakeAuthorize::Staker, None);
+    process(&mut ctx, via_pda(ix)).await.unwrap();
+    let pstate::StakeStateV2::Initialized(meta) = state(&mut ctx, &stake).await else {
+        panic!("expected Initialized");
+    };
+    assert_eq!(meta.authorized.staker, new_staker.to_bytes());
+    assert_eq!(meta.authorized.withdrawer, pda.to_bytes());
+}
+
+#[tokio::test]
+async fn pda_staker_moves_lamports() {
+    let (pda, _) = pda();
+    let source = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let initialized = pstate::StakeStateV2::Initialized(meta(&pda, &pda));
+    let mut ctx = start(&[(source, stake_account(&initialized)), (destination, stake_account(&initialized))]).await;
+
+    // MoveLamports reads its authority positionally instead of collecting signers
+    process(&mut ctx, via_pda(ixn::move_lamports(&source, &destination, &pda, LAMPORTS))).await.unwrap();
+
+    assert_eq!(ctx.banks_client.get_balance(source).await.unwrap(), reserve());
+    assert_eq!(ctx.banks_client.get_balance(destination).await.unwrap(), reserve() + 2 * LAMPORTS);
+}
@@ -0,0 +1,85 @@
+//! Size budget for the deployed program (`minimal` feature).
+//!
+//! Build the artifact, then check it:
+//!
+//!   cargo-build-sbf --no-default-features --features sbf,minimal --manifest-path program/Cargo.toml
+//!   cargo test --features minimal --test program_size
+//!
+//! The budget lives in tests/fixtures/so_size_budget.json as
+//! `{ "<artifact>": <max bytes> }`; an artifact without an entry is only
+//! reported. `SO_SIZE_UPDATE=1` records the current size plus
+//! `HEADROOM_PCT` as the new budget. `PROGRAM_SO` overrides the artifact path.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Room left above the recorded size, so unrelated changes don't fail CI
+const HEADROOM_PCT: u64 = 2;
+
+type Budgets = BTreeMap<String, u64>;
+
+fn budget_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/so_size_budget.json")
+}
+
+fn artifact_path() -> PathBuf {
+    std::env::var_os("PROGRAM_SO")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target/deploy/pinocchio_stake.so"))
+}
+
+fn load_budgets(path: &Path) -> Budgets {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).expect("size budget is not a JSON map of byte counts"),
+        Err(_) => Budgets::new(),
+    }
+}
+
+fn with_headroom(size: u64) -> u64 {
+    size + size * HEADROOM_PCT / 100
+}
+
+/// The budget `size` exceeds, if any
+fn over_budget(budgets: &Budgets, artifact: &str, size: u64) -> Option<u64> {
+    budgets.get(artifact).copied().filter(|&budget| size > budget)
+}
+
+#[test]
+fn program_fits_its_size_budget() {
+    let so = artifact_path();
+    let size = std::fs::metadata(&so)
+        .unwrap_or_else(|e| {
+            panic!(
+                "SBF artifact not found at {} ({e}).\nBuild first: `cargo-build-sbf --no-default-features --features sbf,minimal --manifest-path program/Cargo.toml`",
+                so.display()
+            )
+        })
+        .len();
+    let artifact = so.file_name().unwrap().to_string_lossy().into_owned();
+
+    let path = budget_path();
+    let mut budgets = load_budgets(&path);
+    println!("{artifact}: {size} bytes, budget {:?}", budgets.get(&artifact));
+    if std::env::var_os("SO_SIZE_UPDATE").is_some() {
+        budgets.insert(artifact, with_headroom(size));
+        std::fs::write(&path, serde_json::to_string_pretty(&budgets).unwrap() + "\n").unwrap();
+        return;
+    }
+    if let Some(budget) = over_budget(&budgets, &artifact, size) {
+        panic!("{artifact} is {size} bytes, over its {budget} byte budget; shrink it or rerun with SO_SIZE_UPDATE=1");
+    }
+}
+
+#[test]
+fn budgets_only_fail_when_exceeded() {
+    let budgets: Budgets = [("pinocchio_stake.so".to_string(), 100_000)].into();
+    assert_eq!(over_budget(&budgets, "pinocchio_stake.so", 100_000), None);
+    assert_eq!(over_budget(&budgets, "pinocchio_stake.so", 100_001), Some(100_000));
+    // no recorded budget: reported, never failed
+    assert_eq!(over_budget(&budgets, "other.so", u64::MAX), None);
+    assert_eq!(with_headroom(100_000), 102_000);
+    // the checked-in budget must always parse
+    load_budgets(&budget_path());
+}
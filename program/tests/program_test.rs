@@ -1,4 +1,5 @@
 #![cfg(feature = "e2e")]
+#![allow(deprecated)]
 
 use {
     solana_program_test::*,
@@ -15,18 +16,16 @@ use {
         system_instruction,
         system_program,
         stake::{
-            instruction::{self as sdk_ixn, LockupArgs, StakeError},
+            instruction::{LockupArgs, StakeError},
             program::id,
-            state::{Authorized, Delegation, Lockup, Meta, Stake, StakeAuthorize, StakeStateV2},
+            state::{Authorized, Delegation, Lockup, Stake, StakeAuthorize},
         },
-        stake_history::StakeHistory,
         vote::{
             instruction as vote_instruction,
-            state::{VoteInit, VoteStateV3, VoteStateVersions},
+            state::{VoteInit, VoteStateV3},
         },
     },
     test_case::{test_case, test_matrix},
-    bincode,
 };
 // Use shared adapter for instruction translation + state helpers
 mod common;
@@ -204,9 +203,9 @@ pub async fn get_effective_stake(banks_client: &mut BanksClient, pubkey: &Pubkey
         // Use a simple fallback mirroring native semantics:
         // effective == stake amount when current epoch is strictly greater than activation
         // and less than or equal to deactivation.
-        let act = u64::from_le_bytes(stake.delegation.activation_epoch);
-        let deact = u64::from_le_bytes(stake.delegation.deactivation_epoch);
-        let amount = u64::from_le_bytes(stake.delegation.stake);
+        let act = stake.delegation.activation_epoch.get();
+        let deact = stake.delegation.deactivation_epoch.get();
+        let amount = stake.delegation.stake.get();
         if clock.epoch > act && clock.epoch <= deact { amount } else { 0 }
     } else {
         0
@@ -324,7 +323,7 @@ pub async fn process_instruction<T: Signers + ?Sized>(
     additional_signers: &T,
 ) -> ProgramResult {
     let mut transaction =
-        Transaction::new_with_payer(&[instruction.clone()], Some(&context.payer.pubkey()));
+        Transaction::new_with_payer(std::slice::from_ref(instruction), Some(&context.payer.pubkey()));
 
     transaction.partial_sign(&[&context.payer], context.last_blockhash);
     transaction.sign(additional_signers, context.last_blockhash);
@@ -871,6 +870,91 @@ async fn program_test_stake_delegate() {
     assert_eq!(e, ProgramError::InvalidAccountData);
 }
 
+#[tokio::test]
+async fn program_test_stake_delegate_after_cooldown() {
+    let mut context = program_test().start_with_context().await;
+    let accounts = Accounts::default();
+    accounts.initialize(&mut context).await;
+
+    let vote_account2 = Keypair::new();
+    create_vote(
+        &mut context,
+        &Keypair::new(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &vote_account2,
+    )
+    .await;
+    let vote2_credits = 250;
+    context.increment_vote_account_credits(&vote_account2.pubkey(), vote2_credits);
+
+    let staker_keypair = Keypair::new();
+    let staker = staker_keypair.pubkey();
+    let authorized = Authorized { staker, withdrawer: Pubkey::new_unique() };
+    let minimum_delegation = get_minimum_delegation(&mut context).await;
+
+    let stake =
+        create_independent_stake_account(&mut context, &authorized, minimum_delegation).await;
+    let instruction = ixn::delegate_stake(&stake, &staker, &accounts.vote_account.pubkey());
+    process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap();
+
+    // active, then deactivated: cooling down this epoch
+    advance_epoch(&mut context).await;
+    let instruction = ixn::deactivate_stake(&stake, &staker);
+    process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap();
+    let instruction = ixn::delegate_stake(&stake, &staker, &vote_account2.pubkey());
+    let e = process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap_err();
+    assert!(common::pin_adapter::err::matches_stake_error(&e, StakeError::TooSoonToRedelegate));
+
+    // fully deactivated: the stake is reused for a fresh delegation to any vote
+    advance_epoch(&mut context).await;
+    let (_, cooled, _) = get_stake_account(&mut context.banks_client, &stake).await;
+    let cooled = cooled.unwrap();
+    let instruction = ixn::delegate_stake(&stake, &staker, &vote_account2.pubkey());
+    process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap();
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let (_, stake_data, _) = get_stake_account(&mut context.banks_client, &stake).await;
+    assert_eq!(
+        stake_data.unwrap(),
+        Stake {
+            delegation: Delegation {
+                voter_pubkey: vote_account2.pubkey(),
+                stake: minimum_delegation,
+                activation_epoch: clock.epoch,
+                deactivation_epoch: u64::MAX,
+                ..cooled.delegation
+            },
+            credits_observed: vote2_credits,
+        }
+    );
+
+    // nothing is effective while activating, so it can still be re-pointed
+    refresh_blockhash(&mut context).await;
+    let instruction = ixn::delegate_stake(&stake, &staker, &accounts.vote_account.pubkey());
+    process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap();
+    let (_, stake_data, _) = get_stake_account(&mut context.banks_client, &stake).await;
+    assert_eq!(stake_data.unwrap().delegation.voter_pubkey, accounts.vote_account.pubkey());
+
+    // once active it cannot
+    advance_epoch(&mut context).await;
+    let instruction = ixn::delegate_stake(&stake, &staker, &vote_account2.pubkey());
+    let e = process_instruction(&mut context, &instruction, &vec![&staker_keypair])
+        .await
+        .unwrap_err();
+    assert!(common::pin_adapter::err::matches_stake_error(&e, StakeError::TooSoonToRedelegate));
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StakeLifecycle {
     Uninitialized = 0,
@@ -1364,9 +1448,8 @@ async fn program_test_deactivate(activate: bool) {
     let e = process_instruction(&mut context, &instruction, &vec![&staker_keypair])
         .await
         .unwrap_err();
-    // Accept either native StakeError mapping or our program's mapping
     assert!(
-        e == StakeError::AlreadyDeactivated.into() || e == ProgramError::Custom(0x11),
+        e == StakeError::AlreadyDeactivated.into(),
         "unexpected error for second deactivate: {:?}",
         e
     );
@@ -1377,7 +1460,7 @@ async fn program_test_deactivate(activate: bool) {
         .await
         .unwrap_err();
     assert!(
-        e == StakeError::AlreadyDeactivated.into() || e == ProgramError::Custom(0x11),
+        e == StakeError::AlreadyDeactivated.into(),
         "unexpected error for third deactivate: {:?}",
         e
     );
@@ -1466,8 +1549,8 @@ async fn program_test_merge(merge_source_type: StakeLifecycle, merge_dest_type:
             meta.authorized.withdrawer = withdrawer_keypair.pubkey().to_bytes();
 
             match merge_source_type {
-                StakeLifecycle::Activating => stake.delegation.activation_epoch = clock.epoch.to_le_bytes(),
-                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = clock.epoch.to_le_bytes(),
+                StakeLifecycle::Activating => stake.delegation.activation_epoch = pstate::EpochLE::new(clock.epoch),
+                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = pstate::EpochLE::new(clock.epoch),
                 _ => (),
             }
         }
@@ -1600,8 +1683,8 @@ async fn program_test_move_stake(
         let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
         if let pstate::stake_state_v2::StakeStateV2::Stake(_, ref mut stake, _) = &mut source_stake_state {
             match move_source_type {
-                StakeLifecycle::Activating => stake.delegation.activation_epoch = clock.epoch.to_le_bytes(),
-                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = clock.epoch.to_le_bytes(),
+                StakeLifecycle::Activating => stake.delegation.activation_epoch = pstate::EpochLE::new(clock.epoch),
+                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = pstate::EpochLE::new(clock.epoch),
                 _ => (),
             }
         }
@@ -1871,8 +1954,8 @@ async fn program_test_move_lamports(
         let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
         if let pstate::stake_state_v2::StakeStateV2::Stake(_, ref mut stake, _) = &mut source_stake_state {
             match move_source_type {
-                StakeLifecycle::Activating => stake.delegation.activation_epoch = clock.epoch.to_le_bytes(),
-                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = clock.epoch.to_le_bytes(),
+                StakeLifecycle::Activating => stake.delegation.activation_epoch = pstate::EpochLE::new(clock.epoch),
+                StakeLifecycle::Deactivating => stake.delegation.deactivation_epoch = pstate::EpochLE::new(clock.epoch),
                 _ => (),
             }
         }
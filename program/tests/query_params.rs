@@ -0,0 +1,34 @@
+#![cfg(feature = "extensions")]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::instruction::StakeParams;
+use solana_sdk::instruction::AccountMeta;
+
+async fn query(ix: solana_sdk::instruction::Instruction) -> Vec<u8> {
+    let ctx = common::program_test().start_with_context().await;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    sim.result.unwrap().unwrap();
+    let ret = sim.simulation_details.and_then(|d| d.return_data).expect("program should return data");
+    assert_eq!(ret.program_id, Pubkey::new_from_array(pinocchio_stake::ID));
+    ret.data
+}
+
+#[tokio::test]
+async fn query_params_returns_program_constants() {
+    let data = query(ixn::query_params()).await;
+    assert_eq!(data.len(), StakeParams::LEN);
+    // the same value GetMinimumDelegation returns
+    assert_eq!(data[..8], query(ixn::get_minimum_delegation()).await[..]);
+    assert_eq!(data[8..16], (pinocchio_stake::state::StakeStateV2::size_of() as u64).to_le_bytes());
+    assert_eq!(f64::from_bits(u64::from_le_bytes(data[16..].try_into().unwrap())), 0.09);
+}
+
+#[tokio::test]
+async fn query_params_reads_passed_clock() {
+    let mut ix = ixn::query_params();
+    ix.accounts.push(AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false));
+    assert_eq!(query(ix).await, query(ixn::query_params()).await);
+}
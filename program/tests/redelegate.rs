@@ -0,0 +1,168 @@
+#![cfg(feature = "redelegate-legacy")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::state as pstate;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    transaction::TransactionError,
+};
+use std::str::FromStr;
+
+const STAKE_LAMPORTS: u64 = 5_000_000_000;
+
+fn vote_program_id() -> Pubkey {
+    Pubkey::from_str("Vote111111111111111111111111111111111111111").unwrap()
+}
+
+fn redelegate_ix(stake: &Pubkey, uninitialized: &Pubkey, vote: &Pubkey, staker: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: Pubkey::new_from_array(pinocchio_stake::ID),
+        accounts: vec![
+            AccountMeta::new(*stake, false),
+            AccountMeta::new(*uninitialized, false),
+            AccountMeta::new_readonly(*vote, false),
+            AccountMeta::new_readonly(solana_sdk::stake::config::id(), false),
+            AccountMeta::new_readonly(*staker, true),
+        ],
+        data: vec![15],
+    }
+}
+
+fn add_vote_account(pt: &mut solana_program_test::ProgramTest, key: &Pubkey) {
+    pt.add_account(
+        *key,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0u8; 3762],
+            owner: vote_program_id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// Bootstrap-activated stake is fully effective without any stake history.
+fn delegated_state(staker: &Pubkey, voter: &Pubkey, reserve: u64) -> pstate::StakeStateV2 {
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized { staker: staker.to_bytes(), withdrawer: staker.to_bytes() },
+        lockup: pstate::Lockup::default(),
+    };
+    let stake = pstate::Stake {
+        delegation: pstate::Delegation::new(&voter.to_bytes(), STAKE_LAMPORTS, pstate::EpochLE::MAX),
+        credits_observed: 0u64.to_le_bytes(),
+    };
+    pstate::StakeStateV2::Stake(meta, stake, pstate::StakeFlags::empty())
+}
+
+struct Setup {
+    ctx: ProgramTestContext,
+    staker: Keypair,
+    stake: Pubkey,
+    uninitialized: Pubkey,
+    old_vote: Pubkey,
+    new_vote: Pubkey,
+}
+
+async fn setup() -> Setup {
+    let mut pt = common::program_test();
+    let staker = Keypair::new();
+    let stake = Pubkey::new_unique();
+    let uninitialized = Pubkey::new_unique();
+    let old_vote = Pubkey::new_unique();
+    let new_vote = Pubkey::new_unique();
+    add_vote_account(&mut pt, &old_vote);
+    add_vote_account(&mut pt, &new_vote);
+
+    let space = pstate::StakeStateV2::size_of();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(space);
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    pt.add_account(
+        stake,
+        Account {
+            lamports: reserve + STAKE_LAMPORTS,
+            data: ixn::encode_program_stake_state(&delegated_state(&staker.pubkey(), &old_vote, reserve)),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    pt.add_account(
+        uninitialized,
+        Account {
+            lamports: reserve,
+            data: vec![0u8; space],
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let ctx = pt.start_with_context().await;
+    Setup { ctx, staker, stake, uninitialized, old_vote, new_vote }
+}
+
+async fn send(ctx: &mut ProgramTestContext, ix: Instruction, signer: &Keypair) -> Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer, signer],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(tx)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+#[tokio::test]
+async fn redelegate_moves_effective_stake_to_new_account() {
+    let Setup { mut ctx, staker, stake, uninitialized, new_vote, .. } = setup().await;
+
+    send(&mut ctx, redelegate_ix(&stake, &uninitialized, &new_vote, &staker.pubkey()), &staker)
+        .await
+        .unwrap();
+
+    let (_, src, _) = ixn::get_stake_account(&mut ctx.banks_client, &stake).await;
+    assert_ne!(src.unwrap().delegation.deactivation_epoch, u64::MAX);
+
+    let (_, dst, dst_lamports) = ixn::get_stake_account(&mut ctx.banks_client, &uninitialized).await;
+    let dst = dst.unwrap();
+    assert_eq!(dst.delegation.voter_pubkey, new_vote);
+    assert_eq!(dst.delegation.stake, STAKE_LAMPORTS);
+    assert!(dst_lamports > STAKE_LAMPORTS);
+}
+
+#[tokio::test]
+async fn redelegate_to_same_vote_account_fails() {
+    let Setup { mut ctx, staker, stake, uninitialized, old_vote, .. } = setup().await;
+
+    let err = send(&mut ctx, redelegate_ix(&stake, &uninitialized, &old_vote, &staker.pubkey()), &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(
+            solana_sdk::stake::instruction::StakeError::RedelegateToSameVoteAccount as u32,
+        ))
+    );
+}
+
+#[tokio::test]
+async fn redelegate_requires_staker_signature() {
+    let Setup { mut ctx, stake, uninitialized, new_vote, .. } = setup().await;
+    let imposter = Keypair::new();
+
+    let err = send(&mut ctx, redelegate_ix(&stake, &uninitialized, &new_vote, &imposter.pubkey()), &imposter)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
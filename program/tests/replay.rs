@@ -0,0 +1,126 @@
+#![cfg(feature = "replay")]
+#![allow(deprecated)]
+
+mod common;
+use common::replay::{load_dir, replay_dir, substitute, RecordedInstruction, RecordedTransaction, Rekeying};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    pubkey::Pubkey,
+    signature::Signer,
+    stake::{
+        instruction as sdk_ixn,
+        state::{Authorized, Lockup, Meta, StakeStateV2},
+    },
+    stake_history::StakeHistory,
+};
+
+/// `cargo test --test replay --features replay`, with dumps in
+/// tests/fixtures/replay/ or `REPLAY_DIR=<dir>`
+#[tokio::test]
+async fn recorded_transactions_match_cluster() {
+    let dumps = load_dir(&replay_dir());
+    if dumps.is_empty() {
+        eprintln!("no replay dumps in {}; nothing to compare", replay_dir().display());
+        return;
+    }
+    for (path, recorded) in &dumps {
+        let expected = recorded.expected();
+        // a dump the native program does not reproduce says nothing about this one
+        assert_eq!(recorded.replay_native().await, expected, "native: {} ({})", recorded.signature, path.display());
+        assert_eq!(recorded.replay().await, expected, "{} ({})", recorded.signature, path.display());
+    }
+}
+
+/// A Withdraw by `withdrawer` from an Initialized account, as a dump records it
+fn withdraw_dump(withdrawer: Pubkey) -> RecordedTransaction {
+    let stake = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let meta = Meta {
+        rent_exempt_reserve: 2_282_880,
+        authorized: Authorized { staker: withdrawer, withdrawer },
+        lockup: Lockup::default(),
+    };
+    let mut data = bincode::serialize(&StakeStateV2::Initialized(meta)).unwrap();
+    data.resize(StakeStateV2::size_of(), 0);
+    let account = Account { lamports: 3_282_880, data, owner: solana_sdk::stake::program::id(), executable: false, rent_epoch: 0 };
+    let ix = sdk_ixn::withdraw(&stake, &withdrawer, &recipient, 1_000_000, None);
+    RecordedTransaction {
+        signature: "synthetic".to_string(),
+        clock: Clock::default(),
+        stake_history: StakeHistory::default(),
+        fee_payer: withdrawer,
+        fee: 5_000,
+        pre_accounts: vec![(stake, account.clone()), (withdrawer, Account { lamports: 10_000, ..Account::default() })],
+        instructions: vec![RecordedInstruction {
+            program_id: ix.program_id,
+            accounts: ix.accounts.iter().map(|m| (m.pubkey, m.is_signer, m.is_writable)).collect(),
+            data: ix.data,
+        }],
+        result: Ok(()),
+        post_accounts: vec![
+            (stake, Some(Account { lamports: 2_282_880, ..account })),
+            (withdrawer, Some(Account { lamports: 5_000, ..Account::default() })),
+        ],
+    }
+}
+
+#[test]
+fn rekeying_round_trips_authorities_in_account_data() {
+    let withdrawer = Pubkey::new_unique();
+    let recorded = withdraw_dump(withdrawer);
+    let rekeying = Rekeying::for_signers(&recorded);
+    let stand_in = rekeying.key(&withdrawer);
+    assert_ne!(stand_in, withdrawer);
+    assert_eq!(rekeying.keypairs().map(|kp| kp.pubkey()).collect::<Vec<_>>(), vec![stand_in]);
+
+    let original = recorded.pre_accounts[0].1.data.clone();
+    let mut data = original.clone();
+    rekeying.forward(&mut data);
+    let StakeStateV2::Initialized(meta) = bincode::deserialize(&data).unwrap() else { panic!("expected Initialized") };
+    assert_eq!(meta.authorized.staker, stand_in);
+    assert_eq!(meta.authorized.withdrawer, stand_in);
+    rekeying.back(&mut data);
+    assert_eq!(data, original);
+
+    // non-signers keep their keys
+    let stake = recorded.pre_accounts[0].0;
+    assert_eq!(rekeying.key(&stake), stake);
+}
+
+#[test]
+fn substitute_replaces_every_aligned_or_unaligned_copy() {
+    let (from, to) = (Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([2; 32]));
+    let mut data = [[0u8].as_slice(), &[1; 32], &[3; 5], &[1; 32]].concat();
+    substitute(&mut data, &from, &to);
+    assert_eq!(data, [[0u8].as_slice(), &[2; 32], &[3; 5], &[2; 32]].concat());
+}
+
+#[test]
+fn expected_adds_the_fee_back_to_the_payer() {
+    let withdrawer = Pubkey::new_unique();
+    let expected = withdraw_dump(withdrawer).expected();
+    assert_eq!(expected.result, Ok(()));
+    assert_eq!(expected.accounts[1], (withdrawer, Some((10_000, Pubkey::default(), vec![]))));
+}
+
+#[tokio::test]
+async fn synthetic_dump_replays_on_native() {
+    let recorded = withdraw_dump(Pubkey::new_unique());
+    assert_eq!(recorded.replay_native().await, recorded.expected());
+}
+
+#[test]
+fn dumps_load_as_json_and_bincode() {
+    let dir = std::env::temp_dir().join(format!("replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let recorded = withdraw_dump(Pubkey::new_unique());
+    std::fs::write(dir.join("a.json"), serde_json::to_vec(&recorded).unwrap()).unwrap();
+    std::fs::write(dir.join("b.bin"), bincode::serialize(&recorded).unwrap()).unwrap();
+    std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    let loaded = load_dir(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert!(loaded.iter().all(|(_, dump)| *dump == recorded));
+}
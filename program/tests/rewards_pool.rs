@@ -0,0 +1,100 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+// Native instructions meet a RewardsPool account in the `rewards_pool_*`
+// conformance fixtures; these cover the program-local extensions.
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::{state as pstate, test_utils::StakeStateBuilder};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    transaction::TransactionError,
+};
+use test_case::test_case;
+
+const LAMPORTS: u64 = 5_000_000_000;
+
+enum Extension {
+    DeactivatePartial,
+    WithdrawExcess,
+    CloseStakeAccount,
+    SplitWithSeed,
+    GetDelegationPreview,
+    MergeMany,
+}
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn stake_account(state: &StakeStateBuilder) -> Account {
+    fixture_account(reserve() + LAMPORTS, state)
+}
+
+fn initialized(authority: &Pubkey) -> StakeStateBuilder {
+    StakeStateBuilder::initialized(authority.to_bytes(), authority.to_bytes())
+}
+
+/// `extension` with the RewardsPool account `pool` in its stake account role;
+/// `other` is a second stake account and `authority` signs for every role
+fn instruction(extension: Extension, pool: &Pubkey, other: &Pubkey, authority: &Pubkey) -> Instruction {
+    match extension {
+        Extension::DeactivatePartial => ixn::deactivate_partial(pool, other, authority, LAMPORTS / 2),
+        Extension::WithdrawExcess => ixn::withdraw_excess(pool, authority, other, None),
+        Extension::CloseStakeAccount => ixn::close_stake_account(pool, authority, other, None),
+        Extension::SplitWithSeed => ixn::split_with_seed(pool, authority, LAMPORTS / 2, authority, "pool").0,
+        Extension::GetDelegationPreview => ixn::get_delegation_preview(pool),
+        Extension::MergeMany => ixn::merge_many(pool, &[*other], authority),
+    }
+}
+
+#[test_case(Extension::DeactivatePartial; "deactivate_partial")]
+#[test_case(Extension::WithdrawExcess; "withdraw_excess")]
+#[test_case(Extension::CloseStakeAccount; "close_stake_account")]
+#[test_case(Extension::SplitWithSeed; "split_with_seed")]
+#[test_case(Extension::GetDelegationPreview; "get_delegation_preview")]
+#[test_case(Extension::MergeMany; "merge_many")]
+#[tokio::test]
+async fn rewards_pool_is_rejected(extension: Extension) {
+    let authority = Keypair::new();
+    let pool = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+
+    let mut pt = common::program_test();
+    pt.add_account(pool, stake_account(&StakeStateBuilder::rewards_pool()));
+    pt.add_account(other, stake_account(&initialized(&authority.pubkey())));
+    let ctx = pt.start_with_context().await;
+
+    let ix = instruction(extension, &pool, &other, &authority.pubkey());
+    let signs = ix.accounts.iter().any(|m| m.is_signer && m.pubkey == authority.pubkey());
+    let signers: Vec<&Keypair> = if signs { vec![&ctx.payer, &authority] } else { vec![&ctx.payer] };
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &signers, ctx.last_blockhash);
+
+    let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    let account = ctx.banks_client.get_account(pool).await.unwrap().unwrap();
+    assert_eq!(account, stake_account(&StakeStateBuilder::rewards_pool()));
+}
+
+#[tokio::test]
+async fn merge_many_reports_rewards_pool_source() {
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+
+    let mut pt = common::program_test();
+    pt.add_account(destination, stake_account(&initialized(&authority.pubkey())));
+    pt.add_account(pool, stake_account(&StakeStateBuilder::rewards_pool()));
+    let ctx = pt.start_with_context().await;
+
+    let ix = ixn::merge_many(&destination, &[pool], &authority.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &authority], ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    sim.result.unwrap().unwrap();
+    let ret = sim.simulation_details.and_then(|d| d.return_data).expect("program should return data");
+    let invalid_account_data = u64::from(solana_sdk::program_error::ProgramError::InvalidAccountData);
+    assert_eq!(ret.data, invalid_account_data.to_le_bytes());
+}
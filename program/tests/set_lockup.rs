@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -14,8 +16,8 @@ use solana_sdk::{
 // SetLockupChecked: when lockup not in force, withdrawer must sign and epoch/timestamp updates apply.
 #[tokio::test]
 async fn set_lockup_checked_updates_epoch_with_withdrawer_signature() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Create stake account owned by our program
@@ -52,7 +54,7 @@ async fn set_lockup_checked_updates_epoch_with_withdrawer_signature() {
     // SetLockupChecked: update only the epoch (flag 0x02)
     let new_epoch: u64 = 5;
     let args = LockupArgs { unix_timestamp: None, epoch: Some(new_epoch), custodian: None };
-    let mut ix = ixn::set_lockup_checked(&stake_acc.pubkey(), &args, &withdrawer.pubkey());
+    let ix = ixn::set_lockup_checked(&stake_acc.pubkey(), &args, &withdrawer.pubkey());
     // Ensure withdrawer signer is present in metas (sdk builder adds it)
     let msg = Message::new(&[ix], Some(&ctx.payer.pubkey()));
     let mut tx = Transaction::new_unsigned(msg);
@@ -83,8 +85,8 @@ async fn set_lockup_checked_updates_epoch_with_withdrawer_signature() {
 // SetLockupChecked: when lockup IS in force, custodian must sign; withdrawer not required.
 #[tokio::test]
 async fn set_lockup_checked_custodian_in_force() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Create stake and authorities
@@ -131,7 +133,7 @@ async fn set_lockup_checked_custodian_in_force() {
     // Verify lockup set and custodian recorded
     let acct = ctx.banks_client.get_account(stake_acc.pubkey()).await.unwrap().unwrap();
     let state = pinocchio_stake::state::stake_state_v2::StakeStateV2::deserialize(&acct.data).unwrap();
-    let (mut meta, in_stake) = match state {
+    let (mut meta, _in_stake) = match state {
         pinocchio_stake::state::stake_state_v2::StakeStateV2::Initialized(m) => (m, false),
         pinocchio_stake::state::stake_state_v2::StakeStateV2::Stake(m, _, _) => (m, true),
         other => panic!("unexpected state: {:?}", other),
@@ -5,12 +5,9 @@ use solana_sdk::{pubkey::Pubkey, signer::Signer, transaction::Transaction};
 #[tokio::test]
 async fn smoke_get_minimum_delegation() {
     // 1) Boot a test bank and load your SBF program via helper
-    let mut pt = common::program_test();
+    let pt = common::program_test();
 
-    // Use the Stake builtin id from our crate
-    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
-
-    let mut ctx = pt.start_with_context().await;
+    let ctx = pt.start_with_context().await;
 
     // 2) Build the instruction for GetMinimumDelegation via adapter
     let ix = ixn::get_minimum_delegation();
@@ -27,13 +24,9 @@ async fn smoke_get_minimum_delegation() {
     let ret = sim
         .simulation_details
         .and_then(|d| d.return_data)
-        .expect("program should return data")
-        .data;
-
-    let mut buf = [0u8; 8];
-    let n = core::cmp::min(ret.len(), 8);
-    buf[..n].copy_from_slice(&ret[..n]);
-    let minimum = u64::from_le_bytes(buf);
+        .expect("program should return data");
 
-    assert!(minimum >= 1, "minimum delegation should be >= 1, got {}", minimum);
+    // exactly the u64 LE minimum, attributed to this program
+    assert_eq!(ret.program_id, Pubkey::new_from_array(pinocchio_stake::ID));
+    assert_eq!(ret.data, pinocchio_stake::helpers::get_minimum_delegation().to_le_bytes());
 }
@@ -0,0 +1,139 @@
+//! Simulation snapshot of one stake account's lifecycle (`snapshot` feature).
+//!
+//!   cargo test --features snapshot --test snapshot
+//!   SNAPSHOT_UPDATE=1 cargo test --features snapshot --test snapshot
+//!
+//! `program_matches_snapshot` runs the script against the SBF build and diffs
+//! it with tests/snapshots/pinocchio_stake.json (see common/snapshot.rs).
+
+#![cfg(feature = "snapshot")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use common::snapshot::{check, diff, Scenario, Step};
+use solana_sdk::{
+    account::Account,
+    rent::Rent,
+    stake::{
+        instruction as sdk_ixn,
+        program::id as stake_program_id,
+        state::{Authorized, Lockup, Meta, StakeAuthorize, StakeStateV2},
+    },
+    vote::program::id as vote_program_id,
+};
+
+const SOL: u64 = 1_000_000_000;
+
+/// Deterministic keys so runs are comparable
+fn key(n: u8) -> Keypair {
+    Keypair::new_from_array([n; 32])
+}
+
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(StakeStateV2::size_of())
+}
+
+fn stake_account(state: &StakeStateV2, lamports: u64) -> Account {
+    let mut data = bincode::serialize(state).unwrap();
+    data.resize(StakeStateV2::size_of(), 0);
+    Account { lamports, data, owner: stake_program_id(), executable: false, rent_epoch: 0 }
+}
+
+fn vote_account() -> Account {
+    let path = format!("{}/tests/fixtures/vote/current_new_validator.bin", env!("CARGO_MANIFEST_DIR"));
+    Account { lamports: SOL, data: std::fs::read(path).unwrap(), owner: vote_program_id(), executable: false, rent_epoch: 0 }
+}
+
+/// Initialize, merge, authorize, delegate, split, deactivate and withdraw,
+/// with one rejected withdrawal and a return-data query along the way
+fn lifecycle() -> Scenario {
+    let (stake, split, source, vote, recipient) = (key(1), key(2), key(3), key(4), key(5));
+    let (staker, withdrawer, new_staker) = (key(6), key(7), key(8));
+    let authorized = Authorized { staker: staker.pubkey(), withdrawer: withdrawer.pubkey() };
+    let source_meta = Meta { rent_exempt_reserve: reserve(), authorized, lockup: Lockup::default() };
+
+    let split_ix = sdk_ixn::split(&stake.pubkey(), &new_staker.pubkey(), 2 * SOL, &split.pubkey());
+    let steps = vec![
+        Step {
+            name: "initialize",
+            instruction: sdk_ixn::initialize(&stake.pubkey(), &authorized, &Lockup::default()),
+            signers: vec![],
+        },
+        Step {
+            name: "merge_initialized",
+            instruction: sdk_ixn::merge(&stake.pubkey(), &source.pubkey(), &staker.pubkey()).remove(0),
+            signers: vec![key(6)],
+        },
+        Step {
+            name: "authorize_staker",
+            instruction: sdk_ixn::authorize(&stake.pubkey(), &staker.pubkey(), &new_staker.pubkey(), StakeAuthorize::Staker, None),
+            signers: vec![key(6)],
+        },
+        Step {
+            name: "delegate",
+            instruction: sdk_ixn::delegate_stake(&stake.pubkey(), &new_staker.pubkey(), &vote.pubkey()),
+            signers: vec![key(8)],
+        },
+        Step {
+            // the split account is prefunded and allocated, so only the Split itself runs
+            name: "split",
+            instruction: split_ix.last().unwrap().clone(),
+            signers: vec![key(8)],
+        },
+        Step {
+            name: "deactivate_split",
+            instruction: sdk_ixn::deactivate_stake(&split.pubkey(), &new_staker.pubkey()),
+            signers: vec![key(8)],
+        },
+        Step {
+            name: "withdraw_from_active_rejected",
+            instruction: sdk_ixn::withdraw(&stake.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), 1, None),
+            signers: vec![key(7)],
+        },
+        Step { name: "get_minimum_delegation", instruction: sdk_ixn::get_minimum_delegation(), signers: vec![] },
+        Step {
+            name: "withdraw_split",
+            instruction: sdk_ixn::withdraw(&split.pubkey(), &withdrawer.pubkey(), &recipient.pubkey(), 2 * SOL + reserve(), None),
+            signers: vec![key(7)],
+        },
+    ];
+    Scenario {
+        accounts: vec![
+            (stake.pubkey(), stake_account(&StakeStateV2::Uninitialized, 10 * SOL)),
+            (split.pubkey(), stake_account(&StakeStateV2::Uninitialized, reserve())),
+            (source.pubkey(), stake_account(&StakeStateV2::Initialized(source_meta), SOL + reserve())),
+            (vote.pubkey(), vote_account()),
+            (recipient.pubkey(), Account { lamports: SOL, owner: solana_sdk::system_program::id(), ..Account::default() }),
+        ],
+        steps,
+    }
+}
+
+#[tokio::test]
+async fn program_matches_snapshot() {
+    let snapshot = lifecycle().run(program_test(), |ix| ixn::translate(ix).unwrap()).await;
+    check("pinocchio_stake", &snapshot);
+}
+
+#[tokio::test]
+async fn harness_is_deterministic_and_diffs_changes() {
+    let scenario = lifecycle();
+    let first = scenario.run(program_test_native(), Clone::clone).await;
+    let second = scenario.run(program_test_native(), Clone::clone).await;
+    assert_eq!(first, second);
+    assert!(first.steps.iter().all(|step| step.units > 0 && !step.logs.is_empty()));
+    assert_eq!(first.steps.iter().filter(|step| step.result != "ok").count(), 1, "{first:#?}");
+    assert!(first.steps[7].return_data.is_some());
+
+    let mut changed = second.clone();
+    changed.steps[3].units += 1;
+    changed.steps[4].logs[1] = "Program log: changed".to_string();
+    changed.steps.pop();
+    let changes = diff(&first, &changed);
+    assert_eq!(changes.len(), 3, "{changes:#?}");
+    assert!(changes[0].starts_with("step 3 delegate: units"));
+    assert!(changes[1].starts_with("step 4 split: log line 1"));
+    assert_eq!(changes[2], "step 8 withdraw_split: removed");
+}
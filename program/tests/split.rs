@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 mod common;
 use common::*;
 use common::pin_adapter as ixn;
@@ -5,8 +7,8 @@ use solana_sdk::{pubkey::Pubkey, system_instruction, message::Message};
 
 #[tokio::test]
 async fn split_from_initialized_into_uninitialized() {
-    let mut pt = common::program_test();
-    let mut ctx = pt.start_with_context().await;
+    let pt = common::program_test();
+    let ctx = pt.start_with_context().await;
     let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
 
     // Prepare source stake: rent-exempt + extra lamports for split; Initialized with staker/withdrawer.
@@ -59,3 +61,49 @@ async fn split_from_initialized_into_uninitialized() {
     let res = ctx.banks_client.process_transaction(tx).await;
     assert!(res.is_ok(), "Split should succeed: {:?}", res);
 }
+
+#[cfg(feature = "split-minimum-delegation")]
+#[tokio::test]
+async fn split_initialized_rejects_dust() {
+    use pinocchio_stake::{helpers::get_minimum_delegation, state as pstate};
+    use solana_sdk::{account::Account, instruction::InstructionError, transaction::TransactionError};
+
+    let staker = Keypair::new();
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let state = pstate::StakeStateV2::Initialized(pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized { staker: staker.pubkey().to_bytes(), withdrawer: staker.pubkey().to_bytes() },
+        lockup: pstate::Lockup::default(),
+    });
+    let stake_account = |lamports: u64, state: &pstate::StakeStateV2| Account {
+        lamports,
+        data: ixn::encode_program_stake_state(state),
+        owner: Pubkey::new_from_array(pinocchio_stake::ID),
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let mut pt = common::program_test();
+    let source = Pubkey::new_unique();
+    let dest = Pubkey::new_unique();
+    let minimum = get_minimum_delegation();
+    pt.add_account(source, stake_account(reserve + 3 * minimum, &state));
+    pt.add_account(dest, stake_account(reserve, &pstate::StakeStateV2::Uninitialized));
+    let ctx = pt.start_with_context().await;
+
+    let split = |lamports: u64| {
+        let ix = ixn::split(&source, &staker.pubkey(), lamports, &dest).pop().unwrap();
+        Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &staker], ctx.last_blockhash)
+    };
+    let insufficient = TransactionError::InstructionError(0, InstructionError::InsufficientFunds);
+
+    // destination would hold less than the minimum delegation above its reserve
+    let err = ctx.banks_client.process_transaction(split(minimum - 1)).await.unwrap_err().unwrap();
+    assert_eq!(err, insufficient);
+
+    // source would be left with less than the minimum delegation above its reserve
+    let err = ctx.banks_client.process_transaction(split(2 * minimum + 1)).await.unwrap_err().unwrap();
+    assert_eq!(err, insufficient);
+
+    ctx.banks_client.process_transaction(split(minimum)).await.unwrap();
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::state as pstate;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const SPLIT: u64 = 3_000_000_000;
+const SEED: &str = "split-1";
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn meta(staker: &Keypair) -> pstate::Meta {
+    pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+        authorized: pstate::Authorized {
+            staker: staker.pubkey().to_bytes(),
+            withdrawer: staker.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup::default(),
+    }
+}
+
+/// Initialized source with room for a rent-exempt split; nothing exists at the destination yet
+async fn setup(staker: &Keypair) -> (ProgramTestContext, Pubkey) {
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports: reserve() + 2 * SPLIT,
+            data: ixn::encode_program_stake_state(&pstate::StakeStateV2::Initialized(meta(staker))),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    (pt.start_with_context().await, stake_key)
+}
+
+async fn send(ctx: &ProgramTestContext, ix: solana_sdk::instruction::Instruction, signers: &[&Keypair]) -> Result<(), TransactionError> {
+    let mut all = vec![&ctx.payer];
+    all.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &all, ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+#[tokio::test]
+async fn split_with_seed_creates_destination() {
+    let staker = Keypair::new();
+    let base = Keypair::new();
+    let (ctx, stake_key) = setup(&staker).await;
+
+    let (ix, dest) = ixn::split_with_seed(&stake_key, &staker.pubkey(), SPLIT, &base.pubkey(), SEED);
+    send(&ctx, ix, &[&base, &staker]).await.unwrap();
+
+    let acct = ctx.banks_client.get_account(dest).await.unwrap().expect("destination must exist");
+    assert_eq!(acct.owner, Pubkey::new_from_array(pinocchio_stake::ID));
+    assert_eq!(acct.data.len(), pstate::StakeStateV2::size_of());
+    assert_eq!(acct.lamports, SPLIT);
+    match pstate::StakeStateV2::deserialize(&acct.data).unwrap() {
+        pstate::StakeStateV2::Initialized(m) => assert_eq!(m.authorized.staker, staker.pubkey().to_bytes()),
+        other => panic!("expected Initialized, got {other:?}"),
+    }
+    let source = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    assert_eq!(source.lamports, reserve() + SPLIT);
+}
+
+#[tokio::test]
+async fn split_with_seed_accepts_preallocated_destination() {
+    let staker = Keypair::new();
+    let base = Keypair::new();
+    let (ctx, stake_key) = setup(&staker).await;
+    let (ix, dest) = ixn::split_with_seed(&stake_key, &staker.pubkey(), SPLIT, &base.pubkey(), SEED);
+
+    let allocate = system_instruction::allocate_with_seed(
+        &dest,
+        &base.pubkey(),
+        SEED,
+        pstate::StakeStateV2::size_of() as u64,
+        &Pubkey::new_from_array(pinocchio_stake::ID),
+    );
+    let tx = Transaction::new_signed_with_payer(&[allocate, ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &base, &staker], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let acct = ctx.banks_client.get_account(dest).await.unwrap().unwrap();
+    assert_eq!(acct.lamports, SPLIT);
+}
+
+#[tokio::test]
+async fn split_with_seed_rejects_wrong_destination() {
+    let staker = Keypair::new();
+    let base = Keypair::new();
+    let (ctx, stake_key) = setup(&staker).await;
+
+    let (mut ix, _) = ixn::split_with_seed(&stake_key, &staker.pubkey(), SPLIT, &base.pubkey(), SEED);
+    ix.accounts[1].pubkey = Pubkey::new_unique();
+    let err = send(&ctx, ix, &[&base, &staker]).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+}
+
+#[tokio::test]
+async fn split_with_seed_requires_base_signature() {
+    let staker = Keypair::new();
+    let base = Keypair::new();
+    let (ctx, stake_key) = setup(&staker).await;
+
+    let (mut ix, _) = ixn::split_with_seed(&stake_key, &staker.pubkey(), SPLIT, &base.pubkey(), SEED);
+    ix.accounts[2].is_signer = false;
+    let err = send(&ctx, ix, &[&staker]).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+}
+
+#[tokio::test]
+async fn split_with_seed_requires_staker() {
+    let staker = Keypair::new();
+    let base = Keypair::new();
+    let intruder = Keypair::new();
+    let (ctx, stake_key) = setup(&staker).await;
+
+    let (ix, dest) = ixn::split_with_seed(&stake_key, &intruder.pubkey(), SPLIT, &base.pubkey(), SEED);
+    let err = send(&ctx, ix, &[&base, &intruder]).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+    // the failed transaction rolls back the allocation too
+    assert!(ctx.banks_client.get_account(dest).await.unwrap().is_none());
+}
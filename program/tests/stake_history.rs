@@ -0,0 +1,208 @@
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use common::stake_history::{StakeHistoryFixture, NEW_RATE_ACTIVATION_EPOCH};
+use pinocchio_stake::{helpers::PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH, state as pstate};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    stake::state::Delegation,
+    transaction::TransactionError,
+};
+
+const BOOTSTRAP: u64 = 10_000_000_000;
+const STAKE: u64 = 4_000_000_000;
+
+fn sdk_delegation(voter: &Pubkey, activation_epoch: u64, deactivation_epoch: u64) -> Delegation {
+    Delegation {
+        voter_pubkey: *voter,
+        stake: STAKE,
+        activation_epoch,
+        deactivation_epoch,
+        ..Delegation::default()
+    }
+}
+
+fn program_delegation(d: &Delegation) -> pstate::Delegation {
+    let mut out = pstate::Delegation::new(&d.voter_pubkey.to_bytes(), d.stake, pstate::EpochLE::new(d.activation_epoch));
+    out.deactivation_epoch = pstate::EpochLE::new(d.deactivation_epoch);
+    out
+}
+
+#[test]
+fn fixture_curve_matches_program_math() {
+    let voter = Pubkey::new_unique();
+    let delegations = [sdk_delegation(&voter, 1, 12), sdk_delegation(&voter, 3, u64::MAX)];
+    let fixture = StakeHistoryFixture::from_delegations(BOOTSTRAP, &delegations, 40);
+    let snapshot = fixture.snapshot();
+
+    // the curve is not trivial: warmup spans several epochs
+    let first = fixture.entry(1).unwrap();
+    assert!(first.activating > 0 && first.activating < 2 * STAKE);
+
+    for d in &delegations {
+        let program = program_delegation(d);
+        for epoch in 0..40 {
+            let sdk = d.stake_activating_and_deactivating(epoch, fixture.history(), NEW_RATE_ACTIVATION_EPOCH);
+            let ours = program.stake_activating_and_deactivating(
+                pstate::EpochLE::new(epoch),
+                &snapshot,
+                PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
+            );
+            assert_eq!(
+                (
+                    ours.effective.get(),
+                    ours.activating.get(),
+                    ours.deactivating.get(),
+                ),
+                (sdk.effective, sdk.activating, sdk.deactivating),
+                "epoch {epoch}: {d:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn randomized_histories_match_sdk() {
+    // arbitrary (not self-consistent) cluster histories stress the rounding in
+    // every warmup/cooldown step, including ratios far from the usual range
+    let mut rng = StdRng::seed_from_u64(0xC0_01D0);
+    let voter = Pubkey::new_unique();
+    for _ in 0..300 {
+        let mut fixture = StakeHistoryFixture::new();
+        for epoch in 0..30 {
+            if rng.gen_bool(0.9) {
+                let scale = 1u64 << rng.gen_range(10..60);
+                fixture = fixture.with_entry(
+                    epoch,
+                    rng.gen_range(0..scale),
+                    rng.gen_range(0..scale),
+                    rng.gen_range(0..scale),
+                );
+            }
+        }
+        let snapshot = fixture.snapshot();
+
+        let activation = rng.gen_range(0..10);
+        let deactivation = if rng.gen_bool(0.3) { u64::MAX } else { activation + rng.gen_range(0..20) };
+        let mut d = sdk_delegation(&voter, activation, deactivation);
+        let bits = rng.gen_range(1..60);
+        d.stake = rng.gen_range(1..1u64 << bits);
+        let program = program_delegation(&d);
+
+        for epoch in 0..35 {
+            let sdk = d.stake_activating_and_deactivating(epoch, fixture.history(), NEW_RATE_ACTIVATION_EPOCH);
+            let ours = program.stake_activating_and_deactivating(
+                pstate::EpochLE::new(epoch),
+                &snapshot,
+                PERPETUAL_NEW_WARMUP_COOLDOWN_RATE_EPOCH,
+            );
+            assert_eq!(
+                (
+                    ours.effective.get(),
+                    ours.activating.get(),
+                    ours.deactivating.get(),
+                ),
+                (sdk.effective, sdk.activating, sdk.deactivating),
+                "epoch {epoch}: {d:?}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn withdraw_sees_injected_cooldown() {
+    let mut pt = common::program_test();
+    let withdrawer = Keypair::new();
+    let voter = Pubkey::new_unique();
+    let stake_key = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let delegation = sdk_delegation(&voter, 0, 2);
+    let reserve = solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of());
+    let meta = pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve),
+        authorized: pstate::Authorized {
+            staker: withdrawer.pubkey().to_bytes(),
+            withdrawer: withdrawer.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup::default(),
+    };
+    let stake = pstate::Stake { delegation: program_delegation(&delegation), credits_observed: [0; 8] };
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports: reserve + STAKE,
+            data: ixn::encode_program_stake_state(&pstate::StakeStateV2::Stake(meta, stake, pstate::StakeFlags::empty())),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut ctx = pt.start_with_context().await;
+
+    // a few epochs into cooldown, only part of the stake has come free
+    let epoch = 4;
+    let fixture = StakeHistoryFixture::from_delegations(BOOTSTRAP, &[delegation], epoch);
+    fixture.inject(&mut ctx, epoch).await;
+    let still_staked = delegation.stake(epoch, fixture.history(), NEW_RATE_ACTIVATION_EPOCH);
+    assert!(still_staked > 0 && still_staked < STAKE);
+    let free = STAKE - still_staked;
+
+    let withdraw = |lamports| ixn::withdraw(&stake_key, &withdrawer.pubkey(), &recipient, lamports, None);
+    let send = |ctx: &ProgramTestContext, lamports| {
+        Transaction::new_signed_with_payer(
+            &[withdraw(lamports)],
+            Some(&ctx.payer.pubkey()),
+            &[&ctx.payer, &withdrawer],
+            ctx.last_blockhash,
+        )
+    };
+
+    let err = ctx.banks_client.process_transaction(send(&ctx, free + 1)).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InsufficientFunds));
+
+    ctx.banks_client.process_transaction(send(&ctx, free)).await.unwrap();
+    let account = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    assert_eq!(account.lamports, reserve + still_staked);
+}
+
+/// The program's `log-debug` trace prints one line per stake history syscall
+#[cfg(feature = "log-debug")]
+#[tokio::test]
+async fn warmup_walk_fetches_one_window_per_syscall() {
+    use pinocchio_stake::state::STAKE_HISTORY_FETCH_WINDOW;
+
+    // a delegation far larger than the cluster warms up for dozens of epochs
+    const LARGE_STAKE: u64 = 100_000_000_000;
+    let mut pt = common::program_test();
+    let withdrawer = Keypair::new();
+    let voter = Pubkey::new_unique();
+    let stake_key = Pubkey::new_unique();
+    let delegation = Delegation { stake: LARGE_STAKE, ..sdk_delegation(&voter, 0, u64::MAX) };
+    let state = pinocchio_stake::test_utils::StakeStateBuilder::initialized(withdrawer.pubkey().to_bytes(), withdrawer.pubkey().to_bytes())
+        .delegation(voter.to_bytes(), LARGE_STAKE)
+        .activation_epoch(0);
+    let reserve = pinocchio_stake::test_utils::DEFAULT_RESERVE;
+    pt.add_account(stake_key, fixture_account(reserve + LARGE_STAKE + 1, &state));
+    let mut ctx = pt.start_with_context().await;
+
+    let epoch = 40;
+    let fixture = StakeHistoryFixture::from_delegations(1_000_000_000, &[delegation], epoch);
+    fixture.inject(&mut ctx, epoch).await;
+    let status = delegation.stake_activating_and_deactivating(epoch, fixture.history(), NEW_RATE_ACTIVATION_EPOCH);
+    assert!(status.activating > 0, "still warming up at epoch {epoch}");
+
+    let ix = ixn::withdraw(&stake_key, &withdrawer.pubkey(), &Pubkey::new_unique(), 1, None);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+    let sim = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    sim.result.unwrap().unwrap();
+    let logs = sim.simulation_details.unwrap().logs;
+    let fetches = logs.iter().filter(|line| line.contains("stake_history: fetch")).count();
+
+    // entries 0..40 walked, one syscall per window instead of one per epoch
+    assert_eq!(fetches, (epoch as usize).div_ceil(STAKE_HISTORY_FETCH_WINDOW), "{logs:#?}");
+}
@@ -0,0 +1,238 @@
+#![allow(deprecated)]
+
+// Randomized layout tests: the program's StakeStateV2 (de)serializer must stay
+// byte-for-byte identical to bincode over solana-sdk's StakeStateV2, or stake
+// accounts written by one program would be misread by the other.
+
+use pinocchio_stake::state as pstate;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_sdk::{
+    pubkey::Pubkey,
+    stake::{
+        stake_flags::StakeFlags as SdkStakeFlags,
+        state::{
+            Authorized as SdkAuthorized, Delegation as SdkDelegation, Lockup as SdkLockup,
+            Meta as SdkMeta, Stake as SdkStake, StakeStateV2 as SdkStakeStateV2,
+        },
+    },
+};
+
+const CASES: usize = 2_000;
+const SEED: u64 = 0x5eed_2769;
+
+fn any_meta(rng: &mut StdRng) -> SdkMeta {
+    SdkMeta {
+        rent_exempt_reserve: rng.gen(),
+        authorized: SdkAuthorized {
+            staker: Pubkey::new_from_array(rng.gen()),
+            withdrawer: Pubkey::new_from_array(rng.gen()),
+        },
+        lockup: SdkLockup {
+            unix_timestamp: rng.gen(),
+            epoch: rng.gen(),
+            custodian: Pubkey::new_from_array(rng.gen()),
+        },
+    }
+}
+
+fn any_stake(rng: &mut StdRng) -> SdkStake {
+    SdkStake {
+        delegation: SdkDelegation {
+            voter_pubkey: Pubkey::new_from_array(rng.gen()),
+            stake: rng.gen(),
+            activation_epoch: rng.gen(),
+            deactivation_epoch: rng.gen(),
+            // any finite value; only the bit pattern has to survive
+            warmup_cooldown_rate: rng.gen_range(-1.0e9..1.0e9),
+        },
+        credits_observed: rng.gen(),
+    }
+}
+
+// solana-sdk keeps the flag bits private; go through their one-byte encoding
+fn sdk_flags(bits: u8) -> SdkStakeFlags {
+    bincode::deserialize(&[bits]).unwrap()
+}
+
+fn sdk_flag_bits(flags: &SdkStakeFlags) -> u8 {
+    bincode::serialize(flags).unwrap()[0]
+}
+
+fn any_sdk_state(rng: &mut StdRng) -> SdkStakeStateV2 {
+    match rng.gen_range(0..4) {
+        0 => SdkStakeStateV2::Uninitialized,
+        1 => SdkStakeStateV2::Initialized(any_meta(rng)),
+        2 => SdkStakeStateV2::Stake(any_meta(rng), any_stake(rng), sdk_flags(rng.gen())),
+        _ => SdkStakeStateV2::RewardsPool,
+    }
+}
+
+fn to_program_meta(m: &SdkMeta) -> pstate::Meta {
+    pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(m.rent_exempt_reserve),
+        authorized: pstate::Authorized {
+            staker: m.authorized.staker.to_bytes(),
+            withdrawer: m.authorized.withdrawer.to_bytes(),
+        },
+        lockup: pstate::Lockup {
+            unix_timestamp: m.lockup.unix_timestamp,
+            epoch: m.lockup.epoch,
+            custodian: m.lockup.custodian.to_bytes(),
+        },
+    }
+}
+
+fn to_program_stake(s: &SdkStake) -> pstate::Stake {
+    let d = &s.delegation;
+    pstate::Stake {
+        delegation: pstate::Delegation {
+            voter_pubkey: d.voter_pubkey.to_bytes(),
+            stake: pstate::LamportsLE::new(d.stake),
+            activation_epoch: pstate::EpochLE::new(d.activation_epoch),
+            deactivation_epoch: pstate::EpochLE::new(d.deactivation_epoch),
+            warmup_cooldown_rate: d.warmup_cooldown_rate.to_le_bytes(),
+        },
+        credits_observed: s.credits_observed.to_le_bytes(),
+    }
+}
+
+/// The program's StakeFlags bits are crate-private, so only the flags it can
+/// construct are mapped in the forward direction.
+fn to_program_state(s: &SdkStakeStateV2) -> pstate::StakeStateV2 {
+    match s {
+        SdkStakeStateV2::Uninitialized => pstate::StakeStateV2::Uninitialized,
+        SdkStakeStateV2::Initialized(m) => pstate::StakeStateV2::Initialized(to_program_meta(m)),
+        SdkStakeStateV2::Stake(m, st, f) => {
+            let flags = if *f == SdkStakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED {
+                pstate::StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED
+            } else {
+                pstate::StakeFlags::empty()
+            };
+            pstate::StakeStateV2::Stake(to_program_meta(m), to_program_stake(st), flags)
+        }
+        SdkStakeStateV2::RewardsPool => pstate::StakeStateV2::RewardsPool,
+    }
+}
+
+fn native_bytes(s: &SdkStakeStateV2) -> Vec<u8> {
+    let mut buf = vec![0u8; SdkStakeStateV2::size_of()];
+    bincode::serialize_into(&mut buf[..], s).unwrap();
+    buf
+}
+
+fn program_bytes(s: &pstate::StakeStateV2) -> Vec<u8> {
+    let mut buf = vec![0xAAu8; pstate::StakeStateV2::size_of()];
+    s.serialize(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn account_size_matches_native() {
+    assert_eq!(pstate::StakeStateV2::size_of(), SdkStakeStateV2::size_of());
+}
+
+#[test]
+fn serialize_matches_bincode() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for case in 0..CASES {
+        let mut sdk = any_sdk_state(&mut rng);
+        // keep flags representable on the program side
+        if let SdkStakeStateV2::Stake(_, _, f) = &mut sdk {
+            *f = sdk_flags(sdk_flag_bits(f) & 1);
+        }
+        let program = to_program_state(&sdk);
+        assert_eq!(
+            program_bytes(&program),
+            native_bytes(&sdk),
+            "case {case}: {sdk:?}"
+        );
+    }
+}
+
+#[test]
+fn deserialize_matches_bincode() {
+    let mut rng = StdRng::seed_from_u64(SEED ^ 1);
+    for case in 0..CASES {
+        let sdk = any_sdk_state(&mut rng);
+        let bytes = native_bytes(&sdk);
+
+        let program = pstate::StakeStateV2::deserialize(&bytes).unwrap();
+        // re-encoding through the program reproduces the native account exactly,
+        // including reserved flag bits the program cannot construct itself
+        assert_eq!(program_bytes(&program), bytes, "case {case}: {sdk:?}");
+
+        // and the program's bytes decode back to the same native value
+        let back: SdkStakeStateV2 = bincode::deserialize(&program_bytes(&program)).unwrap();
+        assert_eq!(back, sdk, "case {case}");
+    }
+}
+
+#[test]
+fn rejects_unknown_tag() {
+    let mut bytes = vec![0u8; pstate::StakeStateV2::size_of()];
+    bytes[..4].copy_from_slice(&4u32.to_le_bytes());
+    assert!(pstate::StakeStateV2::deserialize(&bytes).is_err());
+
+    // a tag whose high bytes are set is not a valid variant either
+    bytes[..4].copy_from_slice(&0x0100_0002u32.to_le_bytes());
+    assert!(pstate::StakeStateV2::deserialize(&bytes).is_err());
+}
+
+#[test]
+fn layout_offsets_match_bincode() {
+    use pstate::layout;
+
+    fn at(bytes: &[u8], offset: usize, len: usize) -> &[u8] {
+        &bytes[offset..offset + len]
+    }
+
+    let mut rng = StdRng::seed_from_u64(SEED ^ 2);
+    for case in 0..CASES {
+        let sdk = any_sdk_state(&mut rng);
+        let bytes = native_bytes(&sdk);
+        assert_eq!(bytes.len(), layout::ACCOUNT_SIZE);
+
+        let (meta, stake, flags) = match &sdk {
+            SdkStakeStateV2::Uninitialized => {
+                assert_eq!(layout::tag(&bytes), Some(layout::TAG_UNINITIALIZED));
+                (None, None, None)
+            }
+            SdkStakeStateV2::Initialized(m) => {
+                assert_eq!(layout::tag(&bytes), Some(layout::TAG_INITIALIZED));
+                (Some(m), None, None)
+            }
+            SdkStakeStateV2::Stake(m, s, f) => {
+                assert_eq!(layout::tag(&bytes), Some(layout::TAG_STAKE));
+                (Some(m), Some(s), Some(f))
+            }
+            SdkStakeStateV2::RewardsPool => {
+                assert_eq!(layout::tag(&bytes), Some(layout::TAG_REWARDS_POOL));
+                (None, None, None)
+            }
+        };
+
+        assert_eq!(layout::rent_exempt_reserve(&bytes), meta.map(|m| m.rent_exempt_reserve), "case {case}");
+        assert_eq!(layout::staker(&bytes), meta.map(|m| m.authorized.staker.as_array()), "case {case}");
+        assert_eq!(layout::withdrawer(&bytes), meta.map(|m| m.authorized.withdrawer.as_array()), "case {case}");
+        assert_eq!(layout::lockup_unix_timestamp(&bytes), meta.map(|m| m.lockup.unix_timestamp), "case {case}");
+        assert_eq!(layout::lockup_epoch(&bytes), meta.map(|m| m.lockup.epoch), "case {case}");
+        assert_eq!(layout::custodian(&bytes), meta.map(|m| m.lockup.custodian.as_array()), "case {case}");
+
+        let delegation = stake.map(|s| &s.delegation);
+        assert_eq!(layout::voter_pubkey(&bytes), delegation.map(|d| d.voter_pubkey.as_array()), "case {case}");
+        assert_eq!(layout::delegated_stake(&bytes), delegation.map(|d| d.stake), "case {case}");
+        assert_eq!(layout::activation_epoch(&bytes), delegation.map(|d| d.activation_epoch), "case {case}");
+        assert_eq!(layout::deactivation_epoch(&bytes), delegation.map(|d| d.deactivation_epoch), "case {case}");
+        assert_eq!(layout::credits_observed(&bytes), stake.map(|s| s.credits_observed), "case {case}");
+        if let (Some(d), Some(f)) = (delegation, flags) {
+            assert_eq!(at(&bytes, layout::WARMUP_COOLDOWN_RATE, 8), d.warmup_cooldown_rate.to_le_bytes());
+            assert_eq!(bytes[layout::STAKE_FLAGS], sdk_flag_bits(f));
+        }
+    }
+
+    // short data has no fields at all
+    let stake = native_bytes(&SdkStakeStateV2::Stake(SdkMeta::default(), SdkStake::default(), SdkStakeFlags::empty()));
+    assert_eq!(layout::tag(&stake[..3]), None);
+    assert_eq!(layout::voter_pubkey(&stake[..layout::VOTER_PUBKEY + 31]), None);
+    assert!(layout::voter_pubkey(&stake[..layout::VOTER_PUBKEY + 32]).is_some());
+}
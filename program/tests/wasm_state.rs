@@ -0,0 +1,39 @@
+//! State-only build for browser wallets: without the default `program`
+//! feature the crate must still compile to wasm32, and account data decodes
+//! through the same types the program uses.
+//!
+//! `cargo test --test wasm_state -- --ignored` (needs `rustup target add wasm32-unknown-unknown`)
+
+use std::process::Command;
+
+use pinocchio_stake::state::{stake_state_v2::StakeStateV2, LamportsLE, Lockup, Meta};
+
+#[test]
+#[ignore = "builds the crate for wasm32-unknown-unknown"]
+fn state_only_build_targets_wasm32() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--no-default-features", "--features", "std", "--target", "wasm32-unknown-unknown"])
+        .current_dir(manifest_dir)
+        // a separate target dir, so the outer `cargo test` build is left alone
+        .env("CARGO_TARGET_DIR", format!("{manifest_dir}/target/wasm-state"))
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "state-only wasm32 build failed");
+}
+
+#[test]
+fn decodes_initialized_account_data() {
+    let meta = Meta {
+        rent_exempt_reserve: LamportsLE::new(2_282_880),
+        lockup: Lockup { unix_timestamp: 1_700_000_000, epoch: 600, custodian: [7; 32] },
+        ..Meta::default()
+    };
+    let mut data = [0u8; StakeStateV2::ACCOUNT_SIZE];
+    StakeStateV2::Initialized(meta).serialize(&mut data).unwrap();
+
+    match StakeStateV2::deserialize(&data).unwrap() {
+        StakeStateV2::Initialized(decoded) => assert_eq!(decoded, meta),
+        other => panic!("unexpected state {other:?}"),
+    }
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "extensions")]
+#![allow(deprecated)]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use pinocchio_stake::state as pstate;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    transaction::TransactionError,
+};
+
+const STAKE: u64 = 10_000_000_000;
+const REWARDS: u64 = 1_234_567;
+const RECIPIENT_START: u64 = 1_000_000_000;
+
+fn reserve() -> u64 {
+    solana_sdk::rent::Rent::default().minimum_balance(pstate::StakeStateV2::size_of())
+}
+
+fn meta(withdrawer: &Keypair, lockup_epoch: u64) -> pstate::Meta {
+    pstate::Meta {
+        rent_exempt_reserve: pstate::LamportsLE::new(reserve()),
+        authorized: pstate::Authorized {
+            staker: withdrawer.pubkey().to_bytes(),
+            withdrawer: withdrawer.pubkey().to_bytes(),
+        },
+        lockup: pstate::Lockup { epoch: lockup_epoch, ..pstate::Lockup::default() },
+    }
+}
+
+fn initialized(withdrawer: &Keypair, lockup_epoch: u64) -> pstate::StakeStateV2 {
+    pstate::StakeStateV2::Initialized(meta(withdrawer, lockup_epoch))
+}
+
+/// Fully active (bootstrap) delegation of `STAKE`
+fn delegated(withdrawer: &Keypair) -> pstate::StakeStateV2 {
+    let delegation = pstate::Delegation::new(&Pubkey::new_unique().to_bytes(), STAKE, pstate::EpochLE::MAX);
+    let stake = pstate::Stake { delegation, credits_observed: [0; 8] };
+    pstate::StakeStateV2::Stake(meta(withdrawer, 0), stake, pstate::StakeFlags::empty())
+}
+
+async fn setup(state: &pstate::StakeStateV2, lamports: u64) -> (ProgramTestContext, Pubkey, Pubkey) {
+    let mut pt = common::program_test();
+    let stake_key = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    pt.add_account(
+        stake_key,
+        Account {
+            lamports,
+            data: ixn::encode_program_stake_state(state),
+            owner: Pubkey::new_from_array(pinocchio_stake::ID),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    pt.add_account(recipient, Account { lamports: RECIPIENT_START, ..Account::default() });
+    (pt.start_with_context().await, stake_key, recipient)
+}
+
+async fn withdraw_excess(ctx: &ProgramTestContext, stake: &Pubkey, signer: &Keypair, recipient: &Pubkey) -> Result<(), TransactionError> {
+    let ix = ixn::withdraw_excess(stake, &signer.pubkey(), recipient, None);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, signer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+async fn lamports(ctx: &ProgramTestContext, key: Pubkey) -> u64 {
+    ctx.banks_client.get_account(key).await.unwrap().unwrap().lamports
+}
+
+#[tokio::test]
+async fn withdraw_excess_initialized_leaves_reserve() {
+    let withdrawer = Keypair::new();
+    let (ctx, stake_key, recipient) = setup(&initialized(&withdrawer, 0), reserve() + REWARDS).await;
+
+    withdraw_excess(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap();
+
+    assert_eq!(lamports(&ctx, stake_key).await, reserve());
+    assert_eq!(lamports(&ctx, recipient).await, RECIPIENT_START + REWARDS);
+    let acct = ctx.banks_client.get_account(stake_key).await.unwrap().unwrap();
+    assert!(matches!(pstate::StakeStateV2::deserialize(&acct.data).unwrap(), pstate::StakeStateV2::Initialized(_)));
+}
+
+#[tokio::test]
+async fn withdraw_excess_active_stake_takes_only_rewards() {
+    let withdrawer = Keypair::new();
+    let (ctx, stake_key, recipient) = setup(&delegated(&withdrawer), reserve() + STAKE + REWARDS).await;
+
+    withdraw_excess(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap();
+
+    assert_eq!(lamports(&ctx, stake_key).await, reserve() + STAKE);
+    assert_eq!(lamports(&ctx, recipient).await, RECIPIENT_START + REWARDS);
+}
+
+#[tokio::test]
+async fn withdraw_excess_with_nothing_spare_is_a_no_op() {
+    let withdrawer = Keypair::new();
+    let (ctx, stake_key, recipient) = setup(&initialized(&withdrawer, 0), reserve()).await;
+
+    withdraw_excess(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap();
+
+    assert_eq!(lamports(&ctx, stake_key).await, reserve());
+    assert_eq!(lamports(&ctx, recipient).await, RECIPIENT_START);
+}
+
+#[tokio::test]
+async fn withdraw_excess_requires_withdrawer() {
+    let withdrawer = Keypair::new();
+    let (ctx, stake_key, recipient) = setup(&initialized(&withdrawer, 0), reserve() + REWARDS).await;
+    let intruder = Keypair::new();
+
+    let err = withdraw_excess(&ctx, &stake_key, &intruder, &recipient).await.unwrap_err();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+}
+
+#[tokio::test]
+async fn withdraw_excess_respects_lockup() {
+    let withdrawer = Keypair::new();
+    let (ctx, stake_key, recipient) = setup(&initialized(&withdrawer, u64::MAX), reserve() + REWARDS).await;
+
+    let err = withdraw_excess(&ctx, &stake_key, &withdrawer, &recipient).await.unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(pinocchio_stake::error::StakeError::LockupInForce as u32)
+        )
+    );
+}
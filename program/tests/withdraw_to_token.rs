@@ -0,0 +1,102 @@
+#![cfg(feature = "withdraw-to-token")]
+
+mod common;
+use common::*;
+use common::pin_adapter as ixn;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    program_error::ProgramError,
+    stake::state::Authorized,
+    transaction::TransactionError,
+};
+use std::str::FromStr;
+
+/// SPL token account length
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+fn token_program_id() -> Pubkey {
+    Pubkey::new_from_array(pinocchio_token::ID)
+}
+
+/// An initialized token account of `mint` holding `rent` lamports; `native`
+/// marks it wSOL with all of those lamports as its reserve. Layout: mint,
+/// owner, amount, delegate (COption), state, is_native (COption<u64>),
+/// delegated_amount, close_authority (COption).
+fn token_account(mint: &Pubkey, owner: &Pubkey, rent: u64, native: bool) -> Account {
+    let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+    data[..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[108] = 1;
+    if native {
+        data[109] = 1;
+        data[113..121].copy_from_slice(&rent.to_le_bytes());
+    }
+    Account { lamports: rent, data, owner: token_program_id(), executable: false, rent_epoch: 0 }
+}
+
+fn native_mint() -> Pubkey {
+    Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap()
+}
+
+fn token_amount(account: &Account) -> u64 {
+    u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+}
+
+async fn initialized_stake(ctx: &mut ProgramTestContext, withdrawer: &Keypair, extra: u64) -> Pubkey {
+    let stake = Keypair::new();
+    let space = pinocchio_stake::state::stake_state_v2::StakeStateV2::ACCOUNT_SIZE;
+    let reserve = ctx.banks_client.get_rent().await.unwrap().minimum_balance(space);
+    let program_id = Pubkey::new_from_array(pinocchio_stake::ID);
+    let authorized = Authorized { staker: withdrawer.pubkey(), withdrawer: withdrawer.pubkey() };
+    let ixs = [
+        system_instruction::create_account(&ctx.payer.pubkey(), &stake.pubkey(), reserve + extra, space as u64, &program_id),
+        ixn::initialize(&stake.pubkey(), &authorized, &Default::default()),
+    ];
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&ctx.payer.pubkey()), &[&ctx.payer, &stake], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    stake.pubkey()
+}
+
+#[tokio::test]
+async fn withdraw_to_token_account_syncs_wsol_amount() {
+    let mut pt = common::program_test();
+    let owner = Pubkey::new_unique();
+    let wsol = Pubkey::new_unique();
+    let rent = solana_sdk::rent::Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN);
+    pt.add_account(wsol, token_account(&native_mint(), &owner, rent, true));
+    let mut ctx = pt.start_with_context().await;
+
+    let withdrawer = Keypair::new();
+    let stake = initialized_stake(&mut ctx, &withdrawer, 1_000_000).await;
+    let ix = ixn::withdraw_to_token_account(&stake, &withdrawer.pubkey(), &wsol, 600_000, None);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    let account = ctx.banks_client.get_account(wsol).await.unwrap().unwrap();
+    assert_eq!(account.lamports, rent + 600_000);
+    assert_eq!(token_amount(&account), 600_000);
+}
+
+#[tokio::test]
+async fn withdraw_to_token_account_rejects_non_native_accounts() {
+    let mut pt = common::program_test();
+    let owner = Pubkey::new_unique();
+    let rent = solana_sdk::rent::Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN);
+    // a token account of another mint
+    let other = Pubkey::new_unique();
+    pt.add_account(other, token_account(&Pubkey::new_unique(), &owner, rent, false));
+    // a plain system account
+    let system = Pubkey::new_unique();
+    pt.add_account(system, Account { lamports: rent, ..Account::default() });
+    let mut ctx = pt.start_with_context().await;
+
+    let withdrawer = Keypair::new();
+    let stake = initialized_stake(&mut ctx, &withdrawer, 1_000_000).await;
+    for recipient in [other, system] {
+        let ix = ixn::withdraw_to_token_account(&stake, &withdrawer.pubkey(), &recipient, 600_000, None);
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.payer.pubkey()), &[&ctx.payer, &withdrawer], ctx.last_blockhash);
+        let err = ctx.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::from(u64::from(ProgramError::InvalidAccountData))));
+    }
+}